@@ -13,12 +13,13 @@ use bonsaidb_core::api::{self, Api, ApiName};
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::arc_bytes::OwnedBytes;
 use bonsaidb_core::connection::{
-    AsyncStorageConnection, Database, HasSession, IdentityReference, Session,
+    AsyncStorageConnection, Database, HasSession, IdentityReference, Session, StorageStats,
 };
 use bonsaidb_core::networking::{
-    AlterUserPermissionGroupMembership, AlterUserRoleMembership, AssumeIdentity, CreateDatabase,
-    CreateUser, DeleteDatabase, DeleteUser, ListAvailableSchemas, ListDatabases, LogOutSession,
-    MessageReceived, Payload, UnregisterSubscriber, CURRENT_PROTOCOL_VERSION,
+    AlterUserPermissionGroupMembership, AlterUserRoleMembership, AssumeIdentity, CopyDatabase,
+    CreateDatabase, CreateUser, DeleteDatabase, DeleteUser, GetStorageStats, ListAvailableSchemas,
+    ListDatabases, LogOutSession, MessageReceived, Payload, RenameDatabase, UnregisterSubscriber,
+    CURRENT_PROTOCOL_VERSION,
 };
 use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::schema::{Nameable, Schema, SchemaName, SchemaSummary, Schematic};
@@ -680,6 +681,32 @@ impl AsyncStorageConnection for AsyncClient {
         Ok(())
     }
 
+    async fn rename_database(
+        &self,
+        name: &str,
+        new_name: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.send_api_request(&RenameDatabase {
+            name: name.to_string(),
+            new_name: new_name.to_string(),
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn copy_database(
+        &self,
+        name: &str,
+        destination: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.send_api_request(&CopyDatabase {
+            name: name.to_string(),
+            destination: destination.to_string(),
+        })
+        .await?;
+        Ok(())
+    }
+
     async fn list_databases(&self) -> Result<Vec<Database>, bonsaidb_core::Error> {
         Ok(self.send_api_request(&ListDatabases).await?)
     }
@@ -688,6 +715,10 @@ impl AsyncStorageConnection for AsyncClient {
         Ok(self.send_api_request(&ListAvailableSchemas).await?)
     }
 
+    async fn stats(&self) -> Result<StorageStats, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&GetStorageStats).await?)
+    }
+
     async fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         Ok(self
             .send_api_request(&CreateUser {
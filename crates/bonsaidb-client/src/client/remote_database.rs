@@ -3,16 +3,18 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use bonsaidb_core::connection::{
-    AccessPolicy, AsyncConnection, AsyncLowLevelConnection, HasSchema, HasSession, Range,
-    SerializedQueryKey, Session, Sort,
+    AccessPolicy, AsyncConnection, AsyncLowLevelConnection, DatabaseStats, HasSchema, HasSession,
+    Range, SerializedQueryKey, Session, Sort,
 };
 use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::networking::{
     ApplyTransaction, Compact, CompactCollection, CompactKeyValueStore, Count, DeleteDocs, Get,
-    GetMultiple, LastTransactionId, List, ListExecutedTransactions, ListHeaders, Query,
-    QueryWithDocs, Reduce, ReduceGrouped,
+    GetDatabaseStats, GetMultiple, LastTransactionId, List, ListExecutedTransactions, ListHeaders,
+    ListQuarantinedDocuments, ListViewStatuses, Query, QueryWithDocs, Reduce, ReduceGrouped,
+    ReindexViews,
 };
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
+use bonsaidb_core::schema::view::{QuarantinedDocument, ViewStatus};
 use bonsaidb_core::schema::{self, CollectionName, Schematic, ViewName};
 use bonsaidb_core::transaction::{Executed, OperationResult, Transaction};
 
@@ -36,6 +38,43 @@ impl AsyncRemoteDatabase {
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
+
+    /// Returns the indexing status of each view in this database.
+    pub async fn view_statuses(&self) -> Result<Vec<ViewStatus>, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&ListViewStatuses {
+                database: self.name.to_string(),
+            })
+            .await?)
+    }
+
+    /// Returns the documents that failed to map in `view`.
+    pub async fn quarantined_documents(
+        &self,
+        view: &ViewName,
+    ) -> Result<Vec<QuarantinedDocument>, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&ListQuarantinedDocuments {
+                database: self.name.to_string(),
+                view: view.clone(),
+            })
+            .await?)
+    }
+
+    /// Begins updating the indexes of `views` in the background. If `views`
+    /// is `None`, all views in this database will be updated. The progress can
+    /// be monitored using [`view_statuses()`](Self::view_statuses).
+    pub async fn reindex_views(
+        &self,
+        views: Option<Vec<ViewName>>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.send_api_request(&ReindexViews {
+            database: self.name.to_string(),
+            views,
+        })
+        .await?;
+        Ok(())
+    }
 }
 
 impl Deref for AsyncRemoteDatabase {
@@ -109,6 +148,14 @@ impl AsyncConnection for AsyncRemoteDatabase {
         .await?;
         Ok(())
     }
+
+    async fn stats(&self) -> Result<DatabaseStats, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&GetDatabaseStats {
+                database: self.name.to_string(),
+            })
+            .await?)
+    }
 }
 
 #[async_trait]
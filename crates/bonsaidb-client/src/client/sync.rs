@@ -6,21 +6,22 @@ use bonsaidb_core::admin::{Admin, ADMIN_DATABASE_NAME};
 use bonsaidb_core::api;
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::connection::{
-    AccessPolicy, Connection, Database, HasSchema, HasSession, IdentityReference,
-    LowLevelConnection, Range, SerializedQueryKey, Sort, StorageConnection,
+    AccessPolicy, Connection, Database, DatabaseStats, HasSchema, HasSession, IdentityReference,
+    LowLevelConnection, Range, SerializedQueryKey, Sort, StorageConnection, StorageStats,
 };
 use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::keyvalue::KeyValue;
 use bonsaidb_core::networking::{
     AlterUserPermissionGroupMembership, AlterUserRoleMembership, ApplyTransaction, AssumeIdentity,
-    Compact, CompactCollection, CompactKeyValueStore, Count, CreateDatabase, CreateSubscriber,
-    CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Get, GetMultiple,
-    LastTransactionId, List, ListAvailableSchemas, ListDatabases, ListExecutedTransactions,
-    ListHeaders, Publish, PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped, SubscribeTo,
-    UnsubscribeFrom, CURRENT_PROTOCOL_VERSION,
+    Compact, CompactCollection, CompactKeyValueStore, CopyDatabase, Count, CreateDatabase,
+    CreateSubscriber, CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Get,
+    GetDatabaseStats, GetMultiple, GetStorageStats, LastTransactionId, List, ListAvailableSchemas,
+    ListDatabases, ListExecutedTransactions, ListHeaders, ListQuarantinedDocuments,
+    ListViewStatuses, Publish, PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped,
+    ReindexViews, RenameDatabase, SubscribeTo, UnsubscribeFrom, CURRENT_PROTOCOL_VERSION,
 };
 use bonsaidb_core::pubsub::{AsyncSubscriber, PubSub, Receiver, Subscriber};
-use bonsaidb_core::schema::view::map;
+use bonsaidb_core::schema::view::{map, QuarantinedDocument, ViewStatus};
 use bonsaidb_core::schema::{CollectionName, ViewName};
 use futures::Future;
 use tokio::runtime::{Handle, Runtime};
@@ -149,6 +150,22 @@ impl StorageConnection for BlockingClient {
         Ok(())
     }
 
+    fn rename_database(&self, name: &str, new_name: &str) -> Result<(), bonsaidb_core::Error> {
+        self.send_api_request(&RenameDatabase {
+            name: name.to_string(),
+            new_name: new_name.to_string(),
+        })?;
+        Ok(())
+    }
+
+    fn copy_database(&self, name: &str, destination: &str) -> Result<(), bonsaidb_core::Error> {
+        self.send_api_request(&CopyDatabase {
+            name: name.to_string(),
+            destination: destination.to_string(),
+        })?;
+        Ok(())
+    }
+
     fn list_databases(
         &self,
     ) -> Result<Vec<bonsaidb_core::connection::Database>, bonsaidb_core::Error> {
@@ -161,6 +178,10 @@ impl StorageConnection for BlockingClient {
         Ok(self.send_api_request(&ListAvailableSchemas)?)
     }
 
+    fn stats(&self) -> Result<StorageStats, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&GetStorageStats)?)
+    }
+
     fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         Ok(self.send_api_request(&CreateUser {
             username: username.to_string(),
@@ -304,6 +325,39 @@ impl HasSession for BlockingClient {
 #[derive(Debug, Clone)]
 pub struct BlockingRemoteDatabase(AsyncRemoteDatabase);
 
+impl BlockingRemoteDatabase {
+    /// Returns the indexing status of each view in this database.
+    pub fn view_statuses(&self) -> Result<Vec<ViewStatus>, bonsaidb_core::Error> {
+        Ok(self.0.send_blocking_api_request(&ListViewStatuses {
+            database: self.0.name.to_string(),
+        })?)
+    }
+
+    /// Returns the documents that failed to map in `view`.
+    pub fn quarantined_documents(
+        &self,
+        view: &ViewName,
+    ) -> Result<Vec<QuarantinedDocument>, bonsaidb_core::Error> {
+        Ok(self
+            .0
+            .send_blocking_api_request(&ListQuarantinedDocuments {
+                database: self.0.name.to_string(),
+                view: view.clone(),
+            })?)
+    }
+
+    /// Begins updating the indexes of `views` in the background. If `views`
+    /// is `None`, all views in this database will be updated. The progress can
+    /// be monitored using [`view_statuses()`](Self::view_statuses).
+    pub fn reindex_views(&self, views: Option<Vec<ViewName>>) -> Result<(), bonsaidb_core::Error> {
+        self.0.send_blocking_api_request(&ReindexViews {
+            database: self.0.name.to_string(),
+            views,
+        })?;
+        Ok(())
+    }
+}
+
 impl Connection for BlockingRemoteDatabase {
     type Storage = BlockingClient;
 
@@ -348,6 +402,12 @@ impl Connection for BlockingRemoteDatabase {
         })?;
         Ok(())
     }
+
+    fn stats(&self) -> Result<DatabaseStats, bonsaidb_core::Error> {
+        Ok(self.0.send_blocking_api_request(&GetDatabaseStats {
+            database: self.0.name.to_string(),
+        })?)
+    }
 }
 
 impl LowLevelConnection for BlockingRemoteDatabase {
@@ -28,9 +28,13 @@ use crate::{transaction, Error};
 
 mod has_session;
 mod lowlevel;
+mod stats;
 
 pub use self::has_session::HasSession;
 pub use self::lowlevel::{AsyncLowLevelConnection, HasSchema, LowLevelConnection};
+pub use self::stats::{
+    CacheStats, CollectionStats, DatabaseStats, KeyValueStats, StorageStats, TreeStats, ViewStats,
+};
 
 /// A connection to a database's [`Schema`](schema::Schema), giving access to
 /// [`Collection`s](crate::schema::Collection) and
@@ -107,6 +111,14 @@ pub trait Connection: LowLevelConnection + Sized + Send + Sync {
     ///
     /// * [`Error::Other`]: an error occurred while compacting the database.
     fn compact_key_value_store(&self) -> Result<(), crate::Error>;
+
+    /// Returns usage statistics for this database, including the number of
+    /// documents, view entries and keys stored, and how many bytes each tree
+    /// occupies on disk.
+    ///
+    /// Gathering the statistics reads the index of every tree in the
+    /// database, which may take a while for large databases.
+    fn stats(&self) -> Result<DatabaseStats, crate::Error>;
 }
 
 /// Interacts with a collection over a `Connection`.
@@ -1114,6 +1126,14 @@ pub trait AsyncConnection: AsyncLowLevelConnection + Sized + Send + Sync {
     ///
     /// * [`Error::Other`]: an error occurred while compacting the database.
     async fn compact_key_value_store(&self) -> Result<(), crate::Error>;
+
+    /// Returns usage statistics for this database, including the number of
+    /// documents, view entries and keys stored, and how many bytes each tree
+    /// occupies on disk.
+    ///
+    /// Gathering the statistics reads the index of every tree in the
+    /// database, which may take a while for large databases.
+    async fn stats(&self) -> Result<DatabaseStats, crate::Error>;
 }
 
 /// Interacts with a collection over a `Connection`.
@@ -3018,12 +3038,42 @@ pub trait StorageConnection: HasSession + Sized + Send + Sync {
     /// * [`Error::Other`]: an error occurred while deleting files.
     fn delete_database(&self, name: &str) -> Result<(), crate::Error>;
 
+    /// Renames the database `name` to `new_name`. Existing references to the
+    /// database must not be used after it has been renamed.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::DatabaseNotFound`]: database `name` does not exist.
+    /// * [`Error::InvalidDatabaseName`]: `new_name` is not a valid database
+    ///   name. See [`create_database()`](Self::create_database) for database
+    ///   name requirements.
+    /// * [`Error::DatabaseNameAlreadyTaken`]: `new_name` is already used by
+    ///   another database.
+    fn rename_database(&self, name: &str, new_name: &str) -> Result<(), crate::Error>;
+
+    /// Creates a new database named `destination` containing a copy of the
+    /// documents, views, and key-value entries of database `name`.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::DatabaseNotFound`]: database `name` does not exist.
+    /// * [`Error::InvalidDatabaseName`]: `destination` is not a valid
+    ///   database name. See [`create_database()`](Self::create_database) for
+    ///   database name requirements.
+    /// * [`Error::DatabaseNameAlreadyTaken`]: `destination` is already used by
+    ///   another database.
+    fn copy_database(&self, name: &str, destination: &str) -> Result<(), crate::Error>;
+
     /// Lists the databases in this storage.
     fn list_databases(&self) -> Result<Vec<Database>, crate::Error>;
 
     /// Lists the [`SchemaName`]s registered with this storage.
     fn list_available_schemas(&self) -> Result<Vec<SchemaSummary>, crate::Error>;
 
+    /// Returns usage statistics for every database in this storage. See
+    /// [`Connection::stats()`] for more information.
+    fn stats(&self) -> Result<StorageStats, crate::Error>;
+
     /// Creates a user.
     fn create_user(&self, username: &str) -> Result<u64, crate::Error>;
 
@@ -3208,12 +3258,42 @@ pub trait AsyncStorageConnection: HasSession + Sized + Send + Sync {
     /// * [`Error::Other`]: an error occurred while deleting files.
     async fn delete_database(&self, name: &str) -> Result<(), crate::Error>;
 
+    /// Renames the database `name` to `new_name`. Existing references to the
+    /// database must not be used after it has been renamed.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::DatabaseNotFound`]: database `name` does not exist.
+    /// * [`Error::InvalidDatabaseName`]: `new_name` is not a valid database
+    ///   name. See [`create_database()`](Self::create_database) for database
+    ///   name requirements.
+    /// * [`Error::DatabaseNameAlreadyTaken`]: `new_name` is already used by
+    ///   another database.
+    async fn rename_database(&self, name: &str, new_name: &str) -> Result<(), crate::Error>;
+
+    /// Creates a new database named `destination` containing a copy of the
+    /// documents, views, and key-value entries of database `name`.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::DatabaseNotFound`]: database `name` does not exist.
+    /// * [`Error::InvalidDatabaseName`]: `destination` is not a valid
+    ///   database name. See [`create_database()`](Self::create_database) for
+    ///   database name requirements.
+    /// * [`Error::DatabaseNameAlreadyTaken`]: `destination` is already used by
+    ///   another database.
+    async fn copy_database(&self, name: &str, destination: &str) -> Result<(), crate::Error>;
+
     /// Lists the databases in this storage.
     async fn list_databases(&self) -> Result<Vec<Database>, crate::Error>;
 
     /// Lists the [`SchemaName`]s registered with this storage.
     async fn list_available_schemas(&self) -> Result<Vec<SchemaSummary>, crate::Error>;
 
+    /// Returns usage statistics for every database in this storage. See
+    /// [`AsyncConnection::stats()`] for more information.
+    async fn stats(&self) -> Result<StorageStats, crate::Error>;
+
     /// Creates a user.
     async fn create_user(&self, username: &str) -> Result<u64, crate::Error>;
 
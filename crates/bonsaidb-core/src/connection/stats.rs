@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{CollectionName, ViewName};
+
+/// Usage statistics for every database in a storage, returned from
+/// [`StorageConnection::stats()`](super::StorageConnection::stats).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageStats {
+    /// The statistics of each database.
+    pub databases: Vec<DatabaseStats>,
+    /// The caches of recently read chunks used by the databases.
+    #[serde(default)]
+    pub caches: Vec<CacheStats>,
+}
+
+impl StorageStats {
+    /// Returns the number of bytes all databases occupy on disk.
+    #[must_use]
+    pub fn file_bytes(&self) -> u64 {
+        self.databases.iter().map(DatabaseStats::file_bytes).sum()
+    }
+
+    /// Returns the number of bytes on disk that can be reclaimed by compacting
+    /// all databases.
+    #[must_use]
+    pub fn wasted_bytes(&self) -> u64 {
+        self.databases.iter().map(DatabaseStats::wasted_bytes).sum()
+    }
+}
+
+/// The size of a cache of recently read chunks. The cache is consulted by the
+/// storage layer internally, so its hits, misses and evictions are not
+/// reported.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// The database this cache is dedicated to, or `None` for the cache shared
+    /// by every other database.
+    pub database: Option<String>,
+    /// The number of bytes this cache may use.
+    pub budget_bytes: u64,
+    /// The maximum number of chunks this cache holds.
+    pub capacity: u64,
+}
+
+/// Usage statistics for a database, returned from
+/// [`Connection::stats()`](super::Connection::stats).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatabaseStats {
+    /// The name of the database.
+    pub name: String,
+    /// The statistics of each collection in the database's schema.
+    pub collections: Vec<CollectionStats>,
+    /// The statistics of the database's key-value store.
+    pub key_value: KeyValueStats,
+    /// The number of bytes the database's transaction log occupies on disk.
+    pub transaction_log_bytes: u64,
+}
+
+impl DatabaseStats {
+    /// Returns the number of bytes this database occupies on disk, including
+    /// its transaction log.
+    #[must_use]
+    pub fn file_bytes(&self) -> u64 {
+        self.trees().map(|tree| tree.file_bytes).sum::<u64>() + self.transaction_log_bytes
+    }
+
+    /// Returns the number of bytes on disk that can be reclaimed by compacting
+    /// this database.
+    #[must_use]
+    pub fn wasted_bytes(&self) -> u64 {
+        self.trees().map(TreeStats::wasted_bytes).sum()
+    }
+
+    /// Returns an iterator over the statistics of every tree in this database.
+    pub fn trees(&self) -> impl Iterator<Item = &TreeStats> {
+        self.collections
+            .iter()
+            .flat_map(CollectionStats::trees)
+            .chain(std::iter::once(&self.key_value.tree))
+    }
+}
+
+/// Usage statistics for a collection.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionStats {
+    /// The name of the collection.
+    pub collection: CollectionName,
+    /// The number of documents stored in the collection.
+    pub documents: u64,
+    /// The tree the collection's documents are stored in.
+    pub documents_tree: TreeStats,
+    /// The statistics of each view in the collection.
+    pub views: Vec<ViewStats>,
+    /// The trees the collection's views use to track which documents have
+    /// been indexed.
+    pub view_trees: Vec<TreeStats>,
+}
+
+impl CollectionStats {
+    /// Returns the number of bytes this collection and its views occupy on
+    /// disk.
+    #[must_use]
+    pub fn file_bytes(&self) -> u64 {
+        self.trees().map(|tree| tree.file_bytes).sum()
+    }
+
+    /// Returns the number of bytes on disk that can be reclaimed by compacting
+    /// this collection.
+    #[must_use]
+    pub fn wasted_bytes(&self) -> u64 {
+        self.trees().map(TreeStats::wasted_bytes).sum()
+    }
+
+    /// Returns an iterator over the statistics of every tree used by this
+    /// collection and its views.
+    pub fn trees(&self) -> impl Iterator<Item = &TreeStats> {
+        std::iter::once(&self.documents_tree)
+            .chain(&self.view_trees)
+            .chain(self.views.iter().flat_map(|view| &view.trees))
+    }
+}
+
+/// Usage statistics for a view.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewStats {
+    /// The name of the view.
+    pub view: ViewName,
+    /// The number of unique keys the view has indexed.
+    pub entries: u64,
+    /// The trees the view's index is stored in.
+    pub trees: Vec<TreeStats>,
+}
+
+/// Usage statistics for a key-value store.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyValueStats {
+    /// The number of keys that have been persisted. Keys that are pending
+    /// persistence are not included.
+    pub keys: u64,
+    /// The tree the keys are stored in.
+    pub tree: TreeStats,
+}
+
+/// Usage statistics for a single tree on disk.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeStats {
+    /// The name of the tree.
+    pub name: String,
+    /// The number of entries stored in the tree.
+    pub entries: u64,
+    /// The number of bytes the tree's file occupies on disk. Trees that have
+    /// never been written to report 0.
+    pub file_bytes: u64,
+    /// The number of bytes occupied by the current keys and values stored in
+    /// the tree. This does not include the tree's internal structure, so a
+    /// freshly compacted tree still reports some wasted bytes.
+    pub live_bytes: u64,
+}
+
+impl TreeStats {
+    /// Returns the number of bytes in the tree's file that do not contain
+    /// current keys or values.
+    #[must_use]
+    pub const fn wasted_bytes(&self) -> u64 {
+        self.file_bytes.saturating_sub(self.live_bytes)
+    }
+
+    /// Returns the portion of the tree's file that does not contain current
+    /// keys or values, between 0.0 and 1.0.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn waste_ratio(&self) -> f64 {
+        if self.file_bytes == 0 {
+            0.
+        } else {
+            self.wasted_bytes() as f64 / self.file_bytes as f64
+        }
+    }
+}
@@ -160,7 +160,9 @@ mod implementation {
                 command: Command::Delete,
             })? {
                 Output::Status(status) => Ok(status),
-                Output::Value(_) => unreachable!("invalid output from delete operation"),
+                Output::Value(_) | Output::Entry { .. } => {
+                    unreachable!("invalid output from delete operation")
+                }
             }
         }
 
@@ -314,7 +316,9 @@ mod implementation {
                 .await?
             {
                 Output::Status(status) => Ok(status),
-                Output::Value(_) => unreachable!("invalid output from delete operation"),
+                Output::Value(_) | Output::Entry { .. } => {
+                    unreachable!("invalid output from delete operation")
+                }
             }
         }
 
@@ -413,6 +417,9 @@ pub enum Command {
     },
     /// Delete a key.
     Delete,
+    /// Get the value of a key along with its expiration. Returns
+    /// [`Output::Entry`].
+    GetEntry,
 }
 
 /// Set a key/value pair.
@@ -727,6 +734,14 @@ pub enum Output {
     Status(KeyStatus),
     /// A value was returned.
     Value(Option<Value>),
+    /// A value and its expiration were returned. Both are `None` if the key
+    /// does not exist.
+    Entry {
+        /// The value stored in the key.
+        value: Option<Value>,
+        /// The time the key will expire, if an expiration is set.
+        expiration: Option<Timestamp>,
+    },
 }
 /// The status of an operation on a Key.
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
@@ -105,7 +105,7 @@ where
         match result {
             Output::Value(value) => Ok(value),
             Output::Status(KeyStatus::NotChanged) => Ok(None),
-            Output::Status(_) => unreachable!("Unexpected output from Set"),
+            Output::Status(_) | Output::Entry { .. } => unreachable!("Unexpected output from Set"),
         }
     }
 
@@ -261,7 +261,9 @@ where
             match result {
                 Output::Value(value) => Ok(value),
                 Output::Status(KeyStatus::NotChanged) => Ok(None),
-                Output::Status(_) => unreachable!("Unexpected output from Set"),
+                Output::Status(_) | Output::Entry { .. } => {
+                    unreachable!("Unexpected output from Set")
+                }
             }
         } else {
             panic!("Using future after it's been executed")
@@ -47,6 +47,7 @@ pub mod pubsub;
 
 use std::fmt::Display;
 use std::string::FromUtf8Error;
+use std::time::Duration;
 
 use schema::{view, CollectionName, SchemaName, ViewName};
 use serde::{Deserialize, Serialize};
@@ -60,6 +61,7 @@ use crate::connection::HasSchema;
 use crate::document::{DocumentId, Header, InvalidHexadecimal};
 use crate::key::time::TimeError;
 use crate::key::NextValueError;
+use crate::limits::QuotaResource;
 use crate::schema::InsertError;
 
 /// an enumeration of errors that this crate can produce
@@ -194,6 +196,33 @@ pub enum Error {
     #[error("floating point operation yielded NaN")]
     NotANumber,
 
+    /// A write was attempted on a storage that was opened read-only.
+    #[error("the storage was opened read-only")]
+    ReadOnly,
+
+    /// A write to `database` was rejected because it would exceed the
+    /// database's quota of `limit` for `resource`.
+    #[error("quota exceeded for database '{database}': limited to {limit} {resource}")]
+    QuotaExceeded {
+        /// The name of the database whose quota would be exceeded.
+        database: String,
+        /// The resource whose limit would be exceeded.
+        resource: QuotaResource,
+        /// The configured limit of `resource`.
+        limit: u64,
+    },
+
+    /// A transaction requested a
+    /// [`Durability::GroupCommit`](transaction::Durability::GroupCommit)
+    /// window longer than the storage allows transactions to request.
+    #[error("group commit window of {window:?} exceeds the maximum of {maximum:?}")]
+    GroupCommitWindowTooLong {
+        /// The window requested by the transaction.
+        window: Duration,
+        /// The longest window the storage allows.
+        maximum: Duration,
+    },
+
     /// An error while operating with a time
     #[error("time error: {0}")]
     Time(#[from] TimeError),
@@ -46,9 +46,41 @@
 //! The serialized representation of all mappings emitted for a single `Key`
 //! must be less than 4 gigabytes in size.
 //!
+//! # Quotas
+//!
+//! A storage can be configured to limit the number of bytes, documents, and
+//! key-value keys each database may use. Writes that would exceed a quota fail
+//! with [`Error::QuotaExceeded`](crate::Error::QuotaExceeded), identifying the
+//! [`QuotaResource`] that was exhausted.
+//!
 //! [nebari]: https://github.com/khonsulabs/nebari
 
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
 /// The maximum number of results allowed to be returned from `list_executed_transactions`.
 pub const LIST_TRANSACTIONS_MAX_RESULTS: u32 = 1000;
 /// If no `result_limit` is specified, this value is the limit used by default.
 pub const LIST_TRANSACTIONS_DEFAULT_RESULT_COUNT: u32 = 100;
+
+/// A resource limited by a database's quota.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuotaResource {
+    /// The number of bytes the database occupies on disk.
+    Bytes,
+    /// The number of documents stored across all collections.
+    Documents,
+    /// The number of keys stored in the key-value store.
+    Keys,
+}
+
+impl Display for QuotaResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bytes => f.write_str("bytes"),
+            Self::Documents => f.write_str("documents"),
+            Self::Keys => f.write_str("keys"),
+        }
+    }
+}
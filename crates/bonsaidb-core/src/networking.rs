@@ -3,11 +3,13 @@ use serde::{Deserialize, Serialize};
 
 use crate::api::{Api, ApiName};
 use crate::connection::{
-    AccessPolicy, Database, IdentityReference, Range, SerializedQueryKey, Session, SessionId, Sort,
+    AccessPolicy, Database, DatabaseStats, IdentityReference, Range, SerializedQueryKey, Session,
+    SessionId, Sort, StorageStats,
 };
 use crate::document::{DocumentId, Header, OwnedDocument};
 use crate::keyvalue::{KeyOperation, Output};
 use crate::schema::view::map::{self, MappedSerializedDocuments};
+use crate::schema::view::{QuarantinedDocument, ViewStatus};
 use crate::schema::{CollectionName, NamedReference, Qualified, SchemaSummary, ViewName};
 use crate::transaction::{Executed, OperationResult, Transaction};
 
@@ -61,6 +63,42 @@ impl Api for DeleteDatabase {
     }
 }
 
+/// Renames the database named `name` to `new_name`.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct RenameDatabase {
+    /// The name of the database to rename.
+    pub name: String,
+    /// The new name of the database.
+    pub new_name: String,
+}
+
+impl Api for RenameDatabase {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "RenameDatabase")
+    }
+}
+
+/// Copies the database named `name` into a new database named `destination`.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct CopyDatabase {
+    /// The name of the database to copy.
+    pub name: String,
+    /// The name of the database to create.
+    pub destination: String,
+}
+
+impl Api for CopyDatabase {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "CopyDatabase")
+    }
+}
+
 /// Lists all databases.
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct ListDatabases;
@@ -676,6 +714,88 @@ impl Api for Compact {
     }
 }
 
+/// Returns the usage statistics of a database.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct GetDatabaseStats {
+    /// The name of the database.
+    pub database: String,
+}
+
+impl Api for GetDatabaseStats {
+    type Error = crate::Error;
+    type Response = DatabaseStats;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "GetDatabaseStats")
+    }
+}
+
+/// Returns the usage statistics of every database.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct GetStorageStats;
+
+impl Api for GetStorageStats {
+    type Error = crate::Error;
+    type Response = StorageStats;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "GetStorageStats")
+    }
+}
+
+/// Lists the indexing status of each view in a database.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ListViewStatuses {
+    /// The name of the database.
+    pub database: String,
+}
+
+impl Api for ListViewStatuses {
+    type Error = crate::Error;
+    type Response = Vec<ViewStatus>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ListViewStatuses")
+    }
+}
+
+/// Begins re-indexing views in the background.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ReindexViews {
+    /// The name of the database.
+    pub database: String,
+    /// The views to re-index. If `None`, all views in the database will be
+    /// re-indexed.
+    pub views: Option<Vec<ViewName>>,
+}
+
+impl Api for ReindexViews {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ReindexViews")
+    }
+}
+
+/// Lists the documents excluded from a view due to errors while mapping.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ListQuarantinedDocuments {
+    /// The name of the database.
+    pub database: String,
+    /// The name of the view.
+    pub view: ViewName,
+}
+
+impl Api for ListQuarantinedDocuments {
+    type Error = crate::Error;
+    type Response = Vec<QuarantinedDocument>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ListQuarantinedDocuments")
+    }
+}
+
 /// A networking error.
 #[derive(Clone, thiserror::Error, Debug, Serialize, Deserialize)]
 pub enum Error {
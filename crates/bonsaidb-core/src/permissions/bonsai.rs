@@ -130,6 +130,13 @@ pub enum ServerAction {
     CreateDatabase,
     /// Permits [`StorageConnection::delete_database`](crate::connection::StorageConnection::delete_database).
     DeleteDatabase,
+    /// Permits [`StorageConnection::rename_database`](crate::connection::StorageConnection::rename_database).
+    /// Checked against both the current and the new name of the database.
+    RenameDatabase,
+    /// Permits [`StorageConnection::copy_database`](crate::connection::StorageConnection::copy_database).
+    /// The destination database also requires
+    /// [`CreateDatabase`](Self::CreateDatabase).
+    CopyDatabase,
     /// Permits [`StorageConnection::create_user`](crate::connection::StorageConnection::create_user).
     CreateUser,
     /// Permits [`StorageConnection::delete_user`](crate::connection::StorageConnection::delete_user).
@@ -153,6 +160,9 @@ pub enum ServerAction {
 pub enum DatabaseAction {
     /// The ability to compact data to reclaim space.
     Compact,
+    /// The ability to retrieve usage statistics through
+    /// [`Connection::stats()`](crate::connection::Connection::stats).
+    GetStats,
     /// Actions that operate on a document.
     Document(DocumentAction),
     /// Actions that operate on a view.
@@ -226,6 +236,16 @@ pub enum ViewAction {
     /// [`Connection::delete_docs()`](crate::connection::LowLevelConnection::delete_docs).
     /// See [`view_resource_name`] for the format of view resource names.
     DeleteDocs,
+    /// Allows retrieving the indexing status of a view. See
+    /// [`view_resource_name`] for the format of view resource names.
+    GetStatus,
+    /// Allows requesting a view be re-indexed in the background. See
+    /// [`view_resource_name`] for the format of view resource names.
+    Reindex,
+    /// Allows listing the documents that were quarantined due to errors while
+    /// mapping them. See [`view_resource_name`] for the format of view
+    /// resource names.
+    ListQuarantined,
 }
 
 /// Actions that operate on transactions.
@@ -284,4 +304,6 @@ pub enum EncryptionKeyAction {
     Encrypt,
     /// Uses a key to decrypt data.
     Decrypt,
+    /// Rotates the master key, re-encrypting existing data with the new key.
+    Rotate,
 }
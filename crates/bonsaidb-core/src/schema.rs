@@ -19,8 +19,9 @@ pub use self::schematic::Schematic;
 pub use self::summary::{CollectionSummary, SchemaSummary, ViewSummary};
 pub use self::view::map::{Map, MappedValue, ViewMappedValue};
 pub use self::view::{
-    CollectionMapReduce, DefaultViewSerialization, MapReduce, ReduceResult, SerializedView, View,
-    ViewMapResult, ViewSchema,
+    CollectionMapReduce, DefaultViewSerialization, MapReduce, QuarantinedDocument, ReduceResult,
+    SerializedView, View, ViewIndexState, ViewMapResult, ViewMappingErrorPolicy, ViewSchema,
+    ViewStatus,
 };
 use crate::Error;
 
@@ -10,7 +10,8 @@ use crate::key::{ByteSource, Key, KeyDescription};
 use crate::schema::collection::Collection;
 use crate::schema::view::map::{self, MappedValue};
 use crate::schema::view::{
-    self, MapReduce, Serialized, SerializedView, ViewSchema, ViewUpdatePolicy,
+    self, MapReduce, Serialized, SerializedView, ViewMappingErrorPolicy, ViewSchema,
+    ViewUpdatePolicy,
 };
 use crate::schema::{CollectionName, Schema, SchemaName, View, ViewName};
 use crate::Error;
@@ -258,10 +259,25 @@ where
         self.schema.version()
     }
 
+    fn mapping_error_policy(&self) -> ViewMappingErrorPolicy {
+        self.schema.mapping_error_policy()
+    }
+
     fn view_name(&self) -> ViewName {
         self.view.view_name()
     }
 
+    fn filter(&self, document: &BorrowedDocument<'_>) -> bool {
+        self.schema.filter(document)
+    }
+
+    fn dependencies(
+        &self,
+        document: &BorrowedDocument<'_>,
+    ) -> Result<Option<Vec<u8>>, view::Error> {
+        Ok(self.schema.dependencies(document)?)
+    }
+
     fn map(&self, document: &BorrowedDocument<'_>) -> Result<Vec<map::Serialized>, view::Error> {
         let mappings = self.schema.map(document)?;
 
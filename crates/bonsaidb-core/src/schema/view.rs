@@ -6,7 +6,7 @@ use transmog::{Format, OwnedDeserializer};
 use transmog_pot::Pot;
 
 use crate::connection::{self, AsyncConnection, Connection};
-use crate::document::{BorrowedDocument, CollectionDocument};
+use crate::document::{BorrowedDocument, CollectionDocument, DocumentId};
 use crate::key::{ByteSource, Key, KeyDescription};
 use crate::schema::view::map::{MappedValue, Mappings, ViewMappedValue};
 use crate::schema::{Collection, CollectionName, Name, SerializedCollection, ViewName};
@@ -14,6 +14,7 @@ use crate::AnyError;
 
 /// Types for defining a `Map` within a `View`.
 pub mod map;
+pub mod reducers;
 
 /// Errors that arise when interacting with views.
 #[derive(thiserror::Error, Debug)]
@@ -196,6 +197,62 @@ pub trait ViewSchema: Send + Sync + 'static {
     fn version(&self) -> u64 {
         0
     }
+
+    /// Returns the policy that controls how errors that occur while mapping a
+    /// document are handled. The provided implementation returns
+    /// [`ViewMappingErrorPolicy::Fail`].
+    fn mapping_error_policy(&self) -> ViewMappingErrorPolicy {
+        ViewMappingErrorPolicy::default()
+    }
+
+    /// Returns true if `document` should be mapped by this view. This function
+    /// is called before [`MapReduce::map()`], and allows skipping documents
+    /// using a check that is cheaper than deserializing the document, such as
+    /// inspecting the document's header or raw bytes. If false is returned,
+    /// `map()` is not called and the document will not be included in the
+    /// view.
+    ///
+    /// The provided implementation returns true.
+    #[allow(unused_variables)]
+    fn filter(&self, document: &BorrowedDocument<'_>) -> bool {
+        true
+    }
+
+    /// Returns the data within `document` that [`MapReduce::map()`] depends
+    /// on, such as the serialized values of the fields the view reads. When
+    /// `Some` is returned, the data is stored alongside the document's
+    /// mappings. If a document is updated and its dependencies are unchanged,
+    /// `map()` is not called and the existing mappings are kept.
+    ///
+    /// The returned data should be small, as it is stored for every document
+    /// in the view. The provided implementation returns `None`, which causes
+    /// every updated document to be mapped again.
+    #[allow(unused_variables)]
+    fn dependencies(
+        &self,
+        document: &BorrowedDocument<'_>,
+    ) -> Result<Option<Vec<u8>>, crate::Error> {
+        Ok(None)
+    }
+
+    /// Reduces `mappings` using the built-in [`Reducer`](reducers::Reducer)
+    /// this view is configured with. This is called by the provided
+    /// implementations of [`MapReduce::reduce()`] and
+    /// [`CollectionMapReduce::reduce()`].
+    ///
+    /// The provided implementation returns
+    /// [`ReduceUnimplemented`](crate::Error::ReduceUnimplemented). When
+    /// deriving this trait, a reducer can be selected using
+    /// `#[view_schema(reduce = Sum)]`. See the [`reducers`] module for the
+    /// available reducers.
+    #[allow(unused_variables)]
+    fn builtin_reduce(
+        &self,
+        mappings: &[MappedValue<Self::MappedKey<'_>, <Self::View as View>::Value>],
+        rereduce: bool,
+    ) -> Result<<Self::View as View>::Value, crate::Error> {
+        Err(crate::Error::ReduceUnimplemented)
+    }
 }
 
 /// The policy under which a [`View`] is updated when documents are saved.
@@ -235,6 +292,108 @@ impl std::fmt::Display for ViewUpdatePolicy {
     }
 }
 
+/// The policy that controls how a [`View`] handles errors that occur while
+/// mapping a document.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ViewMappingErrorPolicy {
+    /// The error is returned, and the view cannot be updated until the
+    /// document is changed or removed.
+    #[default]
+    Fail,
+    /// The document is excluded from the view and the error is logged.
+    Skip,
+    /// The document is excluded from the view, and the error is recorded. The
+    /// list of quarantined documents can be retrieved using
+    /// `quarantined_documents()`. Quarantined documents are mapped again when
+    /// they are changed or when the view's version changes.
+    Quarantine,
+}
+
+/// A document that was excluded from a view because mapping it failed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QuarantinedDocument {
+    /// The id of the document.
+    pub id: DocumentId,
+    /// The error that occurred while mapping the document.
+    pub error: String,
+}
+
+/// The indexing status of a view within a database.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ViewStatus {
+    /// The name of the view.
+    pub name: ViewName,
+    /// The current state of the view's index.
+    pub state: ViewIndexState,
+    /// The number of documents whose mappings have been invalidated and not
+    /// yet been re-mapped.
+    pub invalidated_documents: u64,
+}
+
+impl ViewStatus {
+    /// Returns true if the view's index contains the mappings for every
+    /// document in its collection.
+    #[must_use]
+    pub const fn is_up_to_date(&self) -> bool {
+        matches!(self.state, ViewIndexState::UpToDate)
+    }
+}
+
+/// The state of a view's index.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewIndexState {
+    /// The view's integrity has not been checked since the database was
+    /// opened. If the view's version has changed, the next access will cause
+    /// the entire view to be rebuilt.
+    Unverified,
+    /// The view's index is up-to-date.
+    UpToDate,
+    /// One or more documents need to be mapped, and no update is currently
+    /// running.
+    Outdated,
+    /// The view is currently being updated.
+    Rebuilding {
+        /// The number of documents mapped so far by this update.
+        mapped: u64,
+        /// The total number of documents this update will map.
+        total: u64,
+    },
+}
+
+impl ViewIndexState {
+    /// Returns the percentage of documents mapped by the running update, from
+    /// `0.0` to `100.0`. Returns `None` if the view is not being rebuilt.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percent_complete(&self) -> Option<f64> {
+        match self {
+            Self::Rebuilding { mapped, total } => {
+                if *total == 0 {
+                    Some(100.)
+                } else {
+                    Some(*mapped as f64 / *total as f64 * 100.)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ViewIndexState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unverified => f.write_str("unverified"),
+            Self::UpToDate => f.write_str("up-to-date"),
+            Self::Outdated => f.write_str("outdated"),
+            Self::Rebuilding { mapped, total } => write!(
+                f,
+                "rebuilding ({mapped}/{total}, {:.1}%)",
+                self.percent_complete().unwrap_or_default()
+            ),
+        }
+    }
+}
+
 /// The Map/Reduce functionality for a [`ViewSchema`].
 ///
 /// This trait implementation provides the behavior for mapping data from
@@ -255,13 +414,14 @@ pub trait MapReduce: ViewSchema {
     /// queries that ask for a reduce operation will return an error. See [the
     /// user guide's chapter on views for more information on how reduce
     /// works](https://dev.bonsaidb.io/main/guide/about/concepts/view.html#reduce).
-    #[allow(unused_variables)]
+    ///
+    /// The provided implementation calls [`ViewSchema::builtin_reduce()`].
     fn reduce(
         &self,
         mappings: &[MappedValue<Self::MappedKey<'_>, <Self::View as View>::Value>],
         rereduce: bool,
     ) -> Result<<Self::View as View>::Value, crate::Error> {
-        Err(crate::Error::ReduceUnimplemented)
+        self.builtin_reduce(mappings, rereduce)
     }
 }
 
@@ -342,14 +502,15 @@ where
     /// is returned, queries that ask for a reduce operation will return an
     /// error. See [`CouchDB`'s Reduce/Rereduce
     /// documentation](https://docs.couchdb.org/en/stable/ddocs/views/intro.html#reduce-rereduce)
-    /// for the design this implementation will be inspired by
-    #[allow(unused_variables)]
+    /// for the design this implementation will be inspired by.
+    ///
+    /// The provided implementation calls [`ViewSchema::builtin_reduce()`].
     fn reduce(
         &self,
         mappings: &[ViewMappedValue<'_, Self>],
         rereduce: bool,
     ) -> ReduceResult<Self::View> {
-        Err(crate::Error::ReduceUnimplemented)
+        self.builtin_reduce(mappings, rereduce)
     }
 }
 
@@ -383,8 +544,14 @@ pub trait Serialized: Send + Sync {
 
     /// Wraps [`ViewSchema::version`]
     fn version(&self) -> u64;
+    /// Wraps [`ViewSchema::mapping_error_policy`]
+    fn mapping_error_policy(&self) -> ViewMappingErrorPolicy;
     /// Wraps [`View::view_name`]
     fn view_name(&self) -> ViewName;
+    /// Wraps [`ViewSchema::filter`]
+    fn filter(&self, document: &BorrowedDocument<'_>) -> bool;
+    /// Wraps [`ViewSchema::dependencies`]
+    fn dependencies(&self, document: &BorrowedDocument<'_>) -> Result<Option<Vec<u8>>, Error>;
     /// Wraps [`ViewSchema::map`]
     fn map(&self, document: &BorrowedDocument<'_>) -> Result<Vec<map::Serialized>, Error>;
     /// Wraps [`ViewSchema::reduce`]
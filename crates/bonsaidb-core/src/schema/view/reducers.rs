@@ -0,0 +1,624 @@
+//! Ready-made reduce implementations for common aggregations.
+//!
+//! A reducer can be selected when deriving
+//! [`ViewSchema`](crate::schema::ViewSchema) using the `reduce` parameter.
+//! When a reducer is selected, the provided implementations of
+//! [`MapReduce::reduce()`](crate::schema::MapReduce::reduce) and
+//! [`CollectionMapReduce::reduce()`](crate::schema::CollectionMapReduce::reduce)
+//! will use the reducer, which allows views to be reduced without writing a
+//! reduce function:
+//!
+//! ```rust
+//! # mod collection {
+//! # bonsaidb_core::__doctest_prelude!();
+//! # }
+//! # use collection::MyCollection;
+//! use bonsaidb_core::document::{CollectionDocument, Emit};
+//! use bonsaidb_core::schema::view::reducers::Statistics;
+//! use bonsaidb_core::schema::{CollectionMapReduce, View, ViewMapResult, ViewSchema};
+//!
+//! #[derive(Debug, Clone, View, ViewSchema)]
+//! #[view(collection = MyCollection, key = u32, value = Statistics<f32>)]
+//! #[view_schema(reduce = Aggregate)]
+//! # #[view(core = bonsaidb_core)]
+//! # #[view_schema(core = bonsaidb_core)]
+//! pub struct ScoreStatisticsByRank;
+//!
+//! impl CollectionMapReduce for ScoreStatisticsByRank {
+//!     fn map<'doc>(
+//!         &self,
+//!         document: CollectionDocument<<Self::View as View>::Collection>,
+//!     ) -> ViewMapResult<'doc, Self::View> {
+//!         document.header.emit_key_and_value(
+//!             document.contents.rank,
+//!             Statistics::new(document.contents.score),
+//!         )
+//!     }
+//! }
+//! ```
+//!
+//! The reducers in this module operate on a view's values:
+//!
+//! | Reducer            | Value type            | Result                               |
+//! |--------------------|-----------------------|--------------------------------------|
+//! | [`Sum`]            | Any [`Numeric`]       | The sum of all values.               |
+//! | [`Count`]          | Any [`Numeric`]       | The number of mappings.              |
+//! | [`Min`]            | Any `PartialOrd` type | The smallest value.                  |
+//! | [`Max`]            | Any `PartialOrd` type | The largest value.                   |
+//! | [`Average`]        | [`Mean<T>`]           | The count and sum of all values.     |
+//! | [`Aggregate`]      | [`Statistics<T>`]     | The count, sum, minimum and maximum. |
+//! | [`CountDistinct`]  | [`DistinctCount`]     | An estimate of the distinct values.  |
+//!
+//! Custom reducers can be created by implementing [`Reducer`] and passing the
+//! type's path to the `reduce` parameter.
+
+use std::hash::{Hash, Hasher};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// A reduce implementation for values of type `Value`.
+pub trait Reducer<Value> {
+    /// Returns the result of reducing `values` into a single value. If
+    /// `rereduce` is true, the values have already been reduced at least one
+    /// time.
+    fn reduce<'a, I: Iterator<Item = &'a Value>>(
+        values: I,
+        rereduce: bool,
+    ) -> Result<Value, crate::Error>
+    where
+        Value: 'a;
+}
+
+/// A numeric value that can be used with the built-in reducers.
+pub trait Numeric: PartialOrd + Copy + Send + Sync {
+    /// The value representing zero.
+    const ZERO: Self;
+
+    /// Returns the sum of this value and `other`, or `None` if the sum
+    /// overflows.
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    /// Returns `count` converted to this type. If the value cannot be
+    /// represented, the result is saturated.
+    fn from_count(count: u64) -> Self;
+
+    /// Returns this value as an `f64`.
+    fn as_f64(self) -> f64;
+}
+
+macro_rules! impl_numeric {
+    (integer $($type:ident),+) => {
+        $(
+            impl Numeric for $type {
+                const ZERO: Self = 0;
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    $type::checked_add(self, other)
+                }
+
+                fn from_count(count: u64) -> Self {
+                    Self::try_from(count).unwrap_or(Self::MAX)
+                }
+
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+                fn as_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! impl_numeric_float {
+    ($($type:ident),+) => {
+        $(
+            impl Numeric for $type {
+                const ZERO: Self = 0.;
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    Some(self + other)
+                }
+
+                #[allow(clippy::cast_precision_loss)]
+                fn from_count(count: u64) -> Self {
+                    count as $type
+                }
+
+                #[allow(clippy::cast_lossless)]
+                fn as_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )+
+    };
+}
+
+impl_numeric!(integer u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_numeric_float!(f32, f64);
+
+/// Reduces numeric values by adding them together.
+#[derive(Debug, Clone, Copy)]
+pub struct Sum;
+
+impl<T: Numeric> Reducer<T> for Sum {
+    fn reduce<'a, I: Iterator<Item = &'a T>>(values: I, _rereduce: bool) -> Result<T, crate::Error>
+    where
+        T: 'a,
+    {
+        values.try_fold(T::ZERO, |sum, value| add(sum, *value))
+    }
+}
+
+/// Returns the sum of `a` and `b`, or an error if the sum overflows.
+fn add<T: Numeric>(a: T, b: T) -> Result<T, crate::Error> {
+    a.checked_add(b)
+        .ok_or_else(|| crate::Error::other("reducers", "sum overflowed"))
+}
+
+/// Reduces a view to the number of mappings. The mapped values are ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct Count;
+
+impl<T: Numeric> Reducer<T> for Count {
+    fn reduce<'a, I: Iterator<Item = &'a T>>(values: I, rereduce: bool) -> Result<T, crate::Error>
+    where
+        T: 'a,
+    {
+        if rereduce {
+            Sum::reduce(values, rereduce)
+        } else {
+            Ok(T::from_count(values.fold(0, |count, _| count + 1)))
+        }
+    }
+}
+
+/// Reduces values to the smallest value. If there are no values,
+/// `Default::default()` is returned.
+#[derive(Debug, Clone, Copy)]
+pub struct Min;
+
+impl<T: PartialOrd + Clone + Default> Reducer<T> for Min {
+    fn reduce<'a, I: Iterator<Item = &'a T>>(values: I, _rereduce: bool) -> Result<T, crate::Error>
+    where
+        T: 'a,
+    {
+        Ok(values
+            .fold(None, |min: Option<&T>, value| match min {
+                Some(min) if min <= value => Some(min),
+                _ => Some(value),
+            })
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Reduces values to the largest value. If there are no values,
+/// `Default::default()` is returned.
+#[derive(Debug, Clone, Copy)]
+pub struct Max;
+
+impl<T: PartialOrd + Clone + Default> Reducer<T> for Max {
+    fn reduce<'a, I: Iterator<Item = &'a T>>(values: I, _rereduce: bool) -> Result<T, crate::Error>
+    where
+        T: 'a,
+    {
+        Ok(values
+            .fold(None, |max: Option<&T>, value| match max {
+                Some(max) if max >= value => Some(max),
+                _ => Some(value),
+            })
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Reduces [`Mean`] values, allowing the average of the mapped values to be
+/// computed.
+#[derive(Debug, Clone, Copy)]
+pub struct Average;
+
+impl<T: Numeric> Reducer<Mean<T>> for Average {
+    fn reduce<'a, I: Iterator<Item = &'a Mean<T>>>(
+        values: I,
+        _rereduce: bool,
+    ) -> Result<Mean<T>, crate::Error>
+    where
+        Mean<T>: 'a,
+    {
+        values.try_fold(Mean::default(), |mean, value| {
+            Ok(Mean {
+                count: add(mean.count, value.count)?,
+                sum: add(mean.sum, value.sum)?,
+            })
+        })
+    }
+}
+
+/// The number of values and their sum, used to compute the average of values
+/// with the [`Average`] reducer.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Mean<T> {
+    /// The number of values.
+    pub count: u64,
+    /// The sum of all values.
+    pub sum: T,
+}
+
+impl<T: Numeric> Mean<T> {
+    /// Returns a mean containing a single value.
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            count: 1,
+            sum: value,
+        }
+    }
+
+    /// Returns the average of the values, or `None` if there are no values.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum.as_f64() / self.count as f64)
+    }
+}
+
+impl<T: Numeric> Default for Mean<T> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            sum: T::ZERO,
+        }
+    }
+}
+
+impl<T: Numeric> From<T> for Mean<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Reduces [`Statistics`] values, combining the count, sum, minimum and maximum
+/// of all mapped values.
+#[derive(Debug, Clone, Copy)]
+pub struct Aggregate;
+
+impl<T: Numeric> Reducer<Statistics<T>> for Aggregate {
+    fn reduce<'a, I: Iterator<Item = &'a Statistics<T>>>(
+        values: I,
+        _rereduce: bool,
+    ) -> Result<Statistics<T>, crate::Error>
+    where
+        Statistics<T>: 'a,
+    {
+        values.try_fold(Statistics::default(), |stats, value| stats.combine(value))
+    }
+}
+
+/// Multiple aggregations of a set of values, reduced using the [`Aggregate`]
+/// reducer.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Statistics<T> {
+    /// The number of values.
+    pub count: u64,
+    /// The sum of all values.
+    pub sum: T,
+    /// The smallest value, or `None` if there are no values.
+    pub min: Option<T>,
+    /// The largest value, or `None` if there are no values.
+    pub max: Option<T>,
+}
+
+impl<T: Numeric> Statistics<T> {
+    /// Returns statistics containing a single value.
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            count: 1,
+            sum: value,
+            min: Some(value),
+            max: Some(value),
+        }
+    }
+
+    /// Returns the average of the values, or `None` if there are no values.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum.as_f64() / self.count as f64)
+    }
+
+    fn combine(self, other: &Self) -> Result<Self, crate::Error> {
+        Ok(Self {
+            count: add(self.count, other.count)?,
+            sum: add(self.sum, other.sum)?,
+            min: match (self.min, other.min) {
+                (Some(a), Some(b)) => Some(if b < a { b } else { a }),
+                (a, b) => a.or(b),
+            },
+            max: match (self.max, other.max) {
+                (Some(a), Some(b)) => Some(if b > a { b } else { a }),
+                (a, b) => a.or(b),
+            },
+        })
+    }
+}
+
+impl<T: Numeric> Default for Statistics<T> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            sum: T::ZERO,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl<T: Numeric> From<T> for Statistics<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Reduces [`DistinctCount`] values, which estimate the number of distinct
+/// values mapped.
+#[derive(Debug, Clone, Copy)]
+pub struct CountDistinct;
+
+impl Reducer<DistinctCount> for CountDistinct {
+    fn reduce<'a, I: Iterator<Item = &'a DistinctCount>>(
+        values: I,
+        _rereduce: bool,
+    ) -> Result<DistinctCount, crate::Error>
+    where
+        DistinctCount: 'a,
+    {
+        let mut count = DistinctCount::default();
+        for value in values {
+            count.merge(value)?;
+        }
+        Ok(count)
+    }
+}
+
+/// An approximate count of distinct values, reduced using the
+/// [`CountDistinct`] reducer.
+///
+/// This type uses the `HyperLogLog` algorithm, which keeps a fixed-size sketch
+/// of 1,024 registers regardless of how many values are counted. Estimates
+/// have a standard error of roughly 3%.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistinctCount {
+    /// The registers of the sketch. This is empty when no values have been
+    /// counted.
+    #[serde(deserialize_with = "deserialize_registers")]
+    registers: Vec<u8>,
+}
+
+fn deserialize_registers<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let registers = Vec::<u8>::deserialize(deserializer)?;
+    if registers.is_empty() || registers.len() == DistinctCount::REGISTERS {
+        Ok(registers)
+    } else {
+        Err(D::Error::invalid_length(
+            registers.len(),
+            &"no registers or 1024 registers",
+        ))
+    }
+}
+
+impl DistinctCount {
+    const PRECISION: u32 = 10;
+    const REGISTERS: usize = 1 << Self::PRECISION;
+
+    /// Returns a count containing `value`.
+    ///
+    /// Values are hashed using a hash function that doesn't change between
+    /// processes. To ensure consistent results across architectures, values
+    /// should hash the same regardless of the platform's endianness.
+    #[must_use]
+    pub fn of<T: Hash + ?Sized>(value: &T) -> Self {
+        let mut count = Self::default();
+        count.insert(value);
+        count
+    }
+
+    /// Adds `value` to this count.
+    pub fn insert<T: Hash + ?Sized>(&mut self, value: &T) {
+        let mut hasher = Fnv64::default();
+        value.hash(&mut hasher);
+        let hash = mix(hasher.finish());
+
+        let index = usize::try_from(hash >> (64 - Self::PRECISION)).expect("index fits in usize");
+        let remaining = hash << Self::PRECISION;
+        let rank = u8::try_from((remaining.leading_zeros() + 1).min(64 - Self::PRECISION + 1))
+            .expect("rank fits in u8");
+
+        let registers = self.registers_mut();
+        registers[index] = registers[index].max(rank);
+    }
+
+    /// Combines the values counted in `other` into this count.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `other` doesn't contain the expected number of
+    /// registers.
+    pub fn merge(&mut self, other: &Self) -> Result<(), crate::Error> {
+        if other.registers.is_empty() {
+            return Ok(());
+        } else if other.registers.len() != Self::REGISTERS {
+            return Err(crate::Error::other(
+                "DistinctCount",
+                format!(
+                    "expected {} registers, found {}",
+                    Self::REGISTERS,
+                    other.registers.len()
+                ),
+            ));
+        }
+
+        let registers = self.registers_mut();
+        for (register, other) in registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+        Ok(())
+    }
+
+    /// Returns the estimated number of distinct values counted.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn estimate(&self) -> u64 {
+        if self.registers.is_empty() {
+            return 0;
+        }
+
+        let registers = Self::REGISTERS as f64;
+        let alpha = 0.7213 / (1. + 1.079 / registers);
+        let mut zeroes = 0_u32;
+        let mut sum = 0.;
+        for register in &self.registers {
+            if *register == 0 {
+                zeroes += 1;
+            }
+            sum += 2_f64.powi(-i32::from(*register));
+        }
+
+        let estimate = alpha * registers * registers / sum;
+        let estimate = if estimate <= 2.5 * registers && zeroes > 0 {
+            // Small range correction: use linear counting.
+            registers * (registers / f64::from(zeroes)).ln()
+        } else {
+            estimate
+        };
+
+        estimate.round() as u64
+    }
+
+    fn registers_mut(&mut self) -> &mut [u8] {
+        if self.registers.is_empty() {
+            self.registers = vec![0; Self::REGISTERS];
+        }
+        &mut self.registers
+    }
+}
+
+/// A 64-bit FNV-1a hasher, whose output is stable across processes.
+struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Distributes the bits of an FNV hash more evenly. This is the finalizer of
+/// `SplitMix64`.
+fn mix(mut hash: u64) -> u64 {
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn builtin_reducers() {
+    let values = [3_u32, 1, 4, 1, 5];
+    assert_eq!(Sum::reduce(values.iter(), false).unwrap(), 14);
+    assert!(Sum::reduce([u8::MAX, 1].iter(), false).is_err());
+    assert_eq!(Count::reduce(values.iter(), false).unwrap(), 5);
+    assert_eq!(Count::reduce([2_u32, 3].iter(), true).unwrap(), 5);
+    assert_eq!(Min::reduce(values.iter(), false).unwrap(), 1);
+    assert_eq!(Max::reduce(values.iter(), false).unwrap(), 5);
+    assert_eq!(Max::reduce([0_u32; 0].iter(), false).unwrap(), 0);
+
+    let means = values.iter().copied().map(Mean::from).collect::<Vec<_>>();
+    let mean = Average::reduce(means.iter(), false).unwrap();
+    assert_eq!(mean, Mean { count: 5, sum: 14 });
+    assert_eq!(mean.average(), Some(2.8));
+    assert_eq!(Mean::<u32>::default().average(), None);
+    let overflowing = [Mean::new(i8::MIN), Mean::new(-1)];
+    assert!(Average::reduce(overflowing.iter(), false).is_err());
+
+    let stats = values
+        .iter()
+        .copied()
+        .map(Statistics::from)
+        .collect::<Vec<_>>();
+    let (first, second) = stats.split_at(2);
+    let rereduced = [
+        Aggregate::reduce(first.iter(), false).unwrap(),
+        Aggregate::reduce(second.iter(), false).unwrap(),
+        Statistics::default(),
+    ];
+    assert_eq!(
+        Aggregate::reduce(rereduced.iter(), true).unwrap(),
+        Statistics {
+            count: 5,
+            sum: 14,
+            min: Some(1),
+            max: Some(5),
+        }
+    );
+}
+
+#[test]
+#[allow(clippy::cast_precision_loss)]
+fn distinct_count_estimates() {
+    assert_eq!(DistinctCount::default().estimate(), 0);
+    assert_eq!(DistinctCount::of("hello").estimate(), 1);
+
+    for distinct in [100_u64, 10_000, 100_000] {
+        // Count each value twice, split across two sketches.
+        let mut first = DistinctCount::default();
+        let mut second = DistinctCount::default();
+        for value in 0..distinct {
+            first.insert(&value);
+            second.insert(&value);
+        }
+        let reduced = CountDistinct::reduce([first, second].iter(), true).unwrap();
+        let estimate = reduced.estimate();
+        let error = estimate.abs_diff(distinct) as f64 / distinct as f64;
+        assert!(error < 0.1, "estimated {estimate} for {distinct}");
+    }
+}
+
+#[test]
+fn distinct_count_rejects_invalid_registers() {
+    #[derive(Serialize)]
+    struct Registers {
+        registers: Vec<u8>,
+    }
+
+    let truncated = pot::to_vec(&Registers {
+        registers: vec![1; 16],
+    })
+    .unwrap();
+    assert!(pot::from_slice::<DistinctCount>(&truncated).is_err());
+
+    let mut count = DistinctCount::of("hello");
+    let invalid = DistinctCount {
+        registers: vec![1; 16],
+    };
+    assert!(count.merge(&invalid).is_err());
+    assert!(count.merge(&DistinctCount::of("world")).is_ok());
+    assert_eq!(count.estimate(), 2);
+}
@@ -1,9 +1,12 @@
+use std::time::Duration;
+
 use arc_bytes::serde::Bytes;
 use serde::{Deserialize, Serialize};
 
 use crate::connection::{AsyncLowLevelConnection, LowLevelConnection};
 use crate::document::{CollectionHeader, DocumentId, HasHeader, Header, Revision};
 use crate::key::KeyEncoding;
+use crate::keyvalue::Timestamp;
 use crate::schema::{Collection, CollectionName, SerializedCollection};
 use crate::Error;
 
@@ -36,7 +39,9 @@ use crate::Error;
 /// - Durable: When the transaction apply function has finished exectuing,
 ///   BonsaiDb guarantees that all data has been confirmed by the operating
 ///   system as being fully written to disk. This ensures that in the event of a
-///   power outage, no data that has been confirmed will be lost.
+///   power outage, no data that has been confirmed will be lost. This guarantee
+///   can be relaxed in exchange for throughput by using a different
+///   [`Durability`].
 ///
 /// When using one of the high-level functions to push/insert/update/delete
 /// documents, behind the scenes single-[`Operation`] `Transaction`s are
@@ -66,6 +71,10 @@ use crate::Error;
 pub struct Transaction {
     /// The operations in this transaction.
     pub operations: Vec<Operation>,
+    /// The durability this transaction requires. If `None`, the durability
+    /// configured for the database is used.
+    #[serde(default)]
+    pub durability: Option<Durability>,
 }
 
 impl Transaction {
@@ -85,6 +94,12 @@ impl Transaction {
         self
     }
 
+    /// Sets the durability this transaction requires and returns self.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = Some(durability);
+        self
+    }
+
     /// Applies the transaction to the `database`, returning the results of the
     /// operations. All operations will succeed or none will be performed and an
     /// error will be returned.
@@ -110,6 +125,7 @@ impl From<Operation> for Transaction {
     fn from(operation: Operation) -> Self {
         Self {
             operations: vec![operation],
+            durability: None,
         }
     }
 }
@@ -405,6 +421,13 @@ pub enum OperationResult {
     },
 }
 
+/// The [`PubSub`](crate::pubsub::PubSub) topic that a database publishes an
+/// empty message to each time a transaction is recorded in its transaction
+/// log. Subscribing to this topic allows waiting for new transactions instead
+/// of polling
+/// [`list_executed_transactions()`](crate::connection::LowLevelConnection::list_executed_transactions).
+pub const EXECUTED_TRANSACTIONS_TOPIC: &str = "_bonsaidb_executed_transactions";
+
 /// Details about an executed transaction.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Executed {
@@ -413,6 +436,41 @@ pub struct Executed {
 
     /// A list of containing ids of `Documents` changed.
     pub changes: Changes,
+
+    /// The time the transaction was executed. This is `None` for transactions
+    /// that were executed before timestamps were recorded.
+    #[serde(default)]
+    pub timestamp: Option<Timestamp>,
+
+    /// The durability the transaction was committed with. This is `None` for
+    /// transactions that were executed before durability was recorded.
+    #[serde(default)]
+    pub durability: Option<Durability>,
+}
+
+/// Controls when applying a transaction returns relative to its changes being
+/// synchronized to disk.
+///
+/// Every transaction is synchronized to disk before it returns. The storage
+/// layer doesn't support committing without synchronizing, so there is no
+/// durability that trades crash safety for throughput.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Durability {
+    /// Each transaction is synchronized to disk before it returns.
+    #[default]
+    Full,
+    /// Transactions applied concurrently within `window` of each other are
+    /// committed together and synchronized to disk once. Each transaction
+    /// still returns only after it has been synchronized, but may wait up to
+    /// `window` for other transactions to join it.
+    ///
+    /// Storage may limit the window a transaction can request, returning
+    /// [`Error::GroupCommitWindowTooLong`](crate::Error::GroupCommitWindowTooLong)
+    /// for longer windows.
+    GroupCommit {
+        /// How long to wait for other transactions to join a commit.
+        window: Duration,
+    },
 }
 
 /// A list of changes.
@@ -53,7 +53,7 @@ use async_trait::async_trait;
 use aws_config::meta::region::RegionProviderChain;
 use aws_sdk_s3::types::ByteStream;
 use aws_sdk_s3::{Client, Region};
-use bonsaidb_local::vault::{KeyPair, VaultKeyStorage};
+use bonsaidb_local::vault::{KeyPair, OptionalOperationError, VaultKeyStorage};
 use bonsaidb_local::StorageId;
 use tokio::runtime::{self, Handle, Runtime};
 pub use {aws_sdk_s3, http};
@@ -146,6 +146,53 @@ impl S3VaultKeyStorage {
         path
     }
 
+    fn path_for_encryption_key(&self, storage_id: StorageId, name: &str) -> String {
+        // Key names can contain any character, so they are hex-encoded to
+        // ensure they are valid object keys.
+        let object_name = name
+            .bytes()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        format!("{}-keys/{object_name}", self.path_for_id(storage_id))
+    }
+
+    async fn get_object(&self, key: String) -> anyhow::Result<Option<Vec<u8>>> {
+        let client = self.client().await;
+        match client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(response) => Ok(Some(response.body.collect().await?.into_bytes().to_vec())),
+            Err(aws_smithy_client::SdkError::ServiceError(err))
+                if matches!(
+                    err.err(),
+                    aws_sdk_s3::error::GetObjectError {
+                        kind: aws_sdk_s3::error::GetObjectErrorKind::NoSuchKey(_),
+                        ..
+                    }
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(anyhow::anyhow!(err)),
+        }
+    }
+
+    async fn put_object(&self, key: String, contents: Vec<u8>) -> anyhow::Result<()> {
+        let client = self.client().await;
+        client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(contents))
+            .send()
+            .await?;
+        Ok(())
+    }
+
     async fn client(&self) -> aws_sdk_s3::Client {
         let region_provider = RegionProviderChain::first_try(self.region.clone())
             .or_default_provider()
@@ -171,50 +218,45 @@ impl VaultKeyStorage for S3VaultKeyStorage {
 
     fn set_vault_key_for(&self, storage_id: StorageId, key: KeyPair) -> Result<(), Self::Error> {
         self.runtime.block_on(async {
-            let client = self.client().await;
             let key = key.to_bytes()?;
-            client
-                .put_object()
-                .bucket(&self.bucket)
-                .key(self.path_for_id(storage_id))
-                .body(ByteStream::from(key.to_vec()))
-                .send()
-                .await?;
-            Ok(())
+            self.put_object(self.path_for_id(storage_id), key.to_vec())
+                .await
         })
     }
 
     fn vault_key_for(&self, storage_id: StorageId) -> Result<Option<KeyPair>, Self::Error> {
         self.runtime.block_on(async {
-            let client = self.client().await;
-            match client
-                .get_object()
-                .bucket(&self.bucket)
-                .key(self.path_for_id(storage_id))
-                .send()
-                .await
-            {
-                Ok(response) => {
-                    let bytes = response.body.collect().await?.into_bytes();
+            match self.get_object(self.path_for_id(storage_id)).await? {
+                Some(bytes) => {
                     let key = KeyPair::from_bytes(&bytes)
                         .map_err(|err| anyhow::anyhow!(err.to_string()))?;
                     Ok(Some(key))
                 }
-                Err(aws_smithy_client::SdkError::ServiceError(err))
-                    if matches!(
-                        err.err(),
-                        aws_sdk_s3::error::GetObjectError {
-                            kind: aws_sdk_s3::error::GetObjectErrorKind::NoSuchKey(_),
-                            ..
-                        }
-                    ) =>
-                {
-                    Ok(None)
-                }
-                Err(err) => Err(anyhow::anyhow!(err)),
+                None => Ok(None),
             }
         })
     }
+
+    fn set_encryption_key_for(
+        &self,
+        storage_id: StorageId,
+        name: &str,
+        key: &[u8],
+    ) -> Result<(), OptionalOperationError<Self::Error>> {
+        Ok(self.runtime.block_on(
+            self.put_object(self.path_for_encryption_key(storage_id, name), key.to_vec()),
+        )?)
+    }
+
+    fn encryption_key_for(
+        &self,
+        storage_id: StorageId,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, OptionalOperationError<Self::Error>> {
+        Ok(self
+            .runtime
+            .block_on(self.get_object(self.path_for_encryption_key(storage_id, name)))?)
+    }
 }
 
 #[cfg(test)]
@@ -3,14 +3,15 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use bonsaidb_core::connection::{
     self, AccessPolicy, AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection,
-    Connection, HasSchema, HasSession, IdentityReference, LowLevelConnection, Range,
-    SerializedQueryKey, Session, Sort, StorageConnection,
+    Connection, DatabaseStats, HasSchema, HasSession, IdentityReference, LowLevelConnection, Range,
+    SerializedQueryKey, Session, Sort, StorageConnection, StorageStats,
 };
 use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::keyvalue::{AsyncKeyValue, KeyOperation, KeyValue, Output};
 use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::pubsub::{self, AsyncPubSub, AsyncSubscriber, PubSub, Receiver};
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
+use bonsaidb_core::schema::view::{QuarantinedDocument, ViewStatus};
 use bonsaidb_core::schema::{
     self, CollectionName, Nameable, Schema, SchemaName, SchemaSummary, Schematic, ViewName,
 };
@@ -143,6 +144,72 @@ impl AsyncStorage {
             .await?
     }
 
+    /// Restores the data from a previously stored backup `location` as it was
+    /// at `point`.
+    ///
+    /// See [`Storage::restore_to()`] for more information.
+    pub async fn restore_to<L: AnyBackupLocation + 'static>(
+        &self,
+        location: L,
+        point: crate::RestorePoint,
+    ) -> Result<(), Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.restore_to(&location, point))
+            .await?
+    }
+
+    /// Restores the data from a previously stored backup `location` using
+    /// `options`.
+    ///
+    /// See [`Storage::restore_with_options()`] for more information.
+    pub async fn restore_with_options<L: AnyBackupLocation + 'static>(
+        &self,
+        location: L,
+        options: crate::RestoreOptions,
+    ) -> Result<(), Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.restore_with_options(&location, options))
+            .await?
+    }
+
+    /// Verifies that the backup stored in `location` is complete and readable
+    /// without restoring it.
+    ///
+    /// See [`Storage::verify_backup()`] for more information.
+    pub async fn verify_backup<L: AnyBackupLocation + 'static>(
+        &self,
+        location: L,
+    ) -> Result<crate::BackupVerification, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.verify_backup(&location))
+            .await?
+    }
+
+    /// Checks the consistency of all data stored in this storage, returning
+    /// the problems found.
+    ///
+    /// See [`Storage::check_integrity()`] for more information.
+    pub async fn check_integrity(&self) -> Result<crate::IntegrityReport, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.check_integrity())
+            .await?
+    }
+
+    /// Checks the consistency of all data stored in this storage and repairs
+    /// the problems that can be repaired.
+    ///
+    /// See [`Storage::repair_integrity()`] for more information.
+    pub async fn repair_integrity(&self) -> Result<crate::IntegrityReport, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.repair_integrity())
+            .await?
+    }
+
     /// Stores a copy of all data in this instance to `location`.
     pub async fn backup<L: AnyBackupLocation + 'static>(&self, location: L) -> Result<(), Error> {
         let task_self = self.clone();
@@ -151,6 +218,108 @@ impl AsyncStorage {
             .await?
     }
 
+    /// Stores a copy of the databases and collections matching `filter` to
+    /// `location`.
+    ///
+    /// See [`Storage::backup_with_filter()`] for more information.
+    pub async fn backup_with_filter<L: AnyBackupLocation + 'static>(
+        &self,
+        location: L,
+        filter: crate::BackupFilter,
+    ) -> Result<(), Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.backup_with_filter(&location, &filter))
+            .await?
+    }
+
+    /// Stores the changes made since the last backup to `location`.
+    ///
+    /// See [`Storage::backup_incremental()`] for more information.
+    pub async fn backup_incremental<L: AnyBackupLocation + 'static>(
+        &self,
+        location: L,
+    ) -> Result<(), Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.backup_incremental(&location))
+            .await?
+    }
+
+    /// Stores the changes made since the last backup to the databases and
+    /// collections matching `filter` to `location`.
+    ///
+    /// See [`Storage::backup_incremental_with_filter()`] for more information.
+    pub async fn backup_incremental_with_filter<L: AnyBackupLocation + 'static>(
+        &self,
+        location: L,
+        filter: crate::BackupFilter,
+    ) -> Result<(), Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self
+                    .storage
+                    .backup_incremental_with_filter(&location, &filter)
+            })
+            .await?
+    }
+
+    /// Generates a new master key and begins re-encrypting all data stored
+    /// using the master key in the background. Returns the id of the new
+    /// master key.
+    ///
+    /// See [`Storage::rotate_master_key()`] for more information.
+    #[cfg(feature = "encryption")]
+    pub async fn rotate_master_key(&self) -> Result<u32, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.rotate_master_key())
+            .await?
+    }
+
+    /// Copies the database `name` into a new database named
+    /// `destination_name` stored in `destination`.
+    ///
+    /// See [`Storage::copy_database_to()`] for more information.
+    pub async fn copy_database_to(
+        &self,
+        name: &str,
+        destination: &AsyncStorage,
+        destination_name: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let name = name.to_owned();
+        let destination = destination.storage.clone();
+        let destination_name = destination_name.to_owned();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self
+                    .storage
+                    .copy_database_to(&name, &destination, &destination_name)
+            })
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Removes all master keys except the current one.
+    ///
+    /// See [`Storage::retire_master_keys()`] for more information.
+    #[cfg(feature = "encryption")]
+    pub async fn retire_master_keys(&self) -> Result<(), Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.retire_master_keys())
+            .await?
+    }
+
+    /// Returns the progress of re-encrypting the storage after its master key
+    /// was rotated, or `None` if no re-encryption is in progress.
+    #[cfg(feature = "encryption")]
+    pub fn reencryption_status(&self) -> Result<Option<crate::ReencryptionStatus>, Error> {
+        self.storage.reencryption_status()
+    }
+
     /// Restricts an unauthenticated instance to having `effective_permissions`.
     /// Returns `None` if a session has already been established.
     #[must_use]
@@ -301,6 +470,55 @@ impl AsyncDatabase {
             })
     }
 
+    /// Returns the indexing status of each view in this database.
+    pub async fn view_statuses(&self) -> Result<Vec<ViewStatus>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.view_statuses())
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Returns the indexing status of `view`.
+    pub async fn view_status(&self, view: &ViewName) -> Result<ViewStatus, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let view = view.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.view_status(&view))
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Returns the documents that failed to map in `view`.
+    ///
+    /// See [`Database::quarantined_documents()`] for more information.
+    pub async fn quarantined_documents(
+        &self,
+        view: &ViewName,
+    ) -> Result<Vec<QuarantinedDocument>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let view = view.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.quarantined_documents(&view))
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Begins updating the indexes of `views` in the background. If `views`
+    /// is `None`, all views in this database will be updated.
+    ///
+    /// See [`Database::reindex_views()`] for more information.
+    pub async fn reindex_views(
+        &self,
+        views: Option<Vec<ViewName>>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.reindex_views(views.as_deref()))
+            .await
+            .map_err(Error::from)?
+    }
+
     /// Converts this instance into its blocking version, which is able to be
     /// used without async.
     #[must_use]
@@ -409,6 +627,34 @@ impl AsyncStorageConnection for AsyncStorage {
             .map_err(Error::from)?
     }
 
+    async fn rename_database(
+        &self,
+        name: &str,
+        new_name: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let name = name.to_owned();
+        let new_name = new_name.to_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.rename_database(&name, &new_name))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn copy_database(
+        &self,
+        name: &str,
+        destination: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let name = name.to_owned();
+        let destination = destination.to_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.copy_database(&name, &destination))
+            .await
+            .map_err(Error::from)?
+    }
+
     async fn list_databases(&self) -> Result<Vec<connection::Database>, bonsaidb_core::Error> {
         let task_self = self.clone();
         self.runtime
@@ -425,6 +671,14 @@ impl AsyncStorageConnection for AsyncStorage {
             .map_err(Error::from)?
     }
 
+    async fn stats(&self) -> Result<StorageStats, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || StorageConnection::stats(&task_self.storage))
+            .await
+            .map_err(Error::from)?
+    }
+
     async fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         let task_self = self.clone();
         let username = username.to_owned();
@@ -639,6 +893,14 @@ impl AsyncConnection for AsyncDatabase {
             .await
             .map_err(Error::from)?
     }
+
+    async fn stats(&self) -> Result<DatabaseStats, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || Connection::stats(&task_self.database))
+            .await
+            .map_err(Error::from)?
+    }
 }
 
 #[async_trait]
@@ -1,39 +1,253 @@
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use clap::Subcommand;
+use bonsaidb_core::connection::{Connection, DatabaseStats, StorageConnection, TreeStats};
+use bonsaidb_core::schema::{CollectionName, SchemaName};
+use clap::{Args, Subcommand};
 
 use crate::config::StorageConfiguration;
-use crate::{Error, Storage};
+#[cfg(feature = "encryption")]
+use crate::ArchiveEncryption;
+use crate::{
+    ArchiveCompression, ArchiveError, ArchiveOptions, BackupArchive, BackupFilter,
+    BackupVerification, Error, IntegrityReport, RestoreOptions, Storage,
+};
 
 /// Commands for administering the bonsaidb server.
 pub mod admin;
 /// Commands for querying the schemas.
 pub mod schema;
+/// Commands for inspecting and re-indexing views.
+pub mod views;
 
 /// Commands operating on local database storage.
 #[derive(Subcommand, Debug)]
 pub enum StorageCommand {
     /// Back up the storage.
-    #[clap(subcommand)]
-    Backup(Location),
+    Backup {
+        /// Limits which databases and collections are backed up.
+        #[clap(flatten)]
+        filter: FilterArgs,
+        /// The location to store the backup in.
+        #[clap(subcommand)]
+        location: Location,
+    },
+    /// Back up the changes made to the storage since the last backup.
+    IncrementalBackup {
+        /// Limits which databases and collections are backed up.
+        #[clap(flatten)]
+        filter: FilterArgs,
+        /// The location to store the backup in.
+        #[clap(subcommand)]
+        location: Location,
+    },
     /// Restore the storage from backup.
+    Restore {
+        /// Limits which databases and collections are restored.
+        #[clap(flatten)]
+        filter: FilterArgs,
+        /// Restores a database using a different name than it was backed up
+        /// with. May be provided multiple times.
+        #[clap(long = "rename", value_name = "SOURCE=DESTINATION")]
+        renames: Vec<DatabaseRename>,
+        /// The location to restore the backup from.
+        #[clap(subcommand)]
+        location: Location,
+    },
+    /// Verifies that a backup is complete and readable without restoring it.
     #[clap(subcommand)]
-    Restore(Location),
+    Verify(Location),
+    /// Checks the consistency of the data stored in the storage.
+    Fsck {
+        /// If this flag is provided, problems that can be repaired will be.
+        #[clap(long)]
+        repair: bool,
+    },
+    /// Reports how much data is stored and how much disk space can be
+    /// reclaimed by compacting.
+    Stats {
+        /// Limits the report to a single database.
+        database: Option<String>,
+        /// If this flag is provided, the statistics of each tree are listed.
+        #[clap(long)]
+        trees: bool,
+    },
+    /// Renames a database.
+    Rename {
+        /// The name of the database to rename.
+        name: String,
+        /// The new name of the database.
+        new_name: String,
+    },
+    /// Copies a database into a new database.
+    Copy {
+        /// The name of the database to copy.
+        name: String,
+        /// The name of the database to create.
+        destination: String,
+    },
+    /// Exports documents as newline-delimited JSON.
+    Export {
+        /// The name of the database to export.
+        database: String,
+        /// Limits the export to a single collection.
+        #[clap(long)]
+        collection: Option<CollectionName>,
+        /// The file to write the documents to. If not provided, the documents
+        /// are written to stdout.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Imports documents from newline-delimited JSON.
+    Import {
+        /// The name of the database to import into.
+        database: String,
+        /// The file to read the documents from. If not provided, the documents
+        /// are read from stdin.
+        #[clap(long, short)]
+        input: Option<PathBuf>,
+    },
     /// Executes an admin command.
     #[clap(subcommand)]
     Admin(admin::Command),
     /// Executes a schema query.
     Schema(schema::Command),
+    /// Inspects or re-indexes views.
+    #[clap(subcommand)]
+    Views(views::Command),
+    /// Generates a new master key and re-encrypts all data stored using the
+    /// master key in the background.
+    #[cfg(feature = "encryption")]
+    RotateMasterKey {
+        /// If this flag is provided, the progress will be reported until all
+        /// data has been re-encrypted.
+        #[clap(long)]
+        wait: bool,
+    },
 }
 
 /// A backup location.
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum Location {
     /// A filesystem-based backup location.
     Path {
         /// The path to the backup directory.
         path: PathBuf,
     },
+    /// A single-file archive backup location.
+    Archive {
+        /// The path to the archive file.
+        path: PathBuf,
+        /// The options for the archive.
+        #[clap(flatten)]
+        options: ArchiveArgs,
+    },
+}
+
+/// Options for an archive backup location.
+#[derive(Args, Debug, Clone)]
+pub struct ArchiveArgs {
+    /// The compression to use when creating the archive: `none`, `lz4`,
+    /// `zstd`, or `zstd:<level>`.
+    #[clap(long, default_value = "none")]
+    pub compression: ArchiveCompression,
+    /// If this flag is provided, the archive is encrypted using a passphrase
+    /// read from stdin.
+    #[cfg(all(feature = "encryption", feature = "password-hashing"))]
+    #[clap(long)]
+    pub passphrase: bool,
+    /// Encrypts the archive using this named key from the storage's vault.
+    #[cfg(feature = "encryption")]
+    #[clap(long)]
+    pub encryption_key: Option<String>,
+}
+
+impl ArchiveArgs {
+    /// Returns the [`ArchiveOptions`] for these arguments. If a passphrase is
+    /// being read and `creating` is true, the passphrase must be entered
+    /// twice.
+    #[cfg_attr(
+        not(all(feature = "encryption", feature = "password-hashing")),
+        allow(unused_variables)
+    )]
+    pub fn options(&self, storage: &Storage, creating: bool) -> Result<ArchiveOptions, Error> {
+        #[allow(unused_mut)]
+        let mut options = ArchiveOptions::default().compression(self.compression);
+        #[cfg(all(feature = "encryption", feature = "password-hashing"))]
+        if self.passphrase {
+            let passphrase = read_password_from_stdin(creating)?;
+            options = options.encryption(ArchiveEncryption::Passphrase(passphrase));
+        }
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.encryption_key {
+            options = options.encryption(ArchiveEncryption::VaultKey {
+                storage: storage.clone(),
+                key: bonsaidb_core::document::KeyId::Id(key.clone().into()),
+            });
+        }
+        Ok(options)
+    }
+}
+
+/// Arguments limiting which databases and collections are backed up or
+/// restored. Each argument may be provided multiple times. When no arguments
+/// are provided, everything is included.
+#[derive(Args, Debug, Clone, Default)]
+pub struct FilterArgs {
+    /// Only includes the database with this name.
+    #[clap(long = "database", value_name = "NAME")]
+    pub databases: Vec<String>,
+    /// Only includes databases using the schema with this name.
+    #[clap(long = "schema", value_name = "SCHEMA")]
+    pub schemas: Vec<SchemaName>,
+    /// Only includes the collection with this name.
+    #[clap(long = "collection", value_name = "COLLECTION")]
+    pub collections: Vec<CollectionName>,
+}
+
+impl FilterArgs {
+    /// Returns the [`BackupFilter`] for these arguments.
+    pub fn filter(&self) -> BackupFilter {
+        let mut filter = BackupFilter::default();
+        for database in &self.databases {
+            filter = filter.database(database.clone());
+        }
+        for schema in &self.schemas {
+            filter = filter.schema(schema.clone());
+        }
+        for collection in &self.collections {
+            filter = filter.collection(collection.clone());
+        }
+        filter
+    }
+}
+
+/// A database to restore using a different name, parsed from
+/// `<source>=<destination>`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DatabaseRename {
+    /// The name the database was backed up with.
+    pub source: String,
+    /// The name to restore the database as.
+    pub destination: String,
+}
+
+impl FromStr for DatabaseRename {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((source, destination)) if !source.is_empty() && !destination.is_empty() => {
+                Ok(Self {
+                    source: source.to_string(),
+                    destination: destination.to_string(),
+                })
+            }
+            _ => Err(format!("expected <source>=<destination>, found {s:?}")),
+        }
+    }
 }
 
 impl StorageCommand {
@@ -46,10 +260,41 @@ impl StorageCommand {
     /// Executes the command on `storage`.
     pub fn execute_on(self, storage: &Storage) -> Result<(), Error> {
         match self {
-            StorageCommand::Backup(location) => location.backup(storage),
-            StorageCommand::Restore(location) => location.restore(storage),
+            StorageCommand::Backup { filter, location } => {
+                location.backup_with_filter(storage, &filter.filter())
+            }
+            StorageCommand::IncrementalBackup { filter, location } => {
+                location.backup_incremental_with_filter(storage, &filter.filter())
+            }
+            StorageCommand::Restore {
+                filter,
+                renames,
+                location,
+            } => location.restore_with_options(storage, restore_options(&filter, renames)),
+            StorageCommand::Verify(location) => report_verification(&location.verify(storage)?),
+            StorageCommand::Fsck { repair } => fsck(storage, repair),
+            StorageCommand::Stats { database, trees } => stats(storage, database, trees),
+            StorageCommand::Rename { name, new_name } => {
+                storage.rename_database(&name, &new_name)?;
+                Ok(())
+            }
+            StorageCommand::Copy { name, destination } => {
+                storage.copy_database(&name, &destination)?;
+                Ok(())
+            }
+            StorageCommand::Export {
+                database,
+                collection,
+                output,
+            } => export(storage, &database, collection.as_ref(), output.as_deref()),
+            StorageCommand::Import { database, input } => {
+                import(storage, &database, input.as_deref())
+            }
             StorageCommand::Admin(admin) => admin.execute(storage),
             StorageCommand::Schema(schema) => schema.execute(storage),
+            StorageCommand::Views(views) => views.execute(storage),
+            #[cfg(feature = "encryption")]
+            StorageCommand::RotateMasterKey { wait } => rotate_master_key(storage, wait),
         }
     }
 
@@ -57,44 +302,489 @@ impl StorageCommand {
     #[cfg(feature = "async")]
     pub async fn execute_on_async(self, storage: &crate::AsyncStorage) -> Result<(), Error> {
         match self {
-            StorageCommand::Backup(location) => location.backup_async(storage).await,
-            StorageCommand::Restore(location) => location.restore_async(storage).await,
+            StorageCommand::Backup { filter, location } => {
+                location
+                    .backup_with_filter_async(storage, filter.filter())
+                    .await
+            }
+            StorageCommand::IncrementalBackup { filter, location } => {
+                location
+                    .backup_incremental_with_filter_async(storage, filter.filter())
+                    .await
+            }
+            StorageCommand::Restore {
+                filter,
+                renames,
+                location,
+            } => {
+                location
+                    .restore_with_options_async(storage, restore_options(&filter, renames))
+                    .await
+            }
+            StorageCommand::Verify(location) => {
+                report_verification(&location.verify_async(storage).await?)
+            }
+            StorageCommand::Fsck { repair } => {
+                let task_storage = storage.to_blocking();
+                storage
+                    .runtime
+                    .spawn_blocking(move || fsck(&task_storage, repair))
+                    .await?
+            }
+            StorageCommand::Stats { database, trees } => {
+                let task_storage = storage.to_blocking();
+                storage
+                    .runtime
+                    .spawn_blocking(move || stats(&task_storage, database, trees))
+                    .await?
+            }
+            StorageCommand::Rename { name, new_name } => {
+                let task_storage = storage.to_blocking();
+                storage
+                    .runtime
+                    .spawn_blocking(move || task_storage.rename_database(&name, &new_name))
+                    .await??;
+                Ok(())
+            }
+            StorageCommand::Copy { name, destination } => {
+                let task_storage = storage.to_blocking();
+                storage
+                    .runtime
+                    .spawn_blocking(move || task_storage.copy_database(&name, &destination))
+                    .await??;
+                Ok(())
+            }
+            StorageCommand::Export {
+                database,
+                collection,
+                output,
+            } => {
+                let task_storage = storage.to_blocking();
+                storage
+                    .runtime
+                    .spawn_blocking(move || {
+                        export(
+                            &task_storage,
+                            &database,
+                            collection.as_ref(),
+                            output.as_deref(),
+                        )
+                    })
+                    .await?
+            }
+            StorageCommand::Import { database, input } => {
+                let task_storage = storage.to_blocking();
+                storage
+                    .runtime
+                    .spawn_blocking(move || import(&task_storage, &database, input.as_deref()))
+                    .await?
+            }
             StorageCommand::Admin(admin) => admin.execute_async(storage).await,
             StorageCommand::Schema(schema) => schema.execute_async(storage).await,
+            StorageCommand::Views(views) => views.execute_async(storage).await,
+            #[cfg(feature = "encryption")]
+            StorageCommand::RotateMasterKey { wait } => {
+                let task_storage = storage.to_blocking();
+                storage
+                    .runtime
+                    .spawn_blocking(move || rotate_master_key(&task_storage, wait))
+                    .await?
+            }
+        }
+    }
+}
+
+fn export(
+    storage: &Storage,
+    database: &str,
+    collection: Option<&CollectionName>,
+    output: Option<&Path>,
+) -> Result<(), Error> {
+    let database = storage.database_without_schema(database)?;
+    let exported = if let Some(output) = output {
+        database.export_ndjson(collection, BufWriter::new(File::create(output)?))?
+    } else {
+        database.export_ndjson(collection, BufWriter::new(std::io::stdout().lock()))?
+    };
+    // stdout may be receiving the exported documents.
+    eprintln!("Exported {exported} documents");
+    Ok(())
+}
+
+fn import(storage: &Storage, database: &str, input: Option<&Path>) -> Result<(), Error> {
+    let database = storage.database_without_schema(database)?;
+    let imported = if let Some(input) = input {
+        database.import_ndjson(BufReader::new(File::open(input)?))?
+    } else {
+        database.import_ndjson(std::io::stdin().lock())?
+    };
+    eprintln!("Imported {imported} documents");
+    Ok(())
+}
+
+fn restore_options(filter: &FilterArgs, renames: Vec<DatabaseRename>) -> RestoreOptions {
+    let mut options = RestoreOptions::default().filter(filter.filter());
+    for rename in renames {
+        options = options.rename_database(rename.source, rename.destination);
+    }
+    options
+}
+
+fn report_verification(verification: &BackupVerification) -> Result<(), Error> {
+    println!(
+        "Verified {} databases, {} documents, {} key-value entries, and {} incremental backups",
+        verification.databases_verified,
+        verification.documents_verified,
+        verification.key_value_entries_verified,
+        verification.increments_verified
+    );
+    for problem in &verification.problems {
+        println!("{problem}");
+    }
+
+    if verification.is_valid() {
+        Ok(())
+    } else {
+        Err(Error::other(
+            "backup verification",
+            format!("{} problems found", verification.problems.len()),
+        ))
+    }
+}
+
+fn fsck(storage: &Storage, repair: bool) -> Result<(), Error> {
+    let report = if repair {
+        storage.repair_integrity()?
+    } else {
+        storage.check_integrity()?
+    };
+    report_integrity(&report)
+}
+
+fn report_integrity(report: &IntegrityReport) -> Result<(), Error> {
+    println!(
+        "Checked {} databases, {} documents, and {} views",
+        report.databases_checked, report.documents_checked, report.views_checked
+    );
+    for problem in &report.problems {
+        println!("{problem}");
+    }
+
+    if report.is_consistent() {
+        Ok(())
+    } else {
+        let unrepaired = report
+            .problems
+            .iter()
+            .filter(|problem| !problem.repaired)
+            .count();
+        Err(Error::other(
+            "integrity check",
+            format!("{unrepaired} problems found"),
+        ))
+    }
+}
+
+fn stats(storage: &Storage, database: Option<String>, trees: bool) -> Result<(), Error> {
+    let (databases, caches) = if let Some(database) = database {
+        let stats = storage
+            .instance
+            .database_without_schema(&database, Some(storage), None)?
+            .stats()?;
+        (vec![stats], Vec::new())
+    } else {
+        let stats = storage.stats()?;
+        (stats.databases, stats.caches)
+    };
+    for database in &databases {
+        report_database_stats(database, trees);
+    }
+    for cache in &caches {
+        println!(
+            "cache for {}: {} bytes, {} chunks",
+            cache.database.as_deref().unwrap_or("other databases"),
+            cache.budget_bytes,
+            cache.capacity
+        );
+    }
+    Ok(())
+}
+
+fn report_database_stats(database: &DatabaseStats, trees: bool) {
+    println!(
+        "{}: {} bytes on disk, {} bytes reclaimable, {} byte transaction log",
+        database.name,
+        database.file_bytes(),
+        database.wasted_bytes(),
+        database.transaction_log_bytes
+    );
+    for collection in &database.collections {
+        println!(
+            "  collection {}: {} documents, {} bytes on disk, {} bytes reclaimable",
+            collection.collection,
+            collection.documents,
+            collection.file_bytes(),
+            collection.wasted_bytes()
+        );
+        for view in &collection.views {
+            println!("    view {}: {} entries", view.view, view.entries);
+        }
+        if trees {
+            for tree in collection.trees() {
+                report_tree_stats(tree);
+            }
         }
     }
+    println!(
+        "  key-value store: {} keys, {} bytes on disk, {} bytes reclaimable",
+        database.key_value.keys,
+        database.key_value.tree.file_bytes,
+        database.key_value.tree.wasted_bytes()
+    );
+    if trees {
+        report_tree_stats(&database.key_value.tree);
+    }
+}
+
+fn report_tree_stats(tree: &TreeStats) {
+    println!(
+        "    tree {}: {} entries, {} bytes on disk, {} live bytes ({:.1}% reclaimable)",
+        tree.name,
+        tree.entries,
+        tree.file_bytes,
+        tree.live_bytes,
+        tree.waste_ratio() * 100.
+    );
+}
+
+#[cfg(feature = "encryption")]
+fn rotate_master_key(storage: &Storage, wait: bool) -> Result<(), Error> {
+    let master_key_id = storage.rotate_master_key()?;
+    println!("Rotated to master key {master_key_id}");
+    if wait {
+        while let Some(status) = storage.reencryption_status()? {
+            println!(
+                "Re-encrypted {} of {} databases",
+                status.databases_reencrypted, status.total_databases
+            );
+            if status.databases_reencrypted + status.databases_skipped >= status.total_databases {
+                println!(
+                    "Skipped {} databases that could not be opened",
+                    status.databases_skipped
+                );
+                return Ok(());
+            }
+            std::thread::sleep(std::time::Duration::from_secs(1));
+        }
+        println!("Re-encryption complete");
+    }
+    Ok(())
 }
 
 impl Location {
     /// Backs-up `storage` to `self`.
     pub fn backup(&self, storage: &Storage) -> Result<(), Error> {
+        self.backup_with_filter(storage, &BackupFilter::default())
+    }
+
+    /// Backs-up the databases and collections in `storage` matching `filter`
+    /// to `self`.
+    pub fn backup_with_filter(
+        &self,
+        storage: &Storage,
+        filter: &BackupFilter,
+    ) -> Result<(), Error> {
         match self {
-            Location::Path { path } => storage.backup(path),
+            Location::Path { path } => storage.backup_with_filter(path, filter),
+            Location::Archive { path, options } => {
+                let archive = BackupArchive::create(path, options.options(storage, true)?)
+                    .map_err(|err| Error::Backup(Box::new(err)))?;
+                storage.backup_with_filter(&archive, filter)?;
+                archive.finish().map_err(|err| Error::Backup(Box::new(err)))
+            }
+        }
+    }
+
+    /// Backs-up the changes made to `storage` since the last backup to `self`.
+    pub fn backup_incremental(&self, storage: &Storage) -> Result<(), Error> {
+        self.backup_incremental_with_filter(storage, &BackupFilter::default())
+    }
+
+    /// Backs-up the changes made to the databases and collections in
+    /// `storage` matching `filter` since the last backup to `self`.
+    pub fn backup_incremental_with_filter(
+        &self,
+        storage: &Storage,
+        filter: &BackupFilter,
+    ) -> Result<(), Error> {
+        match self {
+            Location::Path { path } => storage.backup_incremental_with_filter(path, filter),
+            Location::Archive { path, options } => {
+                let archive = BackupArchive::open(path, options.options(storage, false)?)
+                    .map_err(|err| Error::Backup(Box::new(err)))?;
+                storage.backup_incremental_with_filter(&archive, filter)?;
+                archive.finish().map_err(|err| Error::Backup(Box::new(err)))
+            }
         }
     }
 
     /// Restores `storage` from `self`.
     pub fn restore(&self, storage: &Storage) -> Result<(), Error> {
+        self.restore_with_options(storage, RestoreOptions::default())
+    }
+
+    /// Restores `storage` from `self` using `options`.
+    pub fn restore_with_options(
+        &self,
+        storage: &Storage,
+        options: RestoreOptions,
+    ) -> Result<(), Error> {
+        match self {
+            Location::Path { path } => storage.restore_with_options(path, options),
+            Location::Archive {
+                path,
+                options: args,
+            } => {
+                let archive = BackupArchive::open(path, args.options(storage, false)?)
+                    .map_err(|err| Error::Backup(Box::new(err)))?;
+                storage.restore_with_options(&archive, options)
+            }
+        }
+    }
+
+    /// Verifies that the backup stored in `self` is complete and readable
+    /// using the schemas registered with `storage`.
+    pub fn verify(&self, storage: &Storage) -> Result<BackupVerification, Error> {
         match self {
-            Location::Path { path } => storage.restore(path),
+            Location::Path { path } => storage.verify_backup(path),
+            Location::Archive { path, options } => {
+                // Opening an archive that doesn't exist creates it.
+                if !path.exists() {
+                    return Err(Error::Backup(Box::new(ArchiveError::NotFound(
+                        path.display().to_string(),
+                    ))));
+                }
+                let archive = BackupArchive::open(path, options.options(storage, false)?)
+                    .map_err(|err| Error::Backup(Box::new(err)))?;
+                storage.verify_backup(&archive)
+            }
         }
     }
 
     /// Backs-up `storage` to `self`.
     #[cfg(feature = "async")]
     pub async fn backup_async(&self, storage: &crate::AsyncStorage) -> Result<(), Error> {
+        self.backup_with_filter_async(storage, BackupFilter::default())
+            .await
+    }
+
+    /// Backs-up the databases and collections in `storage` matching `filter`
+    /// to `self`.
+    #[cfg(feature = "async")]
+    pub async fn backup_with_filter_async(
+        &self,
+        storage: &crate::AsyncStorage,
+        filter: BackupFilter,
+    ) -> Result<(), Error> {
+        match self {
+            Location::Path { path } => storage.backup_with_filter(path.clone(), filter).await,
+            Location::Archive { .. } => {
+                self.execute_blocking(storage, move |location, storage| {
+                    location.backup_with_filter(storage, &filter)
+                })
+                .await
+            }
+        }
+    }
+
+    /// Backs-up the changes made to `storage` since the last backup to `self`.
+    #[cfg(feature = "async")]
+    pub async fn backup_incremental_async(
+        &self,
+        storage: &crate::AsyncStorage,
+    ) -> Result<(), Error> {
+        self.backup_incremental_with_filter_async(storage, BackupFilter::default())
+            .await
+    }
+
+    /// Backs-up the changes made to the databases and collections in
+    /// `storage` matching `filter` since the last backup to `self`.
+    #[cfg(feature = "async")]
+    pub async fn backup_incremental_with_filter_async(
+        &self,
+        storage: &crate::AsyncStorage,
+        filter: BackupFilter,
+    ) -> Result<(), Error> {
         match self {
-            Location::Path { path } => storage.backup(path.clone()).await,
+            Location::Path { path } => {
+                storage
+                    .backup_incremental_with_filter(path.clone(), filter)
+                    .await
+            }
+            Location::Archive { .. } => {
+                self.execute_blocking(storage, move |location, storage| {
+                    location.backup_incremental_with_filter(storage, &filter)
+                })
+                .await
+            }
         }
     }
 
     /// Restores `storage` from `self`.
     #[cfg(feature = "async")]
     pub async fn restore_async(&self, storage: &crate::AsyncStorage) -> Result<(), Error> {
+        self.restore_with_options_async(storage, RestoreOptions::default())
+            .await
+    }
+
+    /// Restores `storage` from `self` using `options`.
+    #[cfg(feature = "async")]
+    pub async fn restore_with_options_async(
+        &self,
+        storage: &crate::AsyncStorage,
+        options: RestoreOptions,
+    ) -> Result<(), Error> {
         match self {
-            Location::Path { path } => storage.restore(path.clone()).await,
+            Location::Path { path } => storage.restore_with_options(path.clone(), options).await,
+            Location::Archive { .. } => {
+                self.execute_blocking(storage, move |location, storage| {
+                    location.restore_with_options(storage, options)
+                })
+                .await
+            }
         }
     }
+
+    /// Verifies that the backup stored in `self` is complete and readable
+    /// using the schemas registered with `storage`.
+    #[cfg(feature = "async")]
+    pub async fn verify_async(
+        &self,
+        storage: &crate::AsyncStorage,
+    ) -> Result<BackupVerification, Error> {
+        match self {
+            Location::Path { path } => storage.verify_backup(path.clone()).await,
+            Location::Archive { .. } => self.execute_blocking(storage, Self::verify).await,
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn execute_blocking<T, F>(
+        &self,
+        storage: &crate::AsyncStorage,
+        operation: F,
+    ) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Self, &Storage) -> Result<T, Error> + Send + 'static,
+    {
+        let location = self.clone();
+        let task_storage = storage.to_blocking();
+        storage
+            .runtime
+            .spawn_blocking(move || operation(&location, &task_storage))
+            .await?
+    }
 }
 
 /// Reads a password from stdin, wrapping the result in a
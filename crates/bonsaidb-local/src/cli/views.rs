@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use bonsaidb_core::schema::view::ViewStatus;
+use bonsaidb_core::schema::ViewName;
+use clap::Subcommand;
+
+use crate::{Database, Storage};
+
+/// A command operating on the views of a database.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Lists the indexing status of each view in a database.
+    Status {
+        /// The name of the database.
+        database: String,
+    },
+    /// Begins re-indexing views in the background.
+    Reindex {
+        /// The name of the database.
+        database: String,
+        /// The views to re-index. If no views are provided, all views in the
+        /// database will be re-indexed.
+        views: Vec<ViewName>,
+        /// If this flag is provided, the progress will be reported until all
+        /// views have finished re-indexing.
+        #[clap(long)]
+        wait: bool,
+    },
+    /// Lists the documents that failed to map in a view.
+    Quarantine {
+        /// The name of the database.
+        database: String,
+        /// The view to list quarantined documents from.
+        view: ViewName,
+    },
+}
+
+impl Command {
+    /// Executes the command on `storage`.
+    pub fn execute(self, storage: &Storage) -> Result<(), crate::Error> {
+        match self {
+            Command::Status { database } => {
+                let database = open_database(storage, &database)?;
+                print_statuses(&database.view_statuses()?);
+            }
+            Command::Reindex {
+                database,
+                views,
+                wait,
+            } => {
+                let database = open_database(storage, &database)?;
+                let views = if views.is_empty() { None } else { Some(views) };
+                database.reindex_views(views.as_deref())?;
+                if wait {
+                    wait_for_views(&database, views.as_deref())?;
+                }
+            }
+            Command::Quarantine { database, view } => {
+                let database = open_database(storage, &database)?;
+                for document in database.quarantined_documents(&view)? {
+                    println!("{}: {}", document.id, document.error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes the command on `storage`.
+    #[cfg(feature = "async")]
+    pub async fn execute_async(self, storage: &crate::AsyncStorage) -> Result<(), crate::Error> {
+        let task_storage = storage.to_blocking();
+        storage
+            .runtime
+            .spawn_blocking(move || self.execute(&task_storage))
+            .await?
+    }
+}
+
+fn open_database(storage: &Storage, name: &str) -> Result<Database, crate::Error> {
+    storage
+        .instance
+        .database_without_schema(name, Some(storage), None)
+}
+
+fn wait_for_views(database: &Database, views: Option<&[ViewName]>) -> Result<(), crate::Error> {
+    loop {
+        let statuses = database
+            .view_statuses()?
+            .into_iter()
+            .filter(|status| views.map_or(true, |views| views.contains(&status.name)))
+            .collect::<Vec<_>>();
+        print_statuses(&statuses);
+        if statuses.iter().all(ViewStatus::is_up_to_date) {
+            return Ok(());
+        }
+
+        println!();
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn print_statuses(statuses: &[ViewStatus]) {
+    for status in statuses {
+        println!(
+            "{}: {} ({} invalidated documents)",
+            status.name, status.state, status.invalidated_documents
+        );
+    }
+}
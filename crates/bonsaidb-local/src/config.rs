@@ -6,9 +6,13 @@ use std::time::Duration;
 #[cfg(feature = "encryption")]
 use bonsaidb_core::document::KeyId;
 use bonsaidb_core::permissions::Permissions;
-use bonsaidb_core::schema::{Schema, SchemaName};
+use bonsaidb_core::schema::{CollectionName, Schema, SchemaName, SerializedCollection};
+use bonsaidb_core::transaction::Durability;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use sysinfo::{CpuRefreshKind, RefreshKind, System, SystemExt};
 
+use crate::database::json::{JsonCollection, SerializedJsonCollection};
 use crate::storage::{DatabaseOpener, StorageSchemaOpener};
 #[cfg(feature = "encryption")]
 use crate::vault::AnyVaultKeyStorage;
@@ -28,9 +32,30 @@ pub struct StorageConfiguration {
 
     /// Prevents storing data on the disk. This is intended for testing purposes
     /// primarily. Keep in mind that the underlying storage format is
-    /// append-only.
+    /// append-only. To keep the data between restarts, configure
+    /// [`snapshots`](Self#structfield.snapshots).
     pub memory_only: bool,
 
+    /// Periodically stores the data of a
+    /// [`memory_only`](Self#structfield.memory_only) storage to disk, and
+    /// restores it when the storage is opened. Ignored unless `memory_only` is
+    /// true. Default value is `None`.
+    pub snapshots: Option<Snapshots>,
+
+    /// Opens the storage for reading only. The storage must already exist.
+    ///
+    /// Operations that write data return
+    /// [`Error::ReadOnly`](bonsaidb_core::Error::ReadOnly), and views are never
+    /// updated, as if every query used
+    /// [`AccessPolicy::NoUpdate`](bonsaidb_core::connection::AccessPolicy::NoUpdate).
+    /// Any number of read-only instances can open the same storage at once,
+    /// but not while it is opened for writing: opening fails with
+    /// [`Error::StorageLocked`](crate::Error::StorageLocked) instead. This
+    /// makes it possible to query a copy or snapshot of a storage without
+    /// modifying it. Expired key-value entries are never removed from a
+    /// read-only storage, and are ignored when read.
+    pub read_only: bool,
+
     /// The unique id of the server. If not specified, the server will randomly
     /// generate a unique id on startup. If the server generated an id and this
     /// value is subsequently set, the generated id will be overridden by the
@@ -65,10 +90,57 @@ pub struct StorageConfiguration {
     /// Controls how the key-value store persists keys, on a per-database basis.
     pub key_value_persistence: KeyValuePersistence,
 
+    /// Configures the caches of recently read data.
+    pub cache: Cache,
+
+    /// Controls when databases are compacted automatically. When `None`,
+    /// databases are only compacted when requested. Default value is `None`.
+    pub compaction_policy: Option<CompactionPolicy>,
+
+    /// Quotas to enforce for specific databases, by name. A database listed
+    /// here ignores [`schema_quotas`](Self#structfield.schema_quotas).
+    pub database_quotas: HashMap<String, Quota>,
+
+    /// Quotas to enforce for every database using a schema.
+    pub schema_quotas: HashMap<SchemaName, Quota>,
+
+    /// The durability of transactions that do not specify one. Default value
+    /// is [`Durability::Full`].
+    pub durability: Durability,
+
+    /// Durabilities to use for specific databases, by name, instead of
+    /// [`durability`](Self#structfield.durability).
+    pub database_durability: HashMap<String, Durability>,
+
+    /// The longest [`Durability::GroupCommit`] window a transaction may
+    /// request using
+    /// [`Transaction::with_durability()`](bonsaidb_core::transaction::Transaction::with_durability).
+    /// Transactions requesting longer windows fail with
+    /// [`GroupCommitWindowTooLong`](bonsaidb_core::Error::GroupCommitWindowTooLong).
+    /// Windows configured using [`durability`](Self#structfield.durability)
+    /// or [`database_durability`](Self#structfield.database_durability) are
+    /// not limited. Default value is 1 second.
+    pub max_group_commit_window: Duration,
+
     /// Sets the default compression algorithm.
     #[cfg(feature = "compression")]
     pub default_compression: Option<Compression>,
 
+    /// Compression algorithms to use for specific collections instead of
+    /// [`default_compression`](Self#structfield.default_compression).
+    #[cfg(feature = "compression")]
+    pub collection_compression: HashMap<CollectionName, Compression>,
+
+    /// Additional dictionaries that can be used to read data compressed with
+    /// [`Compression::ZstdDictionary`]. Dictionaries used by
+    /// [`default_compression`](Self#structfield.default_compression) and
+    /// [`collection_compression`](Self#structfield.collection_compression)
+    /// are always available. When a collection's dictionary is replaced, the
+    /// previous dictionary must be added here for existing data to remain
+    /// readable.
+    #[cfg(all(feature = "compression", feature = "zstd"))]
+    pub compression_dictionaries: Vec<CompressionDictionary>,
+
     /// The permissions granted to authenticated connections to this server.
     pub authenticated_permissions: Permissions,
 
@@ -77,6 +149,7 @@ pub struct StorageConfiguration {
     pub argon: ArgonConfiguration,
 
     pub(crate) initial_schemas: HashMap<SchemaName, Arc<dyn DatabaseOpener>>,
+    pub(crate) json_collections: HashMap<CollectionName, Arc<dyn JsonCollection>>,
 }
 
 impl Default for StorageConfiguration {
@@ -89,6 +162,8 @@ impl Default for StorageConfiguration {
         Self {
             path: None,
             memory_only: false,
+            snapshots: None,
+            read_only: false,
             unique_id: None,
             #[cfg(feature = "encryption")]
             vault_key_storage: None,
@@ -96,13 +171,25 @@ impl Default for StorageConfiguration {
             default_encryption_key: None,
             #[cfg(feature = "compression")]
             default_compression: None,
+            #[cfg(feature = "compression")]
+            collection_compression: HashMap::default(),
+            #[cfg(all(feature = "compression", feature = "zstd"))]
+            compression_dictionaries: Vec::default(),
             workers: Tasks::default_for(&system),
             views: Views::default(),
             key_value_persistence: KeyValuePersistence::default(),
+            cache: Cache::default(),
+            compaction_policy: None,
+            database_quotas: HashMap::default(),
+            schema_quotas: HashMap::default(),
+            durability: Durability::default(),
+            database_durability: HashMap::default(),
+            max_group_commit_window: Duration::from_secs(1),
             authenticated_permissions: Permissions::default(),
             #[cfg(feature = "password-hashing")]
             argon: ArgonConfiguration::default_for(&system),
             initial_schemas: HashMap::default(),
+            json_collections: HashMap::default(),
         }
     }
 }
@@ -111,22 +198,38 @@ impl std::fmt::Debug for StorageConfiguration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut schemas = self.initial_schemas.keys().collect::<Vec<_>>();
         schemas.sort();
+        let mut json_collections = self.json_collections.keys().collect::<Vec<_>>();
+        json_collections.sort();
         let mut f = f.debug_struct("StorageConfiguration");
         f.field("path", &self.path)
             .field("memory_only", &self.memory_only)
+            .field("snapshots", &self.snapshots)
+            .field("read_only", &self.read_only)
             .field("unique_id", &self.unique_id)
             .field("workers", &self.workers)
             .field("views", &self.views)
             .field("key_value_persistence", &self.key_value_persistence)
+            .field("cache", &self.cache)
+            .field("compaction_policy", &self.compaction_policy)
+            .field("database_quotas", &self.database_quotas)
+            .field("schema_quotas", &self.schema_quotas)
+            .field("durability", &self.durability)
+            .field("database_durability", &self.database_durability)
+            .field("max_group_commit_window", &self.max_group_commit_window)
             .field("authenticated_permissions", &self.authenticated_permissions)
-            .field("initial_schemas", &schemas);
+            .field("initial_schemas", &schemas)
+            .field("json_collections", &json_collections);
 
         #[cfg(feature = "encryption")]
         f.field("vault_key_storage", &self.vault_key_storage)
             .field("default_encryption_key", &self.default_encryption_key);
 
         #[cfg(feature = "compression")]
-        f.field("default_compression", &self.default_compression);
+        f.field("default_compression", &self.default_compression)
+            .field("collection_compression", &self.collection_compression);
+
+        #[cfg(all(feature = "compression", feature = "zstd"))]
+        f.field("compression_dictionaries", &self.compression_dictionaries);
 
         #[cfg(feature = "password-hashing")]
         f.field("argon", &self.argon);
@@ -143,6 +246,21 @@ impl StorageConfiguration {
             .insert(S::schema_name(), Arc::new(StorageSchemaOpener::<S>::new()?));
         Ok(())
     }
+
+    /// Allows the documents of `C` to be exported to and imported from JSON.
+    /// Documents are converted using `C`'s [`SerializedCollection`]
+    /// implementation.
+    pub fn register_json_collection<C>(&mut self)
+    where
+        C: SerializedCollection + 'static,
+        C::Contents: Serialize + DeserializeOwned,
+        C::PrimaryKey: Serialize + DeserializeOwned,
+    {
+        self.json_collections.insert(
+            C::collection_name(),
+            Arc::new(SerializedJsonCollection::<C>::default()),
+        );
+    }
 }
 
 /// Configuration options for background tasks.
@@ -338,6 +456,318 @@ impl PersistenceThreshold {
     }
 }
 
+/// Rules for compacting databases automatically.
+///
+/// BonsaiDb's storage is append-only: updating or deleting data leaves the
+/// previous data in the file until the tree is compacted. When a policy is
+/// configured, a background thread periodically examines each tree of each
+/// database, and compacts the trees that meet any of the enabled triggers:
+///
+/// - [`waste_ratio`](Self::waste_ratio): the portion of the tree's file that
+///   no longer contains live data, as reported by
+///   [`TreeStats::waste_ratio()`](bonsaidb_core::connection::TreeStats::waste_ratio).
+/// - [`growth_ratio`](Self::growth_ratio): how much the tree's file has grown
+///   since it was last compacted.
+/// - [`schedule`](Self::schedule): the amount of time between compacting
+///   every tree, regardless of the other triggers.
+///
+/// Compactions are executed through the same background task queue as
+/// [`Connection::compact()`](bonsaidb_core::connection::Connection::compact),
+/// one tree at a time. After each tree is compacted, the policy waits for
+/// [`delay_between_trees`](Self::delay_between_trees) before compacting the
+/// next tree. The delay spaces compactions out, but does not limit the rate
+/// at which a single tree is read or written while it is compacted.
+///
+/// ```rust
+/// # use bonsaidb_local::config::CompactionPolicy;
+/// # use std::time::Duration;
+/// let policy = CompactionPolicy::default()
+///     .waste_ratio(0.25)
+///     .growth_ratio(2.)
+///     .schedule(Duration::from_secs(60 * 60 * 24));
+/// ```
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+#[non_exhaustive]
+pub struct CompactionPolicy {
+    /// How often the trees are examined. Default value is 5 minutes.
+    pub check_interval: Duration,
+    /// Files smaller than this number of bytes are never compacted by the
+    /// [`waste_ratio`](Self::waste_ratio) or
+    /// [`growth_ratio`](Self::growth_ratio) triggers. Default value is 1
+    /// megabyte.
+    pub minimum_file_size: u64,
+    /// Compacts a tree when at least this portion of its file no longer
+    /// contains live data. The tree's index is read to measure the size of its
+    /// current keys and values, which are compared against the size of its
+    /// file. The tree's internal structure is counted as waste, so this should
+    /// be well above 0. Default value is `Some(0.5)`.
+    pub waste_ratio: Option<f64>,
+    /// Compacts a tree when its file has grown by this factor since it was
+    /// last compacted, or since the storage was opened. For example, `2.0`
+    /// compacts a tree once its file has doubled in size. Default value is
+    /// `None`.
+    pub growth_ratio: Option<f64>,
+    /// Compacts every tree once this amount of time has elapsed since the
+    /// last scheduled compaction, or since the storage was opened. Default
+    /// value is `None`.
+    pub schedule: Option<Duration>,
+    /// The amount of time to wait after compacting a tree before compacting
+    /// the next one. Default value is 100 milliseconds.
+    pub delay_between_trees: Duration,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(5 * 60),
+            minimum_file_size: 1024 * 1024,
+            waste_ratio: Some(0.5),
+            growth_ratio: None,
+            schedule: None,
+            delay_between_trees: Duration::from_millis(100),
+        }
+    }
+}
+
+impl CompactionPolicy {
+    /// Sets [`Self::check_interval`](Self#structfield.check_interval) to `interval` and returns self.
+    pub const fn check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+
+    /// Sets [`Self::minimum_file_size`](Self#structfield.minimum_file_size) to `bytes` and returns self.
+    pub const fn minimum_file_size(mut self, bytes: u64) -> Self {
+        self.minimum_file_size = bytes;
+        self
+    }
+
+    /// Sets [`Self::waste_ratio`](Self#structfield.waste_ratio) to `ratio` and returns self.
+    pub const fn waste_ratio(mut self, ratio: f64) -> Self {
+        self.waste_ratio = Some(ratio);
+        self
+    }
+
+    /// Sets [`Self::growth_ratio`](Self#structfield.growth_ratio) to `ratio` and returns self.
+    pub const fn growth_ratio(mut self, ratio: f64) -> Self {
+        self.growth_ratio = Some(ratio);
+        self
+    }
+
+    /// Sets [`Self::schedule`](Self#structfield.schedule) to `interval` and returns self.
+    pub const fn schedule(mut self, interval: Duration) -> Self {
+        self.schedule = Some(interval);
+        self
+    }
+
+    /// Sets [`Self::delay_between_trees`](Self#structfield.delay_between_trees) to `delay` and returns self.
+    pub const fn delay_between_trees(mut self, delay: Duration) -> Self {
+        self.delay_between_trees = delay;
+        self
+    }
+
+    /// Disables the [`waste_ratio`](Self::waste_ratio) trigger and returns self.
+    pub const fn without_waste_ratio(mut self) -> Self {
+        self.waste_ratio = None;
+        self
+    }
+}
+
+/// Configures the caches of recently read chunks of data, which avoid reading
+/// frequently accessed data from disk again.
+///
+/// The [`budget`](Self::budget) is divided between the databases listed in
+/// [`database_weights`](Self::database_weights), which are each given their
+/// own cache, and a cache shared by every other database, which is weighted by
+/// [`default_weight`](Self::default_weight). For example, with a budget of
+/// 100 megabytes, a default weight of 1, and a single database weighted 3, the
+/// database is given a 75 megabyte cache and all other databases share a 25
+/// megabyte cache.
+///
+/// Each cache holds at most as many chunks as fit in its budget when every
+/// chunk is [`max_chunk_length`](Self::max_chunk_length) bytes long, so
+/// caches of smaller chunks use less memory than their budget.
+///
+/// ```rust
+/// # use bonsaidb_local::config::Cache;
+/// let cache = Cache::default()
+///     .budget(64 * 1024 * 1024)
+///     .database_weight("sessions", 2);
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+#[non_exhaustive]
+pub struct Cache {
+    /// The number of bytes all caches may use together. Default value is
+    /// 320,768,000 bytes.
+    pub budget: u64,
+    /// Chunks longer than this number of bytes are never cached. Default
+    /// value is 160,384 bytes.
+    pub max_chunk_length: usize,
+    /// The weight of the cache shared by the databases not listed in
+    /// [`database_weights`](Self::database_weights). Default value is 1.
+    pub default_weight: u32,
+    /// The weights of the databases that are given their own cache, by name.
+    pub database_weights: HashMap<String, u32>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            budget: 2000 * 160_384,
+            max_chunk_length: 160_384,
+            default_weight: 1,
+            database_weights: HashMap::default(),
+        }
+    }
+}
+
+impl Cache {
+    /// Sets [`Self::budget`](Self#structfield.budget) to `bytes` and returns self.
+    pub fn budget(mut self, bytes: u64) -> Self {
+        self.budget = bytes;
+        self
+    }
+
+    /// Sets [`Self::max_chunk_length`](Self#structfield.max_chunk_length) to `bytes` and returns self.
+    pub fn max_chunk_length(mut self, bytes: usize) -> Self {
+        self.max_chunk_length = bytes;
+        self
+    }
+
+    /// Sets [`Self::default_weight`](Self#structfield.default_weight) to `weight` and returns self.
+    pub fn default_weight(mut self, weight: u32) -> Self {
+        self.default_weight = weight;
+        self
+    }
+
+    /// Inserts `weight` into [`Self::database_weights`](Self#structfield.database_weights) for `database` and returns self.
+    pub fn database_weight(mut self, database: impl Into<String>, weight: u32) -> Self {
+        self.database_weights.insert(database.into(), weight);
+        self
+    }
+}
+
+/// Limits on the resources a database may use.
+///
+/// Usage is updated as transactions and key-value operations are executed,
+/// and is stored in the database so that it does not need to be measured
+/// each time the database is opened. The usage is measured by reading every
+/// document and key-value entry the first time a quota is enforced for a
+/// database, and again after the database has been opened without a quota.
+/// Writes that would exceed a limit fail with
+/// [`Error::QuotaExceeded`](bonsaidb_core::Error::QuotaExceeded). Deleting
+/// documents and keys always succeeds.
+///
+/// Bytes are counted as the lengths of the ids and contents of the documents
+/// stored, and of the keys and values of the key-value entries stored.
+/// Replacing a document or an entry only counts the difference in size.
+/// Previous versions of data kept on disk until the database is compacted
+/// are not counted.
+///
+/// ```rust
+/// # use bonsaidb_local::config::Quota;
+/// let quota = Quota::default()
+///     .max_bytes(64 * 1024 * 1024)
+///     .max_documents(10_000);
+/// ```
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[must_use]
+#[non_exhaustive]
+pub struct Quota {
+    /// The maximum number of bytes of documents and key-value entries the
+    /// database may store. Default value is `None`.
+    pub max_bytes: Option<u64>,
+    /// The maximum number of documents that may be stored across all
+    /// collections. Default value is `None`.
+    pub max_documents: Option<u64>,
+    /// The maximum number of keys that may be stored in the key-value store.
+    /// Default value is `None`.
+    pub max_keys: Option<u64>,
+}
+
+impl Quota {
+    /// Sets [`Self::max_bytes`](Self#structfield.max_bytes) to `bytes` and returns self.
+    pub const fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Sets [`Self::max_documents`](Self#structfield.max_documents) to `documents` and returns self.
+    pub const fn max_documents(mut self, documents: u64) -> Self {
+        self.max_documents = Some(documents);
+        self
+    }
+
+    /// Sets [`Self::max_keys`](Self#structfield.max_keys) to `keys` and returns self.
+    pub const fn max_keys(mut self, keys: u64) -> Self {
+        self.max_keys = Some(keys);
+        self
+    }
+}
+
+/// Periodically stores the data of a
+/// [`memory_only`](StorageConfiguration#structfield.memory_only) storage to
+/// disk, and restores it when the storage is opened.
+///
+/// Snapshots are stored in [`path`](Self::path) using
+/// [`Storage::backup()`](crate::Storage::backup). Each full snapshot is
+/// written to a new directory, and replaces the previous snapshot once it has
+/// been completely written. Between full snapshots, each snapshot only stores
+/// the changes made since the previous snapshot using
+/// [`Storage::backup_incremental()`](crate::Storage::backup_incremental),
+/// forming a journal that is replayed when the snapshot is restored. The first
+/// snapshot taken after opening the storage is always a full snapshot.
+///
+/// Changes made after the last snapshot are lost when the storage is closed.
+/// [`Storage::snapshot()`](crate::Storage::snapshot) can be used to store a
+/// snapshot before closing the storage. Databases deleted after the last full
+/// snapshot are restored until the next full snapshot is taken.
+///
+/// ```rust
+/// # use bonsaidb_local::config::Snapshots;
+/// # use std::time::Duration;
+/// let snapshots = Snapshots::new("cache-snapshots")
+///     .interval(Duration::from_secs(30))
+///     .incremental_snapshots(10);
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+#[non_exhaustive]
+pub struct Snapshots {
+    /// The directory snapshots are stored in.
+    pub path: PathBuf,
+    /// The amount of time between snapshots. Default value is 1 minute.
+    pub interval: Duration,
+    /// The number of incremental snapshots stored between full snapshots. If
+    /// 0, every snapshot is a full snapshot. Default value is 60.
+    pub incremental_snapshots: u32,
+}
+
+impl Snapshots {
+    /// Returns the default configuration for storing snapshots in `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            interval: Duration::from_secs(60),
+            incremental_snapshots: 60,
+        }
+    }
+
+    /// Sets [`Self::interval`](Self#structfield.interval) to `interval` and returns self.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets [`Self::incremental_snapshots`](Self#structfield.incremental_snapshots) to `snapshots` and returns self.
+    pub fn incremental_snapshots(mut self, snapshots: u32) -> Self {
+        self.incremental_snapshots = snapshots;
+        self
+    }
+}
+
 /// Storage configuration builder methods.
 pub trait Builder: Sized {
     /// Creates a default configuration with `path` set.
@@ -350,10 +780,25 @@ pub trait Builder: Sized {
     }
     /// Registers the schema and returns self.
     fn with_schema<S: Schema>(self) -> Result<Self, Error>;
+    /// Registers `C` to allow its documents to be exported to and imported
+    /// from JSON, and returns self. See
+    /// [`StorageConfiguration::register_json_collection()`].
+    #[must_use]
+    fn with_json_collection<C>(self) -> Self
+    where
+        C: SerializedCollection + 'static,
+        C::Contents: Serialize + DeserializeOwned,
+        C::PrimaryKey: Serialize + DeserializeOwned;
 
     /// Sets [`StorageConfiguration::memory_only`](StorageConfiguration#structfield.memory_only) to true and returns self.
     #[must_use]
     fn memory_only(self) -> Self;
+    /// Sets [`StorageConfiguration::snapshots`](StorageConfiguration#structfield.snapshots) to `snapshots` and returns self.
+    #[must_use]
+    fn snapshots(self, snapshots: Snapshots) -> Self;
+    /// Sets [`StorageConfiguration::read_only`](StorageConfiguration#structfield.read_only) to true and returns self.
+    #[must_use]
+    fn read_only(self) -> Self;
     /// Sets [`StorageConfiguration::path`](StorageConfiguration#structfield.path) to `path` and returns self.
     #[must_use]
     fn path<P: AsRef<Path>>(self, path: P) -> Self;
@@ -384,9 +829,34 @@ pub trait Builder: Sized {
     #[cfg(feature = "compression")]
     #[must_use]
     fn default_compression(self, compression: Compression) -> Self;
+    /// Inserts `compression` into [`StorageConfiguration::collection_compression`](StorageConfiguration#structfield.collection_compression) for `collection` and returns self.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    fn collection_compression(self, collection: CollectionName, compression: Compression) -> Self;
     /// Sets [`StorageConfiguration::key_value_persistence`](StorageConfiguration#structfield.key_value_persistence) to `persistence` and returns self.
     #[must_use]
     fn key_value_persistence(self, persistence: KeyValuePersistence) -> Self;
+    /// Sets [`StorageConfiguration::cache`](StorageConfiguration#structfield.cache) to `cache` and returns self.
+    #[must_use]
+    fn cache(self, cache: Cache) -> Self;
+    /// Sets [`StorageConfiguration::compaction_policy`](StorageConfiguration#structfield.compaction_policy) to `policy` and returns self.
+    #[must_use]
+    fn compaction_policy(self, policy: CompactionPolicy) -> Self;
+    /// Inserts `quota` into [`StorageConfiguration::database_quotas`](StorageConfiguration#structfield.database_quotas) for `database` and returns self.
+    #[must_use]
+    fn database_quota(self, database: impl Into<String>, quota: Quota) -> Self;
+    /// Inserts `quota` into [`StorageConfiguration::schema_quotas`](StorageConfiguration#structfield.schema_quotas) for `schema` and returns self.
+    #[must_use]
+    fn schema_quota(self, schema: SchemaName, quota: Quota) -> Self;
+    /// Sets [`StorageConfiguration::durability`](StorageConfiguration#structfield.durability) to `durability` and returns self.
+    #[must_use]
+    fn durability(self, durability: Durability) -> Self;
+    /// Inserts `durability` into [`StorageConfiguration::database_durability`](StorageConfiguration#structfield.database_durability) for `database` and returns self.
+    #[must_use]
+    fn database_durability(self, database: impl Into<String>, durability: Durability) -> Self;
+    /// Sets [`StorageConfiguration::max_group_commit_window`](StorageConfiguration#structfield.max_group_commit_window) to `window` and returns self.
+    #[must_use]
+    fn max_group_commit_window(self, window: Duration) -> Self;
     /// Sets [`Self::authenticated_permissions`](Self#structfield.authenticated_permissions) to `authenticated_permissions` and returns self.
     #[must_use]
     fn authenticated_permissions<P: Into<Permissions>>(self, authenticated_permissions: P) -> Self;
@@ -402,11 +872,31 @@ impl Builder for StorageConfiguration {
         Ok(self)
     }
 
+    fn with_json_collection<C>(mut self) -> Self
+    where
+        C: SerializedCollection + 'static,
+        C::Contents: Serialize + DeserializeOwned,
+        C::PrimaryKey: Serialize + DeserializeOwned,
+    {
+        self.register_json_collection::<C>();
+        self
+    }
+
     fn memory_only(mut self) -> Self {
         self.memory_only = true;
         self
     }
 
+    fn snapshots(mut self, snapshots: Snapshots) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
     fn path<P: AsRef<Path>>(mut self, path: P) -> Self {
         self.path = Some(path.as_ref().to_owned());
         self
@@ -438,6 +928,16 @@ impl Builder for StorageConfiguration {
         self
     }
 
+    #[cfg(feature = "compression")]
+    fn collection_compression(
+        mut self,
+        collection: CollectionName,
+        compression: Compression,
+    ) -> Self {
+        self.collection_compression.insert(collection, compression);
+        self
+    }
+
     fn tasks_worker_count(mut self, worker_count: usize) -> Self {
         self.workers.worker_count = worker_count;
         self
@@ -458,6 +958,41 @@ impl Builder for StorageConfiguration {
         self
     }
 
+    fn cache(mut self, cache: Cache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    fn compaction_policy(mut self, policy: CompactionPolicy) -> Self {
+        self.compaction_policy = Some(policy);
+        self
+    }
+
+    fn database_quota(mut self, database: impl Into<String>, quota: Quota) -> Self {
+        self.database_quotas.insert(database.into(), quota);
+        self
+    }
+
+    fn schema_quota(mut self, schema: SchemaName, quota: Quota) -> Self {
+        self.schema_quotas.insert(schema, quota);
+        self
+    }
+
+    fn durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    fn database_durability(mut self, database: impl Into<String>, durability: Durability) -> Self {
+        self.database_durability.insert(database.into(), durability);
+        self
+    }
+
+    fn max_group_commit_window(mut self, window: Duration) -> Self {
+        self.max_group_commit_window = window;
+        self
+    }
+
     fn authenticated_permissions<P: Into<Permissions>>(
         mut self,
         authenticated_permissions: P,
@@ -486,22 +1021,133 @@ pub(crate) trait SystemDefault: Sized {
 }
 
 /// All available compression algorithms.
-#[derive(Debug, Clone, Copy)]
+///
+/// The algorithm used is recorded alongside each compressed block, which
+/// allows changing the algorithm used without rewriting existing data.
+#[derive(Debug, Clone)]
 pub enum Compression {
     /// Compress data using the
     /// [lz4](https://en.wikipedia.org/wiki/LZ4_(compression_algorithm))
     /// algorithm. This is powered by
     /// [lz4_flex](https://crates.io/crates/lz4_flex).
-    Lz4 = 1,
+    Lz4,
+    /// Compress data using the [zstd](https://en.wikipedia.org/wiki/Zstd)
+    /// algorithm. This is powered by [zstd](https://crates.io/crates/zstd).
+    #[cfg(feature = "zstd")]
+    Zstd {
+        /// The compression level, from 1 to 22. 0 uses zstd's default level.
+        level: i32,
+    },
+    /// Compress data using the [zstd](https://en.wikipedia.org/wiki/Zstd)
+    /// algorithm with a dictionary. Dictionaries trained on samples of the
+    /// data being stored greatly improve the compression of small values that
+    /// share a similar structure.
+    #[cfg(feature = "zstd")]
+    ZstdDictionary {
+        /// The compression level, from 1 to 22. 0 uses zstd's default level.
+        level: i32,
+        /// The dictionary to compress with.
+        dictionary: CompressionDictionary,
+    },
 }
 
+#[cfg(feature = "compression")]
 impl Compression {
+    pub(crate) const LZ4: u8 = 1;
+    #[cfg(feature = "zstd")]
+    pub(crate) const ZSTD: u8 = 2;
+    #[cfg(feature = "zstd")]
+    pub(crate) const ZSTD_DICTIONARY: u8 = 3;
+
+    /// Returns the identifier of this algorithm that is stored alongside
+    /// compressed data.
     #[must_use]
-    #[cfg(feature = "compression")]
-    pub(crate) fn from_u8(value: u8) -> Option<Self> {
-        match value {
-            1 => Some(Self::Lz4),
-            _ => None,
+    pub(crate) const fn algorithm(&self) -> u8 {
+        match self {
+            Self::Lz4 => Self::LZ4,
+            #[cfg(feature = "zstd")]
+            Self::Zstd { .. } => Self::ZSTD,
+            #[cfg(feature = "zstd")]
+            Self::ZstdDictionary { .. } => Self::ZSTD_DICTIONARY,
         }
     }
 }
+
+/// A dictionary for [`Compression::ZstdDictionary`].
+///
+/// Preparing a dictionary for use is expensive, so the prepared forms of the
+/// dictionary are created once and shared between clones of this value.
+#[cfg(feature = "zstd")]
+#[derive(Clone)]
+pub struct CompressionDictionary {
+    id: u32,
+    bytes: Arc<Vec<u8>>,
+    decoder: Arc<zstd::dict::DecoderDictionary<'static>>,
+    encoders: Arc<PreparedEncoders>,
+}
+
+/// The prepared encoder dictionaries of a [`CompressionDictionary`], keyed
+/// by compression level.
+#[cfg(feature = "zstd")]
+type PreparedEncoders =
+    parking_lot::Mutex<HashMap<i32, Arc<zstd::dict::EncoderDictionary<'static>>>>;
+
+#[cfg(feature = "zstd")]
+impl CompressionDictionary {
+    /// Returns a dictionary containing `bytes`, which can either be a
+    /// dictionary trained by the `zstd` command line tool or raw content.
+    #[must_use]
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        let bytes = bytes.into();
+        Self {
+            id: crc32fast::hash(&bytes),
+            decoder: Arc::new(zstd::dict::DecoderDictionary::copy(&bytes)),
+            bytes: Arc::new(bytes),
+            encoders: Arc::default(),
+        }
+    }
+
+    /// Trains a dictionary no larger than `max_size` bytes from `samples`.
+    /// Samples should be representative of the values being compressed, such
+    /// as serialized documents.
+    pub fn train<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> Result<Self, Error> {
+        Ok(Self::new(zstd::dict::from_samples(samples, max_size)?))
+    }
+
+    /// Returns the identifier of this dictionary, which is stored alongside
+    /// data compressed with it.
+    #[must_use]
+    pub const fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the contents of this dictionary.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns this dictionary prepared for compressing at `level`.
+    pub(crate) fn encoder(&self, level: i32) -> Arc<zstd::dict::EncoderDictionary<'static>> {
+        self.encoders
+            .lock()
+            .entry(level)
+            .or_insert_with(|| Arc::new(zstd::dict::EncoderDictionary::copy(&self.bytes, level)))
+            .clone()
+    }
+
+    /// Returns this dictionary prepared for decompressing.
+    pub(crate) fn decoder(&self) -> &zstd::dict::DecoderDictionary<'static> {
+        &self.decoder
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl std::fmt::Debug for CompressionDictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompressionDictionary")
+            .field("id", &self.id)
+            .field("len", &self.bytes.len())
+            .finish()
+    }
+}
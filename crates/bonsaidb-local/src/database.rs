@@ -2,14 +2,15 @@ use std::borrow::{Borrow, Cow};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::Infallible;
 use std::ops::{self, Deref};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::u8;
 
-use bonsaidb_core::arc_bytes::serde::CowBytes;
+use bonsaidb_core::arc_bytes::serde::{Bytes, CowBytes};
 use bonsaidb_core::arc_bytes::ArcBytes;
 use bonsaidb_core::connection::{
-    self, AccessPolicy, Connection, HasSchema, HasSession, LowLevelConnection, Range,
-    SerializedQueryKey, Session, Sort, StorageConnection,
+    self, AccessPolicy, Connection, DatabaseStats, HasSchema, HasSession, LowLevelConnection,
+    Range, SerializedQueryKey, Session, Sort, StorageConnection,
 };
 #[cfg(any(feature = "encryption", feature = "compression"))]
 use bonsaidb_core::document::KeyId;
@@ -25,11 +26,11 @@ use bonsaidb_core::permissions::bonsai::{
 };
 use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
-use bonsaidb_core::schema::view::{self};
+use bonsaidb_core::schema::view::{self, QuarantinedDocument, ViewIndexState, ViewStatus};
 use bonsaidb_core::schema::{self, CollectionName, Schema, Schematic, ViewName};
 use bonsaidb_core::transaction::{
-    self, ChangedDocument, Changes, Command, DocumentChanges, Operation, OperationResult,
-    Transaction,
+    self, ChangedDocument, Changes, Command, DocumentChanges, Durability, Operation,
+    OperationResult, Transaction,
 };
 use itertools::Itertools;
 use nebari::io::any::AnyFile;
@@ -38,27 +39,38 @@ use nebari::tree::{
     Unversioned, Versioned,
 };
 use nebari::{AbortError, ExecutingTransaction, Roots, Tree};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use serde::{Deserialize, Serialize};
 use watchable::Watchable;
 
-use crate::config::{Builder, KeyValuePersistence, StorageConfiguration};
+use crate::config::{Builder, KeyValuePersistence, Quota, StorageConfiguration};
+use crate::database::durability::{CommitQueue, PreparedTransactions};
 use crate::database::keyvalue::BackgroundWorkerProcessTarget;
+use crate::database::pubsub::TransactionNotifier;
+use crate::database::quota::{
+    add_to_recorded_usage, document_bytes, forget_recorded_usage, measure_documents, measure_keys,
+    record_usage, recorded_usage, QuotaState, SharedQuotaState, UsageChange, DOCUMENTS_USAGE,
+    KEYS_USAGE, QUOTA_TREE,
+};
 use crate::error::Error;
 use crate::open_trees::OpenTrees;
-use crate::storage::StorageLock;
 #[cfg(feature = "encryption")]
 use crate::storage::TreeVault;
+use crate::storage::{database_stats, StorageLock};
 use crate::views::{
-    mapper, view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
+    mapper, reductions, view_document_map_tree_name, view_entries_tree_name,
+    view_invalidated_docs_tree_name, view_quarantine_tree_name, view_reductions_tree_name,
     ViewEntry,
 };
 use crate::Storage;
 
+mod durability;
+pub(crate) mod json;
 pub mod keyvalue;
 
 pub(crate) mod compat;
 pub mod pubsub;
+pub(crate) mod quota;
 
 /// A database stored in BonsaiDb. This type blocks the current thread when
 /// used. See [`AsyncDatabase`](crate::AsyncDatabase) for this type's async counterpart.
@@ -141,16 +153,22 @@ impl Database {
             }),
         };
 
+        db.initialize_quota(storage.instance.quota(db.name(), &db.data.schema.name))?;
+
         if storage.instance.check_view_integrity_on_database_open() {
             for view in db.data.schema.views() {
                 storage.instance.tasks().spawn_integrity_check(view, &db);
             }
         }
 
-        storage
-            .instance
-            .tasks()
-            .spawn_key_value_expiration_loader(&db);
+        // Expiring keys requires writing to the key-value store. Read-only
+        // storages ignore expired keys when reading them instead.
+        if !storage.instance.is_read_only() {
+            storage
+                .instance
+                .tasks()
+                .spawn_key_value_expiration_loader(&db);
+        }
 
         Ok(db)
     }
@@ -197,6 +215,184 @@ impl Database {
         &self.data.context.roots
     }
 
+    /// Begins enforcing `quota` the first time the database is opened,
+    /// loading the usage recorded while the quota was last enforced. The
+    /// usage is only measured if none was recorded.
+    fn initialize_quota(&self, quota: Option<Quota>) -> Result<(), Error> {
+        let context = &self.data.context;
+        if context.quota_initialized.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let read_only = self.storage.instance.is_read_only();
+        let usage = match quota {
+            Some(_) => match recorded_usage(self)? {
+                Some(usage) => Some(usage),
+                None => {
+                    let documents = measure_documents(self)?;
+                    let keys = measure_keys(self)?;
+                    if !read_only {
+                        record_usage(self, KEYS_USAGE, keys)?;
+                        record_usage(self, DOCUMENTS_USAGE, documents)?;
+                    }
+                    Some(documents + keys)
+                }
+            },
+            None if read_only => None,
+            None => {
+                forget_recorded_usage(self)?;
+                None
+            }
+        };
+
+        let mut quota_state = context.quota.lock();
+        if !context.quota_initialized.swap(true, Ordering::AcqRel) {
+            *quota_state = quota
+                .zip(usage)
+                .map(|(quota, usage)| QuotaState::new(self.name().to_string(), quota, usage));
+        }
+        Ok(())
+    }
+
+    /// Measures all resources this database uses again, if a quota is being
+    /// enforced. Called after documents have been written without going
+    /// through transactions, such as when a database is copied.
+    pub(crate) fn remeasure_quota_usage(&self) -> Result<(), Error> {
+        if self.data.context.quota.lock().is_none() {
+            return Ok(());
+        }
+
+        let documents = measure_documents(self)?;
+        let keys = measure_keys(self)?;
+        record_usage(self, DOCUMENTS_USAGE, documents)?;
+        if let Some(quota_state) = self.data.context.quota.lock().as_mut() {
+            quota_state.set_usage(documents + keys);
+        }
+        Ok(())
+    }
+
+    /// Returns the indexing status of each view in this database.
+    pub fn view_statuses(&self) -> Result<Vec<ViewStatus>, bonsaidb_core::Error> {
+        let mut statuses = self
+            .data
+            .schema
+            .views()
+            .map(|view| self.view_status(&view.view_name()))
+            .collect::<Result<Vec<_>, _>>()?;
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(statuses)
+    }
+
+    /// Returns the indexing status of `view`.
+    pub fn view_status(&self, view: &ViewName) -> Result<ViewStatus, bonsaidb_core::Error> {
+        let view = self.data.schema.view_by_name(view)?;
+        let name = view.view_name();
+        self.check_permission(
+            view_resource_name(self.name(), &name),
+            &BonsaiAction::Database(DatabaseAction::View(ViewAction::GetStatus)),
+        )?;
+        let collection = view.collection();
+        let invalidated_entries = self
+            .roots()
+            .tree(self.collection_tree::<Unversioned, _>(
+                &collection,
+                view_invalidated_docs_tree_name(&name),
+            )?)
+            .map_err(Error::from)?;
+        let invalidated_documents = invalidated_entries
+            .reduce(&(..))
+            .map_err(Error::from)?
+            .alive_keys;
+
+        let tasks = self.storage.instance.tasks();
+        let state = if let Some(progress) =
+            tasks.view_update_progress(self.data.name.clone(), collection.clone(), name.clone())
+        {
+            progress.state()
+        } else if !tasks.view_integrity_checked(self.data.name.clone(), collection, name.clone()) {
+            ViewIndexState::Unverified
+        } else if invalidated_documents > 0 {
+            ViewIndexState::Outdated
+        } else {
+            ViewIndexState::UpToDate
+        };
+
+        Ok(ViewStatus {
+            name,
+            state,
+            invalidated_documents,
+        })
+    }
+
+    /// Returns the documents that failed to map in `view`. Documents are only
+    /// quarantined by views whose
+    /// [`mapping_error_policy()`](view::ViewSchema::mapping_error_policy) is
+    /// [`Quarantine`](view::ViewMappingErrorPolicy::Quarantine). A document is
+    /// removed from the quarantine once it is successfully mapped.
+    pub fn quarantined_documents(
+        &self,
+        view: &ViewName,
+    ) -> Result<Vec<QuarantinedDocument>, bonsaidb_core::Error> {
+        let view = self.data.schema.view_by_name(view)?;
+        let name = view.view_name();
+        self.check_permission(
+            view_resource_name(self.name(), &name),
+            &BonsaiAction::Database(DatabaseAction::View(ViewAction::ListQuarantined)),
+        )?;
+        let quarantine = self
+            .roots()
+            .tree(self.collection_tree::<Unversioned, _>(
+                &view.collection(),
+                view_quarantine_tree_name(&name),
+            )?)
+            .map_err(Error::from)?;
+        quarantine
+            .get_range(&(..))
+            .map_err(Error::from)?
+            .into_iter()
+            .map(|(id, error)| {
+                Ok(QuarantinedDocument {
+                    id: DocumentId::try_from(id.as_slice())?,
+                    error: String::from_utf8_lossy(&error).into_owned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Deletes the indexes of `views` and begins rebuilding them in the
+    /// background, even if they are up to date. If `views` is `None`, all
+    /// views in this database will be rebuilt.
+    ///
+    /// This function waits for each view's stored entries to be invalidated.
+    /// Mapping the invalidated documents happens in the background, and its
+    /// progress can be monitored using
+    /// [`view_statuses()`](Self::view_statuses). Until a view is rebuilt,
+    /// queries that don't wait for the view to update will return incomplete
+    /// results.
+    pub fn reindex_views(&self, views: Option<&[ViewName]>) -> Result<(), bonsaidb_core::Error> {
+        let views = match views {
+            Some(names) => names
+                .iter()
+                .map(|name| self.data.schema.view_by_name(name))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => self.data.schema.views().collect(),
+        };
+        for view in &views {
+            self.check_permission(
+                view_resource_name(self.name(), &view.view_name()),
+                &BonsaiAction::Database(DatabaseAction::View(ViewAction::Reindex)),
+            )?;
+        }
+        self.storage.instance.check_writable()?;
+
+        let tasks = self.storage.instance.tasks();
+        for view in views {
+            tasks.rebuild_view(view, self)?;
+        }
+
+        Ok(())
+    }
+
     fn for_each_in_view<F: FnMut(ViewEntry) -> Result<(), bonsaidb_core::Error> + Send + Sync>(
         &self,
         view: &dyn view::Serialized,
@@ -206,6 +402,31 @@ impl Database {
         access_policy: AccessPolicy,
         mut callback: F,
     ) -> Result<(), bonsaidb_core::Error> {
+        self.access_view(view, access_policy, || {
+            let view_entries = self
+                .roots()
+                .tree(self.collection_tree(
+                    &view.collection(),
+                    view_entries_tree_name(&view.view_name()),
+                )?)
+                .map_err(Error::from)?;
+
+            for entry in Self::create_view_iterator(&view_entries, key, order, limit)? {
+                callback(entry)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Invokes `access` after ensuring `view` is updated according to
+    /// `access_policy`.
+    fn access_view<R>(
+        &self,
+        view: &dyn view::Serialized,
+        access_policy: AccessPolicy,
+        access: impl FnOnce() -> Result<R, bonsaidb_core::Error>,
+    ) -> Result<R, bonsaidb_core::Error> {
         if matches!(access_policy, AccessPolicy::UpdateBefore) {
             self.storage
                 .instance
@@ -223,19 +444,7 @@ impl Database {
                 .map_err(Error::from)?;
         }
 
-        let view_entries = self
-            .roots()
-            .tree(self.collection_tree(
-                &view.collection(),
-                view_entries_tree_name(&view.view_name()),
-            )?)
-            .map_err(Error::from)?;
-
-        {
-            for entry in Self::create_view_iterator(&view_entries, key, order, limit)? {
-                callback(entry)?;
-            }
-        }
+        let result = access()?;
 
         if matches!(access_policy, AccessPolicy::UpdateAfter) {
             let db = self.clone();
@@ -251,12 +460,76 @@ impl Database {
                 .update_view_if_needed(view, &db, false)?;
         }
 
-        Ok(())
+        Ok(result)
+    }
+
+    /// Reduces all entries of `view` within `range`, using the cached
+    /// reductions when they are available.
+    fn reduce_view_range(
+        &self,
+        view: &dyn view::Serialized,
+        range: &Range<Bytes>,
+    ) -> Result<Vec<u8>, bonsaidb_core::Error> {
+        let view_name = view.view_name();
+        let view_entries = self
+            .roots()
+            .tree(self.collection_tree(&view.collection(), view_entries_tree_name(&view_name))?)
+            .map_err(Error::from)?;
+        let view_reductions = self
+            .roots()
+            .tree(self.collection_tree(&view.collection(), view_reductions_tree_name(&view_name))?)
+            .map_err(Error::from)?;
+
+        if let Some(reduced) = reductions::reduce_range(
+            view,
+            &range.map_ref(|bytes| &bytes[..]),
+            &view_entries,
+            &view_reductions,
+        )? {
+            return Ok(reduced);
+        }
+
+        let mappings = Self::create_view_iterator(
+            &view_entries,
+            Some(SerializedQueryKey::Range(range.clone())),
+            Sort::Ascending,
+            None,
+        )?
+        .into_iter()
+        .map(|entry| MappedSerializedValue {
+            key: entry.key,
+            value: entry.reduced_value,
+        })
+        .collect();
+        Self::reduce_mappings(view, mappings)
+    }
+
+    fn reduce_mappings(
+        view: &dyn view::Serialized,
+        mut mappings: Vec<MappedSerializedValue>,
+    ) -> Result<Vec<u8>, bonsaidb_core::Error> {
+        if mappings.len() == 1 {
+            Ok(mappings.pop().unwrap().value.into_vec())
+        } else {
+            let reduced = view
+                .reduce(
+                    &mappings
+                        .iter()
+                        .map(|map| (map.key.as_ref(), map.value.as_ref()))
+                        .collect::<Vec<_>>(),
+                    true,
+                )
+                .map_err(Error::from)?;
+            Ok(reduced)
+        }
     }
 
-    fn open_trees_for_transaction(&self, transaction: &Transaction) -> Result<OpenTrees, Error> {
+    fn open_trees_for_transactions(
+        &self,
+        transactions: &[&Transaction],
+    ) -> Result<OpenTrees, Error> {
         let mut open_trees = OpenTrees::default();
-        for op in &transaction.operations {
+        for op in transactions.iter().flat_map(|t| &t.operations) {
             if self
                 .data
                 .schema
@@ -267,30 +540,7 @@ impl Database {
             }
 
             #[cfg(any(feature = "encryption", feature = "compression"))]
-            let vault = if let Some(encryption_key) =
-                self.collection_encryption_key(&op.collection).cloned()
-            {
-                #[cfg(feature = "encryption")]
-                if let Some(mut vault) = self.storage().tree_vault().cloned() {
-                    vault.key = Some(encryption_key);
-                    Some(vault)
-                } else {
-                    TreeVault::new_if_needed(
-                        Some(encryption_key),
-                        self.storage().vault(),
-                        #[cfg(feature = "compression")]
-                        None,
-                    )
-                }
-
-                #[cfg(not(feature = "encryption"))]
-                {
-                    drop(encryption_key);
-                    return Err(Error::EncryptionDisabled);
-                }
-            } else {
-                self.storage().tree_vault().cloned()
-            };
+            let vault = self.collection_vault(&op.collection)?;
 
             open_trees.open_trees_for_document_change(
                 &op.collection,
@@ -306,8 +556,33 @@ impl Database {
     fn apply_transaction_to_roots(
         &self,
         transaction: &Transaction,
+        durability: Durability,
     ) -> Result<Vec<OperationResult>, Error> {
-        let open_trees = self.open_trees_for_transaction(transaction)?;
+        let mut prepared = self.prepare_transactions(&[transaction], durability)?;
+        let results = prepared.results.pop().unwrap_or_default();
+        prepared.commit()?;
+        Ok(results)
+    }
+
+    /// Executes `transactions` within a single transaction of this database's
+    /// roots, returning the uncommitted transaction along with the results of
+    /// each transaction's operations.
+    fn prepare_transactions(
+        &self,
+        transactions: &[&Transaction],
+        durability: Durability,
+    ) -> Result<PreparedTransactions<'_>, Error> {
+        let mut open_trees = self.open_trees_for_transactions(transactions)?;
+        // While a quota is enforced, the usage recorded for the documents is
+        // updated in the same transaction as the documents.
+        let enforcing_quota = self.data.context.quota.lock().is_some();
+        if enforcing_quota {
+            open_trees.open_tree::<Unversioned>(
+                QUOTA_TREE,
+                #[cfg(any(feature = "encryption", feature = "compression"))]
+                None,
+            );
+        }
 
         let mut roots_transaction = self
             .data
@@ -315,11 +590,21 @@ impl Database {
             .roots
             .transaction::<_, dyn AnyTreeRoot<AnyFile>>(&open_trees.trees)?;
 
+        let mut usage_change = UsageChange::default();
         let mut results = Vec::new();
         let mut changed_documents = Vec::new();
         let mut collection_indexes = HashMap::new();
         let mut collections = Vec::new();
-        for op in &transaction.operations {
+        for op in transactions.iter().flat_map(|t| &t.operations) {
+            if enforcing_quota {
+                Self::record_usage_change(
+                    op,
+                    &mut roots_transaction,
+                    &open_trees.trees_index_by_name,
+                    &mut usage_change,
+                )?;
+            }
+
             let result = self.execute_operation(
                 op,
                 &mut roots_transaction,
@@ -363,6 +648,15 @@ impl Database {
             &changed_documents,
         )?;
 
+        if enforcing_quota {
+            add_to_recorded_usage(
+                &mut roots_transaction,
+                open_trees.trees_index_by_name[QUOTA_TREE],
+                DOCUMENTS_USAGE,
+                &usage_change,
+            )?;
+        }
+
         roots_transaction
             .entry_mut()
             .set_data(compat::serialize_executed_transaction_changes(
@@ -370,11 +664,77 @@ impl Database {
                     collections,
                     documents: changed_documents,
                 }),
+                durability,
             )?)?;
 
-        roots_transaction.commit()?;
+        // The quota is locked until the transaction is committed, preventing
+        // concurrent transactions from exceeding it together.
+        let quota_state = self.data.context.quota.lock();
+        if let Some(quota_state) = quota_state.as_ref() {
+            quota_state.check(&usage_change)?;
+        }
 
-        Ok(results)
+        let mut results = results.into_iter();
+        Ok(PreparedTransactions {
+            transaction: roots_transaction,
+            quota_state,
+            usage_change,
+            notifier: self.data.context.notifier.as_ref(),
+            results: transactions
+                .iter()
+                .map(|t| results.by_ref().take(t.operations.len()).collect())
+                .collect(),
+        })
+    }
+
+    /// Adds the change in usage `operation` will cause to `change`. Replacing
+    /// a document only changes the usage by the difference in size between
+    /// the new and the existing document.
+    fn record_usage_change(
+        operation: &Operation,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+        change: &mut UsageChange,
+    ) -> Result<(), Error> {
+        let mut stored_bytes = |id: &DocumentId| -> Result<Option<u64>, Error> {
+            let mut documents = transaction
+                .tree::<Versioned>(tree_index_map[&document_tree_name(&operation.collection)])
+                .unwrap();
+            documents
+                .get(id.as_ref())?
+                .map(|stored| {
+                    deserialize_document(&stored)
+                        .map(|document| document_bytes(&document.header.id, &document.contents))
+                })
+                .transpose()
+        };
+        match &operation.command {
+            Command::Insert { id, contents } => {
+                change.documents += 1;
+                change.replace(
+                    None,
+                    contents.len() as u64 + id.as_ref().map_or(0, |id| id.len() as u64),
+                );
+            }
+            Command::Update { header, contents } => {
+                let existing_bytes = stored_bytes(&header.id)?;
+                change.replace(existing_bytes, document_bytes(&header.id, contents));
+            }
+            Command::Overwrite { id, contents } => {
+                let existing_bytes = stored_bytes(id)?;
+                if existing_bytes.is_none() {
+                    change.documents += 1;
+                }
+                change.replace(existing_bytes, document_bytes(id, contents));
+            }
+            Command::Delete { header } => {
+                let existing_bytes = stored_bytes(&header.id)?;
+                change.documents -= i64::from(existing_bytes.is_some());
+                change.replace(existing_bytes, 0);
+            }
+            Command::Check { .. } => {}
+        }
+        Ok(())
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
@@ -696,6 +1056,12 @@ impl Database {
                 let view_entries = transaction
                     .unlocked_tree(tree_index_map[&view_entries_tree_name(&name)])
                     .unwrap();
+                let reductions = transaction
+                    .unlocked_tree(tree_index_map[&view_reductions_tree_name(&name)])
+                    .unwrap();
+                let quarantine = transaction
+                    .unlocked_tree(tree_index_map[&view_quarantine_tree_name(&name)])
+                    .unwrap();
                 mapper::DocumentRequest {
                     database: self,
                     document_ids: vec![document_id.clone()],
@@ -707,7 +1073,10 @@ impl Database {
                     document_map,
                     documents,
                     view_entries,
+                    reductions,
+                    quarantine,
                     view,
+                    progress: None,
                 }
                 .map()?;
             }
@@ -838,6 +1207,19 @@ impl Database {
             .or_else(|| self.storage.default_encryption_key())
     }
 
+    /// Returns the permissions that must be checked when using `key`. Named
+    /// keys are checked against the session's permissions using
+    /// [`encryption_key_resource_name()`](bonsaidb_core::permissions::bonsai::encryption_key_resource_name),
+    /// while the master key is available to any session that can access the
+    /// database.
+    #[cfg(feature = "encryption")]
+    fn encryption_key_permissions(&self, key: &KeyId) -> Option<Permissions> {
+        match key {
+            KeyId::Id(_) => self.session().map(|session| session.permissions.clone()),
+            KeyId::Master | KeyId::None => None,
+        }
+    }
+
     #[cfg_attr(
         not(feature = "encryption"),
         allow(
@@ -856,44 +1238,62 @@ impl Database {
         let mut tree = R::tree(name);
 
         #[cfg(any(feature = "encryption", feature = "compression"))]
-        match (
-            self.collection_encryption_key(collection),
-            self.storage().tree_vault().cloned(),
-        ) {
-            (Some(override_key), Some(mut vault)) => {
-                #[cfg(feature = "encryption")]
-                {
-                    vault.key = Some(override_key.clone());
-                    tree = tree.with_vault(vault);
-                }
+        if let Some(vault) = self.collection_vault(collection)? {
+            tree = tree.with_vault(vault);
+        }
 
-                #[cfg(not(feature = "encryption"))]
-                {
-                    return Err(Error::EncryptionDisabled);
-                }
-            }
-            (None, Some(vault)) => {
-                tree = tree.with_vault(vault);
-            }
-            (key, None) => {
-                #[cfg(feature = "encryption")]
-                if let Some(vault) = TreeVault::new_if_needed(
-                    key.cloned(),
-                    self.storage().vault(),
-                    #[cfg(feature = "compression")]
-                    None,
-                ) {
-                    tree = tree.with_vault(vault);
-                }
+        Ok(tree)
+    }
 
-                #[cfg(not(feature = "encryption"))]
-                if key.is_some() {
-                    return Err(Error::EncryptionDisabled);
-                }
-            }
+    /// Returns the vault to use for the trees of `collection`, which applies
+    /// the collection's encryption key and compression algorithm.
+    #[cfg(any(feature = "encryption", feature = "compression"))]
+    #[cfg_attr(
+        not(all(feature = "compression", feature = "zstd")),
+        allow(clippy::let_and_return)
+    )]
+    fn collection_vault(&self, collection: &CollectionName) -> Result<Option<TreeVault>, Error> {
+        let key = self.collection_encryption_key(collection).cloned();
+        #[cfg(not(feature = "encryption"))]
+        if key.is_some() {
+            return Err(Error::EncryptionDisabled);
         }
+        #[cfg(feature = "compression")]
+        let compression = self.storage().collection_compression(collection).cloned();
+
+        let vault = if let Some(mut vault) = self.storage().tree_vault().cloned() {
+            #[cfg(feature = "encryption")]
+            if let Some(key) = key {
+                vault.permissions = self.encryption_key_permissions(&key);
+                vault.key = Some(key);
+            }
+            #[cfg(feature = "compression")]
+            if compression.is_some() {
+                vault.compression = compression;
+            }
+            Some(vault)
+        } else {
+            #[cfg(feature = "encryption")]
+            let vault = TreeVault::new_if_needed(
+                key.clone(),
+                self.storage().vault(),
+                #[cfg(feature = "compression")]
+                compression,
+            )
+            .map(|mut vault| {
+                vault.permissions = key.and_then(|key| self.encryption_key_permissions(&key));
+                vault
+            });
+            #[cfg(not(feature = "encryption"))]
+            let vault = TreeVault::new_if_needed(compression);
+            #[cfg(all(feature = "compression", feature = "zstd"))]
+            let vault = vault.map(|vault| {
+                vault.with_dictionaries(self.storage().compression_dictionaries().clone())
+            });
+            vault
+        };
 
-        Ok(tree)
+        Ok(vault)
     }
 
     pub(crate) fn update_key_expiration<'key>(
@@ -1044,10 +1444,12 @@ impl Connection for Database {
                 .into_iter()
                 .map(|entry| {
                     if let Some(data) = entry.data() {
-                        let changes = compat::deserialize_executed_transaction_changes(data)?;
+                        let executed = compat::deserialize_executed_transaction_changes(data)?;
                         Ok(Some(transaction::Executed {
                             id: entry.id,
-                            changes,
+                            changes: executed.changes,
+                            timestamp: executed.timestamp,
+                            durability: executed.durability,
                         }))
                     } else {
                         Ok(None)
@@ -1090,6 +1492,7 @@ impl Connection for Database {
             database_resource_name(self.name()),
             &BonsaiAction::Database(DatabaseAction::Compact),
         )?;
+        self.storage.instance.check_writable()?;
         self.storage()
             .instance
             .tasks()
@@ -1109,12 +1512,28 @@ impl Connection for Database {
             kv_resource_name(self.name()),
             &BonsaiAction::Database(DatabaseAction::Compact),
         )?;
+        self.storage.instance.check_writable()?;
         self.storage()
             .instance
             .tasks()
             .compact_key_value_store(self.clone())?;
         Ok(())
     }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "trace",
+        skip(self),
+        fields(
+            database = self.name(),
+        )
+    ))]
+    fn stats(&self) -> Result<DatabaseStats, bonsaidb_core::Error> {
+        self.check_permission(
+            database_resource_name(self.name()),
+            &BonsaiAction::Database(DatabaseAction::GetStats),
+        )?;
+        Ok(database_stats(self)?)
+    }
 }
 
 impl LowLevelConnection for Database {
@@ -1154,6 +1573,8 @@ impl LowLevelConnection for Database {
             };
             self.check_permission(resource, &action)?;
         }
+        self.storage.instance.check_writable()?;
+        let durability = self.transaction_durability(&transaction)?;
 
         let mut eager_view_tasks = Vec::new();
         for collection_name in transaction
@@ -1188,8 +1609,14 @@ impl LowLevelConnection for Database {
             }
         }
 
-        self.apply_transaction_to_roots(&transaction)
-            .map_err(bonsaidb_core::Error::from)
+        match durability {
+            Durability::Full => self
+                .apply_transaction_to_roots(&transaction, Durability::Full)
+                .map_err(bonsaidb_core::Error::from),
+            Durability::GroupCommit { window } => {
+                self.apply_transaction_in_group(transaction, window)
+            }
+        }
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(
@@ -1438,6 +1865,7 @@ impl LowLevelConnection for Database {
             collection_resource_name(self.name(), &collection),
             &BonsaiAction::Database(DatabaseAction::Compact),
         )?;
+        self.storage.instance.check_writable()?;
         self.storage()
             .instance
             .tasks()
@@ -1540,23 +1968,21 @@ impl LowLevelConnection for Database {
         key: Option<SerializedQueryKey>,
         access_policy: AccessPolicy,
     ) -> Result<Vec<u8>, bonsaidb_core::Error> {
-        let mut mappings = self.reduce_grouped_by_name(view_name, key, access_policy)?;
-
-        let result = if mappings.len() == 1 {
-            mappings.pop().unwrap().value.into_vec()
-        } else {
-            let view = self.data.schema.view_by_name(view_name)?;
-            view.reduce(
-                &mappings
-                    .iter()
-                    .map(|map| (map.key.as_ref(), map.value.as_ref()))
-                    .collect::<Vec<_>>(),
-                true,
-            )
-            .map_err(Error::from)?
+        let view = self.data.schema.view_by_name(view_name)?;
+        let range = match key {
+            None => Range::default(),
+            Some(SerializedQueryKey::Range(range)) => range,
+            Some(key) => {
+                let mappings = self.reduce_grouped_by_name(view_name, Some(key), access_policy)?;
+                return Self::reduce_mappings(view, mappings);
+            }
         };
 
-        Ok(result)
+        self.check_permission(
+            view_resource_name(self.name(), &view.view_name()),
+            &BonsaiAction::Database(DatabaseAction::View(ViewAction::Reduce)),
+        )?;
+        self.access_view(view, access_policy, || self.reduce_view_range(view, &range))
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(
@@ -1666,6 +2092,12 @@ impl Deref for Context {
 pub(crate) struct ContextData {
     pub(crate) roots: Roots<AnyFile>,
     key_value_state: Arc<Mutex<keyvalue::KeyValueState>>,
+    quota: SharedQuotaState,
+    /// True once the quota of the database has been loaded, or the database
+    /// was opened without a quota.
+    quota_initialized: AtomicBool,
+    commit_queue: Mutex<CommitQueue>,
+    notifier: Option<TransactionNotifier>,
 }
 
 impl Borrow<Roots<AnyFile>> for Context {
@@ -1679,19 +2111,27 @@ impl Context {
         roots: Roots<AnyFile>,
         key_value_persistence: KeyValuePersistence,
         storage_lock: Option<StorageLock>,
+        notifier: Option<TransactionNotifier>,
     ) -> Self {
         let background_worker_target = Watchable::new(BackgroundWorkerProcessTarget::Never);
         let mut background_worker_target_watcher = background_worker_target.watch();
+        let quota = SharedQuotaState::default();
         let key_value_state = Arc::new(Mutex::new(keyvalue::KeyValueState::new(
             key_value_persistence,
             roots.clone(),
             background_worker_target,
+            quota.clone(),
+            notifier.clone(),
         )));
         let background_worker_state = Arc::downgrade(&key_value_state);
         let context = Self {
             data: Arc::new(ContextData {
                 roots,
                 key_value_state,
+                quota,
+                quota_initialized: AtomicBool::new(false),
+                commit_queue: Mutex::default(),
+                notifier,
             }),
         };
         std::thread::Builder::new()
@@ -1724,6 +2164,11 @@ impl Context {
         state.update_key_expiration(tree_key, expiration);
     }
 
+    /// Returns true if other handles to this context exist.
+    pub(crate) fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.data) > 1
+    }
+
     #[cfg(test)]
     pub(crate) fn kv_persistence_watcher(&self) -> watchable::Watcher<Timestamp> {
         let state = self.data.key_value_state.lock();
@@ -1731,8 +2176,10 @@ impl Context {
     }
 }
 
-impl Drop for ContextData {
-    fn drop(&mut self) {
+impl ContextData {
+    /// Persists the key-value entries that have not been persisted yet,
+    /// waiting until they have been written.
+    pub(crate) fn persist_dirty_keys(&self) {
         if let Some(shutdown) = {
             let mut state = self.key_value_state.lock();
             state.shutdown(&self.key_value_state)
@@ -1742,6 +2189,12 @@ impl Drop for ContextData {
     }
 }
 
+impl Drop for ContextData {
+    fn drop(&mut self) {
+        self.persist_dirty_keys();
+    }
+}
+
 pub fn document_tree_name(collection: &CollectionName) -> String {
     format!("collection.{collection:#}")
 }
@@ -1,11 +1,15 @@
 use std::any::type_name;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::keyvalue::Timestamp;
 use bonsaidb_core::schema::CollectionName;
-use bonsaidb_core::transaction::{ChangedDocument, ChangedKey, Changes, DocumentChanges};
+use bonsaidb_core::transaction::{
+    ChangedDocument, ChangedKey, Changes, DocumentChanges, Durability,
+};
 use serde::{Deserialize, Serialize};
 use transmog_versions::Versioned;
 
@@ -36,6 +40,8 @@ impl<T> std::fmt::Display for UnknownVersion<T> {
 enum ChangesVersions {
     Legacy = 0,
     V1 = 1,
+    V2 = 2,
+    V3 = 3,
 }
 
 impl Versioned for ChangesVersions {
@@ -51,12 +57,35 @@ impl TryFrom<u64> for ChangesVersions {
         match value {
             0 => Ok(ChangesVersions::Legacy),
             1 => Ok(ChangesVersions::V1),
+            2 => Ok(ChangesVersions::V2),
+            3 => Ok(ChangesVersions::V3),
             _ => Err(UnknownVersion::default()),
         }
     }
 }
 
-pub fn deserialize_executed_transaction_changes(data: &[u8]) -> Result<Changes, crate::Error> {
+/// The changes of an executed transaction, along with the time it was executed
+/// and the durability it was committed with if they were recorded.
+pub struct ExecutedChanges {
+    pub changes: Changes,
+    pub timestamp: Option<Timestamp>,
+    pub durability: Option<Durability>,
+}
+
+impl From<Changes> for ExecutedChanges {
+    fn from(changes: Changes) -> Self {
+        Self {
+            changes,
+            timestamp: None,
+            durability: None,
+        }
+    }
+}
+
+/// Deserializes the changes of an executed transaction.
+pub fn deserialize_executed_transaction_changes(
+    data: &[u8],
+) -> Result<ExecutedChanges, crate::Error> {
     let (version, data) = transmog_versions::unwrap_version(data);
     match ChangesVersions::try_from(version)? {
         ChangesVersions::Legacy => {
@@ -65,19 +94,61 @@ pub fn deserialize_executed_transaction_changes(data: &[u8]) -> Result<Changes,
                 Err(pot::Error::NotAPot) => ChangesV0::Documents(bincode::deserialize(data)?),
                 other => other?,
             };
-            Changes::try_from(legacy).map_err(crate::Error::from)
+            Ok(ExecutedChanges::from(Changes::try_from(legacy)?))
+        }
+        ChangesVersions::V1 => Ok(ExecutedChanges::from(pot::from_slice::<Changes>(data)?)),
+        ChangesVersions::V2 => {
+            let executed: ExecutedChangesV2 = pot::from_slice(data)?;
+            Ok(ExecutedChanges {
+                changes: executed.changes.into_owned(),
+                timestamp: Some(executed.timestamp),
+                durability: None,
+            })
+        }
+        ChangesVersions::V3 => {
+            let executed: ExecutedChangesV3 = pot::from_slice(data)?;
+            Ok(ExecutedChanges {
+                changes: executed.changes.into_owned(),
+                timestamp: Some(executed.timestamp),
+                durability: Some(executed.durability),
+            })
         }
-        ChangesVersions::V1 => pot::from_slice(data).map_err(crate::Error::from),
     }
 }
 
-pub fn serialize_executed_transaction_changes(changes: &Changes) -> Result<Vec<u8>, crate::Error> {
+pub fn serialize_executed_transaction_changes(
+    changes: &Changes,
+    durability: Durability,
+) -> Result<Vec<u8>, crate::Error> {
     let mut serialized = Vec::new();
-    transmog_versions::write_header(&ChangesVersions::V1, &mut serialized)?;
-    pot::to_writer(changes, &mut serialized)?;
+    transmog_versions::write_header(&ChangesVersions::V3, &mut serialized)?;
+    pot::to_writer(
+        &ExecutedChangesV3 {
+            timestamp: Timestamp::now(),
+            durability,
+            changes: Cow::Borrowed(changes),
+        },
+        &mut serialized,
+    )?;
     Ok(serialized)
 }
 
+/// The changes of an executed transaction and the time it was executed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ExecutedChangesV2<'a> {
+    timestamp: Timestamp,
+    changes: Cow<'a, Changes>,
+}
+
+/// The changes of an executed transaction, the time it was executed, and the
+/// durability it was committed with.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ExecutedChangesV3<'a> {
+    timestamp: Timestamp,
+    durability: Durability,
+    changes: Cow<'a, Changes>,
+}
+
 /// A list of changes.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ChangesV0 {
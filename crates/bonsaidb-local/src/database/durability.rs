@@ -0,0 +1,269 @@
+use std::time::Duration;
+
+use bonsaidb_core::transaction::{Durability, OperationResult, Transaction};
+use nebari::io::any::AnyFile;
+use nebari::ExecutingTransaction;
+use parking_lot::MutexGuard;
+
+use crate::database::pubsub::TransactionNotifier;
+use crate::database::quota::{QuotaState, UsageChange};
+use crate::{Database, Error};
+
+/// Transactions waiting to be committed together.
+#[derive(Default)]
+pub(crate) struct CommitQueue {
+    pending: Vec<PendingTransaction>,
+    /// True while a thread is responsible for committing the pending
+    /// transactions.
+    committing: bool,
+}
+
+struct PendingTransaction {
+    transaction: Transaction,
+    /// The group commit window requested for the transaction.
+    window: Duration,
+    result: flume::Sender<Result<Vec<OperationResult>, bonsaidb_core::Error>>,
+}
+
+/// Transactions that have been executed but not yet committed. The quota is
+/// locked until the transactions are committed, preventing concurrent
+/// transactions from exceeding it together.
+pub(crate) struct PreparedTransactions<'a> {
+    pub transaction: ExecutingTransaction<AnyFile>,
+    pub quota_state: MutexGuard<'a, Option<QuotaState>>,
+    pub usage_change: UsageChange,
+    pub notifier: Option<&'a TransactionNotifier>,
+    /// The results of each transaction's operations.
+    pub results: Vec<Vec<OperationResult>>,
+}
+
+impl PreparedTransactions<'_> {
+    pub fn commit(mut self) -> Result<(), Error> {
+        self.transaction.commit()?;
+        if let Some(notifier) = self.notifier {
+            notifier.notify();
+        }
+
+        if let Some(quota_state) = self.quota_state.as_mut() {
+            quota_state.apply(&self.usage_change);
+        }
+
+        Ok(())
+    }
+}
+
+impl Database {
+    /// Returns the durability `transaction` should be committed with.
+    ///
+    /// Transactions may not request a [`Durability::GroupCommit`] window
+    /// longer than the storage's
+    /// [`max_group_commit_window`](crate::config::StorageConfiguration#structfield.max_group_commit_window).
+    /// Windows configured for the storage or database are not limited.
+    pub(crate) fn transaction_durability(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Durability, bonsaidb_core::Error> {
+        match transaction.durability {
+            Some(Durability::GroupCommit { window }) => {
+                let maximum = self.storage.instance.max_group_commit_window();
+                if window > maximum {
+                    Err(bonsaidb_core::Error::GroupCommitWindowTooLong { window, maximum })
+                } else {
+                    Ok(Durability::GroupCommit { window })
+                }
+            }
+            Some(durability) => Ok(durability),
+            None => Ok(self.storage.instance.durability(self.name())),
+        }
+    }
+
+    /// Queues `transaction` to be committed together with the other
+    /// transactions applied to this database concurrently.
+    ///
+    /// The first transaction queued while no commit is in progress waits for
+    /// `window` to elapse, and then commits the queued transactions on the
+    /// calling thread. Every caller waits for its transaction to be committed.
+    pub(crate) fn apply_transaction_in_group(
+        &self,
+        transaction: Transaction,
+        window: Duration,
+    ) -> Result<Vec<OperationResult>, bonsaidb_core::Error> {
+        let (result_sender, result_receiver) = flume::bounded(1);
+        let start_commit = {
+            let mut queue = self.data.context.data.commit_queue.lock();
+            queue.pending.push(PendingTransaction {
+                transaction,
+                window,
+                result: result_sender,
+            });
+            !std::mem::replace(&mut queue.committing, true)
+        };
+
+        if start_commit {
+            std::thread::sleep(window);
+            self.commit_pending_transactions();
+        }
+
+        result_receiver.recv().map_err(Error::from)?
+    }
+
+    fn spawn_committer(&self) {
+        let database = self.clone();
+        std::thread::Builder::new()
+            .name(String::from("group-commit"))
+            .spawn(move || database.commit_pending_transactions())
+            .unwrap();
+    }
+
+    /// Commits the queued transactions as a single batch. If more transactions
+    /// are queued while committing, a background thread is spawned to commit
+    /// them.
+    fn commit_pending_transactions(&self) {
+        let pending = std::mem::take(&mut self.data.context.data.commit_queue.lock().pending);
+        self.commit_batch(pending);
+
+        let mut queue = self.data.context.data.commit_queue.lock();
+        if queue.pending.is_empty() {
+            queue.committing = false;
+        } else {
+            drop(queue);
+            self.spawn_committer();
+        }
+    }
+
+    fn commit_batch(&self, pending: Vec<PendingTransaction>) {
+        let transactions = pending
+            .iter()
+            .map(|pending| &pending.transaction)
+            .collect::<Vec<_>>();
+        // The batch is recorded with the window of the transaction that
+        // started the commit.
+        let durability = Durability::GroupCommit {
+            window: pending[0].window,
+        };
+
+        match self.prepare_transactions(&transactions, durability) {
+            Ok(mut prepared) => {
+                let results = std::mem::take(&mut prepared.results);
+                match prepared.commit() {
+                    Ok(()) => {
+                        for (pending, results) in pending.iter().zip(results) {
+                            drop(pending.result.send(Ok(results)));
+                        }
+                    }
+                    Err(err) => {
+                        let err = bonsaidb_core::Error::from(err);
+                        for pending in &pending {
+                            drop(pending.result.send(Err(err.clone())));
+                        }
+                    }
+                }
+            }
+            Err(err) if pending.len() == 1 => {
+                drop(pending[0].result.send(Err(err.into())));
+            }
+            Err(_) => {
+                // An error in any transaction aborts the entire batch. Apply
+                // each transaction separately so that only the transactions
+                // that failed return errors.
+                for pending in pending {
+                    let result = self
+                        .apply_transaction_to_roots(
+                            &pending.transaction,
+                            Durability::GroupCommit {
+                                window: pending.window,
+                            },
+                        )
+                        .map_err(bonsaidb_core::Error::from);
+                    drop(pending.result.send(result));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bonsaidb_core::connection::{Connection as _, StorageConnection as _};
+    use bonsaidb_core::schema::SerializedCollection;
+    use bonsaidb_core::test_util::{Basic, TestDirectory};
+    use bonsaidb_core::transaction::{Durability, Operation, Transaction};
+
+    use crate::config::{Builder, StorageConfiguration};
+    use crate::Storage;
+
+    #[test]
+    fn durability_modes() -> anyhow::Result<()> {
+        let window = Duration::from_millis(50);
+        let directory = TestDirectory::new("durability.bonsaidb");
+        let storage = Storage::open(
+            StorageConfiguration::new(&directory)
+                .with_schema::<Basic>()?
+                .database_durability("grouped", Durability::GroupCommit { window }),
+        )?;
+        let grouped = storage.create_database::<Basic>("grouped", false)?;
+
+        let threads = (0..4)
+            .map(|index| {
+                let grouped = grouped.clone();
+                std::thread::spawn(move || Basic::new(index.to_string()).push_into(&grouped))
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap()?;
+        }
+        assert_eq!(Basic::all(&grouped).count()?, 4);
+        let executed = grouped.list_executed_transactions(None, None)?;
+        assert!(executed.len() <= 4);
+        assert!(executed
+            .iter()
+            .all(|executed| executed.durability == Some(Durability::GroupCommit { window })));
+
+        // A transaction's durability overrides the database's.
+        let default = storage.create_database::<Basic>("default", false)?;
+        Transaction::new()
+            .with_durability(Durability::GroupCommit { window })
+            .with(Operation::push_serialized::<Basic>(&Basic::new("grouped"))?)
+            .apply(&default)?;
+        Basic::new("full").push_into(&default)?;
+        let executed = default.list_executed_transactions(None, None)?;
+        assert_eq!(executed.len(), 2);
+        assert_eq!(
+            executed[0].durability,
+            Some(Durability::GroupCommit { window })
+        );
+        assert_eq!(executed[1].durability, Some(Durability::Full));
+        assert_eq!(Basic::all(&default).count()?, 2);
+
+        // Transactions can't request windows longer than the storage allows.
+        let rejected = Basic::new("rejected");
+        let result = Transaction::new()
+            .with_durability(Durability::GroupCommit {
+                window: Duration::from_secs(2),
+            })
+            .with(Operation::push_serialized::<Basic>(&rejected)?)
+            .apply(&default);
+        assert!(matches!(
+            result,
+            Err(bonsaidb_core::Error::GroupCommitWindowTooLong { .. })
+        ));
+        assert_eq!(Basic::all(&default).count()?, 2);
+
+        // A failing transaction doesn't prevent the rest of its batch from
+        // being committed.
+        let existing = Basic::new("existing").push_into(&grouped)?;
+        let conflict = {
+            let grouped = grouped.clone();
+            let id = existing.header.id;
+            std::thread::spawn(move || Basic::new("conflict").insert_into(&id, &grouped))
+        };
+        let pushed = Basic::new("pushed").push_into(&grouped);
+        assert!(conflict.join().unwrap().is_err());
+        pushed?;
+        assert_eq!(Basic::all(&grouped).count()?, 6);
+
+        Ok(())
+    }
+}
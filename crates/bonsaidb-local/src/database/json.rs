@@ -0,0 +1,278 @@
+use std::io::{BufRead, Write};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bonsaidb_core::connection::{HasSession, LowLevelConnection};
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::permissions::bonsai::{
+    collection_resource_name, BonsaiAction, DatabaseAction, DocumentAction,
+};
+use bonsaidb_core::schema::{CollectionName, SerializedCollection};
+use bonsaidb_core::transaction::{Operation, Transaction};
+use derive_where::derive_where;
+use nebari::tree::{ScanEvaluation, Versioned};
+use nebari::AbortError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::database::{deserialize_document, document_tree_name};
+use crate::{Database, DatabaseNonBlocking, Error};
+
+/// The number of documents inserted per transaction when importing.
+const IMPORT_BATCH_SIZE: usize = 1_000;
+
+/// Converts the documents of a collection between their stored format and
+/// JSON.
+pub trait JsonCollection: Send + Sync {
+    fn id_to_json(&self, id: &DocumentId) -> Result<serde_json::Value, Error>;
+    fn id_from_json(&self, id: serde_json::Value) -> Result<DocumentId, Error>;
+    fn contents_to_json(&self, contents: &[u8]) -> Result<serde_json::Value, Error>;
+    /// Returns the serialized contents, and the natural id of the contents if
+    /// the collection provides one.
+    fn contents_from_json(
+        &self,
+        contents: serde_json::Value,
+    ) -> Result<(Vec<u8>, Option<DocumentId>), Error>;
+}
+
+/// Converts documents using the [`SerializedCollection`] implementation of
+/// `C`.
+#[derive_where(Default)]
+pub struct SerializedJsonCollection<C>(PhantomData<C>);
+
+impl<C> JsonCollection for SerializedJsonCollection<C>
+where
+    C: SerializedCollection,
+    C::Contents: Serialize + DeserializeOwned,
+    C::PrimaryKey: Serialize + DeserializeOwned,
+{
+    fn id_to_json(&self, id: &DocumentId) -> Result<serde_json::Value, Error> {
+        let id = id.deserialize::<C::PrimaryKey>()?;
+        Ok(serde_json::to_value(id)?)
+    }
+
+    fn id_from_json(&self, id: serde_json::Value) -> Result<DocumentId, Error> {
+        let id = serde_json::from_value::<C::PrimaryKey>(id)?;
+        Ok(DocumentId::new(&id)?)
+    }
+
+    fn contents_to_json(&self, contents: &[u8]) -> Result<serde_json::Value, Error> {
+        let contents = C::deserialize(contents)?;
+        Ok(serde_json::to_value(contents)?)
+    }
+
+    fn contents_from_json(
+        &self,
+        contents: serde_json::Value,
+    ) -> Result<(Vec<u8>, Option<DocumentId>), Error> {
+        let contents = serde_json::from_value::<C::Contents>(contents)?;
+        let natural_id = C::natural_id(&contents)
+            .map(|id| DocumentId::new(&id))
+            .transpose()?;
+        Ok((C::serialize(&contents)?, natural_id))
+    }
+}
+
+/// A single line of a newline-delimited JSON export.
+#[derive(Serialize, Deserialize)]
+struct JsonDocument {
+    collection: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    contents: serde_json::Value,
+}
+
+impl Database {
+    /// Writes the documents of `collection` to `writer` as newline-delimited
+    /// JSON, one document per line. If `collection` is `None`, the documents
+    /// of every collection in this database are written. Returns the number
+    /// of documents written.
+    ///
+    /// Each line is an object containing the `collection` name, the
+    /// document's `id`, and the document's `contents`. The contents are
+    /// decoded using the collection's
+    /// [`SerializedCollection`] format, which requires each exported
+    /// collection to be registered using
+    /// [`Builder::with_json_collection()`](crate::config::Builder::with_json_collection).
+    pub fn export_ndjson<W: Write>(
+        &self,
+        collection: Option<&CollectionName>,
+        mut writer: W,
+    ) -> Result<u64, Error> {
+        let collections = if let Some(collection) = collection {
+            if self
+                .schematic()
+                .collection_primary_key_description(collection)
+                .is_none()
+            {
+                return Err(Error::Core(bonsaidb_core::Error::CollectionNotFound));
+            }
+            vec![collection.clone()]
+        } else {
+            let mut collections = self.schematic().collections().cloned().collect::<Vec<_>>();
+            collections.sort();
+            collections
+        };
+
+        let mut exported = 0;
+        for collection in &collections {
+            self.check_permission(
+                collection_resource_name(self.name(), collection),
+                &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::List)),
+            )?;
+            let json = self.json_collection(collection)?;
+            let name = collection.to_string();
+            let tree = self.roots().tree(
+                self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?,
+            )?;
+            tree.scan::<Error, _, _, _, _>(
+                &(..),
+                true,
+                |_, _, _| ScanEvaluation::ReadData,
+                |_, _| ScanEvaluation::ReadData,
+                |_, _, document| {
+                    write_document(json.as_ref(), &name, &document, &mut writer)
+                        .map_err(AbortError::Other)?;
+                    exported += 1;
+                    Ok(())
+                },
+            )?;
+        }
+        writer.flush()?;
+
+        Ok(exported)
+    }
+
+    /// Inserts the documents read from `reader`, which contains one JSON
+    /// document per line in the format written by
+    /// [`export_ndjson()`](Self::export_ndjson). Lines without an `id` are
+    /// assigned the document's natural id, or an automatically generated id.
+    /// Returns the number of documents inserted.
+    ///
+    /// Documents are inserted in batches. If an error occurs, the batches
+    /// inserted before the error are kept.
+    pub fn import_ndjson<R: BufRead>(&self, reader: R) -> Result<u64, Error> {
+        let mut transaction = Transaction::new();
+        let mut imported = 0;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let operation = self
+                .import_operation(&line)
+                .map_err(|err| Error::other("ndjson", format!("line {}: {err}", index + 1)))?;
+            transaction.push(operation);
+            if transaction.operations.len() >= IMPORT_BATCH_SIZE {
+                imported += self.apply_import_batch(&mut transaction)?;
+            }
+        }
+        imported += self.apply_import_batch(&mut transaction)?;
+
+        Ok(imported)
+    }
+
+    fn import_operation(&self, line: &str) -> Result<Operation, Error> {
+        let document = serde_json::from_str::<JsonDocument>(line)?;
+        let collection = document.collection.parse::<CollectionName>()?;
+        let json = self.json_collection(&collection)?;
+        let (contents, natural_id) = json.contents_from_json(document.contents)?;
+        let id = match document.id {
+            Some(id) => Some(json.id_from_json(id)?),
+            None => natural_id,
+        };
+        Ok(Operation::insert(collection, id, contents))
+    }
+
+    fn apply_import_batch(&self, transaction: &mut Transaction) -> Result<u64, Error> {
+        if transaction.operations.is_empty() {
+            return Ok(0);
+        }
+
+        let inserted = transaction.operations.len() as u64;
+        self.apply_transaction(std::mem::take(transaction))?;
+        Ok(inserted)
+    }
+
+    fn json_collection(
+        &self,
+        collection: &CollectionName,
+    ) -> Result<Arc<dyn JsonCollection>, Error> {
+        self.storage
+            .instance
+            .json_collection(collection)
+            .ok_or_else(|| Error::JsonCollectionNotRegistered(collection.clone()))
+    }
+}
+
+fn write_document<W: Write>(
+    json: &dyn JsonCollection,
+    collection: &str,
+    document: &[u8],
+    writer: &mut W,
+) -> Result<(), Error> {
+    let document = deserialize_document(document)?;
+    let line = JsonDocument {
+        collection: collection.to_string(),
+        id: Some(json.id_to_json(&document.header.id)?),
+        contents: json.contents_to_json(&document.contents)?,
+    };
+    serde_json::to_writer(&mut *writer, &line)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::StorageConnection as _;
+    use bonsaidb_core::schema::SerializedCollection;
+    use bonsaidb_core::test_util::{Basic, TestDirectory};
+
+    use crate::config::{Builder, StorageConfiguration};
+    use crate::{Error, Storage};
+
+    #[test]
+    fn export_import() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("ndjson.bonsaidb");
+        let storage = Storage::open(
+            StorageConfiguration::new(&directory)
+                .with_schema::<Basic>()?
+                .with_json_collection::<Basic>(),
+        )?;
+        let db = storage.create_database::<Basic>("original", false)?;
+        Basic::new("a").push_into(&db)?;
+        Basic::new("b").with_category("letters").push_into(&db)?;
+
+        let mut exported = Vec::new();
+        assert_eq!(db.export_ndjson(None, &mut exported)?, 2);
+        let lines = std::str::from_utf8(&exported)?.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let first = serde_json::from_str::<serde_json::Value>(lines[0])?;
+        assert_eq!(first["collection"], "khonsulabs_._basic");
+        assert_eq!(first["contents"]["value"], "a");
+
+        let imported = storage.create_database::<Basic>("imported", false)?;
+        assert_eq!(imported.import_ndjson(exported.as_slice())?, 2);
+        let documents = Basic::all(&imported).query()?;
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1].contents.category.as_deref(), Some("letters"));
+
+        // Documents without an id are assigned one.
+        let line = br#"{"collection":"khonsulabs_._basic","contents":{"value":"c","category":null,"parent_id":null,"tags":[]}}"#;
+        assert_eq!(imported.import_ndjson(&line[..])?, 1);
+        assert_eq!(Basic::all(&imported).count()?, 3);
+
+        // Collections must be registered to be converted.
+        let unregistered_directory = TestDirectory::new("ndjson-unregistered.bonsaidb");
+        let unregistered = Storage::open(
+            StorageConfiguration::new(&unregistered_directory).with_schema::<Basic>()?,
+        )?;
+        let db = unregistered.create_database::<Basic>("basic", false)?;
+        assert!(matches!(
+            db.export_ndjson(None, Vec::new()),
+            Err(Error::JsonCollectionNotRegistered(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -11,7 +11,7 @@ use bonsaidb_core::keyvalue::{
 use bonsaidb_core::permissions::bonsai::{
     keyvalue_key_resource_name, BonsaiAction, DatabaseAction, KeyValueAction,
 };
-use bonsaidb_core::transaction::{ChangedKey, Changes};
+use bonsaidb_core::transaction::{ChangedKey, Changes, Durability};
 use nebari::io::any::AnyFile;
 use nebari::tree::{CompareSwap, Operation, Root, ScanEvaluation, Unversioned};
 use nebari::{AbortError, ArcBytes, Roots};
@@ -21,6 +21,10 @@ use watchable::{Watchable, Watcher};
 
 use crate::config::KeyValuePersistence;
 use crate::database::compat;
+use crate::database::pubsub::TransactionNotifier;
+use crate::database::quota::{
+    add_to_recorded_usage, SharedQuotaState, UsageChange, KEYS_USAGE, QUOTA_TREE,
+};
 use crate::storage::StorageLock;
 use crate::tasks::{Job, Keyed, Task};
 use crate::{Database, DatabaseNonBlocking, Error};
@@ -61,6 +65,12 @@ impl KeyValue for Database {
             keyvalue_key_resource_name(self.name(), op.namespace.as_deref(), &op.key),
             &BonsaiAction::Database(DatabaseAction::KeyValue(KeyValueAction::ExecuteOperation)),
         )?;
+        if !matches!(
+            op.command,
+            Command::Get { delete: false } | Command::GetEntry
+        ) {
+            self.storage.instance.check_writable()?;
+        }
         self.data.context.perform_kv_operation(op)
     }
 }
@@ -120,11 +130,62 @@ impl Database {
 
         Ok(all_entries)
     }
+
+    /// Writes every key-value entry of this database into `destination`,
+    /// replacing entries stored under the same keys.
+    pub(crate) fn copy_key_value_entries_to(&self, destination: &Database) -> Result<(), Error> {
+        let entries = self.all_key_value_entries()?;
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let state = &destination.data.context.key_value_state;
+        let (staged_keys, notifier) = {
+            let mut state = state.lock();
+            for ((namespace, key), entry) in entries {
+                let full_key = full_key(namespace.as_deref(), &key);
+                state.update_key_expiration(&full_key, entry.expiration);
+                state.set(full_key, entry);
+            }
+            (state.stage_dirty_keys(), state.notifier.clone())
+        };
+        // If the destination is already persisting keys, the copied entries
+        // are persisted by its background worker instead.
+        if let Some(keys) = staged_keys {
+            KeyValueState::persist_keys(state, destination.roots(), notifier.as_ref(), &keys)?;
+        }
+        Ok(())
+    }
 }
 
 pub(crate) const KEY_TREE: &str = "kv";
 
-fn full_key(namespace: Option<&str>, key: &str) -> String {
+/// The number of bytes a numeric value counts towards a database's quota.
+const NUMERIC_LENGTH: u64 = 8;
+
+/// Returns `entry` unless it expired at or before `now`. Expired keys are
+/// removed before each operation executes, but only once they have been loaded
+/// by the key-value expiration loader, which never runs for read-only
+/// storages.
+fn unexpired(entry: Option<Entry>, now: Timestamp) -> Option<Entry> {
+    entry.filter(|entry| entry.expiration.map_or(true, |expiration| expiration > now))
+}
+
+/// Returns the number of bytes `value` counts towards a database's quota.
+fn value_bytes(value: &Value) -> u64 {
+    match value {
+        Value::Bytes(bytes) => bytes.len() as u64,
+        Value::Numeric(_) => NUMERIC_LENGTH,
+    }
+}
+
+/// Returns the number of bytes `entry`, stored under `full_key`, counts
+/// towards a database's quota.
+pub(crate) fn entry_bytes(full_key: &str, entry: &Entry) -> u64 {
+    full_key.len() as u64 + value_bytes(&entry.value)
+}
+
+pub(crate) fn full_key(namespace: Option<&str>, key: &str) -> String {
     let full_length = namespace.map_or_else(|| 0, str::len) + key.len() + 1;
     let mut full_key = String::with_capacity(full_length);
     if let Some(ns) = namespace {
@@ -216,6 +277,8 @@ pub struct KeyValueState {
     keys_being_persisted: Option<Arc<BTreeMap<String, Option<Entry>>>>,
     last_persistence: Watchable<Timestamp>,
     shutdown: Option<flume::Sender<()>>,
+    quota: SharedQuotaState,
+    notifier: Option<TransactionNotifier>,
 }
 
 impl KeyValueState {
@@ -223,6 +286,8 @@ impl KeyValueState {
         persistence: KeyValuePersistence,
         roots: Roots<AnyFile>,
         background_worker_target: Watchable<BackgroundWorkerProcessTarget>,
+        quota: SharedQuotaState,
+        notifier: Option<TransactionNotifier>,
     ) -> Self {
         Self {
             roots,
@@ -235,11 +300,16 @@ impl KeyValueState {
             keys_being_persisted: None,
             last_persistence: Watchable::new(Timestamp::MIN),
             shutdown: None,
+            quota,
+            notifier,
         }
     }
 
+    /// Begins persisting the dirty keys, returning a receiver that is notified
+    /// once every key has been persisted. Keys that are already being
+    /// persisted are waited for as well.
     pub fn shutdown(&mut self, state: &Arc<Mutex<KeyValueState>>) -> Option<flume::Receiver<()>> {
-        if self.keys_being_persisted.is_none() && self.commit_dirty_keys(state) {
+        if self.keys_being_persisted.is_some() || self.commit_dirty_keys(state) {
             let (shutdown_sender, shutdown_receiver) = flume::bounded(1);
             self.shutdown = Some(shutdown_sender);
             Some(shutdown_receiver)
@@ -256,12 +326,28 @@ impl KeyValueState {
         let now = Timestamp::now();
         // If there are any keys that have expired, clear them before executing any operations.
         self.remove_expired_keys(now);
+
+        // The quota stays locked until the operation completes to ensure the
+        // change measured is the change applied.
+        let quota = self.quota.clone();
+        let mut quota_state = quota.lock();
+        let usage_change = if let Some(quota_state) = quota_state.as_ref() {
+            let change = self.usage_change(&op)?;
+            quota_state.check(&change)?;
+            Some(change)
+        } else {
+            None
+        };
+
         let result = match op.command {
             Command::Set(command) => {
                 self.execute_set_operation(op.namespace.as_deref(), &op.key, command, now)
             }
             Command::Get { delete } => {
-                self.execute_get_operation(op.namespace.as_deref(), &op.key, delete)
+                self.execute_get_operation(op.namespace.as_deref(), &op.key, delete, now)
+            }
+            Command::GetEntry => {
+                self.execute_get_entry_operation(op.namespace.as_deref(), &op.key, now)
             }
             Command::Delete => self.execute_delete_operation(op.namespace.as_deref(), &op.key),
             Command::Increment { amount, saturating } => self.execute_increment_operation(
@@ -280,6 +366,9 @@ impl KeyValueState {
             ),
         };
         if result.is_ok() {
+            if let (Some(quota_state), Some(change)) = (quota_state.as_mut(), usage_change) {
+                quota_state.apply(&change);
+            }
             if self.needs_commit(now) {
                 self.commit_dirty_keys(state);
             }
@@ -288,6 +377,49 @@ impl KeyValueState {
         result
     }
 
+    /// Returns the change in usage `op` will cause if it succeeds. Replacing
+    /// an entry only changes the usage by the difference in size between the
+    /// new and the existing entry.
+    fn usage_change(&self, op: &KeyOperation) -> Result<UsageChange, bonsaidb_core::Error> {
+        let full_key = full_key(op.namespace.as_deref(), &op.key);
+        let existing_bytes = self
+            .get(&full_key)
+            .map_err(Error::from)?
+            .map(|entry| entry_bytes(&full_key, &entry));
+        let written = |value_bytes: u64| {
+            let mut change = UsageChange {
+                keys: i64::from(existing_bytes.is_none()),
+                ..UsageChange::default()
+            };
+            change.replace(existing_bytes, full_key.len() as u64 + value_bytes);
+            change
+        };
+        Ok(match &op.command {
+            Command::Set(set) => {
+                let updating = match set.check {
+                    Some(KeyCheck::OnlyIfPresent) => existing_bytes.is_some(),
+                    Some(KeyCheck::OnlyIfVacant) => existing_bytes.is_none(),
+                    None => true,
+                };
+                if updating {
+                    written(value_bytes(&set.value))
+                } else {
+                    UsageChange::default()
+                }
+            }
+            Command::Increment { .. } | Command::Decrement { .. } => written(NUMERIC_LENGTH),
+            Command::Get { delete: true } | Command::Delete => {
+                let mut change = UsageChange {
+                    keys: -i64::from(existing_bytes.is_some()),
+                    ..UsageChange::default()
+                };
+                change.replace(existing_bytes, 0);
+                change
+            }
+            Command::Get { delete: false } | Command::GetEntry => UsageChange::default(),
+        })
+    }
+
     #[cfg_attr(
         feature = "tracing",
         tracing::instrument(level = "trace", skip(self, set, now),)
@@ -425,6 +557,7 @@ impl KeyValueState {
         namespace: Option<&str>,
         key: &str,
         delete: bool,
+        now: Timestamp,
     ) -> Result<Output, bonsaidb_core::Error> {
         let full_key = full_key(namespace, key);
         let entry = if delete {
@@ -433,7 +566,26 @@ impl KeyValueState {
             self.get(&full_key).map_err(Error::from)?
         };
 
-        Ok(Output::Value(entry.map(|e| e.value)))
+        Ok(Output::Value(unexpired(entry, now).map(|e| e.value)))
+    }
+
+    fn execute_get_entry_operation(
+        &mut self,
+        namespace: Option<&str>,
+        key: &str,
+        now: Timestamp,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        let entry = self.get(&full_key(namespace, key)).map_err(Error::from)?;
+        Ok(match unexpired(entry, now) {
+            Some(entry) => Output::Entry {
+                value: Some(entry.value),
+                expiration: entry.expiration,
+            },
+            None => Output::Entry {
+                value: None,
+                expiration: None,
+            },
+        })
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
@@ -636,6 +788,17 @@ impl KeyValueState {
         {
             let key = self.expiration_order.pop_front().unwrap();
             self.expiring_keys.remove(&key);
+            let quota = self.quota.clone();
+            if let Some(quota_state) = quota.lock().as_mut() {
+                let mut change = UsageChange {
+                    keys: -1,
+                    ..UsageChange::default()
+                };
+                if let Ok(Some(entry)) = self.get(&key) {
+                    change.replace(Some(entry_bytes(&key, &entry)), 0);
+                }
+                quota_state.apply(&change);
+            }
             self.dirty_keys.insert(key, None);
         }
     }
@@ -664,9 +827,10 @@ impl KeyValueState {
         if let Some(keys) = self.stage_dirty_keys() {
             let roots = self.roots.clone();
             let state = state.clone();
+            let notifier = self.notifier.clone();
             std::thread::Builder::new()
                 .name(String::from("keyvalue-persist"))
-                .spawn(move || Self::persist_keys(&state, &roots, &keys))
+                .spawn(move || Self::persist_keys(&state, &roots, notifier.as_ref(), &keys))
                 .unwrap();
             self.last_commit = Timestamp::now();
             true
@@ -684,16 +848,23 @@ impl KeyValueState {
     fn persist_keys(
         key_value_state: &Arc<Mutex<KeyValueState>>,
         roots: &Roots<AnyFile>,
+        notifier: Option<&TransactionNotifier>,
         keys: &BTreeMap<String, Option<Entry>>,
     ) -> Result<(), bonsaidb_core::Error> {
-        let mut transaction = roots
-            .transaction(&[Unversioned::tree(KEY_TREE)])
-            .map_err(Error::from)?;
+        // While a quota is enforced, the usage recorded for the key-value
+        // store is updated in the same transaction as the keys.
+        let enforcing_quota = key_value_state.lock().quota.lock().is_some();
+        let mut trees = vec![Unversioned::tree(KEY_TREE)];
+        if enforcing_quota {
+            trees.push(Unversioned::tree(QUOTA_TREE));
+        }
+        let mut transaction = roots.transaction(&trees).map_err(Error::from)?;
         let all_keys = keys
             .keys()
             .map(|key| ArcBytes::from(key.as_bytes().to_vec()))
             .collect();
         let mut changed_keys = Vec::new();
+        let mut usage_change = UsageChange::default();
         transaction
             .tree::<Unversioned>(0)
             .unwrap()
@@ -703,6 +874,21 @@ impl KeyValueState {
                     let full_key = std::str::from_utf8(key).unwrap();
                     let (namespace, key) = split_key(full_key).unwrap();
 
+                    if enforcing_quota {
+                        let existing_bytes = existing_value
+                            .as_ref()
+                            .and_then(|value| bincode::deserialize::<Entry>(value).ok())
+                            .map(|entry| entry_bytes(full_key, &entry));
+                        let new_bytes = keys
+                            .get(full_key)
+                            .unwrap()
+                            .as_ref()
+                            .map(|entry| entry_bytes(full_key, entry));
+                        usage_change.keys +=
+                            i64::from(new_bytes.is_some()) - i64::from(existing_value.is_some());
+                        usage_change.replace(existing_bytes, new_bytes.unwrap_or_default());
+                    }
+
                     if let Some(new_value) = keys.get(full_key).unwrap() {
                         changed_keys.push(ChangedKey {
                             namespace,
@@ -726,13 +912,20 @@ impl KeyValueState {
             .map_err(Error::from)?;
 
         if !changed_keys.is_empty() {
+            if enforcing_quota {
+                add_to_recorded_usage(&mut transaction, 1, KEYS_USAGE, &usage_change)?;
+            }
             transaction
                 .entry_mut()
                 .set_data(compat::serialize_executed_transaction_changes(
                     &Changes::Keys(changed_keys),
+                    Durability::Full,
                 )?)
                 .map_err(Error::from)?;
             transaction.commit().map_err(Error::from)?;
+            if let Some(notifier) = notifier {
+                notifier.notify();
+            }
         }
 
         // If we are shutting down, check if we still have dirty keys.
@@ -758,7 +951,7 @@ impl KeyValueState {
             }
         };
         if let Some(final_keys) = final_keys {
-            Self::persist_keys(key_value_state, roots, &final_keys)?;
+            Self::persist_keys(key_value_state, roots, notifier, &final_keys)?;
         }
         Ok(())
     }
@@ -891,7 +1084,7 @@ mod tests {
             .file_manager(AnyFileManager::std())
             .open()?;
 
-        let context = Context::new(sled.clone(), persistence, None);
+        let context = Context::new(sled.clone(), persistence, None, None);
 
         test_contents(context, sled)?;
 
@@ -1176,6 +1369,7 @@ mod tests {
             sled,
             KeyValuePersistence::lazy([PersistenceThreshold::after_changes(2)]),
             None,
+            None,
         );
         context
             .perform_kv_operation(KeyOperation {
@@ -105,3 +105,35 @@ impl pubsub::Subscriber for Subscriber {
         &self.receiver
     }
 }
+
+/// Publishes an empty message to
+/// [`EXECUTED_TRANSACTIONS_TOPIC`](bonsaidb_core::transaction::EXECUTED_TRANSACTIONS_TOPIC)
+/// each time a transaction is recorded in a database's transaction log.
+#[derive(Clone)]
+pub(crate) struct TransactionNotifier {
+    relay: Relay,
+    topic: Vec<u8>,
+}
+
+impl TransactionNotifier {
+    pub fn new(relay: Relay, database: &str) -> Self {
+        let topic = pot::to_vec(bonsaidb_core::transaction::EXECUTED_TRANSACTIONS_TOPIC)
+            .expect("serializing a str can't fail");
+        Self {
+            relay,
+            topic: database_topic(database, &topic),
+        }
+    }
+
+    pub fn notify(&self) {
+        self.relay.publish_raw(self.topic.clone(), Vec::new());
+    }
+}
+
+impl std::fmt::Debug for TransactionNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransactionNotifier")
+            .field("topic", &self.topic)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,397 @@
+use std::ops::Add;
+use std::sync::Arc;
+
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::limits::QuotaResource;
+use nebari::io::any::AnyFile;
+use nebari::tree::{ScanEvaluation, Unversioned, Versioned};
+use nebari::{AbortError, ExecutingTransaction};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Quota;
+use crate::database::keyvalue::{entry_bytes, full_key};
+use crate::database::{deserialize_document, document_tree_name};
+use crate::{Database, DatabaseNonBlocking, Error};
+
+/// The tree storing the usage of the databases whose quota is enforced.
+pub const QUOTA_TREE: &str = "quota";
+/// The key of the usage of a database's documents, updated by each
+/// transaction.
+pub const DOCUMENTS_USAGE: &[u8] = b"documents";
+/// The key of the usage of a database's key-value entries, updated each time
+/// the entries are persisted.
+pub const KEYS_USAGE: &[u8] = b"keys";
+
+/// The quota state of a database, shared between its transactions and its
+/// key-value store. `None` when no quota applies to the database, or before
+/// the database has been opened.
+pub type SharedQuotaState = Arc<Mutex<Option<QuotaState>>>;
+
+/// The usage of a database being limited by a [`Quota`].
+#[derive(Debug)]
+pub struct QuotaState {
+    database: String,
+    quota: Quota,
+    usage: Usage,
+}
+
+/// The resources a database is using. Bytes are the lengths of the ids and
+/// contents of the documents stored, and of the keys and values of the
+/// key-value entries stored.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct Usage {
+    pub bytes: u64,
+    pub documents: u64,
+    pub keys: u64,
+}
+
+impl Usage {
+    fn applied(mut self, change: &UsageChange) -> Self {
+        self.bytes = apply_signed(self.bytes, change.bytes);
+        self.documents = apply_signed(self.documents, change.documents);
+        self.keys = apply_signed(self.keys, change.keys);
+        self
+    }
+}
+
+impl Add for Usage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            bytes: self.bytes.saturating_add(rhs.bytes),
+            documents: self.documents.saturating_add(rhs.documents),
+            keys: self.keys.saturating_add(rhs.keys),
+        }
+    }
+}
+
+/// The change in [`Usage`] caused by a write.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct UsageChange {
+    pub bytes: i64,
+    pub documents: i64,
+    pub keys: i64,
+}
+
+impl UsageChange {
+    /// Adds the change of replacing a value of `previous` bytes, if one was
+    /// stored, with a value of `bytes` bytes.
+    pub fn replace(&mut self, previous: Option<u64>, bytes: u64) {
+        self.bytes = self
+            .bytes
+            .saturating_add(signed(bytes))
+            .saturating_sub(signed(previous.unwrap_or_default()));
+    }
+}
+
+fn signed(bytes: u64) -> i64 {
+    i64::try_from(bytes).unwrap_or(i64::MAX)
+}
+
+impl QuotaState {
+    pub fn new(database: String, quota: Quota, usage: Usage) -> Self {
+        Self {
+            database,
+            quota,
+            usage,
+        }
+    }
+
+    /// Returns an error if applying `change` would exceed the quota. Changes
+    /// that reduce usage are always allowed.
+    pub fn check(&self, change: &UsageChange) -> Result<(), bonsaidb_core::Error> {
+        self.check_limit(
+            QuotaResource::Bytes,
+            self.quota.max_bytes,
+            self.usage.bytes,
+            u64::try_from(change.bytes).unwrap_or_default(),
+        )?;
+        self.check_limit(
+            QuotaResource::Documents,
+            self.quota.max_documents,
+            self.usage.documents,
+            u64::try_from(change.documents).unwrap_or_default(),
+        )?;
+        self.check_limit(
+            QuotaResource::Keys,
+            self.quota.max_keys,
+            self.usage.keys,
+            u64::try_from(change.keys).unwrap_or_default(),
+        )
+    }
+
+    fn check_limit(
+        &self,
+        resource: QuotaResource,
+        limit: Option<u64>,
+        current: u64,
+        added: u64,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match limit {
+            Some(limit) if added > 0 && current.saturating_add(added) > limit => {
+                Err(bonsaidb_core::Error::QuotaExceeded {
+                    database: self.database.clone(),
+                    resource,
+                    limit,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Records `change` as having been written.
+    pub fn apply(&mut self, change: &UsageChange) {
+        self.usage = self.usage.applied(change);
+    }
+
+    /// Replaces the usage with a new measurement.
+    pub fn set_usage(&mut self, usage: Usage) {
+        self.usage = usage;
+    }
+}
+
+fn apply_signed(current: u64, change: i64) -> u64 {
+    if change >= 0 {
+        current.saturating_add(change.unsigned_abs())
+    } else {
+        current.saturating_sub(change.unsigned_abs())
+    }
+}
+
+/// Returns the number of bytes a document counts towards a database's quota.
+pub fn document_bytes(id: &DocumentId, contents: &[u8]) -> u64 {
+    (id.len() + contents.len()) as u64
+}
+
+/// Returns the usage recorded while `database`'s quota was last enforced, or
+/// `None` if no usage has been recorded.
+pub fn recorded_usage(database: &Database) -> Result<Option<Usage>, Error> {
+    if !has_recorded_usage(database)? {
+        return Ok(None);
+    }
+
+    let tree = database.roots().tree(Unversioned::tree(QUOTA_TREE))?;
+    match (tree.get(DOCUMENTS_USAGE)?, tree.get(KEYS_USAGE)?) {
+        (Some(documents), Some(keys)) => Ok(Some(
+            bincode::deserialize::<Usage>(&documents)? + bincode::deserialize::<Usage>(&keys)?,
+        )),
+        _ => Ok(None),
+    }
+}
+
+/// Stores `usage` under `key`, replacing the usage previously recorded.
+pub fn record_usage(database: &Database, key: &'static [u8], usage: Usage) -> Result<(), Error> {
+    let tree = database.roots().tree(Unversioned::tree(QUOTA_TREE))?;
+    tree.set(key.to_vec(), bincode::serialize(&usage)?)?;
+    Ok(())
+}
+
+/// Removes the usage recorded for `database`. Writes only keep the recorded
+/// usage up to date while a quota is enforced, so it is measured again the
+/// next time a quota is enforced.
+pub fn forget_recorded_usage(database: &Database) -> Result<(), Error> {
+    if has_recorded_usage(database)? {
+        let tree = database.roots().tree(Unversioned::tree(QUOTA_TREE))?;
+        tree.remove(DOCUMENTS_USAGE)?;
+        tree.remove(KEYS_USAGE)?;
+    }
+    Ok(())
+}
+
+fn has_recorded_usage(database: &Database) -> Result<bool, Error> {
+    Ok(database
+        .storage
+        .instance
+        .tree_file_length(database.name(), QUOTA_TREE)?
+        .is_some())
+}
+
+/// Adds `change` to the usage recorded under `key` as part of `transaction`,
+/// where `tree_index` is the index of the [`QUOTA_TREE`] in the transaction.
+/// Nothing is written if no usage has been recorded.
+pub fn add_to_recorded_usage(
+    transaction: &mut ExecutingTransaction<AnyFile>,
+    tree_index: usize,
+    key: &'static [u8],
+    change: &UsageChange,
+) -> Result<(), Error> {
+    if *change == UsageChange::default() {
+        return Ok(());
+    }
+
+    let mut tree = transaction.tree::<Unversioned>(tree_index).unwrap();
+    if let Some(recorded) = tree.get(key)? {
+        let usage = bincode::deserialize::<Usage>(&recorded)?.applied(change);
+        tree.set(key.to_vec(), bincode::serialize(&usage)?)?;
+    }
+    Ok(())
+}
+
+/// Measures the usage of the documents stored in `database` by reading each
+/// of them.
+pub fn measure_documents(database: &Database) -> Result<Usage, Error> {
+    let mut usage = Usage::default();
+    for collection in database.schematic().collections() {
+        let tree_name = document_tree_name(collection);
+        // Avoid creating empty files for trees that have never been written to.
+        if database
+            .storage
+            .instance
+            .tree_file_length(database.name(), &tree_name)?
+            .is_none()
+        {
+            continue;
+        }
+
+        let tree = database
+            .roots()
+            .tree(database.collection_tree::<Versioned, _>(collection, tree_name)?)?;
+        tree.scan::<Error, _, _, _, _>(
+            &(..),
+            true,
+            |_, _, _| ScanEvaluation::ReadData,
+            |_, _| ScanEvaluation::ReadData,
+            |_, _, value| {
+                let document = deserialize_document(&value).map_err(AbortError::Other)?;
+                usage.bytes += document_bytes(&document.header.id, &document.contents);
+                usage.documents += 1;
+                Ok(())
+            },
+        )?;
+    }
+    Ok(usage)
+}
+
+/// Measures the usage of the key-value entries stored in `database`,
+/// including entries that have not been persisted yet.
+pub fn measure_keys(database: &Database) -> Result<Usage, Error> {
+    let mut usage = Usage::default();
+    for ((namespace, key), entry) in database.all_key_value_entries()? {
+        let full_key = full_key(namespace.as_deref(), &key);
+        usage.bytes += entry_bytes(&full_key, &entry);
+        usage.keys += 1;
+    }
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::StorageConnection as _;
+    use bonsaidb_core::keyvalue::KeyValue;
+    use bonsaidb_core::schema::{Schema, SerializedCollection};
+    use bonsaidb_core::test_util::{Basic, TestDirectory};
+
+    use super::*;
+    use crate::config::{Builder, StorageConfiguration};
+    use crate::Storage;
+
+    fn exceeded(error: &bonsaidb_core::Error) -> Option<QuotaResource> {
+        match error {
+            bonsaidb_core::Error::QuotaExceeded { resource, .. } => Some(*resource),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn documents_and_keys() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("quota-documents-keys.bonsaidb");
+        let configuration = StorageConfiguration::new(&directory)
+            .with_schema::<Basic>()?
+            .schema_quota(
+                Basic::schema_name(),
+                Quota::default().max_documents(2).max_keys(1),
+            );
+        let storage = Storage::open(configuration.clone())?;
+        let db = storage.create_database::<Basic>("basic", false)?;
+
+        let first = Basic::new("a").push_into(&db)?;
+        Basic::new("b").push_into(&db)?;
+        let error = Basic::new("c").push_into(&db).unwrap_err().error;
+        assert_eq!(exceeded(&error), Some(QuotaResource::Documents));
+        first.delete(&db)?;
+        let third = Basic::new("c").push_into(&db)?;
+
+        db.set_key("a", &1_u32).execute()?;
+        let error = db.set_key("b", &1_u32).execute().unwrap_err();
+        assert_eq!(exceeded(&error), Some(QuotaResource::Keys));
+        // Replacing an existing key doesn't use another key.
+        db.set_key("a", &2_u32).execute()?;
+        db.delete_key("a")?;
+        db.set_key("b", &1_u32).execute()?;
+        drop((db, storage));
+
+        // The usage recorded is loaded when the database is opened again.
+        let storage = Storage::open(configuration)?;
+        let db = storage.database::<Basic>("basic")?;
+        let error = Basic::new("d").push_into(&db).unwrap_err().error;
+        assert_eq!(exceeded(&error), Some(QuotaResource::Documents));
+        third.delete(&db)?;
+        Basic::new("d").push_into(&db)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn bytes() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("quota-bytes.bonsaidb");
+        let storage = Storage::open(
+            StorageConfiguration::new(&directory)
+                .with_schema::<Basic>()?
+                .database_quota("limited", Quota::default().max_bytes(1024 * 1024)),
+        )?;
+        let limited = storage.create_database::<Basic>("limited", false)?;
+        let unlimited = storage.create_database::<Basic>("unlimited", false)?;
+
+        Basic::new("small").push_into(&limited)?;
+        let error = Basic::new("a".repeat(2 * 1024 * 1024))
+            .push_into(&limited)
+            .unwrap_err()
+            .error;
+        assert_eq!(exceeded(&error), Some(QuotaResource::Bytes));
+        let error = limited
+            .set_key("large", &"a".repeat(2 * 1024 * 1024))
+            .execute()
+            .unwrap_err();
+        assert_eq!(exceeded(&error), Some(QuotaResource::Bytes));
+
+        Basic::new("a".repeat(2 * 1024 * 1024)).push_into(&unlimited)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn replacing_counts_difference() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("quota-replacing.bonsaidb");
+        let configuration = StorageConfiguration::new(&directory)
+            .with_schema::<Basic>()?
+            .database_quota("limited", Quota::default().max_bytes(4096));
+        let storage = Storage::open(configuration.clone())?;
+        let db = storage.create_database::<Basic>("limited", false)?;
+
+        // Replacing data of the same size repeatedly doesn't use more bytes.
+        let mut document = Basic::new("a".repeat(1024)).push_into(&db)?;
+        for value in ["b", "c", "d", "e"] {
+            db.set_key("key", &value.repeat(1024)).execute()?;
+            document.contents.value = value.repeat(1024);
+            document.update(&db)?;
+        }
+        let error = db.set_key("key", &"a".repeat(4096)).execute().unwrap_err();
+        assert_eq!(exceeded(&error), Some(QuotaResource::Bytes));
+        drop((db, storage));
+
+        // The recorded usage includes both the document and the entry.
+        let storage = Storage::open(configuration)?;
+        let db = storage.database::<Basic>("limited")?;
+        let error = Basic::new("a".repeat(2048))
+            .push_into(&db)
+            .unwrap_err()
+            .error;
+        assert_eq!(exceeded(&error), Some(QuotaResource::Bytes));
+        db.delete_key("key")?;
+        Basic::new("a".repeat(2048)).push_into(&db)?;
+
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 use bonsaidb_core::permissions::PermissionDenied;
 use bonsaidb_core::pubsub::{Disconnected, TryReceiveError};
-use bonsaidb_core::schema::{view, InsertError, InvalidNameError};
+use bonsaidb_core::schema::{view, CollectionName, InsertError, InvalidNameError};
 use bonsaidb_core::AnyError;
 use nebari::AbortError;
 
@@ -71,6 +71,36 @@ pub enum Error {
     #[error("a backup error: {0}")]
     Backup(Box<dyn AnyError>),
 
+    /// A collection's documents can't be converted to or from JSON because the
+    /// collection wasn't registered using
+    /// [`Builder::with_json_collection()`](crate::config::Builder::with_json_collection).
+    #[error("collection {0} is not registered for json conversion")]
+    JsonCollectionNotRegistered(CollectionName),
+
+    /// A backup can't be restored to the requested point in time.
+    #[error("restore point unavailable: {0}")]
+    RestorePointUnavailable(String),
+
+    /// An incremental backup of the contained database was requested using a
+    /// different set of collections than its full backup was stored with.
+    #[error("incremental backup of {0} selects different collections than its full backup")]
+    BackupFilterMismatch(String),
+
+    /// A snapshot was requested from a storage that isn't configured to store
+    /// snapshots. See [`StorageConfiguration::snapshots`](crate::config::StorageConfiguration#structfield.snapshots).
+    #[error("snapshots are not configured")]
+    SnapshotsNotConfigured,
+
+    /// A read-only storage can't be opened while another process has the
+    /// storage open for writing.
+    #[error("the storage is open for writing by another process")]
+    StorageLocked,
+
+    /// An operation requires that no data is being re-encrypted after a
+    /// master key rotation.
+    #[error("data is being re-encrypted after a master key rotation")]
+    ReencryptionInProgress,
+
     /// An error occurred with a password hash.
     #[cfg(all(feature = "password-hashing", feature = "cli"))]
     #[error("error reading password: {0}")]
@@ -107,6 +137,12 @@ impl From<Disconnected> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::other("json", err)
+    }
+}
+
 impl From<bincode::Error> for Error {
     fn from(err: bincode::Error) -> Self {
         Self::other("bincode", err)
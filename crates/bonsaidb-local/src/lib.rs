@@ -37,7 +37,14 @@ pub use bonsaidb_core as core;
 pub use self::database::pubsub::Subscriber;
 pub use self::database::{Database, DatabaseNonBlocking};
 pub use self::error::Error;
-pub use self::storage::{BackupLocation, Storage, StorageId, StorageNonBlocking};
+#[cfg(feature = "encryption")]
+pub use self::storage::ReencryptionStatus;
+pub use self::storage::{
+    ArchiveCompression, ArchiveEncryption, ArchiveError, ArchiveOptions, BackupArchive,
+    BackupEntry, BackupFilter, BackupLocation, BackupProblem, BackupVerification, IntegrityProblem,
+    IntegrityProblemKind, IntegrityReport, RestoreOptions, RestorePoint, RestoreProgress, Storage,
+    StorageId, StorageNonBlocking,
+};
 
 #[cfg(feature = "async")]
 mod r#async;
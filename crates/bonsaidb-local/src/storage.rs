@@ -6,6 +6,7 @@ use std::io::{Read, Write};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 use bonsaidb_core::admin::database::{self, ByName, Database as DatabaseRecord};
 use bonsaidb_core::admin::user::User;
@@ -14,7 +15,7 @@ use bonsaidb_core::circulate;
 pub use bonsaidb_core::circulate::Relay;
 use bonsaidb_core::connection::{
     self, Connection, HasSession, Identity, IdentityReference, LowLevelConnection, Session,
-    SessionAuthentication, SessionId, StorageConnection,
+    SessionAuthentication, SessionId, StorageConnection, StorageStats,
 };
 use bonsaidb_core::document::CollectionDocument;
 #[cfg(any(feature = "encryption", feature = "compression"))]
@@ -25,19 +26,24 @@ use bonsaidb_core::permissions::bonsai::{
 };
 use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::schema::{
-    Nameable, NamedCollection, Schema, SchemaName, SchemaSummary, Schematic,
+    CollectionName, Nameable, NamedCollection, Schema, SchemaName, SchemaSummary, Schematic,
 };
+use bonsaidb_core::transaction::Durability;
 use fs2::FileExt;
 use itertools::Itertools;
 use nebari::io::any::{AnyFile, AnyFileManager};
 use nebari::io::FileManager;
-use nebari::{ChunkCache, ThreadPool};
+use nebari::ThreadPool;
 use parking_lot::{Mutex, RwLock};
 use rand::{thread_rng, Rng};
 
 #[cfg(feature = "compression")]
 use crate::config::Compression;
-use crate::config::{KeyValuePersistence, StorageConfiguration};
+#[cfg(all(feature = "compression", feature = "zstd"))]
+use crate::config::CompressionDictionary;
+use crate::config::{KeyValuePersistence, Quota, StorageConfiguration};
+use crate::database::json::JsonCollection;
+use crate::database::pubsub::TransactionNotifier;
 use crate::database::Context;
 use crate::tasks::manager::Manager;
 use crate::tasks::TaskManager;
@@ -51,8 +57,26 @@ mod argon;
 mod token_authentication;
 
 mod backup;
+mod cache;
+mod compaction;
+mod copy;
+mod fsck;
 mod pubsub;
-pub use backup::{AnyBackupLocation, BackupLocation};
+#[cfg(feature = "encryption")]
+mod reencryption;
+mod snapshots;
+mod stats;
+pub use backup::{
+    AnyBackupLocation, ArchiveCompression, ArchiveEncryption, ArchiveError, ArchiveOptions,
+    BackupArchive, BackupEntry, BackupFilter, BackupLocation, BackupProblem, BackupVerification,
+    RestoreOptions, RestorePoint, RestoreProgress,
+};
+use cache::ChunkCaches;
+pub use fsck::{IntegrityProblem, IntegrityProblemKind, IntegrityReport};
+#[cfg(feature = "encryption")]
+pub use reencryption::ReencryptionStatus;
+use snapshots::Snapshotter;
+pub(crate) use stats::{database_file_bytes, database_stats};
 
 /// A file-based, multi-database, multi-user database engine. This type blocks
 /// the current thread when used. See [`AsyncStorage`](crate::AsyncStorage) for
@@ -252,12 +276,27 @@ struct Data {
     #[cfg(feature = "encryption")]
     pub(crate) vault: Arc<Vault>,
     #[cfg(feature = "encryption")]
+    pending_reencryption: Mutex<Option<reencryption::PendingReencryption>>,
+    #[cfg(feature = "encryption")]
     default_encryption_key: Option<KeyId>,
     #[cfg(any(feature = "compression", feature = "encryption"))]
     tree_vault: Option<TreeVault>,
+    #[cfg(feature = "compression")]
+    collection_compression: HashMap<CollectionName, Compression>,
+    #[cfg(all(feature = "compression", feature = "zstd"))]
+    compression_dictionaries: Arc<HashMap<u32, CompressionDictionary>>,
     pub(crate) key_value_persistence: KeyValuePersistence,
-    chunk_cache: ChunkCache,
+    chunk_caches: ChunkCaches,
     pub(crate) check_view_integrity_on_database_open: bool,
+    database_quotas: HashMap<String, Quota>,
+    schema_quotas: HashMap<SchemaName, Quota>,
+    durability: Durability,
+    database_durability: HashMap<String, Durability>,
+    max_group_commit_window: Duration,
+    json_collections: HashMap<CollectionName, Arc<dyn JsonCollection>>,
+    snapshots: Option<Snapshotter>,
+    read_only: bool,
+    memory_only: bool,
     relay: Relay,
 }
 
@@ -280,7 +319,10 @@ impl Storage {
         }
         let tasks = TaskManager::new(manager);
 
-        fs::create_dir_all(&owned_path)?;
+        let read_only = configuration.read_only;
+        if !read_only {
+            fs::create_dir_all(&owned_path)?;
+        }
 
         let storage_lock = Self::lookup_or_create_id(&configuration, &owned_path)?;
 
@@ -304,10 +346,27 @@ impl Storage {
         let parallelization = configuration.workers.parallelization;
         let check_view_integrity_on_database_open = configuration.views.check_integrity_on_open;
         let key_value_persistence = configuration.key_value_persistence;
+        let compaction_policy = configuration.compaction_policy;
         #[cfg(feature = "password-hashing")]
         let argon = argon::Hasher::new(configuration.argon);
         #[cfg(feature = "encryption")]
+        let pending_reencryption = reencryption::PendingReencryption::load(&owned_path)?;
+        #[cfg(feature = "encryption")]
         let default_encryption_key = configuration.default_encryption_key;
+        #[cfg(all(feature = "compression", feature = "zstd"))]
+        let compression_dictionaries = Arc::new(
+            configuration
+                .default_compression
+                .iter()
+                .chain(configuration.collection_compression.values())
+                .filter_map(|compression| match compression {
+                    Compression::ZstdDictionary { dictionary, .. } => Some(dictionary),
+                    Compression::Lz4 | Compression::Zstd { .. } => None,
+                })
+                .chain(&configuration.compression_dictionaries)
+                .map(|dictionary| (dictionary.id(), dictionary.clone()))
+                .collect::<HashMap<_, _>>(),
+        );
         #[cfg(all(feature = "compression", feature = "encryption"))]
         let tree_vault = TreeVault::new_if_needed(
             default_encryption_key.clone(),
@@ -318,8 +377,15 @@ impl Storage {
         let tree_vault = TreeVault::new_if_needed(default_encryption_key.clone(), &vault);
         #[cfg(all(feature = "compression", not(feature = "encryption")))]
         let tree_vault = TreeVault::new_if_needed(configuration.default_compression);
+        #[cfg(all(feature = "compression", feature = "zstd"))]
+        let tree_vault =
+            tree_vault.map(|vault| vault.with_dictionaries(compression_dictionaries.clone()));
 
         let authenticated_permissions = configuration.authenticated_permissions;
+        let snapshots = configuration
+            .snapshots
+            .filter(|_| configuration.memory_only && !read_only)
+            .map(Snapshotter::new);
 
         let storage = Self {
             instance: StorageInstance {
@@ -335,18 +401,33 @@ impl Storage {
                     #[cfg(feature = "encryption")]
                     vault,
                     #[cfg(feature = "encryption")]
+                    pending_reencryption: Mutex::new(pending_reencryption),
+                    #[cfg(feature = "encryption")]
                     default_encryption_key,
                     #[cfg(any(feature = "compression", feature = "encryption"))]
                     tree_vault,
+                    #[cfg(feature = "compression")]
+                    collection_compression: configuration.collection_compression,
+                    #[cfg(all(feature = "compression", feature = "zstd"))]
+                    compression_dictionaries,
                     path: owned_path,
                     file_manager,
-                    chunk_cache: ChunkCache::new(2000, 160_384),
+                    chunk_caches: ChunkCaches::new(&configuration.cache),
                     threadpool: ThreadPool::new(parallelization),
                     schemas: RwLock::new(configuration.initial_schemas),
                     available_databases: RwLock::default(),
                     open_roots: Mutex::default(),
                     key_value_persistence,
                     check_view_integrity_on_database_open,
+                    database_quotas: configuration.database_quotas,
+                    schema_quotas: configuration.schema_quotas,
+                    durability: configuration.durability,
+                    database_durability: configuration.database_durability,
+                    max_group_commit_window: configuration.max_group_commit_window,
+                    json_collections: configuration.json_collections,
+                    snapshots,
+                    read_only,
+                    memory_only: configuration.memory_only,
                     relay: Relay::default(),
                 }),
             },
@@ -356,7 +437,23 @@ impl Storage {
 
         storage.cache_available_databases()?;
 
-        storage.create_admin_database_if_needed()?;
+        if read_only {
+            storage.register_schema::<Admin>()?;
+        } else {
+            storage.create_admin_database_if_needed()?;
+
+            if let Some(policy) = compaction_policy {
+                compaction::CompactionScheduler::spawn(&storage.instance.data, policy)?;
+            }
+
+            if let Some(snapshots) = &storage.instance.data.snapshots {
+                snapshots.restore_latest(&storage)?;
+                Snapshotter::spawn(&storage.instance.data)?;
+            }
+
+            #[cfg(feature = "encryption")]
+            storage.instance.resume_reencryption();
+        }
 
         Ok(storage)
     }
@@ -382,6 +479,25 @@ impl Storage {
             }
         };
 
+        if configuration.read_only {
+            // A read-only storage never creates the id file, and shares its
+            // lock with other read-only instances.
+            // Waiting for a writer to close the storage could block forever.
+            let mut file = File::open(id_path)?;
+            file.try_lock_shared().map_err(|err| {
+                if err.raw_os_error() == fs2::lock_contended_error().raw_os_error() {
+                    Error::StorageLocked
+                } else {
+                    Error::Io(err)
+                }
+            })?;
+            let id = match configuration.unique_id {
+                Some(id) => id,
+                None => Self::read_id(&mut file)?,
+            };
+            return Ok(StorageLock::new(StorageId(id), file));
+        }
+
         let (id, file) = if let Some(id) = configuration.unique_id {
             // The configuraiton id override is not persisted to disk. This is
             // mostly to prevent someone from accidentally adding this
@@ -407,12 +523,7 @@ impl Storage {
                 // server if the file can't be read or contains unexpected data.
                 let mut file = File::open(id_path)?;
                 file.lock_exclusive()?;
-                let mut bytes = Vec::new();
-                file.read_to_end(&mut bytes)?;
-                let existing_id =
-                    String::from_utf8(bytes).expect("server-id contains invalid data");
-
-                (existing_id.parse().expect("server-id isn't numeric"), file)
+                (Self::read_id(&mut file)?, file)
             } else {
                 let id = { thread_rng().gen::<u64>() };
                 let mut file = File::create(id_path)?;
@@ -426,6 +537,14 @@ impl Storage {
         Ok(StorageLock::new(StorageId(id), file))
     }
 
+    fn read_id(file: &mut File) -> Result<u64, Error> {
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let existing_id = String::from_utf8(bytes).expect("server-id contains invalid data");
+
+        Ok(existing_id.parse().expect("server-id isn't numeric"))
+    }
+
     fn cache_available_databases(&self) -> Result<(), Error> {
         let available_databases = self
             .admin()
@@ -464,6 +583,14 @@ impl Storage {
         self.instance.data.lock.id()
     }
 
+    /// Returns true if this storage was opened with
+    /// [`StorageConfiguration::read_only`](StorageConfiguration#structfield.read_only)
+    /// set.
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.instance.is_read_only()
+    }
+
     #[must_use]
     pub(crate) fn parallelization(&self) -> usize {
         self.instance.data.parallelization
@@ -481,6 +608,21 @@ impl Storage {
         self.instance.data.tree_vault.as_ref()
     }
 
+    #[must_use]
+    #[cfg(feature = "compression")]
+    pub(crate) fn collection_compression(
+        &self,
+        collection: &CollectionName,
+    ) -> Option<&Compression> {
+        self.instance.data.collection_compression.get(collection)
+    }
+
+    #[must_use]
+    #[cfg(all(feature = "compression", feature = "zstd"))]
+    pub(crate) fn compression_dictionaries(&self) -> &Arc<HashMap<u32, CompressionDictionary>> {
+        &self.instance.data.compression_dictionaries
+    }
+
     #[must_use]
     #[cfg(feature = "encryption")]
     pub(crate) fn default_encryption_key(&self) -> Option<&KeyId> {
@@ -604,7 +746,7 @@ impl Debug for Data {
             .field("sessions", &self.sessions)
             .field("subscribers", &self.subscribers)
             .field("key_value_persistence", &self.key_value_persistence)
-            .field("chunk_cache", &self.chunk_cache)
+            .field("chunk_caches", &self.chunk_caches)
             .field(
                 "check_view_integrity_on_database_open",
                 &self.check_view_integrity_on_database_open,
@@ -628,6 +770,8 @@ impl Debug for Data {
         }
         #[cfg(any(feature = "compression", feature = "encryption"))]
         f.field("tree_vault", &self.tree_vault);
+        #[cfg(feature = "compression")]
+        f.field("collection_compression", &self.collection_compression);
 
         f.finish()
     }
@@ -647,7 +791,7 @@ impl StorageInstance {
 
             let mut config = nebari::Config::new(self.data.path.join(task_name))
                 .file_manager(self.data.file_manager.clone())
-                .cache(self.data.chunk_cache.clone())
+                .cache(self.data.chunk_caches.for_database(name))
                 .shared_thread_pool(&self.data.threadpool);
 
             #[cfg(any(feature = "encryption", feature = "compression"))]
@@ -660,6 +804,7 @@ impl StorageInstance {
                 roots,
                 self.data.key_value_persistence.clone(),
                 Some(self.data.lock.clone()),
+                Some(TransactionNotifier::new(self.data.relay.clone(), name)),
             );
 
             open_roots.insert(name.to_owned(), context.clone());
@@ -676,6 +821,51 @@ impl StorageInstance {
         self.data.check_view_integrity_on_database_open
     }
 
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.data.read_only
+    }
+
+    /// Returns an error if this storage was opened read-only.
+    pub(crate) fn check_writable(&self) -> Result<(), bonsaidb_core::Error> {
+        if self.data.read_only {
+            Err(bonsaidb_core::Error::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the quota to enforce for the database `name` using `schema`.
+    pub(crate) fn quota(&self, name: &str, schema: &SchemaName) -> Option<Quota> {
+        self.data
+            .database_quotas
+            .get(name)
+            .or_else(|| self.data.schema_quotas.get(schema))
+            .copied()
+    }
+
+    /// Returns the durability of transactions applied to the database `name`
+    /// that do not specify one.
+    pub(crate) fn durability(&self, name: &str) -> Durability {
+        self.data
+            .database_durability
+            .get(name)
+            .copied()
+            .unwrap_or(self.data.durability)
+    }
+
+    /// Returns the longest group commit window a transaction may request.
+    pub(crate) fn max_group_commit_window(&self) -> Duration {
+        self.data.max_group_commit_window
+    }
+
+    /// Returns the JSON conversion registered for `collection`.
+    pub(crate) fn json_collection(
+        &self,
+        collection: &CollectionName,
+    ) -> Option<Arc<dyn JsonCollection>> {
+        self.data.json_collections.get(collection).cloned()
+    }
+
     pub(crate) fn relay(&self) -> &'_ Relay {
         &self.data.relay
     }
@@ -992,6 +1182,7 @@ impl StorageConnection for StorageInstance {
         let mut available_databases = self.data.available_databases.write();
         let admin = self.admin();
         if !available_databases.contains_key(name) {
+            self.check_writable()?;
             admin
                 .collection::<DatabaseRecord>()
                 .push(&admin::Database {
@@ -1015,6 +1206,7 @@ impl StorageConnection for StorageInstance {
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
     fn delete_database(&self, name: &str) -> Result<(), bonsaidb_core::Error> {
+        self.check_writable()?;
         let admin = self.admin();
         let mut available_databases = self.data.available_databases.write();
         available_databases.remove(name);
@@ -1029,6 +1221,8 @@ impl StorageConnection for StorageInstance {
                 .delete_directory(&database_folder)
                 .map_err(Error::Nebari)?;
         }
+        // Deleting a partially restored database rolls back the restore.
+        backup::RestoreState::remove(&self.data.path, name)?;
 
         if let Some(entry) = admin
             .view::<database::ByName>()
@@ -1044,6 +1238,18 @@ impl StorageConnection for StorageInstance {
         }
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    fn rename_database(&self, name: &str, new_name: &str) -> Result<(), bonsaidb_core::Error> {
+        self.rename_database_in_place(name, new_name)
+            .map_err(bonsaidb_core::Error::from)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    fn copy_database(&self, name: &str, destination: &str) -> Result<(), bonsaidb_core::Error> {
+        self.copy_database_to(name, self, destination)
+            .map_err(bonsaidb_core::Error::from)
+    }
+
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     fn list_databases(&self) -> Result<Vec<connection::Database>, bonsaidb_core::Error> {
         let available_databases = self.data.available_databases.read();
@@ -1072,6 +1278,11 @@ impl StorageConnection for StorageInstance {
             .collect())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn stats(&self) -> Result<StorageStats, bonsaidb_core::Error> {
+        Ok(self.stats_with(None)?)
+    }
+
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         let result = self
@@ -1257,6 +1468,20 @@ impl StorageConnection for Storage {
         self.instance.delete_database(name)
     }
 
+    fn rename_database(&self, name: &str, new_name: &str) -> Result<(), bonsaidb_core::Error> {
+        for name in [name, new_name] {
+            self.check_permission(
+                database_resource_name(name),
+                &BonsaiAction::Server(ServerAction::RenameDatabase),
+            )?;
+        }
+        self.instance.rename_database(name, new_name)
+    }
+
+    fn copy_database(&self, name: &str, destination: &str) -> Result<(), bonsaidb_core::Error> {
+        self.copy_database_to(name, self, destination)
+    }
+
     fn list_databases(&self) -> Result<Vec<connection::Database>, bonsaidb_core::Error> {
         self.check_permission(
             bonsaidb_resource_name(),
@@ -1273,6 +1498,14 @@ impl StorageConnection for Storage {
         self.instance.list_available_schemas()
     }
 
+    fn stats(&self) -> Result<StorageStats, bonsaidb_core::Error> {
+        self.check_permission(
+            bonsaidb_resource_name(),
+            &BonsaiAction::Server(ServerAction::ListDatabases),
+        )?;
+        Ok(self.instance.stats_with(Some(self))?)
+    }
+
     fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         self.check_permission(
             bonsaidb_resource_name(),
@@ -1503,7 +1736,7 @@ fn name_validation_tests() {
 
 /// The unique id of a [`Storage`] instance.
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
-pub struct StorageId(u64);
+pub struct StorageId(pub(crate) u64);
 
 impl StorageId {
     /// Returns the id as a u64.
@@ -1530,11 +1763,18 @@ impl Display for StorageId {
 #[cfg(any(feature = "compression", feature = "encryption"))]
 pub(crate) struct TreeVault {
     #[cfg(feature = "compression")]
-    compression: Option<Compression>,
+    pub compression: Option<Compression>,
+    /// The dictionaries available to decompress data compressed with
+    /// [`Compression::ZstdDictionary`].
+    #[cfg(all(feature = "compression", feature = "zstd"))]
+    dictionaries: Arc<HashMap<u32, CompressionDictionary>>,
     #[cfg(feature = "encryption")]
     pub key: Option<KeyId>,
     #[cfg(feature = "encryption")]
     pub vault: Arc<Vault>,
+    /// The permissions to check before using a named encryption key.
+    #[cfg(feature = "encryption")]
+    pub permissions: Option<Permissions>,
 }
 
 #[cfg(all(feature = "compression", feature = "encryption"))]
@@ -1550,22 +1790,123 @@ impl TreeVault {
             Some(Self {
                 key,
                 compression,
+                #[cfg(feature = "zstd")]
+                dictionaries: Arc::default(),
                 vault: vault.clone(),
+                permissions: None,
             })
         }
     }
 
-    fn header(&self, compressed: bool) -> u8 {
-        let mut bits = if self.key.is_some() { 0b1000_0000 } else { 0 };
+    fn header(&self, algorithm: u8) -> u8 {
+        let bits = if self.key.is_some() { 0b1000_0000 } else { 0 };
+        bits | algorithm
+    }
+}
+
+#[cfg(feature = "compression")]
+impl TreeVault {
+    /// Payloads smaller than this aren't compressed.
+    const MINIMUM_COMPRESSION_LENGTH: usize = 128;
+
+    #[cfg(feature = "zstd")]
+    pub(crate) fn with_dictionaries(
+        mut self,
+        dictionaries: Arc<HashMap<u32, CompressionDictionary>>,
+    ) -> Self {
+        self.dictionaries = dictionaries;
+        self
+    }
+
+    /// Compresses `payload` using the configured algorithm, returning the
+    /// algorithm's identifier and the compressed payload. Returns `None` if
+    /// `payload` should be stored uncompressed.
+    fn compress(&self, payload: &[u8]) -> Result<Option<(u8, Vec<u8>)>, Error> {
+        let Some(compression) = &self.compression else {
+            return Ok(None);
+        };
+        if payload.len() < Self::MINIMUM_COMPRESSION_LENGTH {
+            return Ok(None);
+        }
+
+        let compressed = match compression {
+            Compression::Lz4 => lz4_flex::block::compress_prepend_size(payload),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => {
+                let mut compressed = uncompressed_length(payload).to_vec();
+                compressed.extend(zstd::bulk::compress(payload, *level)?);
+                compressed
+            }
+            #[cfg(feature = "zstd")]
+            Compression::ZstdDictionary { level, dictionary } => {
+                let mut compressed = dictionary.id().to_le_bytes().to_vec();
+                compressed.extend(uncompressed_length(payload));
+                compressed.extend(
+                    zstd::bulk::Compressor::with_prepared_dictionary(&dictionary.encoder(*level))?
+                        .compress(payload)?,
+                );
+                compressed
+            }
+        };
+        Ok(Some((compression.algorithm(), compressed)))
+    }
 
-        if compressed {
-            if let Some(compression) = self.compression {
-                bits |= compression as u8;
+    /// Decompresses `payload`, which was compressed using the algorithm
+    /// identified by `algorithm`. Data is decompressed based on the algorithm
+    /// it was stored with, regardless of the algorithm currently configured.
+    fn decompress(&self, algorithm: u8, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        match algorithm {
+            0 => Ok(payload.to_vec()),
+            Compression::LZ4 => {
+                lz4_flex::block::decompress_size_prepended(payload).map_err(Error::from)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::ZSTD => {
+                let (length, payload) = split_u32(payload)?;
+                Ok(zstd::bulk::decompress(payload, length as usize)?)
             }
+            #[cfg(feature = "zstd")]
+            Compression::ZSTD_DICTIONARY => {
+                let (id, payload) = split_u32(payload)?;
+                let dictionary = self.dictionaries.get(&id).ok_or_else(|| {
+                    Error::other(
+                        "compression",
+                        format!("compression dictionary {id} is not configured"),
+                    )
+                })?;
+                let (length, payload) = split_u32(payload)?;
+                Ok(
+                    zstd::bulk::Decompressor::with_prepared_dictionary(dictionary.decoder())?
+                        .decompress(payload, length as usize)?,
+                )
+            }
+            other => Err(Error::other(
+                "compression",
+                format!("unsupported compression algorithm {other}"),
+            )),
         }
+    }
+}
+
+/// Returns the length of `payload` encoded in the format lz4-flex uses for
+/// `decompress_size_prepended`.
+#[cfg(all(feature = "compression", feature = "zstd"))]
+fn uncompressed_length(payload: &[u8]) -> [u8; 4] {
+    u32::try_from(payload.len())
+        .expect("nebari doesn't support >32 bit blocks")
+        .to_le_bytes()
+}
 
-        bits
+#[cfg(all(feature = "compression", feature = "zstd"))]
+fn split_u32(payload: &[u8]) -> Result<(u32, &[u8]), Error> {
+    if payload.len() < 4 {
+        return Err(Error::other("compression", "compressed payload truncated"));
     }
+    let (value, payload) = payload.split_at(4);
+    Ok((
+        u32::from_le_bytes(value.try_into().expect("checked length")),
+        payload,
+    ))
 }
 
 #[cfg(all(feature = "compression", feature = "encryption"))]
@@ -1575,22 +1916,19 @@ impl nebari::Vault for TreeVault {
     fn encrypt(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
         // TODO this allocates too much. The vault should be able to do an
         // in-place encryption operation so that we can use a single buffer.
-        let mut includes_compression = false;
-        let compressed = match (payload.len(), self.compression) {
-            (128..=usize::MAX, Some(Compression::Lz4)) => {
-                includes_compression = true;
-                Cow::Owned(lz4_flex::block::compress_prepend_size(payload))
-            }
-            _ => Cow::Borrowed(payload),
+        let (algorithm, compressed) = match self.compress(payload)? {
+            Some((algorithm, compressed)) => (algorithm, Cow::Owned(compressed)),
+            None => (0, Cow::Borrowed(payload)),
         };
 
         let mut complete = if let Some(key) = &self.key {
-            self.vault.encrypt_payload(key, &compressed, None)?
+            self.vault
+                .encrypt_payload(key, &compressed, self.permissions.as_ref())?
         } else {
             compressed.into_owned()
         };
 
-        let header = self.header(includes_compression);
+        let header = self.header(algorithm);
         if header != 0 {
             let header = [b't', b'r', b'v', header];
             complete.splice(0..0, header);
@@ -1606,19 +1944,17 @@ impl nebari::Vault for TreeVault {
             let encrypted = (header & 0b1000_0000) != 0;
             let compression = header & 0b0111_1111;
             let decrypted = if encrypted {
-                Cow::Owned(self.vault.decrypt_payload(payload, None)?)
+                Cow::Owned(
+                    self.vault
+                        .decrypt_payload(payload, self.permissions.as_ref())?,
+                )
             } else {
                 Cow::Borrowed(payload)
             };
-            #[allow(clippy::single_match)] // Make it an error when we add a new algorithm
-            return Ok(match Compression::from_u8(compression) {
-                Some(Compression::Lz4) => {
-                    lz4_flex::block::decompress_size_prepended(&decrypted).map_err(Error::from)?
-                }
-                None => decrypted.into_owned(),
-            });
+            return self.decompress(compression, &decrypted);
         }
-        self.vault.decrypt_payload(payload, None)
+        self.vault
+            .decrypt_payload(payload, self.permissions.as_ref())
     }
 }
 
@@ -1684,6 +2020,8 @@ impl TreeVault {
     pub(crate) fn new_if_needed(compression: Option<Compression>) -> Option<Self> {
         compression.map(|compression| Self {
             compression: Some(compression),
+            #[cfg(feature = "zstd")]
+            dictionaries: Arc::default(),
         })
     }
 }
@@ -1693,23 +2031,15 @@ impl nebari::Vault for TreeVault {
     type Error = Error;
 
     fn encrypt(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
-        Ok(match (payload.len(), self.compression) {
-            (128..=usize::MAX, Some(Compression::Lz4)) => {
-                let mut destination =
-                    vec![0; lz4_flex::block::get_maximum_output_size(payload.len()) + 8];
-                let compressed_length =
-                    lz4_flex::block::compress_into(payload, &mut destination[8..])
-                        .expect("lz4-flex documents this shouldn't fail");
-                destination.truncate(compressed_length + 8);
-                destination[0..4].copy_from_slice(&[b't', b'r', b'v', Compression::Lz4 as u8]);
-                // to_le_bytes() makes it compatible with lz4-flex decompress_size_prepended.
-                let uncompressed_length =
-                    u32::try_from(payload.len()).expect("nebari doesn't support >32 bit blocks");
-                destination[4..8].copy_from_slice(&uncompressed_length.to_le_bytes());
-                destination
+        Ok(match self.compress(payload)? {
+            Some((algorithm, compressed)) => {
+                let mut complete = Vec::with_capacity(compressed.len() + 4);
+                complete.extend_from_slice(&[b't', b'r', b'v', algorithm]);
+                complete.extend_from_slice(&compressed);
+                complete
             }
             // TODO this shouldn't copy
-            _ => payload.to_vec(),
+            None => payload.to_vec(),
         })
     }
 
@@ -1723,13 +2053,7 @@ impl nebari::Vault for TreeVault {
                 return Err(Error::EncryptionDisabled);
             }
 
-            #[allow(clippy::single_match)] // Make it an error when we add a new algorithm
-            return Ok(match Compression::from_u8(compression) {
-                Some(Compression::Lz4) => {
-                    lz4_flex::block::decompress_size_prepended(payload).map_err(Error::from)?
-                }
-                None => payload.to_vec(),
-            });
+            return self.decompress(compression, payload);
         }
         Ok(payload.to_vec())
     }
@@ -1741,6 +2065,7 @@ impl TreeVault {
         key.map(|key| Self {
             key: Some(key),
             vault: vault.clone(),
+            permissions: None,
         })
     }
 
@@ -1760,7 +2085,8 @@ impl nebari::Vault for TreeVault {
 
     fn encrypt(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
         if let Some(key) = &self.key {
-            self.vault.encrypt_payload(key, payload, None)
+            self.vault
+                .encrypt_payload(key, payload, self.permissions.as_ref())
         } else {
             // TODO does this need to copy?
             Ok(payload.to_vec())
@@ -1768,7 +2094,8 @@ impl nebari::Vault for TreeVault {
     }
 
     fn decrypt(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
-        self.vault.decrypt_payload(payload, None)
+        self.vault
+            .decrypt_payload(payload, self.permissions.as_ref())
     }
 }
 
@@ -1,17 +1,28 @@
+use std::collections::{hash_map, BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::DirEntry;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
 use bonsaidb_core::connection::{LowLevelConnection, Range, Sort, StorageConnection};
 use bonsaidb_core::document::DocumentId;
-use bonsaidb_core::schema::{Collection, Qualified, SchemaName};
-use bonsaidb_core::transaction::{Operation, Transaction};
+use bonsaidb_core::keyvalue::Timestamp;
+use bonsaidb_core::schema::{Collection, CollectionName, Qualified, SchemaName};
+use bonsaidb_core::transaction::{Changes, Operation, Transaction};
 use bonsaidb_core::{admin, AnyError};
+use serde::{Deserialize, Serialize};
 
 use crate::database::keyvalue::Entry;
 use crate::database::DatabaseNonBlocking;
 use crate::{Database, Error, Storage};
 
+mod archive;
+mod verify;
+
+pub use self::archive::{
+    ArchiveCompression, ArchiveEncryption, ArchiveError, ArchiveOptions, BackupArchive,
+};
+pub use self::verify::{BackupEntry, BackupProblem, BackupVerification};
+
 /// A location to store and restore a database from.
 pub trait BackupLocation: Send + Sync {
     /// The error type for the backup location.
@@ -53,44 +64,182 @@ pub trait BackupLocation: Send + Sync {
 
 impl Storage {
     /// Stores a copy of all data in this instance to `location`.
+    ///
+    /// This always stores a full copy of each database. Any incremental
+    /// backups previously stored in `location` will no longer be restored. To
+    /// only store the changes since the last backup, use
+    /// [`backup_incremental()`](Self::backup_incremental).
     pub fn backup<L: AnyBackupLocation>(&self, location: &L) -> Result<(), Error> {
+        self.backup_with_filter(location, &BackupFilter::default())
+    }
+
+    /// Stores a copy of the databases and collections matching `filter` to
+    /// `location`.
+    ///
+    /// See [`backup()`](Self::backup) for more information.
+    pub fn backup_with_filter<L: AnyBackupLocation>(
+        &self,
+        location: &L,
+        filter: &BackupFilter,
+    ) -> Result<(), Error> {
+        for database in self.databases_to_back_up(filter)? {
+            Self::backup_database(&database, location, filter)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the changes made since the last backup to `location`.
+    ///
+    /// For each database, the id of the last transaction that was backed up is
+    /// recorded in `location`. Only the documents that were changed or deleted
+    /// by transactions executed after that transaction are stored. If a
+    /// database has not been backed up to `location` before, a full backup of
+    /// it is stored instead.
+    ///
+    /// Key-value entries are stored when their changes have been persisted.
+    /// Changes that are pending due to the database's
+    /// [`KeyValuePersistence`](crate::config::KeyValuePersistence) are stored
+    /// by a later incremental backup.
+    ///
+    /// [`restore()`](Self::restore) restores the full backup and then applies
+    /// each incremental backup in the order they were stored.
+    pub fn backup_incremental<L: AnyBackupLocation>(&self, location: &L) -> Result<(), Error> {
+        self.backup_incremental_with_filter(location, &BackupFilter::default())
+    }
+
+    /// Stores the changes made since the last backup to the databases and
+    /// collections matching `filter` to `location`.
+    ///
+    /// The collections selected by `filter` must match the ones selected when
+    /// the full backup of each database was stored. If they differ,
+    /// [`Error::BackupFilterMismatch`] is returned, and a new full backup must
+    /// be stored using [`backup_with_filter()`](Self::backup_with_filter).
+    ///
+    /// See [`backup_incremental()`](Self::backup_incremental) for more
+    /// information.
+    pub fn backup_incremental_with_filter<L: AnyBackupLocation>(
+        &self,
+        location: &L,
+        filter: &BackupFilter,
+    ) -> Result<(), Error> {
+        for database in self.databases_to_back_up(filter)? {
+            Self::backup_database_incremental(&database, location, filter)?;
+        }
+
+        Ok(())
+    }
+
+    fn databases_to_back_up(&self, filter: &BackupFilter) -> Result<Vec<Database>, Error> {
         let databases = {
             self.instance
                 .data
                 .available_databases
                 .read()
-                .keys()
-                .cloned()
+                .iter()
+                .filter(|(name, schema)| filter.includes_database(name, schema))
+                .map(|(name, _)| name.clone())
                 .collect::<Vec<_>>()
         };
 
-        for name in databases {
-            let database = self
-                .instance
-                .database_without_schema(&name, Some(self), None)?;
-            Self::backup_database(&database, location)?;
-        }
-
-        Ok(())
+        databases
+            .into_iter()
+            .map(|name| {
+                self.instance
+                    .database_without_schema(&name, Some(self), None)
+            })
+            .collect()
     }
 
     /// Restores all data from a previously stored backup `location`.
     pub fn restore<L: AnyBackupLocation>(&self, location: &L) -> Result<(), Error> {
+        self.restore_to(location, RestorePoint::Latest)
+    }
+
+    /// Restores the data from a previously stored backup `location` as it was
+    /// at `point`.
+    ///
+    /// Each database is restored by restoring its full backup and then
+    /// replaying the changes recorded by its incremental backups up to
+    /// `point`. An error is returned if `point` is earlier than the full
+    /// backup.
+    ///
+    /// If a document was changed both before and after `point` within the same
+    /// incremental backup, the document's contents at `point` were not
+    /// recorded. In this situation, the database is restored as it was at the
+    /// end of the previous backup instead, and
+    /// [`RestoreProgress::restored_through`] reports the transaction the
+    /// database was restored through. Taking incremental backups more
+    /// frequently provides more points that can be restored.
+    ///
+    /// Key-value entries are restored as they were when the last incremental
+    /// backup that is entirely before `point` was stored.
+    pub fn restore_to<L: AnyBackupLocation>(
+        &self,
+        location: &L,
+        point: RestorePoint,
+    ) -> Result<(), Error> {
+        self.restore_with_options(location, RestoreOptions::default().point(point))
+    }
+
+    /// Restores the data from a previously stored backup `location` using
+    /// `options`.
+    ///
+    /// Documents are restored in batches of approximately
+    /// [`RestoreOptions::batch_bytes()`] bytes, limiting the amount of memory
+    /// used and the size of each transaction. The progress of restoring
+    /// each database is recorded after each batch. If a restore is
+    /// interrupted, calling this function again resumes restoring from the
+    /// last recorded batch. Alternatively, deleting the partially restored
+    /// database rolls back the restore.
+    ///
+    /// Only the databases and collections matching
+    /// [`RestoreOptions::filter()`] are restored. Databases can be restored
+    /// using a different name than they were backed up with using
+    /// [`RestoreOptions::rename_database()`].
+    pub fn restore_with_options<L: AnyBackupLocation>(
+        &self,
+        location: &L,
+        mut options: RestoreOptions,
+    ) -> Result<(), Error> {
+        self.instance.check_writable()?;
         for schema in location
             .list_schemas()
             .map_err(|err| Error::Backup(Box::new(err)))?
         {
-            for database in location
+            for source_name in location
                 .list_databases(&schema)
                 .map_err(|err| Error::Backup(Box::new(err)))?
             {
-                // The admin database is already going to be created by the process of creating a database.
-                self.create_database_with_schema(&database, schema.clone(), true)?;
+                if !options.filter.includes_database(&source_name, &schema) {
+                    continue;
+                }
+
+                let name = options.destination_name(&source_name).to_string();
+                let mut state = RestoreState::load(&self.instance.data.path, &name)?;
+                let exists = self
+                    .instance
+                    .data
+                    .available_databases
+                    .read()
+                    .contains_key(&name);
+                if !exists {
+                    // The restore is recorded before the database is created,
+                    // ensuring a database created by an interrupted restore
+                    // is always resumed rather than treated as existing data.
+                    if state.is_none() {
+                        let new_state = RestoreState::default();
+                        new_state.save(&self.instance.data.path, &name)?;
+                        state = Some(new_state);
+                    }
+                    // The admin database is already going to be created by the process of creating a database.
+                    self.create_database_with_schema(&name, schema.clone(), true)?;
+                }
 
-                let database =
-                    self.instance
-                        .database_without_schema(&database, Some(self), None)?;
-                Self::restore_database(&database, location)?;
+                let database = self
+                    .instance
+                    .database_without_schema(&name, Some(self), None)?;
+                Self::restore_database(&database, &source_name, location, &mut options, state)?;
             }
         }
 
@@ -100,9 +249,30 @@ impl Storage {
     pub(crate) fn backup_database(
         database: &Database,
         location: &dyn AnyBackupLocation,
+        filter: &BackupFilter,
     ) -> Result<(), Error> {
         let schema = database.schematic().name.clone();
-        for collection in database.schematic().collections() {
+        // The transaction id must be read before any documents are read to
+        // ensure that incremental backups include every change that might not
+        // be included in this backup.
+        let transaction_id = database.last_transaction_id()?;
+        // Locations can't remove stored objects, so the increments stored
+        // after a previous full backup still exist. Each full backup begins a
+        // new generation of increments to ensure they are never restored.
+        let generation = BackupManifest::load(&schema, database.name(), location)?
+            .map_or(1, |previous| previous.generation + 1);
+        let manifest = BackupManifest {
+            transaction_id,
+            timestamp: transaction_timestamp(database, transaction_id)?,
+            generation,
+            collections: filter.backed_up_collections(),
+            increments: Vec::new(),
+        };
+        for collection in database
+            .schematic()
+            .collections()
+            .filter(|collection| filter.includes_collection(collection))
+        {
             let documents = database.list_from_collection(
                 Range::from(..),
                 Sort::Ascending,
@@ -120,56 +290,319 @@ impl Storage {
                     &document.contents,
                 )?;
             }
-            for ((namespace, key), entry) in database.all_key_value_entries()? {
-                let full_name = format!("{}._key._{key}", namespace.as_deref().unwrap_or(""));
+        }
+        Self::backup_key_value_entries(database, location, KEY_VALUE_CONTAINER)?;
+        manifest.store(database, location)?;
+        Ok(())
+    }
+
+    pub(crate) fn backup_database_incremental(
+        database: &Database,
+        location: &dyn AnyBackupLocation,
+        filter: &BackupFilter,
+    ) -> Result<(), Error> {
+        let mut manifest = if let Some(manifest) =
+            BackupManifest::load(&database.schematic().name, database.name(), location)?
+        {
+            manifest
+        } else {
+            return Self::backup_database(database, location, filter);
+        };
+        // An increment must contain the same collections as the full backup
+        // it is restored on top of.
+        if manifest.collections != filter.backed_up_collections() {
+            return Err(Error::BackupFilterMismatch(database.name().to_string()));
+        }
+
+        let mut increment = BackupIncrement {
+            starting_transaction_id: manifest.last_transaction_id().map(|id| id + 1),
+            transaction_id: None,
+            timestamp: None,
+            changes: Vec::new(),
+            deleted_keys: Vec::new(),
+        };
+        let mut changed_keys = BTreeSet::new();
+        let mut starting_id = increment.starting_transaction_id;
+        loop {
+            let executed = database.list_executed_transactions(starting_id, None)?;
+            if executed.is_empty() {
+                break;
+            }
+
+            for transaction in executed {
+                starting_id = Some(transaction.id + 1);
+                increment.transaction_id = Some(transaction.id);
+                increment.timestamp = transaction.timestamp;
+                match transaction.changes {
+                    Changes::Documents(documents) => {
+                        for (collection, document) in documents
+                            .into_iter()
+                            .filter(|(collection, _)| filter.includes_collection(collection))
+                        {
+                            increment.changes.push(BackedUpChange {
+                                transaction_id: transaction.id,
+                                timestamp: transaction.timestamp,
+                                collection,
+                                id: document.id,
+                                deleted: document.deleted,
+                            });
+                        }
+                    }
+                    Changes::Keys(keys) => {
+                        changed_keys.extend(keys.into_iter().map(|key| (key.namespace, key.key)));
+                    }
+                }
+            }
+        }
+
+        if increment.transaction_id.is_none() {
+            // Nothing has changed since the last backup.
+            return Ok(());
+        }
+
+        let schema = database.schematic().name.clone();
+        let generation = manifest.generation;
+        let index = manifest.increments.len() + 1;
+        for (collection, id) in increment.changed_documents() {
+            if let Some(document) = database.get_from_collection(id.clone(), collection)? {
                 location.store(
                     &schema,
                     database.name(),
-                    "_kv",
-                    &full_name,
-                    &pot::to_vec(&entry)?,
+                    &increment_container(generation, index, &collection.encoded()),
+                    &id.to_string(),
+                    &document.contents,
                 )?;
             }
         }
+
+        // Only the key-value entries changed since the previous backup are
+        // stored. Keys that no longer exist are recorded as deleted.
+        if !changed_keys.is_empty() {
+            let entries = database.all_key_value_entries()?;
+            let container = increment_container(generation, index, KEY_VALUE_CONTAINER);
+            for (namespace, key) in changed_keys {
+                if let Some(entry) = entries.get(&(namespace.clone(), key.clone())) {
+                    location.store(
+                        &schema,
+                        database.name(),
+                        &container,
+                        &key_value_entry_name(namespace.as_deref(), &key),
+                        &pot::to_vec(entry)?,
+                    )?;
+                } else {
+                    increment.deleted_keys.push((namespace, key));
+                }
+            }
+        }
+        location.store(
+            &schema,
+            database.name(),
+            BACKUP_CONTAINER,
+            &increment_name(generation, index),
+            &pot::to_vec(&increment)?,
+        )?;
+
+        manifest.increments.push(increment.summary());
+        manifest.store(database, location)?;
+
         Ok(())
     }
 
+    fn backup_key_value_entries(
+        database: &Database,
+        location: &dyn AnyBackupLocation,
+        container: &str,
+    ) -> Result<(), Error> {
+        let schema = &database.schematic().name;
+        for ((namespace, key), entry) in database.all_key_value_entries()? {
+            location.store(
+                schema,
+                database.name(),
+                container,
+                &key_value_entry_name(namespace.as_deref(), &key),
+                &pot::to_vec(&entry)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Restores the database named `source_name` in `location` into
+    /// `database`.
     pub(crate) fn restore_database(
         database: &Database,
+        source_name: &str,
         location: &dyn AnyBackupLocation,
+        options: &mut RestoreOptions,
+        state: Option<RestoreState>,
     ) -> Result<(), Error> {
         let schema = database.schematic().name.clone();
-        let mut transaction = Transaction::new();
+        let storage_path = &database.storage.instance.data.path;
+        let manifest = BackupManifest::load(&schema, source_name, location)?;
+        if let Some(transaction_id) = manifest
+            .as_ref()
+            .and_then(|manifest| manifest.transaction_id)
+        {
+            let timestamp = manifest.as_ref().and_then(|manifest| manifest.timestamp);
+            if !options.point.includes(transaction_id, timestamp) {
+                return Err(Error::RestorePointUnavailable(format!(
+                    "the full backup of {source_name} is more recent than the restore point"
+                )));
+            }
+        }
+
+        // When resuming, the batch that was being restored when the restore
+        // was interrupted may have already been written, so existing documents
+        // are overwritten rather than treated as conflicts.
+        let resuming = state.is_some();
+        let mut state = state.unwrap_or_default();
+        state.save(storage_path, database.name())?;
+
         // Restore all the collections. However, there's one collection we don't
         // want to restore: the Databases list. This will be recreated during
         // the process of restoring the backup, so we skip it.
         let database_collection = admin::Database::collection_name();
-        for collection in database
+        let mut collections = database
             .schematic()
             .collections()
-            .filter(|c| *c != &database_collection)
+            .filter(|c| *c != &database_collection && options.filter.includes_collection(c))
+            .map(|collection| {
+                let mut ids = location
+                    .list_stored(&schema, source_name, &collection.encoded())?
+                    .into_iter()
+                    .filter(|id_string| id_string.parse::<DocumentId>().is_ok())
+                    .collect::<Vec<_>>();
+                ids.sort_unstable();
+                Ok((collection.clone(), ids))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        collections.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let generation = manifest.as_ref().map_or(0, |manifest| manifest.generation);
+        let restored_through = manifest.as_ref().and_then(|manifest| {
+            manifest
+                .increments
+                .iter()
+                .take(state.increments_restored)
+                .rev()
+                .find_map(|increment| increment.transaction_id)
+                .or(manifest.transaction_id)
+        });
+        let increments = manifest.map_or(0, |manifest| manifest.increments.len());
+        let mut progress = RestoreProgress {
+            database: database.name().to_string(),
+            documents_restored: 0,
+            total_documents: collections.iter().map(|(_, ids)| ids.len() as u64).sum(),
+            increments_restored: state.increments_restored as u64,
+            total_increments: increments as u64,
+            restored_through,
+        };
+        progress.documents_restored = collections
+            .iter()
+            .take(state.collection)
+            .map(|(_, ids)| ids.len() as u64)
+            .sum::<u64>()
+            + state.document as u64;
+        options.report_progress(&progress);
+
+        for (collection_index, (collection, ids)) in
+            collections.iter().enumerate().skip(state.collection)
         {
             let collection_name = collection.encoded();
-            for (id, id_string) in location
-                .list_stored(&schema, database.name(), &collection_name)?
-                .into_iter()
-                .filter_map(|id_string| {
-                    id_string
-                        .parse::<DocumentId>()
-                        .ok()
-                        .map(|id| (id, id_string))
-                })
-            {
-                let contents =
-                    location.load(&schema, database.name(), &collection_name, &id_string)?;
-                transaction.push(Operation::insert(collection.clone(), Some(id), contents));
+            let first_document = if collection_index == state.collection {
+                state.document
+            } else {
+                0
+            };
+            let mut document = first_document;
+            while document < ids.len() {
+                let mut transaction = Transaction::new();
+                let mut batch_bytes = 0;
+                // Each batch contains at least one document, even if the
+                // document is larger than the batch size.
+                while document < ids.len()
+                    && (transaction.operations.is_empty() || batch_bytes < options.batch_bytes)
+                {
+                    let id_string = &ids[document];
+                    let id = id_string.parse::<DocumentId>()?;
+                    let contents =
+                        location.load(&schema, source_name, &collection_name, id_string)?;
+                    batch_bytes += contents.len();
+                    if resuming {
+                        transaction.push(Operation::overwrite(collection.clone(), id, contents));
+                    } else {
+                        transaction.push(Operation::insert(collection.clone(), Some(id), contents));
+                    }
+                    document += 1;
+                }
+                let batch_documents = transaction.operations.len();
+                database.apply_transaction(transaction)?;
+
+                state.collection = collection_index;
+                state.document = document;
+                state.save(storage_path, database.name())?;
+                progress.documents_restored += batch_documents as u64;
+                options.report_progress(&progress);
+            }
+        }
+        state.collection = collections.len();
+        state.document = 0;
+        state.save(storage_path, database.name())?;
+
+        for index in state.increments_restored + 1..=increments {
+            match Self::restore_increment(
+                database,
+                source_name,
+                location,
+                generation,
+                index,
+                options,
+            )? {
+                RestoredIncrement::Complete(transaction_id) => {
+                    state.increments_restored = index;
+                    state.save(storage_path, database.name())?;
+                    progress.increments_restored = index as u64;
+                    progress.restored_through = transaction_id.or(progress.restored_through);
+                    options.report_progress(&progress);
+                }
+                RestoredIncrement::Partial(transaction_id) => {
+                    progress.restored_through = transaction_id.or(progress.restored_through);
+                    options.report_progress(&progress);
+                    break;
+                }
+                RestoredIncrement::Skipped => {
+                    log::warn!(
+                        "{} can't be restored to the requested point, restoring through transaction {:?} instead",
+                        database.name(),
+                        progress.restored_through
+                    );
+                    options.report_progress(&progress);
+                    break;
+                }
             }
         }
-        database.apply_transaction(transaction)?;
 
-        for full_key in location.list_stored(&schema, database.name(), "_kv")? {
+        // Each incremental backup only stores the key-value entries that
+        // changed, so the entries of the full backup are overlaid with the
+        // changes of each restored increment.
+        let mut key_value_entries = BTreeMap::new();
+        for full_key in location.list_stored(&schema, source_name, KEY_VALUE_CONTAINER)? {
+            key_value_entries.insert(full_key, String::from(KEY_VALUE_CONTAINER));
+        }
+        for index in 1..=state.increments_restored {
+            let container = increment_container(generation, index, KEY_VALUE_CONTAINER);
+            for full_key in location.list_stored(&schema, source_name, &container)? {
+                key_value_entries.insert(full_key, container.clone());
+            }
+            let increment =
+                BackupIncrement::load(&schema, source_name, location, generation, index)?;
+            for (namespace, key) in &increment.deleted_keys {
+                key_value_entries.remove(&key_value_entry_name(namespace.as_deref(), key));
+            }
+        }
+
+        for (full_key, container) in key_value_entries {
             if let Some((namespace, key)) = full_key.split_once("._key._") {
-                let entry = location.load(&schema, database.name(), "_kv", &full_key)?;
+                let entry = location.load(&schema, source_name, &container, &full_key)?;
                 let entry = pot::from_slice::<Entry>(&entry)?;
                 let namespace = if namespace.is_empty() {
                     None
@@ -180,8 +613,582 @@ impl Storage {
             }
         }
 
+        RestoreState::remove(storage_path, database.name())?;
+
         Ok(())
     }
+
+    /// Applies the changes from an incremental backup up to the restore point.
+    fn restore_increment(
+        database: &Database,
+        source_name: &str,
+        location: &dyn AnyBackupLocation,
+        generation: u64,
+        index: usize,
+        options: &RestoreOptions,
+    ) -> Result<RestoredIncrement, Error> {
+        let schema = &database.schematic().name;
+        let increment = BackupIncrement::load(schema, source_name, location, generation, index)?;
+
+        let fully_included = increment
+            .transaction_id
+            .map_or(true, |id| options.point.includes(id, increment.timestamp));
+
+        let database_collection = admin::Database::collection_name();
+        let changes = increment
+            .changes_to_restore(options.point)
+            .into_iter()
+            .filter(|((collection, _), _)| {
+                *collection != &database_collection
+                    && options.filter.includes_collection(collection)
+            })
+            .collect::<Vec<_>>();
+        if changes
+            .iter()
+            .any(|(_, inclusion)| matches!(inclusion, Inclusion::Partial))
+        {
+            // The contents of a document at the restore point weren't
+            // recorded, so none of this increment can be restored.
+            return Ok(RestoredIncrement::Skipped);
+        }
+
+        let mut stored_documents = HashMap::new();
+        let mut transaction = Transaction::new();
+        let mut batch_bytes = 0;
+        for ((collection, id), inclusion) in changes {
+            if matches!(inclusion, Inclusion::Excluded) {
+                continue;
+            }
+
+            let container = increment_container(generation, index, &collection.encoded());
+            let stored = match stored_documents.entry(collection) {
+                hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                hash_map::Entry::Vacant(entry) => entry.insert(
+                    location
+                        .list_stored(schema, source_name, &container)?
+                        .into_iter()
+                        .collect::<HashSet<_>>(),
+                ),
+            };
+            let id_string = id.to_string();
+            if stored.contains(&id_string) {
+                let contents = location.load(schema, source_name, &container, &id_string)?;
+                batch_bytes += contents.len();
+                transaction.push(Operation::overwrite(
+                    collection.clone(),
+                    id.clone(),
+                    contents,
+                ));
+            } else if let Some(existing) = database.get_from_collection(id.clone(), collection)? {
+                // The document was deleted.
+                transaction.push(Operation::delete(collection.clone(), existing.header));
+            }
+
+            if batch_bytes >= options.batch_bytes {
+                database.apply_transaction(std::mem::take(&mut transaction))?;
+                batch_bytes = 0;
+            }
+        }
+        database.apply_transaction(transaction)?;
+
+        if fully_included {
+            Ok(RestoredIncrement::Complete(increment.transaction_id))
+        } else {
+            let last_restored = increment
+                .changes
+                .iter()
+                .filter(|change| {
+                    options
+                        .point
+                        .includes(change.transaction_id, change.timestamp)
+                })
+                .map(|change| change.transaction_id)
+                .max();
+            Ok(RestoredIncrement::Partial(last_restored))
+        }
+    }
+}
+
+/// Options for restoring a backup.
+#[must_use]
+pub struct RestoreOptions {
+    point: RestorePoint,
+    batch_bytes: usize,
+    filter: BackupFilter,
+    renames: HashMap<String, String>,
+    progress: Option<Box<dyn FnMut(&RestoreProgress) + Send>>,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        Self {
+            point: RestorePoint::Latest,
+            batch_bytes: Self::DEFAULT_BATCH_BYTES,
+            filter: BackupFilter::default(),
+            renames: HashMap::new(),
+            progress: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for RestoreOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RestoreOptions")
+            .field("point", &self.point)
+            .field("batch_bytes", &self.batch_bytes)
+            .field("filter", &self.filter)
+            .field("renames", &self.renames)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RestoreOptions {
+    /// The default number of bytes of documents restored in each
+    /// transaction: 16 megabytes.
+    pub const DEFAULT_BATCH_BYTES: usize = 16 * 1024 * 1024;
+
+    /// Restores the backup as it was at `point`. The default is
+    /// [`RestorePoint::Latest`].
+    pub fn point(mut self, point: RestorePoint) -> Self {
+        self.point = point;
+        self
+    }
+
+    /// Restores documents in transactions containing approximately
+    /// `batch_bytes` bytes of document contents. Documents are added to each
+    /// transaction until their total size reaches `batch_bytes`, and each
+    /// transaction contains at least one document. The default is
+    /// [`Self::DEFAULT_BATCH_BYTES`].
+    pub fn batch_bytes(mut self, batch_bytes: usize) -> Self {
+        self.batch_bytes = batch_bytes;
+        self
+    }
+
+    /// Only restores the databases and collections matching `filter`. By
+    /// default, everything stored in the backup is restored.
+    ///
+    /// Databases are matched using the name they were backed up with.
+    pub fn filter(mut self, filter: BackupFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Restores the database that was backed up as `source` into a database
+    /// named `destination`. The destination database is created using the
+    /// backed up database's schema if it does not exist.
+    pub fn rename_database(
+        mut self,
+        source: impl Into<String>,
+        destination: impl Into<String>,
+    ) -> Self {
+        self.renames.insert(source.into(), destination.into());
+        self
+    }
+
+    /// Returns the name to restore the database backed up as `source_name`
+    /// into.
+    fn destination_name<'a>(&'a self, source_name: &'a str) -> &'a str {
+        self.renames
+            .get(source_name)
+            .map_or(source_name, String::as_str)
+    }
+
+    /// Invokes `callback` with the progress of the restore after each batch of
+    /// documents is restored.
+    pub fn on_progress<F: FnMut(&RestoreProgress) + Send + 'static>(mut self, callback: F) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    fn report_progress(&mut self, progress: &RestoreProgress) {
+        if let Some(callback) = &mut self.progress {
+            callback(progress);
+        }
+    }
+}
+
+/// Selects which databases and collections are included when backing up or
+/// restoring. By default, everything is included.
+///
+/// Each kind of criteria is only checked once it has been provided. For
+/// example, filtering by database name and collection includes only the
+/// matching collections within the named databases. Key-value entries are
+/// always included with each selected database.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[must_use]
+pub struct BackupFilter {
+    databases: Option<HashSet<String>>,
+    schemas: Option<HashSet<SchemaName>>,
+    collections: Option<HashSet<CollectionName>>,
+}
+
+impl BackupFilter {
+    /// Includes the database named `name`.
+    pub fn database(mut self, name: impl Into<String>) -> Self {
+        self.databases
+            .get_or_insert_with(HashSet::new)
+            .insert(name.into());
+        self
+    }
+
+    /// Includes databases that use the schema named `schema`.
+    pub fn schema(mut self, schema: SchemaName) -> Self {
+        self.schemas.get_or_insert_with(HashSet::new).insert(schema);
+        self
+    }
+
+    /// Includes the collection named `collection`.
+    pub fn collection(mut self, collection: CollectionName) -> Self {
+        self.collections
+            .get_or_insert_with(HashSet::new)
+            .insert(collection);
+        self
+    }
+
+    /// Returns true if the database named `name` using `schema` is included.
+    #[must_use]
+    pub fn includes_database(&self, name: &str, schema: &SchemaName) -> bool {
+        self.databases
+            .as_ref()
+            .map_or(true, |databases| databases.contains(name))
+            && self
+                .schemas
+                .as_ref()
+                .map_or(true, |schemas| schemas.contains(schema))
+    }
+
+    /// Returns true if the collection named `collection` is included.
+    #[must_use]
+    pub fn includes_collection(&self, collection: &CollectionName) -> bool {
+        self.collections
+            .as_ref()
+            .map_or(true, |collections| collections.contains(collection))
+    }
+
+    /// Returns the collections included in a backup of each selected database,
+    /// in the form stored in the backup's manifest.
+    fn backed_up_collections(&self) -> Option<BTreeSet<CollectionName>> {
+        self.collections
+            .as_ref()
+            .map(|collections| collections.iter().cloned().collect())
+    }
+}
+
+/// The progress of restoring a database from a backup.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RestoreProgress {
+    /// The name of the database being restored.
+    pub database: String,
+    /// The number of documents from the full backup that have been restored.
+    pub documents_restored: u64,
+    /// The number of documents in the full backup.
+    pub total_documents: u64,
+    /// The number of incremental backups that have been applied.
+    pub increments_restored: u64,
+    /// The number of incremental backups stored for this database.
+    pub total_increments: u64,
+    /// The id of the last transaction whose changes have been restored, if
+    /// known. When the restore point can't be restored exactly, this reports
+    /// the earlier point that was restored instead.
+    pub restored_through: Option<u64>,
+}
+
+/// The name of the directory that tracks the progress of restoring each
+/// database, allowing an interrupted restore to be resumed.
+const RESTORES_DIRECTORY: &str = "restores";
+
+/// The progress of restoring a database, recorded after each batch.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RestoreState {
+    /// The index of the collection being restored.
+    collection: usize,
+    /// The number of documents of the collection that have been restored.
+    document: usize,
+    /// The number of incremental backups that have been applied.
+    increments_restored: usize,
+}
+
+impl RestoreState {
+    fn path(storage_path: &Path, database_name: &str) -> PathBuf {
+        storage_path.join(RESTORES_DIRECTORY).join(database_name)
+    }
+
+    fn load(storage_path: &Path, database_name: &str) -> Result<Option<Self>, Error> {
+        match std::fs::read(Self::path(storage_path, database_name)) {
+            Ok(contents) => Ok(Some(bincode::deserialize(&contents)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::from(err)),
+        }
+    }
+
+    fn save(&self, storage_path: &Path, database_name: &str) -> Result<(), Error> {
+        let path = Self::path(storage_path, database_name);
+        std::fs::create_dir_all(storage_path.join(RESTORES_DIRECTORY))?;
+        std::fs::write(path, bincode::serialize(self)?)?;
+        Ok(())
+    }
+
+    pub(crate) fn remove(storage_path: &Path, database_name: &str) -> Result<(), Error> {
+        std::fs::remove_file(Self::path(storage_path, database_name))
+            .ignore_not_found()
+            .map(|_| ())
+            .map_err(Error::from)
+    }
+
+    /// Removes the progress of every restore into the storage at
+    /// `storage_path`.
+    pub(crate) fn remove_all(storage_path: &Path) -> Result<(), Error> {
+        std::fs::remove_dir_all(storage_path.join(RESTORES_DIRECTORY))
+            .ignore_not_found()
+            .map(|_| ())
+            .map_err(Error::from)
+    }
+}
+
+/// A point in time to restore a backup to.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RestorePoint {
+    /// Restore all of the data stored in the backup.
+    Latest,
+    /// Restore each database as it was after the transaction with this id was
+    /// executed. Transaction ids are specific to each database.
+    Transaction(u64),
+    /// Restore each database as it was at this time.
+    Timestamp(Timestamp),
+}
+
+impl RestorePoint {
+    /// Returns true if the transaction with `transaction_id` executed at
+    /// `timestamp` should be restored.
+    fn includes(self, transaction_id: u64, timestamp: Option<Timestamp>) -> bool {
+        match self {
+            Self::Latest => true,
+            Self::Transaction(id) => transaction_id <= id,
+            // Transactions executed before timestamps were recorded are assumed
+            // to have been executed before the restore point.
+            Self::Timestamp(point) => timestamp.map_or(true, |timestamp| timestamp <= point),
+        }
+    }
+}
+
+/// The result of restoring an incremental backup.
+enum RestoredIncrement {
+    /// Every change was restored. Contains the id of the increment's last
+    /// transaction.
+    Complete(Option<u64>),
+    /// Only the changes before the restore point were restored. Contains the
+    /// id of the last restored transaction.
+    Partial(Option<u64>),
+    /// A document was changed both before and after the restore point, so no
+    /// changes were restored.
+    Skipped,
+}
+
+/// Whether the changes to a document should be restored.
+#[derive(Clone, Copy)]
+enum Inclusion {
+    /// All changes are before the restore point.
+    Included,
+    /// All changes are after the restore point.
+    Excluded,
+    /// The document was changed both before and after the restore point.
+    Partial,
+}
+
+/// Returns the time the transaction with `transaction_id` was executed, if it
+/// was recorded.
+fn transaction_timestamp(
+    database: &Database,
+    transaction_id: Option<u64>,
+) -> Result<Option<Timestamp>, Error> {
+    if let Some(transaction_id) = transaction_id {
+        Ok(database
+            .list_executed_transactions(Some(transaction_id), Some(1))?
+            .into_iter()
+            .find(|transaction| transaction.id == transaction_id)
+            .and_then(|transaction| transaction.timestamp))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The container that backup metadata is stored in.
+const BACKUP_CONTAINER: &str = "_backup";
+/// The name of the [`BackupManifest`] within [`BACKUP_CONTAINER`].
+const MANIFEST_NAME: &str = "manifest";
+/// The container that key-value entries are stored in.
+const KEY_VALUE_CONTAINER: &str = "_kv";
+
+/// Returns the name of the increment `index` of `generation` within
+/// [`BACKUP_CONTAINER`]. Manifests stored before generations were introduced
+/// use generation 0.
+fn increment_name(generation: u64, index: usize) -> String {
+    if generation == 0 {
+        format!("increment.{index}")
+    } else {
+        format!("increment.{generation}.{index}")
+    }
+}
+
+fn increment_container(generation: u64, index: usize, container: &str) -> String {
+    if generation == 0 {
+        format!("_increment.{index}.{container}")
+    } else {
+        format!("_increment.{generation}.{index}.{container}")
+    }
+}
+
+fn key_value_entry_name(namespace: Option<&str>, key: &str) -> String {
+    format!("{}._key._{key}", namespace.unwrap_or(""))
+}
+
+/// Information about the backups stored for a database.
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    /// The id of the last transaction included in the full backup.
+    transaction_id: Option<u64>,
+    /// The time the last transaction included in the full backup was
+    /// executed.
+    timestamp: Option<Timestamp>,
+    /// Identifies the full backup that the increments belong to. Each full
+    /// backup increments the generation.
+    #[serde(default)]
+    generation: u64,
+    /// The collections included in the full backup, or None if every
+    /// collection was included.
+    #[serde(default)]
+    collections: Option<BTreeSet<CollectionName>>,
+    /// The incremental backups stored since the full backup, in the order
+    /// they must be restored.
+    increments: Vec<IncrementSummary>,
+}
+
+impl BackupManifest {
+    fn load(
+        schema: &SchemaName,
+        database_name: &str,
+        location: &dyn AnyBackupLocation,
+    ) -> Result<Option<Self>, Error> {
+        if location
+            .list_stored(schema, database_name, BACKUP_CONTAINER)?
+            .iter()
+            .any(|name| name == MANIFEST_NAME)
+        {
+            let manifest = location.load(schema, database_name, BACKUP_CONTAINER, MANIFEST_NAME)?;
+            Ok(Some(pot::from_slice(&manifest)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn store(&self, database: &Database, location: &dyn AnyBackupLocation) -> Result<(), Error> {
+        location.store(
+            &database.schematic().name,
+            database.name(),
+            BACKUP_CONTAINER,
+            MANIFEST_NAME,
+            &pot::to_vec(self)?,
+        )
+    }
+
+    fn last_transaction_id(&self) -> Option<u64> {
+        self.increments
+            .iter()
+            .rev()
+            .find_map(|increment| increment.transaction_id)
+            .or(self.transaction_id)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IncrementSummary {
+    starting_transaction_id: Option<u64>,
+    transaction_id: Option<u64>,
+}
+
+/// The list of changes stored in an incremental backup.
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupIncrement {
+    /// The id of the first transaction that could be included in this
+    /// increment.
+    starting_transaction_id: Option<u64>,
+    /// The id of the last transaction included in this increment.
+    transaction_id: Option<u64>,
+    /// The time the last transaction included in this increment was executed.
+    timestamp: Option<Timestamp>,
+    /// Each document change, in the order the changes were executed.
+    changes: Vec<BackedUpChange>,
+    /// The key-value entries that were deleted since the previous backup.
+    #[serde(default)]
+    deleted_keys: Vec<(Option<String>, String)>,
+}
+
+impl BackupIncrement {
+    fn load(
+        schema: &SchemaName,
+        database_name: &str,
+        location: &dyn AnyBackupLocation,
+        generation: u64,
+        index: usize,
+    ) -> Result<Self, Error> {
+        let increment = location.load(
+            schema,
+            database_name,
+            BACKUP_CONTAINER,
+            &increment_name(generation, index),
+        )?;
+        Ok(pot::from_slice(&increment)?)
+    }
+
+    fn summary(&self) -> IncrementSummary {
+        IncrementSummary {
+            starting_transaction_id: self.starting_transaction_id,
+            transaction_id: self.transaction_id,
+        }
+    }
+
+    /// Returns each document changed by this increment, without duplicates.
+    fn changed_documents(&self) -> BTreeSet<(&CollectionName, &DocumentId)> {
+        self.changes
+            .iter()
+            .map(|change| (&change.collection, &change.id))
+            .collect()
+    }
+
+    /// Returns each document changed by this increment, along with whether its
+    /// changes should be restored to reach `point`.
+    fn changes_to_restore(
+        &self,
+        point: RestorePoint,
+    ) -> BTreeMap<(&CollectionName, &DocumentId), Inclusion> {
+        let mut documents = BTreeMap::new();
+        for change in &self.changes {
+            let included = point.includes(change.transaction_id, change.timestamp);
+            documents
+                .entry((&change.collection, &change.id))
+                .and_modify(|inclusion: &mut Inclusion| match (*inclusion, included) {
+                    (Inclusion::Included, false) | (Inclusion::Excluded, true) => {
+                        *inclusion = Inclusion::Partial;
+                    }
+                    _ => {}
+                })
+                .or_insert(if included {
+                    Inclusion::Included
+                } else {
+                    Inclusion::Excluded
+                });
+        }
+        documents
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackedUpChange {
+    transaction_id: u64,
+    timestamp: Option<Timestamp>,
+    collection: CollectionName,
+    id: DocumentId,
+    deleted: bool,
 }
 
 pub trait AnyBackupLocation: Send + Sync {
@@ -212,6 +1219,17 @@ pub trait AnyBackupLocation: Send + Sync {
         container: &str,
         name: &str,
     ) -> Result<Vec<u8>, Error>;
+
+    /// Verifies that the backup stored in this location is complete and
+    /// readable using the schemas registered with `storage`.
+    ///
+    /// See [`Storage::verify_backup()`] for more information.
+    fn verify(&self, storage: &Storage) -> Result<BackupVerification, Error>
+    where
+        Self: Sized,
+    {
+        storage.verify_backup(self)
+    }
 }
 
 impl<L, E> AnyBackupLocation for L
@@ -440,13 +1458,19 @@ fn container_folder(
 
 #[cfg(test)]
 mod tests {
-    use bonsaidb_core::connection::{Connection as _, StorageConnection as _};
+    use std::sync::Arc;
+
+    use bonsaidb_core::connection::{
+        Connection as _, LowLevelConnection as _, StorageConnection as _,
+    };
     use bonsaidb_core::keyvalue::KeyValue;
-    use bonsaidb_core::schema::SerializedCollection;
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
     use bonsaidb_core::test_util::{Basic, TestDirectory};
+    use parking_lot::Mutex;
 
+    use super::RestoreState;
     use crate::config::{Builder, KeyValuePersistence, PersistenceThreshold, StorageConfiguration};
-    use crate::Storage;
+    use crate::{BackupFilter, Error, RestoreOptions, RestorePoint, Storage};
 
     #[test]
     fn backup_restore() -> anyhow::Result<()> {
@@ -495,4 +1519,347 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn incremental_backup_restore() -> anyhow::Result<()> {
+        let backup_destination = TestDirectory::new("incremental-backup-restore.bonsaidb.backup");
+
+        let (updated_doc, deleted_doc, new_doc) = {
+            let database_directory = TestDirectory::new("incremental-backup-restore.bonsaidb");
+            let storage = Storage::open(
+                StorageConfiguration::new(&database_directory).with_schema::<Basic>()?,
+            )?;
+
+            let db = storage.create_database::<Basic>("basic", false)?;
+            let mut updated_doc = Basic::new("original").push_into(&db)?;
+            let deleted_doc = Basic::new("deleted").push_into(&db)?;
+            db.set_numeric_key("key1", 1_u64).execute()?;
+
+            storage.backup(&backup_destination.0).unwrap();
+
+            // Change the database after the full backup.
+            updated_doc.contents.value = String::from("updated");
+            updated_doc.update(&db)?;
+            deleted_doc.delete(&db)?;
+            let new_doc = Basic::new("new").push_into(&db)?;
+            db.set_numeric_key("key2", 2_u64).execute()?;
+
+            storage.backup_incremental(&backup_destination.0).unwrap();
+            // Backing up again without any changes shouldn't affect the
+            // restored data.
+            storage.backup_incremental(&backup_destination.0).unwrap();
+
+            (updated_doc, deleted_doc, new_doc)
+        };
+
+        let database_directory = TestDirectory::new("incremental-backup-restore.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        restored_storage.restore(&backup_destination.0).unwrap();
+
+        let db = restored_storage.database::<Basic>("basic")?;
+        let doc = Basic::get(&updated_doc.header.id, &db)?.expect("updated document not found");
+        assert_eq!(doc.contents.value, "updated");
+        assert!(Basic::get(&deleted_doc.header.id, &db)?.is_none());
+        let doc = Basic::get(&new_doc.header.id, &db)?.expect("new document not found");
+        assert_eq!(doc.contents.value, "new");
+        assert_eq!(db.get_key("key1").into_u64()?, Some(1));
+        assert_eq!(db.get_key("key2").into_u64()?, Some(2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn full_backup_replaces_increments() -> anyhow::Result<()> {
+        let backup_destination =
+            TestDirectory::new("full-backup-replaces-increments.bonsaidb.backup");
+
+        let (doc, new_doc) = {
+            let database_directory = TestDirectory::new("full-backup-replaces-increments.bonsaidb");
+            let storage = Storage::open(
+                StorageConfiguration::new(&database_directory).with_schema::<Basic>()?,
+            )?;
+
+            let db = storage.create_database::<Basic>("basic", false)?;
+            let mut doc = Basic::new("original").push_into(&db)?;
+            db.set_numeric_key("kept", 1_u64).execute()?;
+            storage.backup(&backup_destination.0).unwrap();
+
+            doc.contents.value = String::from("stale");
+            doc.update(&db)?;
+            db.set_numeric_key("stale", 1_u64).execute()?;
+            storage.backup_incremental(&backup_destination.0).unwrap();
+
+            // A new full backup must not restore the previous increments.
+            doc.contents.value = String::from("fresh");
+            doc.update(&db)?;
+            db.delete_key("stale")?;
+            storage.backup(&backup_destination.0).unwrap();
+
+            // Only the changed keys are stored in this increment, and the
+            // deletion of "kept" must be restored.
+            let new_doc = Basic::new("new").push_into(&db)?;
+            db.delete_key("kept")?;
+            db.set_numeric_key("added", 2_u64).execute()?;
+            storage.backup_incremental(&backup_destination.0).unwrap();
+
+            (doc, new_doc)
+        };
+
+        let database_directory = TestDirectory::new("full-backup-replaces-increments.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        restored_storage.restore(&backup_destination.0).unwrap();
+
+        let db = restored_storage.database::<Basic>("basic")?;
+        let restored = Basic::get(&doc.header.id, &db)?.expect("document not found");
+        assert_eq!(restored.contents.value, "fresh");
+        let restored = Basic::get(&new_doc.header.id, &db)?.expect("new document not found");
+        assert_eq!(restored.contents.value, "new");
+        assert_eq!(db.get_key("stale").into_u64()?, None);
+        assert_eq!(db.get_key("kept").into_u64()?, None);
+        assert_eq!(db.get_key("added").into_u64()?, Some(2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn point_in_time_restore() -> anyhow::Result<()> {
+        let backup_destination = TestDirectory::new("point-in-time-restore.bonsaidb.backup");
+
+        let (updated_doc, new_doc, deleted_doc, restore_point) = {
+            let database_directory = TestDirectory::new("point-in-time-restore.bonsaidb");
+            let storage = Storage::open(
+                StorageConfiguration::new(&database_directory).with_schema::<Basic>()?,
+            )?;
+
+            let db = storage.create_database::<Basic>("basic", false)?;
+            let mut updated_doc = Basic::new("original").push_into(&db)?;
+            let deleted_doc = Basic::new("deleted").push_into(&db)?;
+            storage.backup(&backup_destination.0).unwrap();
+
+            updated_doc.contents.value = String::from("updated");
+            updated_doc.update(&db)?;
+            let restore_point = db.last_transaction_id()?.unwrap();
+
+            // These changes happen after the restore point.
+            let new_doc = Basic::new("new").push_into(&db)?;
+            deleted_doc.delete(&db)?;
+
+            storage.backup_incremental(&backup_destination.0).unwrap();
+
+            (updated_doc, new_doc, deleted_doc, restore_point)
+        };
+
+        let database_directory = TestDirectory::new("point-in-time-restore.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        restored_storage
+            .restore_to(
+                &backup_destination.0,
+                RestorePoint::Transaction(restore_point),
+            )
+            .unwrap();
+
+        let db = restored_storage.database::<Basic>("basic")?;
+        let doc = Basic::get(&updated_doc.header.id, &db)?.expect("updated document not found");
+        assert_eq!(doc.contents.value, "updated");
+        assert!(Basic::get(&new_doc.header.id, &db)?.is_none());
+        let doc = Basic::get(&deleted_doc.header.id, &db)?.expect("deleted document not found");
+        assert_eq!(doc.contents.value, "deleted");
+
+        // A point before the full backup can't be restored.
+        let database_directory = TestDirectory::new("point-in-time-restore-early.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        assert!(matches!(
+            restored_storage.restore_to(&backup_destination.0, RestorePoint::Transaction(0)),
+            Err(Error::RestorePointUnavailable(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn restore_point_falls_back_to_increment_boundary() -> anyhow::Result<()> {
+        let backup_destination = TestDirectory::new("restore-point-boundary.bonsaidb.backup");
+
+        let (doc, full_backup_point, restore_point) = {
+            let database_directory = TestDirectory::new("restore-point-boundary.bonsaidb");
+            let storage = Storage::open(
+                StorageConfiguration::new(&database_directory).with_schema::<Basic>()?,
+            )?;
+
+            let db = storage.create_database::<Basic>("basic", false)?;
+            let mut doc = Basic::new("original").push_into(&db)?;
+            storage.backup(&backup_destination.0).unwrap();
+            let full_backup_point = db.last_transaction_id()?.unwrap();
+
+            // The document is changed both before and after the restore point
+            // within the same increment.
+            doc.contents.value = String::from("before");
+            doc.update(&db)?;
+            let restore_point = db.last_transaction_id()?.unwrap();
+            doc.contents.value = String::from("after");
+            doc.update(&db)?;
+            storage.backup_incremental(&backup_destination.0).unwrap();
+
+            (doc, full_backup_point, restore_point)
+        };
+
+        let database_directory = TestDirectory::new("restore-point-boundary.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        let reported = Arc::new(Mutex::new(None));
+        let progress = reported.clone();
+        restored_storage
+            .restore_with_options(
+                &backup_destination.0,
+                RestoreOptions::default()
+                    .point(RestorePoint::Transaction(restore_point))
+                    .on_progress(move |status| {
+                        *progress.lock() = status.restored_through;
+                    }),
+            )
+            .unwrap();
+        assert_eq!(*reported.lock(), Some(full_backup_point));
+
+        let db = restored_storage.database::<Basic>("basic")?;
+        let restored = Basic::get(&doc.header.id, &db)?.expect("document not found");
+        assert_eq!(restored.contents.value, "original");
+
+        Ok(())
+    }
+
+    #[test]
+    fn batched_restore() -> anyhow::Result<()> {
+        let backup_destination = TestDirectory::new("batched-restore.bonsaidb.backup");
+
+        let doc_ids = {
+            let database_directory = TestDirectory::new("batched-restore.bonsaidb");
+            let storage = Storage::open(
+                StorageConfiguration::new(&database_directory).with_schema::<Basic>()?,
+            )?;
+
+            let db = storage.create_database::<Basic>("basic", false)?;
+            let mut doc_ids = Vec::new();
+            for i in 0..5 {
+                doc_ids.push(Basic::new(i.to_string()).push_into(&db)?.header.id);
+            }
+            storage.backup(&backup_destination.0).unwrap();
+            doc_ids
+        };
+
+        let database_directory = TestDirectory::new("batched-restore.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let progress = reported.clone();
+        restored_storage
+            .restore_with_options(
+                &backup_destination.0,
+                RestoreOptions::default()
+                    .batch_bytes(1)
+                    .on_progress(move |status| {
+                        progress.lock().push(status.documents_restored);
+                    }),
+            )
+            .unwrap();
+        // Every document is larger than the batch size, so each batch contains
+        // a single document.
+        assert_eq!(&*reported.lock(), &[0, 1, 2, 3, 4, 5]);
+
+        let db = restored_storage.database::<Basic>("basic")?;
+        for (i, id) in doc_ids.iter().enumerate() {
+            let doc = Basic::get(id, &db)?.expect("restored document not found");
+            assert_eq!(doc.contents.value, i.to_string());
+        }
+
+        // Simulate a restore that was interrupted after the first batch. The
+        // restore should resume rather than fail because the database already
+        // exists.
+        RestoreState {
+            collection: 0,
+            document: 2,
+            increments_restored: 0,
+        }
+        .save(database_directory.as_ref(), "basic")?;
+        restored_storage.restore(&backup_destination.0).unwrap();
+        assert!(
+            RestoreState::load(database_directory.as_ref(), "basic")?.is_none(),
+            "restore state not removed after completing"
+        );
+
+        // Restoring again without an interrupted restore is an error.
+        assert!(restored_storage.restore(&backup_destination.0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn selective_backup_restore() -> anyhow::Result<()> {
+        let backup_destination = TestDirectory::new("selective-backup-restore.bonsaidb.backup");
+
+        let doc = {
+            let database_directory = TestDirectory::new("selective-backup-restore.bonsaidb");
+            let storage = Storage::open(
+                StorageConfiguration::new(&database_directory).with_schema::<Basic>()?,
+            )?;
+
+            let tenant_a = storage.create_database::<Basic>("tenant-a", false)?;
+            let doc = Basic::new("tenant-a").push_into(&tenant_a)?;
+            let tenant_b = storage.create_database::<Basic>("tenant-b", false)?;
+            Basic::new("tenant-b").push_into(&tenant_b)?;
+
+            storage
+                .backup_with_filter(
+                    &backup_destination.0,
+                    &BackupFilter::default().database("tenant-a"),
+                )
+                .unwrap();
+
+            // Incremental backups must select the same collections as the full
+            // backup they build upon.
+            let result = storage.backup_incremental_with_filter(
+                &backup_destination.0,
+                &BackupFilter::default()
+                    .database("tenant-a")
+                    .collection(Basic::collection_name()),
+            );
+            assert!(matches!(result, Err(Error::BackupFilterMismatch(name)) if name == "tenant-a"));
+            storage.backup_incremental_with_filter(
+                &backup_destination.0,
+                &BackupFilter::default().database("tenant-a"),
+            )?;
+
+            doc
+        };
+
+        let database_directory = TestDirectory::new("selective-backup-restore.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        restored_storage
+            .restore_with_options(
+                &backup_destination.0,
+                RestoreOptions::default().rename_database("tenant-a", "staging"),
+            )
+            .unwrap();
+
+        let db = restored_storage.database::<Basic>("staging")?;
+        let restored = Basic::get(&doc.header.id, &db)?.expect("restored document not found");
+        assert_eq!(restored.contents.value, "tenant-a");
+        assert!(restored_storage.database::<Basic>("tenant-a").is_err());
+        assert!(restored_storage.database::<Basic>("tenant-b").is_err());
+
+        // Filtering out the only backed up database restores nothing.
+        restored_storage
+            .restore_with_options(
+                &backup_destination.0,
+                RestoreOptions::default().filter(BackupFilter::default().database("tenant-b")),
+            )
+            .unwrap();
+        assert!(restored_storage.database::<Basic>("tenant-b").is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,860 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(feature = "encryption")]
+use bonsaidb_core::document::KeyId;
+use bonsaidb_core::schema::{Qualified, SchemaName};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::BackupLocation;
+
+/// The bytes at the start and end of every archive.
+const MAGIC: &[u8; 8] = b"BonsaiAr";
+/// The current version of the archive format.
+const CURRENT_VERSION: u32 = 1;
+
+/// A [`BackupLocation`] that stores all objects in a single file.
+///
+/// The archive begins with a header describing how objects are compressed and
+/// encrypted, followed by each stored object. A manifest containing the
+/// location and checksum of each object is written to the end of the archive
+/// by [`finish()`](Self::finish). Storing to an existing archive appends the
+/// new objects and a new manifest, which allows incremental backups to be
+/// stored in the same archive as the full backup they are based on.
+///
+/// The manifest is compressed and encrypted the same way as the objects
+/// stored in the archive. If storing to an existing archive is interrupted
+/// before its new manifest is written, [`open()`](Self::open) falls back to the
+/// last complete manifest in the archive.
+///
+/// Archives are never rewritten in place: objects that are stored again, such
+/// as when a full backup is stored to an existing archive, and the manifests of
+/// previous backups remain in the file. Use [`create()`](Self::create) to start
+/// a new archive when this space should be reclaimed.
+#[derive(Debug)]
+pub struct BackupArchive {
+    header: ArchiveHeader,
+    cipher: Option<Cipher>,
+    state: Mutex<ArchiveState>,
+}
+
+#[derive(Debug)]
+struct ArchiveState {
+    file: File,
+    entries: BTreeMap<EntryKey, ArchiveEntry>,
+    modified: bool,
+}
+
+impl BackupArchive {
+    /// Creates a new archive at `path` using `options`. If a file already
+    /// exists at `path`, it will be overwritten.
+    pub fn create<P: AsRef<Path>>(path: P, options: ArchiveOptions) -> Result<Self, ArchiveError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let (header, cipher) = options.into_header()?;
+        let header_bytes = pot::to_vec(&header)?;
+        file.write_all(MAGIC)?;
+        file.write_u32::<LittleEndian>(u32::try_from(header_bytes.len()).map_err(|_| {
+            ArchiveError::InvalidArchive(String::from("archive header too large"))
+        })?)?;
+        file.write_all(&header_bytes)?;
+
+        Ok(Self {
+            header,
+            cipher,
+            state: Mutex::new(ArchiveState {
+                file,
+                entries: BTreeMap::new(),
+                modified: true,
+            }),
+        })
+    }
+
+    /// Opens the archive at `path`, creating it using `options` if it does not
+    /// exist.
+    ///
+    /// When opening an existing archive, the compression stored in the
+    /// archive's header is used. If the archive is encrypted, `options` must
+    /// contain the encryption it was created with.
+    pub fn open<P: AsRef<Path>>(path: P, options: ArchiveOptions) -> Result<Self, ArchiveError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Self::create(path, options);
+        }
+
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut magic = [0; MAGIC.len()];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(ArchiveError::InvalidArchive(String::from(
+                "missing archive header",
+            )));
+        }
+        let header_length = file.read_u32::<LittleEndian>()?;
+        let mut header_bytes = vec![0; to_usize(u64::from(header_length))?];
+        file.read_exact(&mut header_bytes)?;
+        let header = pot::from_slice::<ArchiveHeader>(&header_bytes)?;
+        if header.version > CURRENT_VERSION {
+            return Err(ArchiveError::InvalidArchive(format!(
+                "unsupported archive version {}",
+                header.version
+            )));
+        }
+        let cipher = options.into_cipher(&header)?;
+
+        // Read the footer: the sealed manifest, followed by its length and the
+        // magic bytes. If the process crashed while appending to the archive,
+        // the end of the file will not be a valid footer, and the manifest
+        // written by the last finished backup is used instead.
+        let data_start = (MAGIC.len() + 4) as u64 + u64::from(header_length);
+        let end = file.seek(SeekFrom::End(0))?;
+        let manifest = if let Some(manifest) = read_footer(&mut file, data_start, end)? {
+            let manifest = unseal(&header, cipher.as_ref(), &manifest)?;
+            pot::from_slice::<ArchiveManifest>(&manifest)?
+        } else if let Some((footer_end, manifest)) =
+            find_last_footer(&mut file, &header, cipher.as_ref(), data_start, end)?
+        {
+            log::warn!(
+                "backup archive {path:?} was not finished, ignoring the last {} bytes",
+                end - footer_end
+            );
+            manifest
+        } else {
+            return Err(ArchiveError::InvalidArchive(String::from(
+                "the archive was not finished",
+            )));
+        };
+
+        Ok(Self {
+            header,
+            cipher,
+            state: Mutex::new(ArchiveState {
+                file,
+                entries: manifest
+                    .entries
+                    .into_iter()
+                    .map(|entry| (entry.key.clone(), entry))
+                    .collect(),
+                modified: false,
+            }),
+        })
+    }
+
+    /// Writes the manifest of all stored objects to the end of the archive.
+    /// This must be called after storing a backup for the archive to be
+    /// readable.
+    ///
+    /// If an archive is dropped without calling this function, the manifest is
+    /// written when it is dropped, but any errors will only be logged.
+    pub fn finish(self) -> Result<(), ArchiveError> {
+        self.write_manifest()
+    }
+
+    /// Returns the compression used by this archive.
+    #[must_use]
+    pub const fn compression(&self) -> ArchiveCompression {
+        self.header.compression
+    }
+
+    /// Returns true if the objects in this archive are encrypted.
+    #[must_use]
+    pub const fn is_encrypted(&self) -> bool {
+        self.header.encryption.is_some()
+    }
+
+    fn write_manifest(&self) -> Result<(), ArchiveError> {
+        let mut state = self.state.lock();
+        if !state.modified {
+            return Ok(());
+        }
+
+        let manifest = ArchiveManifest {
+            entries: state.entries.values().cloned().collect(),
+        };
+        let manifest = seal(&self.header, self.cipher.as_ref(), &pot::to_vec(&manifest)?)?;
+        state.file.seek(SeekFrom::End(0))?;
+        state.file.write_all(&manifest)?;
+        state
+            .file
+            .write_u64::<LittleEndian>(manifest.len() as u64)?;
+        state.file.write_all(MAGIC)?;
+        state.file.sync_all()?;
+        state.modified = false;
+        Ok(())
+    }
+
+    fn entry_key(
+        schema: &SchemaName,
+        database_name: &str,
+        container: &str,
+        name: &str,
+    ) -> EntryKey {
+        EntryKey {
+            schema: schema.encoded(),
+            database: database_name.to_string(),
+            container: container.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Drop for BackupArchive {
+    fn drop(&mut self) {
+        if let Err(err) = self.write_manifest() {
+            log::error!("Error writing backup archive manifest: {err}");
+        }
+    }
+}
+
+impl BackupLocation for BackupArchive {
+    type Error = ArchiveError;
+
+    fn store(
+        &self,
+        schema: &SchemaName,
+        database_name: &str,
+        container: &str,
+        name: &str,
+        object: &[u8],
+    ) -> Result<(), Self::Error> {
+        let sealed = seal(&self.header, self.cipher.as_ref(), object)?;
+        let key = Self::entry_key(schema, database_name, container, name);
+
+        let mut state = self.state.lock();
+        let offset = state.file.seek(SeekFrom::End(0))?;
+        state.file.write_all(&sealed)?;
+        state.entries.insert(
+            key.clone(),
+            ArchiveEntry {
+                key,
+                offset,
+                length: sealed.len() as u64,
+                checksum: crc32fast::hash(object),
+            },
+        );
+        state.modified = true;
+
+        Ok(())
+    }
+
+    fn list_schemas(&self) -> Result<Vec<SchemaName>, Self::Error> {
+        let state = self.state.lock();
+        let mut schemas = state
+            .entries
+            .keys()
+            .filter_map(|key| SchemaName::parse_encoded(&key.schema).ok())
+            .collect::<Vec<_>>();
+        schemas.dedup();
+        Ok(schemas)
+    }
+
+    fn list_databases(&self, schema: &SchemaName) -> Result<Vec<String>, Self::Error> {
+        let schema = schema.encoded();
+        let state = self.state.lock();
+        let mut databases = state
+            .entries
+            .keys()
+            .filter(|key| key.schema == schema)
+            .map(|key| key.database.clone())
+            .collect::<Vec<_>>();
+        databases.dedup();
+        Ok(databases)
+    }
+
+    fn list_stored(
+        &self,
+        schema: &SchemaName,
+        database_name: &str,
+        container: &str,
+    ) -> Result<Vec<String>, Self::Error> {
+        let schema = schema.encoded();
+        let state = self.state.lock();
+        Ok(state
+            .entries
+            .keys()
+            .filter(|key| {
+                key.schema == schema && key.database == database_name && key.container == container
+            })
+            .map(|key| key.name.clone())
+            .collect())
+    }
+
+    fn load(
+        &self,
+        schema: &SchemaName,
+        database_name: &str,
+        container: &str,
+        name: &str,
+    ) -> Result<Vec<u8>, Self::Error> {
+        let key = Self::entry_key(schema, database_name, container, name);
+        let (sealed, checksum) = {
+            let mut state = self.state.lock();
+            let entry = state
+                .entries
+                .get(&key)
+                .cloned()
+                .ok_or_else(|| ArchiveError::NotFound(key.to_string()))?;
+            state.file.seek(SeekFrom::Start(entry.offset))?;
+            let mut sealed = vec![0; to_usize(entry.length)?];
+            state.file.read_exact(&mut sealed)?;
+            (sealed, entry.checksum)
+        };
+
+        let object = unseal(&self.header, self.cipher.as_ref(), &sealed)?;
+        if crc32fast::hash(&object) == checksum {
+            Ok(object)
+        } else {
+            Err(ArchiveError::ChecksumMismatch(key.to_string()))
+        }
+    }
+}
+
+/// Options for creating a [`BackupArchive`].
+#[derive(Debug, Default)]
+#[must_use]
+pub struct ArchiveOptions {
+    compression: ArchiveCompression,
+    encryption: Option<ArchiveEncryption>,
+}
+
+impl ArchiveOptions {
+    /// Compresses each object stored in the archive using `compression`. The
+    /// default is [`ArchiveCompression::None`].
+    pub fn compression(mut self, compression: ArchiveCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Encrypts each object stored in the archive using `encryption`. By
+    /// default, archives are not encrypted.
+    pub fn encryption(mut self, encryption: ArchiveEncryption) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    fn into_header(self) -> Result<(ArchiveHeader, Option<Cipher>), ArchiveError> {
+        let (encryption, cipher) = match self.encryption {
+            Some(encryption) => {
+                let (header, cipher) = encryption.into_cipher(None)?;
+                (Some(header), Some(cipher))
+            }
+            None => (None, None),
+        };
+        Ok((
+            ArchiveHeader {
+                version: CURRENT_VERSION,
+                compression: self.compression,
+                encryption,
+            },
+            cipher,
+        ))
+    }
+
+    fn into_cipher(self, header: &ArchiveHeader) -> Result<Option<Cipher>, ArchiveError> {
+        match (&header.encryption, self.encryption) {
+            (Some(existing), Some(encryption)) => {
+                let (_, cipher) = encryption.into_cipher(Some(existing))?;
+                Ok(Some(cipher))
+            }
+            (Some(_), None) => Err(ArchiveError::EncryptionRequired),
+            (None, Some(_)) => Err(ArchiveError::Encryption(String::from(
+                "the archive is not encrypted",
+            ))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// The compression used for objects stored in a [`BackupArchive`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub enum ArchiveCompression {
+    /// Objects are not compressed.
+    #[default]
+    None,
+    /// Objects are compressed using
+    /// [lz4_flex](https://crates.io/crates/lz4_flex).
+    #[cfg(feature = "compression")]
+    Lz4,
+    /// Objects are compressed using [zstd](https://crates.io/crates/zstd) at
+    /// the given compression level.
+    #[cfg(feature = "zstd")]
+    Zstd {
+        /// The compression level, from 1 to 22. 0 uses zstd's default level.
+        level: i32,
+    },
+}
+
+impl FromStr for ArchiveCompression {
+    type Err = String;
+
+    /// Parses `none`, `lz4`, `zstd`, or `zstd:<level>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':').unwrap_or((s, "")) {
+            ("none", "") => Ok(Self::None),
+            #[cfg(feature = "compression")]
+            ("lz4", "") => Ok(Self::Lz4),
+            #[cfg(feature = "zstd")]
+            ("zstd", level) => {
+                let level = if level.is_empty() {
+                    0
+                } else {
+                    level
+                        .parse()
+                        .map_err(|_| format!("invalid zstd level: {level}"))?
+                };
+                Ok(Self::Zstd { level })
+            }
+            _ => Err(format!("unsupported compression: {s}")),
+        }
+    }
+}
+
+/// The encryption used for objects stored in a [`BackupArchive`].
+#[derive(Debug)]
+pub enum ArchiveEncryption {
+    /// Objects are encrypted using XChaCha20Poly1305 with a key derived from
+    /// this passphrase using Argon2.
+    #[cfg(all(feature = "encryption", feature = "password-hashing"))]
+    Passphrase(bonsaidb_core::connection::SensitiveString),
+    /// Objects are encrypted using a key from `storage`'s vault. The archive
+    /// can only be read using a storage that has access to the same key.
+    #[cfg(feature = "encryption")]
+    VaultKey {
+        /// The storage whose vault contains the encryption key.
+        storage: crate::Storage,
+        /// The id of the key to encrypt with.
+        key: KeyId,
+    },
+}
+
+impl ArchiveEncryption {
+    #[cfg_attr(
+        not(feature = "encryption"),
+        allow(unused_variables, clippy::needless_pass_by_value)
+    )]
+    fn into_cipher(
+        self,
+        existing: Option<&EncryptionHeader>,
+    ) -> Result<(EncryptionHeader, Cipher), ArchiveError> {
+        match self {
+            #[cfg(all(feature = "encryption", feature = "password-hashing"))]
+            Self::Passphrase(passphrase) => {
+                let salt = match existing {
+                    Some(EncryptionHeader::Passphrase { salt }) => salt.clone(),
+                    None => {
+                        use rand::Rng;
+                        rand::thread_rng().gen::<[u8; 16]>().to_vec()
+                    }
+                    Some(_) => {
+                        return Err(ArchiveError::Encryption(String::from(
+                            "the archive is not encrypted with a passphrase",
+                        )))
+                    }
+                };
+                let mut key = zeroize::Zeroizing::new([0; 32]);
+                argon2::Argon2::default()
+                    .hash_password_into(passphrase.as_bytes(), &salt, &mut *key)
+                    .map_err(|err| ArchiveError::Encryption(err.to_string()))?;
+                Ok((
+                    EncryptionHeader::Passphrase { salt },
+                    Cipher::Passphrase(key),
+                ))
+            }
+            #[cfg(feature = "encryption")]
+            Self::VaultKey { storage, key } => {
+                match existing {
+                    Some(EncryptionHeader::VaultKey { key: existing_key })
+                        if existing_key != &key =>
+                    {
+                        return Err(ArchiveError::Encryption(format!(
+                            "the archive is encrypted with a different key: {existing_key:?}"
+                        )))
+                    }
+                    Some(EncryptionHeader::VaultKey { .. }) | None => {}
+                    #[cfg(feature = "password-hashing")]
+                    Some(EncryptionHeader::Passphrase { .. }) => {
+                        return Err(ArchiveError::Encryption(String::from(
+                            "the archive is encrypted with a passphrase",
+                        )))
+                    }
+                }
+                Ok((
+                    EncryptionHeader::VaultKey { key: key.clone() },
+                    Cipher::VaultKey { storage, key },
+                ))
+            }
+        }
+    }
+}
+
+/// How the objects in an archive are encrypted, stored in the archive's
+/// header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum EncryptionHeader {
+    #[cfg(feature = "password-hashing")]
+    Passphrase { salt: Vec<u8> },
+    #[cfg(feature = "encryption")]
+    VaultKey { key: KeyId },
+}
+
+enum Cipher {
+    #[cfg(all(feature = "encryption", feature = "password-hashing"))]
+    Passphrase(zeroize::Zeroizing<[u8; 32]>),
+    #[cfg(feature = "encryption")]
+    VaultKey { storage: crate::Storage, key: KeyId },
+}
+
+impl std::fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(all(feature = "encryption", feature = "password-hashing"))]
+            Self::Passphrase(_) => f.debug_tuple("Passphrase").finish_non_exhaustive(),
+            #[cfg(feature = "encryption")]
+            Self::VaultKey { key, .. } => f
+                .debug_struct("VaultKey")
+                .field("key", key)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl Cipher {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+        match self {
+            #[cfg(feature = "password-hashing")]
+            Self::Passphrase(key) => {
+                use chacha20poly1305::aead::generic_array::GenericArray;
+                use chacha20poly1305::aead::Aead;
+                use chacha20poly1305::{KeyInit, XChaCha20Poly1305};
+                use rand::Rng;
+
+                let nonce: [u8; 24] = rand::thread_rng().gen();
+                let encrypted = XChaCha20Poly1305::new(GenericArray::from_slice(&key[..]))
+                    .encrypt(GenericArray::from_slice(&nonce), data)
+                    .map_err(|err| ArchiveError::Encryption(err.to_string()))?;
+                let mut sealed = Vec::with_capacity(nonce.len() + encrypted.len());
+                sealed.extend_from_slice(&nonce);
+                sealed.extend_from_slice(&encrypted);
+                Ok(sealed)
+            }
+            Self::VaultKey { storage, key } => storage
+                .vault()
+                .encrypt_payload(key, data, vault_key_permissions(storage, key).as_ref())
+                .map_err(|err| ArchiveError::Encryption(err.to_string())),
+        }
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+        match self {
+            #[cfg(feature = "password-hashing")]
+            Self::Passphrase(key) => {
+                use chacha20poly1305::aead::generic_array::GenericArray;
+                use chacha20poly1305::aead::Aead;
+                use chacha20poly1305::{KeyInit, XChaCha20Poly1305};
+
+                if data.len() < 24 {
+                    return Err(ArchiveError::Encryption(String::from(
+                        "encrypted object is too short",
+                    )));
+                }
+                let (nonce, encrypted) = data.split_at(24);
+                XChaCha20Poly1305::new(GenericArray::from_slice(&key[..]))
+                    .decrypt(GenericArray::from_slice(nonce), encrypted)
+                    .map_err(|err| ArchiveError::Encryption(err.to_string()))
+            }
+            Self::VaultKey { storage, key } => storage
+                .vault()
+                .decrypt_payload(data, vault_key_permissions(storage, key).as_ref())
+                .map_err(|err| ArchiveError::Encryption(err.to_string())),
+        }
+    }
+}
+
+/// Returns the permissions to check before using `key`. Only named keys are
+/// subject to permission checks.
+#[cfg(feature = "encryption")]
+fn vault_key_permissions(
+    storage: &crate::Storage,
+    key: &KeyId,
+) -> Option<bonsaidb_core::permissions::Permissions> {
+    use bonsaidb_core::connection::HasSession;
+
+    match key {
+        KeyId::Id(_) => storage.session().map(|session| session.permissions.clone()),
+        KeyId::Master | KeyId::None => None,
+    }
+}
+
+#[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+fn seal(
+    header: &ArchiveHeader,
+    cipher: Option<&Cipher>,
+    data: &[u8],
+) -> Result<Vec<u8>, ArchiveError> {
+    let compressed = match header.compression {
+        ArchiveCompression::None => data.to_vec(),
+        #[cfg(feature = "compression")]
+        ArchiveCompression::Lz4 => lz4_flex::block::compress_prepend_size(data),
+        #[cfg(feature = "zstd")]
+        ArchiveCompression::Zstd { level } => zstd::bulk::compress(data, level)?,
+    };
+    #[cfg(feature = "encryption")]
+    if let Some(cipher) = cipher {
+        return cipher.encrypt(&compressed);
+    }
+    Ok(compressed)
+}
+
+#[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+fn unseal(
+    header: &ArchiveHeader,
+    cipher: Option<&Cipher>,
+    data: &[u8],
+) -> Result<Vec<u8>, ArchiveError> {
+    #[cfg(feature = "encryption")]
+    let decrypted = if let Some(cipher) = cipher {
+        cipher.decrypt(data)?
+    } else {
+        data.to_vec()
+    };
+    #[cfg(not(feature = "encryption"))]
+    let decrypted = data.to_vec();
+    match header.compression {
+        ArchiveCompression::None => Ok(decrypted),
+        #[cfg(feature = "compression")]
+        ArchiveCompression::Lz4 => lz4_flex::block::decompress_size_prepended(&decrypted)
+            .map_err(|err| ArchiveError::Compression(err.to_string())),
+        #[cfg(feature = "zstd")]
+        ArchiveCompression::Zstd { .. } => Ok(zstd::stream::decode_all(&decrypted[..])?),
+    }
+}
+
+/// The length of the fixed-size portion of a footer: the length of the
+/// manifest followed by the magic bytes.
+const FOOTER_LENGTH: u64 = MAGIC.len() as u64 + 8;
+
+/// Reads the sealed manifest of the footer ending at `footer_end`, returning
+/// None if there is no footer ending at that location.
+fn read_footer(
+    file: &mut File,
+    data_start: u64,
+    footer_end: u64,
+) -> Result<Option<Vec<u8>>, ArchiveError> {
+    if footer_end < data_start + FOOTER_LENGTH {
+        return Ok(None);
+    }
+    file.seek(SeekFrom::Start(footer_end - FOOTER_LENGTH))?;
+    let manifest_length = file.read_u64::<LittleEndian>()?;
+    let mut magic = [0; MAGIC.len()];
+    file.read_exact(&mut magic)?;
+    if &magic != MAGIC || manifest_length > footer_end - FOOTER_LENGTH - data_start {
+        return Ok(None);
+    }
+    let manifest_start = footer_end - FOOTER_LENGTH - manifest_length;
+    file.seek(SeekFrom::Start(manifest_start))?;
+    let mut manifest = vec![0; to_usize(manifest_length)?];
+    file.read_exact(&mut manifest)?;
+    Ok(Some(manifest))
+}
+
+/// Searches backwards from `end` for the most recent footer whose manifest can
+/// be read, returning the offset the footer ends at and its manifest.
+fn find_last_footer(
+    file: &mut File,
+    header: &ArchiveHeader,
+    cipher: Option<&Cipher>,
+    data_start: u64,
+    end: u64,
+) -> Result<Option<(u64, ArchiveManifest)>, ArchiveError> {
+    const CHUNK_LENGTH: u64 = 64 * 1024;
+    let mut buffer = Vec::new();
+    let mut chunk_end = end;
+    while chunk_end > data_start {
+        let chunk_start = chunk_end.saturating_sub(CHUNK_LENGTH).max(data_start);
+        buffer.resize(to_usize(chunk_end - chunk_start)?, 0);
+        file.seek(SeekFrom::Start(chunk_start))?;
+        file.read_exact(&mut buffer)?;
+        for (index, window) in buffer.windows(MAGIC.len()).enumerate().rev() {
+            if window != MAGIC {
+                continue;
+            }
+            let footer_end = chunk_start + (index + MAGIC.len()) as u64;
+            if let Some(manifest) = read_footer(file, data_start, footer_end)? {
+                // Object data can contain the magic bytes, so only a footer
+                // whose manifest can be decoded is accepted.
+                if let Some(manifest) = unseal(header, cipher, &manifest)
+                    .ok()
+                    .and_then(|manifest| pot::from_slice::<ArchiveManifest>(&manifest).ok())
+                {
+                    return Ok(Some((footer_end, manifest)));
+                }
+            }
+        }
+        if chunk_start == data_start {
+            break;
+        }
+        // Overlap the chunks so that magic bytes spanning a chunk boundary are
+        // found.
+        chunk_end = chunk_start + MAGIC.len() as u64 - 1;
+    }
+    Ok(None)
+}
+
+fn to_usize(length: u64) -> Result<usize, ArchiveError> {
+    usize::try_from(length)
+        .map_err(|_| ArchiveError::InvalidArchive(format!("length too large: {length}")))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveHeader {
+    version: u32,
+    compression: ArchiveCompression,
+    encryption: Option<EncryptionHeader>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveManifest {
+    entries: Vec<ArchiveEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ArchiveEntry {
+    key: EntryKey,
+    offset: u64,
+    length: u64,
+    checksum: u32,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+struct EntryKey {
+    schema: String,
+    database: String,
+    container: String,
+    name: String,
+}
+
+impl std::fmt::Display for EntryKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}/{}",
+            self.schema, self.database, self.container, self.name
+        )
+    }
+}
+
+/// An error from a [`BackupArchive`].
+#[derive(thiserror::Error, Debug)]
+pub enum ArchiveError {
+    /// An io error occurred.
+    #[error("an IO error occurred: {0}")]
+    Io(#[from] std::io::Error),
+    /// An error occurred serializing the archive's header or manifest.
+    #[error("error while serializing: {0}")]
+    Serialization(#[from] pot::Error),
+    /// The file is not a valid archive.
+    #[error("invalid archive: {0}")]
+    InvalidArchive(String),
+    /// The requested object is not stored in the archive.
+    #[error("object not found: {0}")]
+    NotFound(String),
+    /// The contents of an object did not match the checksum recorded when it
+    /// was stored.
+    #[error("checksum mismatch: {0}")]
+    ChecksumMismatch(String),
+    /// An error occurred decompressing an object.
+    #[error("error decompressing: {0}")]
+    Compression(String),
+    /// An error occurred encrypting or decrypting an object.
+    #[error("encryption error: {0}")]
+    Encryption(String),
+    /// The archive is encrypted, but no encryption was provided when opening
+    /// it.
+    #[error("the archive is encrypted, but no encryption was provided")]
+    EncryptionRequired,
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::{Connection as _, StorageConnection as _};
+    use bonsaidb_core::schema::SerializedCollection;
+    use bonsaidb_core::test_util::{Basic, TestDirectory};
+
+    use super::*;
+    use crate::config::{Builder, StorageConfiguration};
+    use crate::Storage;
+
+    fn options() -> ArchiveOptions {
+        let options = ArchiveOptions::default();
+        #[cfg(feature = "compression")]
+        let options = options.compression(ArchiveCompression::Lz4);
+        #[cfg(all(feature = "encryption", feature = "password-hashing"))]
+        let options = options.encryption(ArchiveEncryption::Passphrase(
+            bonsaidb_core::connection::SensitiveString(String::from("hunter2")),
+        ));
+        options
+    }
+
+    #[test]
+    fn archive_backup_restore() -> anyhow::Result<()> {
+        let backup_directory = TestDirectory::new("archive-backup-restore.bonsaidb.backup");
+        std::fs::create_dir_all(&backup_directory)?;
+        let archive_path = backup_directory.0.join("backup.bonsaidb-archive");
+
+        let (original_doc, new_doc) = {
+            let database_directory = TestDirectory::new("archive-backup-restore.bonsaidb");
+            let storage = Storage::open(
+                StorageConfiguration::new(&database_directory).with_schema::<Basic>()?,
+            )?;
+            let db = storage.create_database::<Basic>("basic", false)?;
+            let original_doc = Basic::new("original").push_into(&db)?;
+
+            let archive = BackupArchive::create(&archive_path, options())?;
+            storage.backup(&archive)?;
+            archive.finish()?;
+
+            // Append an incremental backup to the same archive.
+            let new_doc = Basic::new("new").push_into(&db)?;
+            let archive = BackupArchive::open(&archive_path, options())?;
+            storage.backup_incremental(&archive)?;
+            archive.finish()?;
+
+            // Simulate a crash while appending another backup.
+            let mut file = OpenOptions::new().append(true).open(&archive_path)?;
+            file.write_all(b"an unfinished object")?;
+            file.write_all(MAGIC)?;
+
+            (original_doc, new_doc)
+        };
+
+        #[cfg(all(feature = "encryption", feature = "password-hashing"))]
+        assert!(matches!(
+            BackupArchive::open(&archive_path, ArchiveOptions::default()),
+            Err(ArchiveError::EncryptionRequired)
+        ));
+
+        let database_directory = TestDirectory::new("archive-backup-restore.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        let archive = BackupArchive::open(&archive_path, options())?;
+        restored_storage.restore(&archive)?;
+
+        let db = restored_storage.database::<Basic>("basic")?;
+        let doc = Basic::get(&original_doc.header.id, &db)?.expect("original document not found");
+        assert_eq!(doc.contents.value, "original");
+        let doc = Basic::get(&new_doc.header.id, &db)?.expect("new document not found");
+        assert_eq!(doc.contents.value, "new");
+
+        Ok(())
+    }
+}
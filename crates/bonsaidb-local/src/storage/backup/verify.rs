@@ -0,0 +1,413 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Display;
+
+use bonsaidb_core::document::{BorrowedDocument, DocumentId};
+use bonsaidb_core::schema::{CollectionName, SchemaName, Schematic};
+use serde::de::DeserializeOwned;
+
+use super::{
+    increment_container, increment_name, AnyBackupLocation, BackupIncrement, BackupManifest,
+    BACKUP_CONTAINER, KEY_VALUE_CONTAINER, MANIFEST_NAME,
+};
+use crate::database::keyvalue::Entry;
+use crate::{Error, Storage};
+
+impl Storage {
+    /// Verifies that the backup stored in `location` is complete and readable
+    /// without restoring it.
+    ///
+    /// Every object in the backup is loaded, which validates the checksums
+    /// stored by locations that record them, such as
+    /// [`BackupArchive`](crate::BackupArchive). Each document is checked
+    /// against the [`Schematic`] registered with this storage for its
+    /// database's schema by mapping it with each of its collection's views,
+    /// and each key-value entry is deserialized. The incremental backups
+    /// listed in each database's manifest, and the documents they record as
+    /// changed, are checked to be present. Because the manifest is stored
+    /// after the rest of a database's backup, a missing manifest indicates
+    /// that the backup was interrupted.
+    ///
+    /// Problems with the backup's contents are reported in the returned
+    /// [`BackupVerification`]. An error is only returned if `location` can't
+    /// be listed.
+    pub fn verify_backup<L: AnyBackupLocation>(
+        &self,
+        location: &L,
+    ) -> Result<BackupVerification, Error> {
+        let mut verification = BackupVerification::default();
+        for schema in location.list_schemas()? {
+            let opener = self.instance.data.schemas.read().get(&schema).cloned();
+            for database in location.list_databases(&schema)? {
+                if let Some(opener) = &opener {
+                    let mut verifier = DatabaseVerifier {
+                        schematic: opener.schematic(),
+                        schema: &schema,
+                        database: &database,
+                        location,
+                        verification: &mut verification,
+                    };
+                    verifier.verify()?;
+                } else {
+                    verification.problems.push(BackupProblem::UnknownSchema {
+                        schema: schema.clone(),
+                        database,
+                    });
+                }
+                verification.databases_verified += 1;
+            }
+        }
+
+        Ok(verification)
+    }
+}
+
+struct DatabaseVerifier<'a> {
+    schematic: &'a Schematic,
+    schema: &'a SchemaName,
+    database: &'a str,
+    location: &'a dyn AnyBackupLocation,
+    verification: &'a mut BackupVerification,
+}
+
+impl DatabaseVerifier<'_> {
+    fn verify(&mut self) -> Result<(), Error> {
+        let manifest = self
+            .load_if_stored(BACKUP_CONTAINER, MANIFEST_NAME)?
+            .and_then(|manifest| {
+                self.deserialize::<BackupManifest>(BACKUP_CONTAINER, MANIFEST_NAME, &manifest)
+            });
+
+        let schematic = self.schematic;
+        for collection in schematic.collections() {
+            self.verify_documents(collection, &collection.encoded())?;
+        }
+        self.verify_key_value_entries(KEY_VALUE_CONTAINER)?;
+
+        if let Some(manifest) = manifest {
+            for index in 1..=manifest.increments.len() {
+                self.verify_increment(manifest.generation, index)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_increment(&mut self, generation: u64, index: usize) -> Result<(), Error> {
+        let name = increment_name(generation, index);
+        let Some(increment) = self.load_if_stored(BACKUP_CONTAINER, &name)? else {
+            return Ok(());
+        };
+        let Some(increment) =
+            self.deserialize::<BackupIncrement>(BACKUP_CONTAINER, &name, &increment)
+        else {
+            return Ok(());
+        };
+
+        // Only the last change to each document determines whether it should
+        // have been stored.
+        let mut deleted_by_collection = BTreeMap::<&CollectionName, BTreeMap<String, bool>>::new();
+        for change in &increment.changes {
+            deleted_by_collection
+                .entry(&change.collection)
+                .or_default()
+                .insert(change.id.to_string(), change.deleted);
+        }
+
+        for (collection, documents) in deleted_by_collection {
+            let container = increment_container(generation, index, &collection.encoded());
+            if !self.schematic.collections().any(|c| c == collection) {
+                self.report_corrupt(
+                    &container,
+                    &name,
+                    format!("changes to unknown collection {collection}"),
+                );
+                continue;
+            }
+
+            let stored = self.verify_documents(collection, &container)?;
+            for (id, deleted) in documents {
+                if !deleted && !stored.contains(&id) {
+                    self.report_missing(&container, &id);
+                }
+            }
+        }
+        self.verify_key_value_entries(&increment_container(
+            generation,
+            index,
+            KEY_VALUE_CONTAINER,
+        ))?;
+        self.verification.increments_verified += 1;
+
+        Ok(())
+    }
+
+    /// Verifies each document stored in `container`, returning the names of
+    /// the stored documents.
+    fn verify_documents(
+        &mut self,
+        collection: &CollectionName,
+        container: &str,
+    ) -> Result<HashSet<String>, Error> {
+        let stored = self
+            .location
+            .list_stored(self.schema, self.database, container)?;
+        for id_string in &stored {
+            let Ok(id) = id_string.parse::<DocumentId>() else {
+                self.report_corrupt(container, id_string, String::from("invalid document id"));
+                continue;
+            };
+            let Some(contents) = self.load(container, id_string) else {
+                continue;
+            };
+
+            let document = BorrowedDocument::new(id, &contents[..]);
+            let schematic = self.schematic;
+            for view in schematic.views_in_collection(collection) {
+                if let Err(err) = view.map(&document) {
+                    self.report_corrupt(
+                        container,
+                        id_string,
+                        format!("error mapping view {}: {err}", view.view_name()),
+                    );
+                    break;
+                }
+            }
+            self.verification.documents_verified += 1;
+        }
+
+        Ok(stored.into_iter().collect())
+    }
+
+    fn verify_key_value_entries(&mut self, container: &str) -> Result<(), Error> {
+        for name in self
+            .location
+            .list_stored(self.schema, self.database, container)?
+        {
+            if let Some(entry) = self.load(container, &name) {
+                if self
+                    .deserialize::<Entry>(container, &name, &entry)
+                    .is_some()
+                {
+                    self.verification.key_value_entries_verified += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the object `name` from `container`, reporting it as missing if
+    /// it isn't stored.
+    fn load_if_stored(&mut self, container: &str, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        if self
+            .location
+            .list_stored(self.schema, self.database, container)?
+            .iter()
+            .any(|stored| stored == name)
+        {
+            Ok(self.load(container, name))
+        } else {
+            self.report_missing(container, name);
+            Ok(None)
+        }
+    }
+
+    /// Loads the object `name` from `container`, reporting it as corrupt if it
+    /// can't be loaded.
+    fn load(&mut self, container: &str, name: &str) -> Option<Vec<u8>> {
+        match self
+            .location
+            .load(self.schema, self.database, container, name)
+        {
+            Ok(contents) => Some(contents),
+            Err(err) => {
+                self.report_corrupt(container, name, err.to_string());
+                None
+            }
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        &mut self,
+        container: &str,
+        name: &str,
+        contents: &[u8],
+    ) -> Option<T> {
+        match pot::from_slice(contents) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.report_corrupt(container, name, err.to_string());
+                None
+            }
+        }
+    }
+
+    fn entry(&self, container: &str, name: &str) -> BackupEntry {
+        BackupEntry {
+            schema: self.schema.clone(),
+            database: self.database.to_string(),
+            container: container.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    fn report_missing(&mut self, container: &str, name: &str) {
+        let entry = self.entry(container, name);
+        self.verification
+            .problems
+            .push(BackupProblem::Missing(entry));
+    }
+
+    fn report_corrupt(&mut self, container: &str, name: &str, reason: String) {
+        let entry = self.entry(container, name);
+        self.verification
+            .problems
+            .push(BackupProblem::Corrupt { entry, reason });
+    }
+}
+
+/// The result of verifying a backup using [`Storage::verify_backup()`].
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct BackupVerification {
+    /// The number of databases found in the backup.
+    pub databases_verified: u64,
+    /// The number of documents that were loaded and checked.
+    pub documents_verified: u64,
+    /// The number of key-value entries that were loaded and checked.
+    pub key_value_entries_verified: u64,
+    /// The number of incremental backups that were checked.
+    pub increments_verified: u64,
+    /// The problems found in the backup.
+    pub problems: Vec<BackupProblem>,
+}
+
+impl BackupVerification {
+    /// Returns true if no problems were found.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A problem found while verifying a backup.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BackupProblem {
+    /// The schema of a backed up database is not registered with the storage,
+    /// so its contents could not be verified.
+    UnknownSchema {
+        /// The name of the schema.
+        schema: SchemaName,
+        /// The name of the database.
+        database: String,
+    },
+    /// An object that the backup refers to is not stored.
+    Missing(BackupEntry),
+    /// An object could not be loaded or deserialized.
+    Corrupt {
+        /// The object that is corrupt.
+        entry: BackupEntry,
+        /// A description of the problem.
+        reason: String,
+    },
+}
+
+impl Display for BackupProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownSchema { schema, database } => {
+                write!(f, "{database}: schema {schema} is not registered")
+            }
+            Self::Missing(entry) => write!(f, "{entry}: missing"),
+            Self::Corrupt { entry, reason } => write!(f, "{entry}: {reason}"),
+        }
+    }
+}
+
+/// The location of an object within a backup.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BackupEntry {
+    /// The schema of the database the object belongs to.
+    pub schema: SchemaName,
+    /// The name of the database the object belongs to.
+    pub database: String,
+    /// The container the object is stored in.
+    pub container: String,
+    /// The name of the object.
+    pub name: String,
+}
+
+impl Display for BackupEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}/{}",
+            self.schema, self.database, self.container, self.name
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::StorageConnection as _;
+    use bonsaidb_core::schema::{Qualified, SerializedCollection};
+    use bonsaidb_core::test_util::{Basic, TestDirectory};
+
+    use super::*;
+    use crate::config::{Builder, StorageConfiguration};
+    use crate::storage::backup::container_folder;
+    use crate::BackupLocation;
+
+    #[test]
+    fn verify_backup() -> anyhow::Result<()> {
+        let backup_destination = TestDirectory::new("verify-backup.bonsaidb.backup");
+        let database_directory = TestDirectory::new("verify-backup.bonsaidb");
+        let storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        let db = storage.create_database::<Basic>("basic", false)?;
+        let doc = Basic::new("original").push_into(&db)?;
+        storage.backup(&backup_destination.0)?;
+        Basic::new("new").push_into(&db)?;
+        storage.backup_incremental(&backup_destination.0)?;
+
+        let verification = storage.verify_backup(&backup_destination.0)?;
+        assert!(verification.is_valid(), "{:?}", verification.problems);
+        // The admin database is backed up alongside the basic database.
+        assert_eq!(verification.databases_verified, 2);
+        assert_eq!(verification.increments_verified, 1);
+        assert!(verification.documents_verified >= 2);
+
+        // Overwrite a document with contents that can't be deserialized, and
+        // remove the incremental backup.
+        let schema = db.schematic().name.clone();
+        let collection = Basic::collection_name().encoded();
+        BackupLocation::store(
+            backup_destination.0.as_path(),
+            &schema,
+            "basic",
+            &collection,
+            &doc.header.id.to_string(),
+            b"not a document",
+        )?;
+        std::fs::remove_file(
+            container_folder(&backup_destination.0, &schema, "basic", BACKUP_CONTAINER)
+                .join(increment_name(1, 1)),
+        )?;
+
+        let verification = storage.verify_backup(&backup_destination.0)?;
+        assert!(!verification.is_valid());
+        assert!(verification.problems.iter().any(|problem| matches!(
+            problem,
+            BackupProblem::Corrupt { entry, .. }
+                if entry.container == collection && entry.name == doc.header.id.to_string()
+        )));
+        assert!(verification
+            .problems
+            .contains(&BackupProblem::Missing(BackupEntry {
+                schema,
+                database: String::from("basic"),
+                container: String::from(BACKUP_CONTAINER),
+                name: increment_name(1, 1),
+            })));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use bonsaidb_core::connection::CacheStats;
+use nebari::ChunkCache;
+
+use crate::config::Cache;
+
+/// The chunk caches used by a storage's databases, divided according to a
+/// [`Cache`] configuration.
+#[derive(Debug)]
+pub(crate) struct ChunkCaches {
+    shared: SizedCache,
+    databases: HashMap<String, SizedCache>,
+}
+
+#[derive(Debug)]
+struct SizedCache {
+    cache: ChunkCache,
+    budget_bytes: u64,
+    capacity: u64,
+}
+
+impl ChunkCaches {
+    pub fn new(config: &Cache) -> Self {
+        let total_weight = config
+            .database_weights
+            .values()
+            .map(|weight| u128::from(*weight))
+            .sum::<u128>()
+            + u128::from(config.default_weight);
+        let sized = |weight: u32| {
+            let budget_bytes = if total_weight == 0 {
+                0
+            } else {
+                // The result is never larger than the budget, so it fits.
+                u64::try_from(u128::from(config.budget) * u128::from(weight) / total_weight)
+                    .unwrap()
+            };
+            let max_chunk_length = config.max_chunk_length.max(1);
+            // A cache always holds at least one chunk.
+            let capacity = usize::try_from(budget_bytes / max_chunk_length as u64)
+                .unwrap_or(usize::MAX)
+                .max(1);
+            SizedCache {
+                cache: ChunkCache::new(capacity, max_chunk_length),
+                budget_bytes,
+                capacity: capacity as u64,
+            }
+        };
+
+        Self {
+            shared: sized(config.default_weight),
+            databases: config
+                .database_weights
+                .iter()
+                .map(|(name, weight)| (name.clone(), sized(*weight)))
+                .collect(),
+        }
+    }
+
+    /// Returns the cache used by the database `name`.
+    pub fn for_database(&self, name: &str) -> ChunkCache {
+        self.databases
+            .get(name)
+            .unwrap_or(&self.shared)
+            .cache
+            .clone()
+    }
+
+    /// Returns the sizes of the shared cache and of each database's cache,
+    /// ordered by database name.
+    pub fn stats(&self) -> Vec<CacheStats> {
+        let mut databases = self.databases.iter().collect::<Vec<_>>();
+        databases.sort_by(|(a, _), (b, _)| a.cmp(b));
+        std::iter::once(self.shared.stats(None))
+            .chain(
+                databases
+                    .into_iter()
+                    .map(|(name, cache)| cache.stats(Some(name.clone()))),
+            )
+            .collect()
+    }
+}
+
+impl SizedCache {
+    fn stats(&self, database: Option<String>) -> CacheStats {
+        CacheStats {
+            database,
+            budget_bytes: self.budget_bytes,
+            capacity: self.capacity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::{CacheStats, StorageConnection as _};
+    use bonsaidb_core::schema::SerializedCollection;
+    use bonsaidb_core::test_util::{Basic, TestDirectory};
+
+    use crate::config::{Builder, Cache, StorageConfiguration};
+    use crate::Storage;
+
+    #[test]
+    fn weighted_caches() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("weighted-caches.bonsaidb");
+        let storage = Storage::open(
+            StorageConfiguration::new(&directory)
+                .with_schema::<Basic>()?
+                .cache(
+                    Cache::default()
+                        .budget(4_000)
+                        .max_chunk_length(100)
+                        .database_weight("hot", 3),
+                ),
+        )?;
+        let hot = storage.create_database::<Basic>("hot", false)?;
+        let cold = storage.create_database::<Basic>("cold", false)?;
+        Basic::new("a").push_into(&hot)?;
+        Basic::new("b").push_into(&cold)?;
+
+        assert_eq!(
+            storage.stats()?.caches,
+            vec![
+                CacheStats {
+                    database: None,
+                    budget_bytes: 1_000,
+                    capacity: 10,
+                },
+                CacheStats {
+                    database: Some(String::from("hot")),
+                    budget_bytes: 3_000,
+                    capacity: 30,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Instant;
+
+use bonsaidb_core::connection::TreeStats;
+
+use super::{database_stats, Data, StorageInstance};
+use crate::config::CompactionPolicy;
+use crate::tasks::{tree_targets, Target};
+use crate::{Database, DatabaseNonBlocking, Error};
+
+/// Compacts the trees of every database according to a [`CompactionPolicy`].
+pub struct CompactionScheduler {
+    storage: Weak<Data>,
+    policy: CompactionPolicy,
+    started_at: Instant,
+    trees: HashMap<(String, String), TreeState>,
+    last_scheduled_compaction: HashMap<String, Instant>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TreeState {
+    /// The size of the tree's file after it was last compacted, or when it
+    /// was first examined.
+    baseline_size: u64,
+}
+
+impl CompactionScheduler {
+    /// Spawns a thread that applies `policy` until `storage` is dropped.
+    pub fn spawn(storage: &Arc<Data>, policy: CompactionPolicy) -> Result<(), Error> {
+        let scheduler = Self {
+            storage: Arc::downgrade(storage),
+            policy,
+            started_at: Instant::now(),
+            trees: HashMap::new(),
+            last_scheduled_compaction: HashMap::new(),
+        };
+        thread::Builder::new()
+            .name(String::from("compaction-policy"))
+            .spawn(move || scheduler.run())?;
+        Ok(())
+    }
+
+    fn run(mut self) {
+        loop {
+            thread::sleep(self.policy.check_interval);
+            let storage = match self.storage.upgrade() {
+                Some(data) => StorageInstance { data },
+                None => break,
+            };
+            if let Err(err) = self.check(&storage) {
+                log::error!("Error applying compaction policy: {err}");
+            }
+        }
+    }
+
+    fn check(&mut self, storage: &StorageInstance) -> Result<(), Error> {
+        let databases = storage
+            .data
+            .available_databases
+            .read()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in databases {
+            let database = match storage.database_without_schema(&name, None, None) {
+                Ok(database) => database,
+                // The database was deleted, or its schema isn't registered with
+                // this storage.
+                Err(Error::Core(
+                    bonsaidb_core::Error::DatabaseNotFound(_)
+                    | bonsaidb_core::Error::SchemaNotRegistered(_),
+                )) => continue,
+                Err(other) => return Err(other),
+            };
+            self.check_database(storage, &database)?;
+        }
+
+        let databases = storage.data.available_databases.read();
+        self.trees
+            .retain(|(database, _), _| databases.contains_key(database));
+        self.last_scheduled_compaction
+            .retain(|database, _| databases.contains_key(database));
+        Ok(())
+    }
+
+    fn check_database(
+        &mut self,
+        storage: &StorageInstance,
+        database: &Database,
+    ) -> Result<(), Error> {
+        let scheduled = self.policy.schedule.map_or(false, |schedule| {
+            let last_compaction = self
+                .last_scheduled_compaction
+                .get(database.name())
+                .copied()
+                .unwrap_or(self.started_at);
+            last_compaction.elapsed() >= schedule
+        });
+
+        // The waste of each tree is estimated by comparing the size of its file
+        // to the size of its live keys and values.
+        let tree_stats = if !scheduled && self.policy.waste_ratio.is_some() {
+            let stats = database_stats(database)?;
+            stats
+                .trees()
+                .map(|tree| (tree.name.clone(), tree.clone()))
+                .collect::<HashMap<_, _>>()
+        } else {
+            HashMap::new()
+        };
+
+        for target in tree_targets(database) {
+            let tree_name = match &target {
+                Target::VersionedTree(name) | Target::UnversionedTree(name) => name.clone(),
+                Target::Collection(_) | Target::KeyValue | Target::Database => {
+                    unreachable!("only individual trees are gathered")
+                }
+            };
+            let size = match storage.tree_file_length(database.name(), &tree_name)? {
+                Some(size) => size,
+                // The tree has not been written to.
+                None => continue,
+            };
+
+            let key = (database.name().to_string(), tree_name);
+            let state = *self.trees.entry(key.clone()).or_insert(TreeState {
+                baseline_size: size,
+            });
+
+            if scheduled || self.needs_compaction(size, state, tree_stats.get(&key.1)) {
+                storage
+                    .tasks()
+                    .spawn_compact_target(database.clone(), target)
+                    .receive()??;
+                let compacted_size = storage
+                    .tree_file_length(database.name(), &key.1)?
+                    .unwrap_or_default();
+                self.trees.insert(
+                    key,
+                    TreeState {
+                        baseline_size: compacted_size,
+                    },
+                );
+                thread::sleep(self.policy.delay_between_trees);
+            }
+        }
+
+        if scheduled {
+            self.last_scheduled_compaction
+                .insert(database.name().to_string(), Instant::now());
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn needs_compaction(&self, size: u64, state: TreeState, stats: Option<&TreeStats>) -> bool {
+        if size < self.policy.minimum_file_size {
+            return false;
+        }
+
+        if let Some(growth_ratio) = self.policy.growth_ratio {
+            if size as f64 >= state.baseline_size as f64 * growth_ratio {
+                return true;
+            }
+        }
+
+        if let (Some(waste_ratio), Some(stats)) = (self.policy.waste_ratio, stats) {
+            if stats.waste_ratio() >= waste_ratio {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bonsaidb_core::connection::StorageConnection as _;
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+    use bonsaidb_core::test_util::{Basic, TestDirectory};
+
+    use crate::config::{Builder, CompactionPolicy, StorageConfiguration};
+    use crate::database::document_tree_name;
+    use crate::Storage;
+
+    #[test]
+    fn waste_ratio() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("compaction-policy.bonsaidb");
+        let storage = Storage::open(
+            StorageConfiguration::new(&directory)
+                .with_schema::<Basic>()?
+                .compaction_policy(
+                    CompactionPolicy::default()
+                        .check_interval(Duration::from_millis(100))
+                        .minimum_file_size(0)
+                        .waste_ratio(0.5)
+                        .delay_between_trees(Duration::ZERO),
+                ),
+        )?;
+        let db = storage.create_database::<Basic>("basic", false)?;
+
+        let path = directory.0.join("basic").join(format!(
+            "{}.nebari",
+            document_tree_name(&Basic::collection_name())
+        ));
+
+        // Overwriting a document leaves each previous version in the file. The
+        // document is overwritten until the policy notices that most of the
+        // file no longer contains live data, and compacts it.
+        let mut document = Basic::new("a").push_into(&db)?;
+        let mut largest_size = 0;
+        let mut index = 0_u64;
+        loop {
+            let size = std::fs::metadata(&path)?.len();
+            if size < largest_size {
+                break;
+            }
+            largest_size = size;
+            assert!(index < 1000, "tree was not compacted");
+            index += 1;
+            document.contents.value = index.to_string().repeat(1024);
+            document.update(&db)?;
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        assert_eq!(
+            Basic::get(&document.header.id, &db)?
+                .expect("document missing")
+                .contents
+                .value,
+            index.to_string().repeat(1024)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn append_only_trees_are_not_compacted() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("compaction-policy-append-only.bonsaidb");
+        let storage = Storage::open(
+            StorageConfiguration::new(&directory)
+                .with_schema::<Basic>()?
+                .compaction_policy(
+                    CompactionPolicy::default()
+                        .check_interval(Duration::from_millis(50))
+                        .minimum_file_size(0)
+                        .waste_ratio(0.5)
+                        .delay_between_trees(Duration::ZERO),
+                ),
+        )?;
+        let db = storage.create_database::<Basic>("basic", false)?;
+
+        let path = directory.0.join("basic").join(format!(
+            "{}.nebari",
+            document_tree_name(&Basic::collection_name())
+        ));
+
+        // Each document is much larger than the tree's structure, so nearly
+        // all of the file contains live data even though it keeps growing.
+        let mut previous_size = 0;
+        for index in 0..20_u64 {
+            Basic::new(index.to_string().repeat(16 * 1024)).push_into(&db)?;
+            std::thread::sleep(Duration::from_millis(20));
+            let size = std::fs::metadata(&path)?.len();
+            assert!(size > previous_size, "tree was compacted");
+            previous_size = size;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(std::fs::metadata(&path)?.len(), previous_size);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+use bonsaidb_core::admin::database::Database as DatabaseRecord;
+use bonsaidb_core::admin::ADMIN_DATABASE_NAME;
+use bonsaidb_core::arc_bytes::ArcBytes;
+use bonsaidb_core::connection::{HasSession, StorageConnection};
+use bonsaidb_core::permissions::bonsai::{database_resource_name, BonsaiAction, ServerAction};
+use bonsaidb_core::schema::{CollectionName, NamedCollection, SerializedCollection};
+use nebari::io::any::AnyFile;
+use nebari::tree::{
+    CompareSwap, KeyOperation, Operation, Root, ScanEvaluation, TreeRoot, Unversioned, Versioned,
+};
+use nebari::AbortError;
+
+use super::StorageInstance;
+use crate::database::Context;
+use crate::tasks::{collection_tree_targets, Target};
+use crate::{Database, DatabaseNonBlocking, Error, Storage};
+
+/// The number of entries written to the destination tree per transaction.
+const BATCH_SIZE: usize = 10_000;
+
+impl Storage {
+    /// Copies the database `name` into a new database named
+    /// `destination_name` stored in `destination`. The schema of the database
+    /// must be registered with `destination`.
+    ///
+    /// This storage must permit [`ServerAction::CopyDatabase`] on `name`, and
+    /// `destination` must permit [`ServerAction::CreateDatabase`] on
+    /// `destination_name`.
+    pub fn copy_database_to(
+        &self,
+        name: &str,
+        destination: &Storage,
+        destination_name: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.check_permission(
+            database_resource_name(name),
+            &BonsaiAction::Server(ServerAction::CopyDatabase),
+        )?;
+        destination.check_permission(
+            database_resource_name(destination_name),
+            &BonsaiAction::Server(ServerAction::CreateDatabase),
+        )?;
+        self.instance
+            .copy_database_to(name, &destination.instance, destination_name)
+            .map_err(bonsaidb_core::Error::from)
+    }
+}
+
+impl StorageInstance {
+    /// Creates the database `destination_name` in `destination` and copies the
+    /// trees of database `name` into it. The trees are copied entry by entry
+    /// without deserializing documents or remapping views. If the copy fails,
+    /// the partially copied database is deleted.
+    ///
+    /// Writes made to the source database while it is being copied may not be
+    /// included in the copy.
+    pub(crate) fn copy_database_to(
+        &self,
+        name: &str,
+        destination: &StorageInstance,
+        destination_name: &str,
+    ) -> Result<(), Error> {
+        destination.check_writable()?;
+        // The admin database stores the storage's own records, and is always
+        // opened under its fixed name.
+        if name == ADMIN_DATABASE_NAME {
+            return Err(Error::Core(bonsaidb_core::Error::InvalidDatabaseName(
+                name.to_string(),
+            )));
+        }
+        let schema = self
+            .data
+            .available_databases
+            .read()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| bonsaidb_core::Error::DatabaseNotFound(name.to_string()))?;
+        destination.create_database_with_schema(destination_name, schema, false)?;
+
+        let result = self
+            .database_without_schema(name, None, None)
+            .and_then(|source| {
+                let copy = destination.database_without_schema(destination_name, None, None)?;
+                copy_database_contents(&source, &copy)?;
+                copy.remeasure_quota_usage()
+            });
+        if result.is_err() {
+            let _: Result<_, _> = destination.delete_database(destination_name);
+        }
+        result
+    }
+
+    /// Renames the database `name` to `new_name` by renaming its directory.
+    /// The database's record is updated first, and the database is then
+    /// closed, persisting its pending key-value entries, and its directory is
+    /// renamed while no other database can be opened.
+    ///
+    /// Memory-only storages don't store databases in directories, and
+    /// databases that are open elsewhere can't be closed, so these are renamed
+    /// by copying instead.
+    pub(crate) fn rename_database_in_place(&self, name: &str, new_name: &str) -> Result<(), Error> {
+        self.check_writable()?;
+        if self.data.memory_only || self.database_is_open(name) {
+            return self.rename_database_by_copying(name, new_name);
+        }
+        Storage::validate_name(new_name)?;
+        // The admin database stores the storage's own records, and is always
+        // opened under its fixed name.
+        for name in [name, new_name] {
+            if name == ADMIN_DATABASE_NAME {
+                return Err(Error::Core(bonsaidb_core::Error::InvalidDatabaseName(
+                    name.to_string(),
+                )));
+            }
+        }
+        {
+            let available_databases = self.data.available_databases.read();
+            if !available_databases.contains_key(name) {
+                return Err(Error::Core(bonsaidb_core::Error::DatabaseNotFound(
+                    name.to_string(),
+                )));
+            } else if available_databases.contains_key(new_name) {
+                return Err(Error::Core(bonsaidb_core::Error::DatabaseNameAlreadyTaken(
+                    new_name.to_string(),
+                )));
+            }
+        }
+
+        let admin = self.admin();
+        let mut record = DatabaseRecord::load(name, &admin)?
+            .ok_or_else(|| bonsaidb_core::Error::DatabaseNotFound(name.to_string()))?;
+        record.contents.name = new_name.to_string();
+        record.update(&admin)?;
+
+        let result = self.rename_database_directory(name, new_name);
+        if !matches!(result, Ok(true)) {
+            record.contents.name = name.to_string();
+            record.update(&admin)?;
+        }
+        match result {
+            Ok(true) => Ok(()),
+            // The database was opened after it was checked.
+            Ok(false) => self.rename_database_by_copying(name, new_name),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns true if a handle to the database `name` is open.
+    fn database_is_open(&self, name: &str) -> bool {
+        self.data
+            .open_roots
+            .lock()
+            .get(name)
+            .map_or(false, Context::is_shared)
+    }
+
+    /// Closes the database `name` and renames its directory to `new_name`.
+    /// Returns false without renaming anything if the database is open.
+    fn rename_database_directory(&self, name: &str, new_name: &str) -> Result<bool, Error> {
+        // Holding both locks prevents the database from being opened until it
+        // has been renamed.
+        let mut available_databases = self.data.available_databases.write();
+        let schema = available_databases
+            .get(name)
+            .cloned()
+            .ok_or_else(|| bonsaidb_core::Error::DatabaseNotFound(name.to_string()))?;
+        if available_databases.contains_key(new_name) {
+            return Err(Error::Core(bonsaidb_core::Error::DatabaseNameAlreadyTaken(
+                new_name.to_string(),
+            )));
+        }
+        let mut open_roots = self.data.open_roots.lock();
+        if open_roots.get(name).map_or(false, Context::is_shared) {
+            return Ok(false);
+        }
+        if let Some(context) = open_roots.remove(name) {
+            context.persist_dirty_keys();
+        }
+
+        let path = self.data.path.join(name);
+        if path.exists() {
+            std::fs::rename(&path, self.data.path.join(new_name))?;
+        }
+
+        available_databases.remove(name);
+        available_databases.insert(new_name.to_string(), schema);
+        Ok(true)
+    }
+
+    /// Renames the database `name` by copying it to `new_name` and deleting
+    /// the original.
+    fn rename_database_by_copying(&self, name: &str, new_name: &str) -> Result<(), Error> {
+        self.copy_database_to(name, self, new_name)?;
+        self.delete_database(name)?;
+        Ok(())
+    }
+}
+
+fn copy_database_contents(source: &Database, destination: &Database) -> Result<(), Error> {
+    for collection in source.schematic().collections() {
+        for target in collection_tree_targets(source, collection) {
+            match target {
+                Target::VersionedTree(tree) => {
+                    copy_collection_tree::<Versioned>(source, destination, collection, tree)?;
+                }
+                Target::UnversionedTree(tree) => {
+                    copy_collection_tree::<Unversioned>(source, destination, collection, tree)?;
+                }
+                Target::Collection(_) | Target::KeyValue | Target::Database => {
+                    unreachable!("only individual trees are gathered")
+                }
+            }
+        }
+    }
+    source.copy_key_value_entries_to(destination)
+}
+
+fn copy_collection_tree<R: Root<Value = ArcBytes<'static>>>(
+    source: &Database,
+    destination: &Database,
+    collection: &CollectionName,
+    tree: String,
+) -> Result<(), Error> {
+    // Avoid creating empty files for trees that have never been written to.
+    if source
+        .storage
+        .instance
+        .tree_file_length(source.name(), &tree)?
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    let source_tree = source
+        .roots()
+        .tree(source.collection_tree::<R, _>(collection, tree.clone())?)?;
+    let destination_root = destination.collection_tree::<R, _>(collection, tree)?;
+    let mut batch = Vec::new();
+    source_tree.scan::<Error, _, _, _, _>(
+        &(..),
+        true,
+        |_, _, _| ScanEvaluation::ReadData,
+        |_, _| ScanEvaluation::ReadData,
+        |key, _, value| {
+            batch.push((key, value));
+            if batch.len() >= BATCH_SIZE {
+                write_batch(destination, &destination_root, &mut batch)
+                    .map_err(AbortError::Other)?;
+            }
+            Ok(())
+        },
+    )?;
+    write_batch(destination, &destination_root, &mut batch)
+}
+
+fn write_batch<R: Root<Value = ArcBytes<'static>>>(
+    database: &Database,
+    root: &TreeRoot<R, AnyFile>,
+    batch: &mut Vec<(ArcBytes<'static>, ArcBytes<'static>)>,
+) -> Result<(), Error> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    // Entries are scanned in key order, which is the order `modify` requires.
+    let (keys, values): (Vec<_>, Vec<_>) = batch.drain(..).unzip();
+    let mut values = values.into_iter();
+    let transaction = database.roots().transaction(&[root.clone()])?;
+    transaction.tree::<R>(0).unwrap().modify(
+        keys,
+        Operation::CompareSwap(CompareSwap::new(&mut |_, _| {
+            KeyOperation::Set(values.next().unwrap())
+        })),
+    )?;
+    transaction.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::{Connection as _, StorageConnection as _};
+    use bonsaidb_core::keyvalue::KeyValue;
+    use bonsaidb_core::schema::SerializedCollection;
+    use bonsaidb_core::test_util::{Basic, BasicCount, TestDirectory};
+
+    use crate::config::{Builder, StorageConfiguration};
+    use crate::Storage;
+
+    #[test]
+    fn rename_and_copy() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("copy-database.bonsaidb");
+        let storage = Storage::open(StorageConfiguration::new(&directory).with_schema::<Basic>()?)?;
+        let db = storage.create_database::<Basic>("original", false)?;
+        let doc = Basic::new("a").push_into(&db)?;
+        Basic::new("b").push_into(&db)?;
+        assert_eq!(db.view::<BasicCount>().reduce()?, 2);
+        db.set_numeric_key("key", 1_u64).execute()?;
+        drop(db);
+
+        storage.rename_database("original", "renamed")?;
+        assert!(matches!(
+            storage.database::<Basic>("original"),
+            Err(bonsaidb_core::Error::DatabaseNotFound(_))
+        ));
+        assert!(!directory.0.join("original").exists());
+        assert!(directory.0.join("renamed").exists());
+        let renamed = storage.database::<Basic>("renamed")?;
+        assert_eq!(
+            Basic::get(&doc.header.id, &renamed)?
+                .unwrap()
+                .contents
+                .value,
+            "a"
+        );
+        assert_eq!(renamed.view::<BasicCount>().reduce()?, 2);
+        assert_eq!(renamed.get_key("key").into_u64()?, Some(1));
+
+        // The original name can be used by a new database.
+        let recreated = storage.create_database::<Basic>("original", false)?;
+        assert_eq!(recreated.view::<BasicCount>().reduce()?, 0);
+        assert!(matches!(
+            storage.rename_database("renamed", "original"),
+            Err(bonsaidb_core::Error::DatabaseNameAlreadyTaken(_))
+        ));
+
+        storage.copy_database("renamed", "copy")?;
+        assert!(matches!(
+            storage.copy_database("renamed", "copy"),
+            Err(bonsaidb_core::Error::DatabaseNameAlreadyTaken(_))
+        ));
+        let copy = storage.database::<Basic>("copy")?;
+        Basic::new("c").push_into(&copy)?;
+        assert_eq!(copy.view::<BasicCount>().reduce()?, 3);
+        assert_eq!(renamed.view::<BasicCount>().reduce()?, 2);
+
+        let other_directory = TestDirectory::new("copy-database-other.bonsaidb");
+        let other =
+            Storage::open(StorageConfiguration::new(&other_directory).with_schema::<Basic>()?)?;
+        storage.copy_database_to("copy", &other, "copied")?;
+        let copied = other.database::<Basic>("copied")?;
+        assert_eq!(copied.view::<BasicCount>().reduce()?, 3);
+        assert_eq!(copied.get_key("key").into_u64()?, Some(1));
+
+        // A database that is still open is renamed by copying it.
+        storage.rename_database("copy", "moved")?;
+        drop(copy);
+        assert!(matches!(
+            storage.database::<Basic>("copy"),
+            Err(bonsaidb_core::Error::DatabaseNotFound(_))
+        ));
+        let moved = storage.database::<Basic>("moved")?;
+        assert_eq!(moved.view::<BasicCount>().reduce()?, 3);
+        assert!(storage
+            .list_databases()?
+            .iter()
+            .any(|database| database.name == "moved"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,808 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use bonsaidb_core::admin::database::Database as DatabaseRecord;
+use bonsaidb_core::arc_bytes::OwnedBytes;
+use bonsaidb_core::connection::StorageConnection;
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::schema::{view, CollectionName, SchemaName, SerializedCollection, ViewName};
+use nebari::io::any::AnyFile;
+use nebari::tree::{Root, ScanEvaluation, TreeRoot, Unversioned, Versioned};
+use nebari::ArcBytes;
+
+use crate::database::keyvalue::{Entry, KEY_TREE};
+use crate::database::{deserialize_document, document_tree_name};
+use crate::views::integrity_scanner::invalidate_view;
+use crate::views::mapper::{Map, Mapper};
+use crate::views::{
+    view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
+    view_quarantine_tree_name, DocumentMap, ViewEntry,
+};
+use crate::{Database, Error, Storage};
+
+/// The name of the file nebari stores each database's transaction log in. A
+/// directory containing this file is a database.
+pub(super) const TRANSACTION_LOG_FILE: &str = "_transactions";
+
+/// The name of the directory that database directories without an admin
+/// record are moved into when repairing a storage.
+const QUARANTINE_DIRECTORY: &str = "quarantine";
+
+impl Storage {
+    /// Checks the consistency of all data stored in this storage, returning
+    /// the problems found.
+    ///
+    /// Every tree of every database is read, which verifies each stored
+    /// chunk's checksum and that each encrypted value can be decrypted. Each
+    /// document is deserialized, and each view's entries are compared against
+    /// its document map and the documents in its collection. The records in
+    /// the admin database are compared against the databases stored on disk.
+    ///
+    /// This check should be performed while the storage is not being used by
+    /// anything else, as changes made while the check is running may be
+    /// reported as problems.
+    pub fn check_integrity(&self) -> Result<IntegrityReport, Error> {
+        self.fsck(false)
+    }
+
+    /// Checks the consistency of all data stored in this storage and repairs
+    /// the problems that can be repaired.
+    ///
+    /// Views with any problems are rebuilt from their collection's documents.
+    /// Duplicate admin records for a database are removed. Database
+    /// directories that have no record in the admin database are never
+    /// deleted: they are moved into the `quarantine` directory within the
+    /// storage's directory, where they can be inspected and either removed or
+    /// restored manually.
+    /// Unreadable documents and key-value entries can't be repaired, and must
+    /// be restored from a backup.
+    ///
+    /// See [`check_integrity()`](Self::check_integrity) for more information.
+    pub fn repair_integrity(&self) -> Result<IntegrityReport, Error> {
+        self.instance.check_writable()?;
+        self.fsck(true)
+    }
+
+    fn fsck(&self, repair: bool) -> Result<IntegrityReport, Error> {
+        let mut report = IntegrityReport::default();
+        self.check_database_records(repair, &mut report)?;
+
+        let databases = self
+            .instance
+            .data
+            .available_databases
+            .read()
+            .iter()
+            .map(|(name, schema)| (name.clone(), schema.clone()))
+            .collect::<Vec<_>>();
+        for (name, schema) in databases {
+            if !self.instance.data.schemas.read().contains_key(&schema) {
+                report.push(
+                    name,
+                    IntegrityProblemKind::UnregisteredSchema { schema },
+                    false,
+                );
+                continue;
+            }
+
+            let database = self
+                .instance
+                .database_without_schema(&name, Some(self), None)?;
+            DatabaseChecker {
+                database: &database,
+                repair,
+                report: &mut report,
+            }
+            .check()?;
+            report.databases_checked += 1;
+        }
+
+        self.check_orphaned_databases(repair, &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Checks that each database has exactly one record in the admin
+    /// database.
+    fn check_database_records(
+        &self,
+        repair: bool,
+        report: &mut IntegrityReport,
+    ) -> Result<(), Error> {
+        let admin = self.admin();
+        let mut names = HashSet::new();
+        for record in DatabaseRecord::all(&admin).query()? {
+            if !names.insert(record.contents.name.clone()) {
+                if repair {
+                    record.delete(&admin)?;
+                }
+                report.push(
+                    record.contents.name,
+                    IntegrityProblemKind::DuplicateDatabaseRecord,
+                    repair,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks for database directories that have no record in the admin
+    /// database.
+    fn check_orphaned_databases(
+        &self,
+        repair: bool,
+        report: &mut IntegrityReport,
+    ) -> Result<(), Error> {
+        for entry in std::fs::read_dir(&self.instance.data.path)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !entry.file_type()?.is_dir() || !path.join(TRANSACTION_LOG_FILE).exists() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if self
+                .instance
+                .data
+                .available_databases
+                .read()
+                .contains_key(&name)
+            {
+                continue;
+            }
+
+            if repair {
+                let destination = quarantine_directory(&self.instance.data.path, &path, &name)?;
+                log::warn!("moved database {name}, which has no admin record, to {destination:?}");
+            }
+            report.push(name, IntegrityProblemKind::OrphanedDatabase, repair);
+        }
+        Ok(())
+    }
+}
+
+/// Moves the database directory at `path` into the quarantine directory,
+/// returning its new location. If a database with the same name has already
+/// been quarantined, a numeric suffix is added to the name.
+fn quarantine_directory(
+    storage_path: &Path,
+    path: &Path,
+    name: &str,
+) -> Result<PathBuf, std::io::Error> {
+    let quarantine = storage_path.join(QUARANTINE_DIRECTORY);
+    std::fs::create_dir_all(&quarantine)?;
+    let mut destination = quarantine.join(name);
+    let mut suffix = 0_u32;
+    while destination.exists() {
+        suffix += 1;
+        destination = quarantine.join(format!("{name}.{suffix}"));
+    }
+    std::fs::rename(path, &destination)?;
+    Ok(destination)
+}
+
+struct DatabaseChecker<'a> {
+    database: &'a Database,
+    repair: bool,
+    report: &'a mut IntegrityReport,
+}
+
+impl DatabaseChecker<'_> {
+    fn check(&mut self) -> Result<(), Error> {
+        let database = self.database;
+        for collection in database.schematic().collections() {
+            let Some(documents) = self.check_documents(collection)? else {
+                // The views can't be compared against documents that can't be
+                // read.
+                continue;
+            };
+
+            for view in database.schematic().views_in_collection(collection) {
+                let problems = self.check_view(view, &documents)?;
+                let repaired = self.repair && !problems.is_empty();
+                if repaired {
+                    self.rebuild_view(view)?;
+                }
+                for problem in problems {
+                    self.push(problem, repaired);
+                }
+                self.report.views_checked += 1;
+            }
+        }
+
+        self.check_key_value_entries()
+    }
+
+    /// Reads every document in `collection`, returning the ids of the
+    /// documents, or `None` if the collection's tree couldn't be read.
+    fn check_documents(
+        &mut self,
+        collection: &CollectionName,
+    ) -> Result<Option<HashSet<DocumentId>>, Error> {
+        let tree_name = document_tree_name(collection);
+        let mut ids = HashSet::new();
+        let mut problems = Vec::new();
+        let result = self.read_tree(
+            self.database
+                .collection_tree::<Versioned, _>(collection, tree_name.clone())?,
+            |key, value| {
+                let id = match DocumentId::try_from(key.as_slice()) {
+                    Ok(id) => id,
+                    Err(err) => {
+                        problems.push(IntegrityProblemKind::CorruptDocument {
+                            collection: collection.clone(),
+                            id: None,
+                            error: err.to_string(),
+                        });
+                        return;
+                    }
+                };
+                let error = match deserialize_document(&value) {
+                    Ok(document) if document.header.id == id => None,
+                    Ok(document) => Some(format!(
+                        "the document's header contains the id {}",
+                        document.header.id
+                    )),
+                    Err(err) => Some(err.to_string()),
+                };
+                if let Some(error) = error {
+                    problems.push(IntegrityProblemKind::CorruptDocument {
+                        collection: collection.clone(),
+                        id: Some(id.clone()),
+                        error,
+                    });
+                }
+                ids.insert(id);
+            },
+        );
+        self.report.documents_checked += ids.len() as u64;
+        for problem in problems {
+            self.push(problem, false);
+        }
+
+        if let Err(err) = result {
+            self.push(
+                IntegrityProblemKind::UnreadableTree {
+                    tree: tree_name,
+                    error: err.to_string(),
+                },
+                false,
+            );
+            Ok(None)
+        } else {
+            Ok(Some(ids))
+        }
+    }
+
+    /// Compares the entries and document map of `view` against `documents`,
+    /// returning the problems found.
+    fn check_view(
+        &self,
+        view: &dyn view::Serialized,
+        documents: &HashSet<DocumentId>,
+    ) -> Result<Vec<IntegrityProblemKind>, Error> {
+        let view_name = view.view_name();
+        let mut problems = Vec::new();
+
+        // Waiting for the view's integrity check ensures that views whose
+        // version has changed have already been invalidated, and that no
+        // mapping started by the integrity check is still running.
+        if let Err(err) = self.wait_for_integrity_check(view) {
+            problems.push(IntegrityProblemKind::CorruptViewData {
+                view: view_name,
+                error: err.to_string(),
+            });
+            return Ok(problems);
+        }
+
+        let collection = view.collection();
+        // Documents that are waiting to be mapped may not be reflected in the
+        // view yet, so they are excluded from the comparisons.
+        let mut invalidated = HashSet::new();
+        let document_id = |key: &[u8]| DocumentId::try_from(key).ok();
+
+        if !self.read_view_tree(
+            &collection,
+            &mut problems,
+            view_invalidated_docs_tree_name(&view_name),
+            |key, _| {
+                if let Some(id) = document_id(&key) {
+                    invalidated.insert(id);
+                }
+            },
+        )? {
+            return Ok(problems);
+        }
+
+        // Documents that failed to map are quarantined rather than mapped.
+        let mut quarantined = HashSet::new();
+        if !self.read_view_tree(
+            &collection,
+            &mut problems,
+            view_quarantine_tree_name(&view_name),
+            |key, _| {
+                if let Some(id) = document_id(&key) {
+                    quarantined.insert(id);
+                }
+            },
+        )? {
+            return Ok(problems);
+        }
+
+        let mut document_maps = HashMap::<DocumentId, HashSet<OwnedBytes>>::new();
+        let mut corrupt = Vec::new();
+        if !self.read_view_tree(
+            &collection,
+            &mut problems,
+            view_document_map_tree_name(&view_name),
+            |key, value| {
+                let Some(id) = document_id(&key) else {
+                    corrupt.push(String::from("invalid document id in document map"));
+                    return;
+                };
+                match bincode::deserialize::<DocumentMap>(&value) {
+                    Ok(map) => {
+                        document_maps.insert(id, map.keys);
+                    }
+                    Err(err) => corrupt.push(err.to_string()),
+                }
+            },
+        )? {
+            return Ok(problems);
+        }
+
+        let mut mapped = HashSet::<(DocumentId, OwnedBytes)>::new();
+        let mut orphaned_entries = BTreeSet::new();
+        if !self.read_view_tree(
+            &collection,
+            &mut problems,
+            view_entries_tree_name(&view_name),
+            |key, value| match bincode::deserialize::<ViewEntry>(&value) {
+                Ok(entry) => {
+                    let key = OwnedBytes::from(key.as_slice());
+                    for mapping in entry.mappings {
+                        let id = mapping.source.id;
+                        if invalidated.contains(&id) {
+                            continue;
+                        }
+                        let document_maps_key = document_maps
+                            .get(&id)
+                            .map_or(false, |keys| keys.contains(&key));
+                        if !documents.contains(&id) || !document_maps_key {
+                            orphaned_entries.insert(id);
+                        } else {
+                            mapped.insert((id, key.clone()));
+                        }
+                    }
+                }
+                Err(err) => corrupt.push(err.to_string()),
+            },
+        )? {
+            return Ok(problems);
+        }
+
+        for error in corrupt {
+            problems.push(IntegrityProblemKind::CorruptViewData {
+                view: view_name.clone(),
+                error,
+            });
+        }
+        for document in orphaned_entries {
+            problems.push(IntegrityProblemKind::OrphanedViewEntry {
+                view: view_name.clone(),
+                document,
+            });
+        }
+
+        let mut document_map_ids = document_maps.keys().cloned().collect::<Vec<_>>();
+        document_map_ids.sort();
+        for id in document_map_ids {
+            if invalidated.contains(&id) {
+                continue;
+            }
+            let keys = &document_maps[&id];
+            if !documents.contains(&id) {
+                // Deleted documents leave an empty document map behind.
+                if !keys.is_empty() {
+                    problems.push(IntegrityProblemKind::OrphanedDocumentMap {
+                        view: view_name.clone(),
+                        document: id,
+                    });
+                }
+            } else if keys
+                .iter()
+                .any(|key| !mapped.contains(&(id.clone(), key.clone())))
+            {
+                problems.push(IntegrityProblemKind::MissingViewEntry {
+                    view: view_name.clone(),
+                    document: id,
+                });
+            }
+        }
+
+        let mut unmapped = documents
+            .iter()
+            .filter(|id| {
+                !invalidated.contains(*id)
+                    && !quarantined.contains(*id)
+                    && !document_maps.contains_key(*id)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        unmapped.sort();
+        for document in unmapped {
+            problems.push(IntegrityProblemKind::UnmappedDocument {
+                view: view_name.clone(),
+                document,
+            });
+        }
+
+        Ok(problems)
+    }
+
+    /// Reads every entry in the view tree `tree_name`, returning false and
+    /// recording a problem if the tree couldn't be read.
+    fn read_view_tree<F: FnMut(ArcBytes<'static>, ArcBytes<'static>)>(
+        &self,
+        collection: &CollectionName,
+        problems: &mut Vec<IntegrityProblemKind>,
+        tree_name: String,
+        callback: F,
+    ) -> Result<bool, Error> {
+        let root = self
+            .database
+            .collection_tree::<Unversioned, _>(collection, tree_name.clone())?;
+        match self.read_tree(root, callback) {
+            Ok(()) => Ok(true),
+            Err(err) => {
+                problems.push(IntegrityProblemKind::UnreadableTree {
+                    tree: tree_name,
+                    error: err.to_string(),
+                });
+                Ok(false)
+            }
+        }
+    }
+
+    fn wait_for_integrity_check(&self, view: &dyn view::Serialized) -> Result<(), Error> {
+        let tasks = self.database.storage.instance.tasks();
+        if let Some(job) = tasks.spawn_integrity_check(view, self.database) {
+            if let Some(mapper) = job.receive()?? {
+                let mapper = mapper.lock().take();
+                if let Some(mapper) = mapper {
+                    mapper.receive()??;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the data stored for `view` and maps every document in its
+    /// collection again.
+    fn rebuild_view(&self, view: &dyn view::Serialized) -> Result<(), Error> {
+        invalidate_view(
+            self.database,
+            &view.collection(),
+            &view.view_name(),
+            view.version(),
+        )?;
+        self.database
+            .storage
+            .instance
+            .tasks()
+            .jobs
+            .lookup_or_enqueue(Mapper {
+                database: self.database.clone(),
+                map: Map {
+                    database: self.database.data.name.clone(),
+                    collection: view.collection(),
+                    view_name: view.view_name(),
+                },
+            })
+            .receive()??;
+        Ok(())
+    }
+
+    fn check_key_value_entries(&mut self) -> Result<(), Error> {
+        let mut problems = Vec::new();
+        let result = self.read_tree(Unversioned::tree(KEY_TREE), |key, value| {
+            if let Err(err) = bincode::deserialize::<Entry>(&value) {
+                problems.push(IntegrityProblemKind::CorruptKeyValueEntry {
+                    key: String::from_utf8_lossy(&key).into_owned(),
+                    error: err.to_string(),
+                });
+            }
+        });
+        for problem in problems {
+            self.push(problem, false);
+        }
+        if let Err(err) = result {
+            self.push(
+                IntegrityProblemKind::UnreadableTree {
+                    tree: String::from(KEY_TREE),
+                    error: err.to_string(),
+                },
+                false,
+            );
+        }
+        Ok(())
+    }
+
+    /// Reads every key and value stored in the tree `root`, invoking
+    /// `callback` for each entry.
+    fn read_tree<R: Root, F: FnMut(ArcBytes<'static>, ArcBytes<'static>)>(
+        &self,
+        root: TreeRoot<R, AnyFile>,
+        mut callback: F,
+    ) -> Result<(), Error> {
+        let tree = self.database.roots().tree(root)?;
+        tree.scan::<Infallible, _, _, _, _>(
+            &(..),
+            true,
+            |_, _, _| ScanEvaluation::ReadData,
+            |_, _| ScanEvaluation::ReadData,
+            |key, _, value| {
+                callback(key, value);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+
+    fn push(&mut self, kind: IntegrityProblemKind, repaired: bool) {
+        self.report
+            .push(self.database.data.name.to_string(), kind, repaired);
+    }
+}
+
+/// The result of checking the integrity of a [`Storage`].
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct IntegrityReport {
+    /// The number of databases that were checked.
+    pub databases_checked: u64,
+    /// The number of documents that were read.
+    pub documents_checked: u64,
+    /// The number of views that were checked.
+    pub views_checked: u64,
+    /// The problems found.
+    pub problems: Vec<IntegrityProblem>,
+}
+
+impl IntegrityReport {
+    /// Returns true if every problem found was repaired.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.problems.iter().all(|problem| problem.repaired)
+    }
+
+    fn push(&mut self, database: String, kind: IntegrityProblemKind, repaired: bool) {
+        self.problems.push(IntegrityProblem {
+            database,
+            kind,
+            repaired,
+        });
+    }
+}
+
+/// A problem found while checking the integrity of a [`Storage`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IntegrityProblem {
+    /// The name of the database the problem was found in.
+    pub database: String,
+    /// The kind of problem.
+    pub kind: IntegrityProblemKind,
+    /// True if the problem was repaired.
+    pub repaired: bool,
+}
+
+impl Display for IntegrityProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.database, self.kind)?;
+        if self.repaired {
+            f.write_str(" (repaired)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A kind of [`IntegrityProblem`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum IntegrityProblemKind {
+    /// A tree could not be read. This is caused by data that doesn't match its
+    /// checksum, or by encrypted data that can't be decrypted.
+    UnreadableTree {
+        /// The name of the tree.
+        tree: String,
+        /// The error that occurred reading the tree.
+        error: String,
+    },
+    /// A document could not be deserialized.
+    CorruptDocument {
+        /// The collection of the document.
+        collection: CollectionName,
+        /// The id of the document, if it could be read.
+        id: Option<DocumentId>,
+        /// The error that occurred deserializing the document.
+        error: String,
+    },
+    /// Data stored for a view could not be deserialized.
+    CorruptViewData {
+        /// The name of the view.
+        view: ViewName,
+        /// The error that occurred deserializing the data.
+        error: String,
+    },
+    /// A view entry refers to a document that doesn't exist, or that is no
+    /// longer mapped to the entry's key.
+    OrphanedViewEntry {
+        /// The name of the view.
+        view: ViewName,
+        /// The id of the document the entry refers to.
+        document: DocumentId,
+    },
+    /// A view's document map contains keys for a document that doesn't exist.
+    OrphanedDocumentMap {
+        /// The name of the view.
+        view: ViewName,
+        /// The id of the document.
+        document: DocumentId,
+    },
+    /// A key in a view's document map has no corresponding view entry.
+    MissingViewEntry {
+        /// The name of the view.
+        view: ViewName,
+        /// The id of the document.
+        document: DocumentId,
+    },
+    /// A document has not been mapped by a view and isn't waiting to be
+    /// mapped.
+    UnmappedDocument {
+        /// The name of the view.
+        view: ViewName,
+        /// The id of the document.
+        document: DocumentId,
+    },
+    /// A key-value entry could not be deserialized.
+    CorruptKeyValueEntry {
+        /// The namespace and key of the entry.
+        key: String,
+        /// The error that occurred deserializing the entry.
+        error: String,
+    },
+    /// The database's schema is not registered with the storage, so its
+    /// contents could not be checked.
+    UnregisteredSchema {
+        /// The name of the schema.
+        schema: SchemaName,
+    },
+    /// The admin database contains more than one record for the database.
+    DuplicateDatabaseRecord,
+    /// A database is stored on disk, but the admin database has no record of
+    /// it.
+    OrphanedDatabase,
+}
+
+impl Display for IntegrityProblemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnreadableTree { tree, error } => write!(f, "tree {tree} is unreadable: {error}"),
+            Self::CorruptDocument {
+                collection,
+                id: Some(id),
+                error,
+            } => write!(f, "document {id} in {collection} is corrupt: {error}"),
+            Self::CorruptDocument {
+                collection,
+                id: None,
+                error,
+            } => write!(f, "a document in {collection} is corrupt: {error}"),
+            Self::CorruptViewData { view, error } => {
+                write!(f, "data for view {view} is corrupt: {error}")
+            }
+            Self::OrphanedViewEntry { view, document } => write!(
+                f,
+                "view {view} has an entry for document {document}, which no longer maps to it"
+            ),
+            Self::OrphanedDocumentMap { view, document } => write!(
+                f,
+                "view {view} has a document map for document {document}, which doesn't exist"
+            ),
+            Self::MissingViewEntry { view, document } => {
+                write!(f, "view {view} is missing an entry for document {document}")
+            }
+            Self::UnmappedDocument { view, document } => {
+                write!(f, "document {document} has not been mapped by view {view}")
+            }
+            Self::CorruptKeyValueEntry { key, error } => {
+                write!(f, "key-value entry {key} is corrupt: {error}")
+            }
+            Self::UnregisteredSchema { schema } => write!(f, "schema {schema} is not registered"),
+            Self::DuplicateDatabaseRecord => {
+                f.write_str("the admin database contains duplicate records")
+            }
+            Self::OrphanedDatabase => f.write_str("the admin database has no record of it"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::{Connection as _, StorageConnection as _};
+    use bonsaidb_core::schema::View;
+    use bonsaidb_core::test_util::{Basic, BasicByParentIdEager, TestDirectory};
+
+    use super::*;
+    use crate::config::{Builder, StorageConfiguration};
+
+    #[test]
+    fn check_and_repair() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("check-and-repair.bonsaidb");
+        let storage = Storage::open(StorageConfiguration::new(&directory).with_schema::<Basic>()?)?;
+        let db = storage.create_database::<Basic>("basic", false)?;
+        Basic::new("a").push_into(&db)?;
+        Basic::new("b").push_into(&db)?;
+
+        let report = storage.check_integrity()?;
+        assert!(report.problems.is_empty(), "{:?}", report.problems);
+        assert_eq!(report.databases_checked, 2);
+        assert!(report.documents_checked >= 2);
+
+        // Store a document map for a document that doesn't exist, and create
+        // a database directory that the admin database has no record of.
+        let view = BasicByParentIdEager.view_name();
+        let missing = DocumentId::from_u64(u64::MAX);
+        db.roots()
+            .tree(db.collection_tree::<Unversioned, _>(
+                &Basic::collection_name(),
+                view_document_map_tree_name(&view),
+            )?)?
+            .set(
+                missing.to_vec(),
+                bincode::serialize(&DocumentMap {
+                    keys: [OwnedBytes::from(&b"key"[..])].into_iter().collect(),
+                    dependencies: None,
+                })?,
+            )?;
+        let orphan = directory.0.join("orphan");
+        std::fs::create_dir(&orphan)?;
+        std::fs::write(orphan.join(TRANSACTION_LOG_FILE), b"")?;
+
+        let report = storage.check_integrity()?;
+        assert!(!report.is_consistent());
+        let problems = report
+            .problems
+            .iter()
+            .map(|problem| (problem.database.as_str(), &problem.kind))
+            .collect::<Vec<_>>();
+        assert!(problems.contains(&(
+            "basic",
+            &IntegrityProblemKind::OrphanedDocumentMap {
+                view,
+                document: missing,
+            }
+        )));
+        assert!(problems.contains(&("orphan", &IntegrityProblemKind::OrphanedDatabase)));
+
+        let report = storage.repair_integrity()?;
+        assert!(report.is_consistent(), "{:?}", report.problems);
+        assert!(!orphan.exists());
+        assert!(directory
+            .0
+            .join(QUARANTINE_DIRECTORY)
+            .join("orphan")
+            .join(TRANSACTION_LOG_FILE)
+            .exists());
+
+        let report = storage.check_integrity()?;
+        assert!(report.problems.is_empty(), "{:?}", report.problems);
+        assert_eq!(
+            db.view::<BasicByParentIdEager>().query()?.len(),
+            2,
+            "the view should be rebuilt"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use bonsaidb_core::connection::HasSession;
+use bonsaidb_core::document::KeyId;
+use bonsaidb_core::permissions::bonsai::{encryption_key_resource_name, EncryptionKeyAction};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::StorageInstance;
+use crate::tasks::{reencrypt_database, Job, Keyed, Task};
+use crate::{Error, Storage};
+
+/// The name of the file that tracks an in-progress re-encryption, allowing it
+/// to resume after the storage is reopened.
+const PENDING_REENCRYPTION_FILE: &str = "master-key-rotation";
+
+/// The progress of re-encrypting a [`Storage`] after its master key was
+/// rotated.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReencryptionStatus {
+    /// The id of the master key that data is being re-encrypted with.
+    pub master_key_id: u32,
+    /// The number of databases that have been re-encrypted.
+    pub databases_reencrypted: u64,
+    /// The number of databases that could not be opened, and were skipped.
+    /// Skipped databases are retried the next time the storage is opened, and
+    /// the re-encryption remains in progress until they have been
+    /// re-encrypted.
+    pub databases_skipped: u64,
+    /// The total number of databases being re-encrypted.
+    pub total_databases: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct PendingReencryption {
+    master_key_id: u32,
+    completed_databases: Vec<String>,
+    /// Databases that could not be opened during this run of the
+    /// re-encryption job.
+    #[serde(skip)]
+    skipped_databases: Vec<String>,
+}
+
+impl PendingReencryption {
+    fn path(storage_path: &Path) -> PathBuf {
+        storage_path.join(PENDING_REENCRYPTION_FILE)
+    }
+
+    pub(super) fn load(storage_path: &Path) -> Result<Option<Self>, Error> {
+        match fs::read(Self::path(storage_path)) {
+            Ok(contents) => Ok(Some(bincode::deserialize(&contents)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::from(err)),
+        }
+    }
+
+    fn save(&self, storage_path: &Path) -> Result<(), Error> {
+        fs::write(Self::path(storage_path), bincode::serialize(self)?)?;
+        Ok(())
+    }
+
+    fn remove(storage_path: &Path) -> Result<(), Error> {
+        match fs::remove_file(Self::path(storage_path)) {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(Error::from(err)),
+        }
+    }
+}
+
+impl Storage {
+    /// Generates a new master key and begins re-encrypting all data stored
+    /// using the master key in the background. Named encryption keys are
+    /// re-sealed using the new master key before any databases are
+    /// re-encrypted. Previous master keys are retained, ensuring data can be
+    /// read while it is being re-encrypted. Returns the id of the new master
+    /// key.
+    ///
+    /// The progress of the re-encryption can be monitored using
+    /// [`reencryption_status()`](Self::reencryption_status). If the storage is
+    /// closed before the re-encryption finishes, it will resume the next time
+    /// the storage is opened. Once it has finished, the previous master keys
+    /// can be removed using
+    /// [`retire_master_keys()`](Self::retire_master_keys).
+    pub fn rotate_master_key(&self) -> Result<u32, Error> {
+        self.check_permission(
+            encryption_key_resource_name(&KeyId::Master),
+            &EncryptionKeyAction::Rotate,
+        )?;
+        self.instance.check_writable()?;
+
+        let master_key_id = {
+            let mut pending = self.instance.data.pending_reencryption.lock();
+            let master_key_id = self.vault().rotate_master_key()?;
+            // Any in-progress re-encryption must start over using the new key.
+            let state = PendingReencryption {
+                master_key_id,
+                completed_databases: Vec::new(),
+                skipped_databases: Vec::new(),
+            };
+            state.save(&self.instance.data.path)?;
+            *pending = Some(state);
+            master_key_id
+        };
+
+        self.instance.resume_reencryption();
+
+        Ok(master_key_id)
+    }
+
+    /// Removes all master keys except the current one.
+    ///
+    /// This should be called after a
+    /// [rotation's re-encryption](Self::rotate_master_key) has finished.
+    /// Backups and exports that were encrypted using a retired master key can
+    /// no longer be decrypted by this storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReencryptionInProgress`] if data is still being
+    /// re-encrypted.
+    pub fn retire_master_keys(&self) -> Result<(), Error> {
+        self.check_permission(
+            encryption_key_resource_name(&KeyId::Master),
+            &EncryptionKeyAction::Rotate,
+        )?;
+        self.instance.check_writable()?;
+
+        // Holding the lock prevents a rotation from beginning while the keys
+        // are retired.
+        let pending = self.instance.data.pending_reencryption.lock();
+        if pending.is_some() {
+            return Err(Error::ReencryptionInProgress);
+        }
+        self.vault().retire_master_keys()?;
+        Ok(())
+    }
+
+    /// Returns the progress of re-encrypting the storage after its master key
+    /// was rotated, or `None` if no re-encryption is in progress.
+    pub fn reencryption_status(&self) -> Result<Option<ReencryptionStatus>, Error> {
+        let pending = self.instance.data.pending_reencryption.lock();
+        if let Some(pending) = pending.as_ref() {
+            let databases = self.instance.data.available_databases.read();
+            let existing = |names: &Vec<String>| {
+                names
+                    .iter()
+                    .filter(|name| databases.contains_key(name.as_str()))
+                    .count() as u64
+            };
+            Ok(Some(ReencryptionStatus {
+                master_key_id: pending.master_key_id,
+                databases_reencrypted: existing(&pending.completed_databases),
+                databases_skipped: existing(&pending.skipped_databases),
+                total_databases: databases.len() as u64,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl StorageInstance {
+    /// Spawns the background re-encryption job if a re-encryption is pending.
+    pub(super) fn resume_reencryption(&self) {
+        if self.data.pending_reencryption.lock().is_some() {
+            self.tasks().jobs.lookup_or_enqueue(Reencryptor {
+                storage: self.clone(),
+            });
+        }
+    }
+
+    /// Returns the name of the next database that needs to be re-encrypted,
+    /// along with the master key id it is being re-encrypted with.
+    ///
+    /// Once every database has been re-encrypted, the pending re-encryption is
+    /// cleared. If any databases were skipped, it remains pending so that they
+    /// are retried the next time the storage is opened.
+    fn next_database_to_reencrypt(&self) -> Result<Option<(u32, String)>, Error> {
+        let mut pending = self.data.pending_reencryption.lock();
+        if let Some(state) = pending.as_ref() {
+            let next = self
+                .data
+                .available_databases
+                .read()
+                .keys()
+                .find(|name| {
+                    !state.completed_databases.contains(*name)
+                        && !state.skipped_databases.contains(*name)
+                })
+                .cloned();
+            if let Some(name) = next {
+                return Ok(Some((state.master_key_id, name)));
+            }
+
+            if state.skipped_databases.is_empty() {
+                PendingReencryption::remove(&self.data.path)?;
+                *pending = None;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn mark_database_reencrypted(&self, master_key_id: u32, name: String) -> Result<(), Error> {
+        let mut pending = self.data.pending_reencryption.lock();
+        if let Some(state) = pending
+            .as_mut()
+            .filter(|state| state.master_key_id == master_key_id)
+        {
+            state.completed_databases.push(name);
+            state.save(&self.data.path)?;
+        }
+        Ok(())
+    }
+
+    fn mark_database_skipped(&self, master_key_id: u32, name: String) {
+        let mut pending = self.data.pending_reencryption.lock();
+        if let Some(state) = pending
+            .as_mut()
+            .filter(|state| state.master_key_id == master_key_id)
+        {
+            state.skipped_databases.push(name);
+        }
+    }
+
+    fn reencrypt(&self) -> Result<(), Error> {
+        self.data.vault.reseal_named_keys()?;
+        while let Some((master_key_id, name)) = self.next_database_to_reencrypt()? {
+            match self.database_without_schema(&name, None, None) {
+                Ok(database) => reencrypt_database(&database)?,
+                // The database was deleted while it was waiting to be
+                // re-encrypted.
+                Err(Error::Core(bonsaidb_core::Error::DatabaseNotFound(_))) => {}
+                Err(err) => {
+                    log::error!("Skipping re-encryption of database {name}: {err}");
+                    self.mark_database_skipped(master_key_id, name);
+                    continue;
+                }
+            }
+            self.mark_database_reencrypted(master_key_id, name)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Reencryptor {
+    storage: StorageInstance,
+}
+
+impl Job for Reencryptor {
+    type Error = Error;
+    type Output = ();
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn execute(&mut self) -> Result<Self::Output, Self::Error> {
+        let result = self.storage.reencrypt();
+        if let Err(err) = &result {
+            log::error!("Error re-encrypting storage: {err}");
+        }
+        result
+    }
+}
+
+impl Keyed<Task> for Reencryptor {
+    fn key(&self) -> Task {
+        Task::Reencryption
+    }
+}
@@ -0,0 +1,240 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use parking_lot::Mutex;
+
+use super::backup::RestoreState;
+use super::{Data, StorageInstance};
+use crate::config::Snapshots;
+use crate::{Error, Storage};
+
+/// The extension of a full snapshot's directory while it is being written.
+const PARTIAL_EXTENSION: &str = "partial";
+
+impl Storage {
+    /// Stores a snapshot of this storage's data as configured by
+    /// [`StorageConfiguration::snapshots`](crate::config::StorageConfiguration#structfield.snapshots).
+    /// Snapshots are stored periodically in the background, but calling this
+    /// function before closing the storage ensures no changes are lost.
+    ///
+    /// Returns [`Error::SnapshotsNotConfigured`] if snapshots aren't
+    /// configured for this storage.
+    pub fn snapshot(&self) -> Result<(), Error> {
+        self.instance
+            .data
+            .snapshots
+            .as_ref()
+            .ok_or(Error::SnapshotsNotConfigured)?
+            .take(self)
+    }
+}
+
+/// Stores and restores snapshots of a memory-only storage according to its
+/// [`Snapshots`] configuration.
+#[derive(Debug)]
+pub(crate) struct Snapshotter {
+    config: Snapshots,
+    state: Mutex<SnapshotState>,
+}
+
+#[derive(Debug, Default)]
+struct SnapshotState {
+    /// The id of the full snapshot taken by this storage, which incremental
+    /// snapshots are stored in.
+    current: Option<u64>,
+    /// The number of incremental snapshots stored in `current`.
+    incremental_snapshots: u32,
+}
+
+impl Snapshotter {
+    pub fn new(config: Snapshots) -> Self {
+        Self {
+            config,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Restores the most recent snapshot into `storage`, if one exists.
+    pub fn restore_latest(&self, storage: &Storage) -> Result<(), Error> {
+        // The databases of a memory-only storage never outlive it, so the
+        // progress of a restore interrupted by a previous instance no longer
+        // applies.
+        RestoreState::remove_all(&storage.instance.data.path)?;
+        if let Some(id) = self.complete_snapshots()?.last() {
+            storage.restore(&self.snapshot_path(*id))?;
+        }
+        Ok(())
+    }
+
+    /// Spawns a thread that stores snapshots of `storage` until it is dropped.
+    pub fn spawn(storage: &Arc<Data>) -> Result<(), Error> {
+        let storage = Arc::downgrade(storage);
+        thread::Builder::new()
+            .name(String::from("snapshots"))
+            .spawn(move || loop {
+                let interval = match storage.upgrade() {
+                    Some(data) => match &data.snapshots {
+                        Some(snapshotter) => snapshotter.config.interval,
+                        None => break,
+                    },
+                    None => break,
+                };
+                thread::sleep(interval);
+
+                let storage = match storage.upgrade() {
+                    Some(data) => Storage::from(StorageInstance { data }),
+                    None => break,
+                };
+                if let Err(err) = storage.snapshot() {
+                    log::error!("Error storing snapshot: {err}");
+                }
+            })?;
+        Ok(())
+    }
+
+    /// Stores a snapshot of `storage`. A full snapshot is stored if this
+    /// storage hasn't stored one yet, or if the configured number of
+    /// incremental snapshots have been stored since the last one.
+    fn take(&self, storage: &Storage) -> Result<(), Error> {
+        let mut state = self.state.lock();
+        match state.current {
+            Some(id) if state.incremental_snapshots < self.config.incremental_snapshots => {
+                storage.backup_incremental(&self.snapshot_path(id))?;
+                state.incremental_snapshots += 1;
+            }
+            _ => {
+                let previous = self.complete_snapshots()?;
+                let id = previous.last().map_or(0, |id| id + 1);
+                // The snapshot is written to a separate directory first so
+                // that the previous snapshot remains intact until this one is
+                // complete.
+                let partial = self.snapshot_path(id).with_extension(PARTIAL_EXTENSION);
+                remove_directory(&partial)?;
+                storage.backup(&partial)?;
+                fs::rename(&partial, self.snapshot_path(id))?;
+                for previous in previous {
+                    remove_directory(&self.snapshot_path(previous))?;
+                }
+
+                *state = SnapshotState {
+                    current: Some(id),
+                    incremental_snapshots: 0,
+                };
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the ids of the completely written full snapshots, in ascending
+    /// order.
+    fn complete_snapshots(&self) -> Result<Vec<u64>, Error> {
+        let entries = match fs::read_dir(&self.config.path) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(Error::from(err)),
+        };
+        let mut ids = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                if let Some(id) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.parse::<u64>().ok())
+                {
+                    ids.push(id);
+                }
+            }
+        }
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    fn snapshot_path(&self, id: u64) -> PathBuf {
+        self.config.path.join(format!("{id:020}"))
+    }
+}
+
+fn remove_directory(path: &Path) -> Result<(), Error> {
+    match fs::remove_dir_all(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(Error::from(err)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::StorageConnection as _;
+    use bonsaidb_core::keyvalue::KeyValue;
+    use bonsaidb_core::schema::SerializedCollection;
+    use bonsaidb_core::test_util::{Basic, TestDirectory};
+
+    use crate::config::{Builder, Snapshots, StorageConfiguration};
+    use crate::{Error, Storage};
+
+    #[test]
+    fn memory_snapshots() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("memory-snapshots.bonsaidb");
+        let snapshots = TestDirectory::new("memory-snapshots.bonsaidb.snapshots");
+        let configuration = || {
+            StorageConfiguration::new(&directory)
+                .memory_only()
+                .snapshots(Snapshots::new(&snapshots).incremental_snapshots(1))
+                .with_schema::<Basic>()
+        };
+
+        let (first, second) = {
+            let storage = Storage::open(configuration()?)?;
+            let db = storage.create_database::<Basic>("basic", false)?;
+            let first = Basic::new("first").push_into(&db)?;
+            db.set_numeric_key("key", 1_u64).execute()?;
+            // The first snapshot is a full snapshot.
+            storage.snapshot()?;
+            let second = Basic::new("second").push_into(&db)?;
+            // The second snapshot only stores the new document.
+            storage.snapshot()?;
+            (first, second)
+        };
+
+        let storage = Storage::open(configuration()?)?;
+        let db = storage.database::<Basic>("basic")?;
+        assert_eq!(
+            Basic::get(&first.header.id, &db)?.unwrap().contents.value,
+            "first"
+        );
+        assert_eq!(
+            Basic::get(&second.header.id, &db)?.unwrap().contents.value,
+            "second"
+        );
+        assert_eq!(db.get_key("key").into_u64()?, Some(1));
+
+        // After the configured number of incremental snapshots, a new full
+        // snapshot replaces the previous one.
+        let deleted = Basic::get(&first.header.id, &db)?.unwrap();
+        deleted.delete(&db)?;
+        storage.snapshot()?;
+        storage.snapshot()?;
+        drop((db, storage));
+        let snapshot_directories = std::fs::read_dir(&snapshots)?.count();
+        assert_eq!(snapshot_directories, 1);
+
+        let storage = Storage::open(configuration()?)?;
+        let db = storage.database::<Basic>("basic")?;
+        assert!(Basic::get(&first.header.id, &db)?.is_none());
+        assert_eq!(Basic::all(&db).count()?, 1);
+
+        // Storages without snapshots can't store them.
+        let unconfigured_directory = TestDirectory::new("memory-snapshots-unconfigured.bonsaidb");
+        let unconfigured =
+            Storage::open(StorageConfiguration::new(&unconfigured_directory).memory_only())?;
+        assert!(matches!(
+            unconfigured.snapshot(),
+            Err(Error::SnapshotsNotConfigured)
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+use std::convert::Infallible;
+use std::path::Path;
+
+use bonsaidb_core::connection::{
+    CollectionStats, Connection, DatabaseStats, KeyValueStats, StorageStats, TreeStats, ViewStats,
+};
+use bonsaidb_core::schema::CollectionName;
+use nebari::io::any::AnyFile;
+use nebari::io::FileManager;
+use nebari::tree::{Root, ScanEvaluation, TreeRoot, Unversioned, Versioned};
+
+use super::fsck::TRANSACTION_LOG_FILE;
+use super::StorageInstance;
+use crate::database::document_tree_name;
+use crate::database::keyvalue::KEY_TREE;
+use crate::tasks::{tree_targets, Target};
+use crate::views::{
+    view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
+    view_quarantine_tree_name, view_reductions_tree_name, view_versions_tree_name,
+};
+use crate::{Database, DatabaseNonBlocking, Error, Storage};
+
+impl StorageInstance {
+    /// Returns the statistics of every database whose schema is registered.
+    /// When `storage` is provided, its permissions are checked for each
+    /// database.
+    pub(crate) fn stats_with(&self, storage: Option<&Storage>) -> Result<StorageStats, Error> {
+        let mut names = self
+            .data
+            .available_databases
+            .read()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+
+        let mut stats = StorageStats::default();
+        for name in names {
+            let database = match self.database_without_schema(&name, storage, None) {
+                Ok(database) => database,
+                // The database was deleted, or its schema isn't registered with
+                // this storage.
+                Err(Error::Core(
+                    bonsaidb_core::Error::DatabaseNotFound(_)
+                    | bonsaidb_core::Error::SchemaNotRegistered(_),
+                )) => continue,
+                Err(other) => return Err(other),
+            };
+            stats.databases.push(database.stats()?);
+        }
+        stats.caches = self.data.chunk_caches.stats();
+        Ok(stats)
+    }
+
+    /// Returns the length of the file at `path`, or `None` if it doesn't
+    /// exist.
+    pub(crate) fn file_length(&self, path: &Path) -> Result<Option<u64>, Error> {
+        let file_manager = &self.data.file_manager;
+        if file_manager.exists(path).map_err(Error::Nebari)? {
+            file_manager
+                .file_length(path)
+                .map(Some)
+                .map_err(Error::Nebari)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the length of the file storing the tree `tree` in `database`,
+    /// or `None` if the tree has not been written to.
+    pub(crate) fn tree_file_length(
+        &self,
+        database: &str,
+        tree: &str,
+    ) -> Result<Option<u64>, Error> {
+        self.file_length(&self.data.path.join(database).join(format!("{tree}.nebari")))
+    }
+}
+
+/// Gathers the usage statistics of `database`.
+pub(crate) fn database_stats(database: &Database) -> Result<DatabaseStats, Error> {
+    let mut collections = Vec::new();
+    for collection in database.schematic().collections() {
+        let documents_tree = tree_stats::<Versioned>(
+            database,
+            Some(collection),
+            document_tree_name(collection),
+            |index| index.value_length,
+        )?;
+        let view_trees = vec![unversioned_tree_stats(
+            database,
+            Some(collection),
+            view_versions_tree_name(collection),
+        )?];
+        let mut views = Vec::new();
+        for view in database.data.schema.views_in_collection(collection) {
+            let name = view.view_name();
+            let trees = [
+                view_entries_tree_name(&name),
+                view_document_map_tree_name(&name),
+                view_invalidated_docs_tree_name(&name),
+                view_reductions_tree_name(&name),
+                view_quarantine_tree_name(&name),
+            ]
+            .into_iter()
+            .map(|tree| unversioned_tree_stats(database, Some(collection), tree))
+            .collect::<Result<Vec<_>, _>>()?;
+            views.push(ViewStats {
+                view: name,
+                entries: trees[0].entries,
+                trees,
+            });
+        }
+        collections.push(CollectionStats {
+            collection: collection.clone(),
+            documents: documents_tree.entries,
+            documents_tree,
+            views,
+            view_trees,
+        });
+    }
+
+    let tree = unversioned_tree_stats(database, None, KEY_TREE.to_string())?;
+    Ok(DatabaseStats {
+        name: database.name().to_string(),
+        collections,
+        key_value: KeyValueStats {
+            keys: tree.entries,
+            tree,
+        },
+        transaction_log_bytes: transaction_log_length(database)?,
+    })
+}
+
+/// Returns the number of bytes `database` occupies on disk, including its
+/// transaction log. Unlike [`database_stats()`], the trees are not read.
+pub(crate) fn database_file_bytes(database: &Database) -> Result<u64, Error> {
+    let storage = &database.storage().instance;
+    let mut bytes = transaction_log_length(database)?;
+    for target in tree_targets(database) {
+        match target {
+            Target::VersionedTree(name) | Target::UnversionedTree(name) => {
+                bytes += storage
+                    .tree_file_length(database.name(), &name)?
+                    .unwrap_or_default();
+            }
+            Target::Collection(_) | Target::KeyValue | Target::Database => {
+                unreachable!("only individual trees are gathered")
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+fn transaction_log_length(database: &Database) -> Result<u64, Error> {
+    let storage = &database.storage().instance;
+    storage
+        .file_length(
+            &storage
+                .data
+                .path
+                .join(database.name())
+                .join(TRANSACTION_LOG_FILE),
+        )
+        .map(Option::unwrap_or_default)
+}
+
+fn unversioned_tree_stats(
+    database: &Database,
+    collection: Option<&CollectionName>,
+    name: String,
+) -> Result<TreeStats, Error> {
+    tree_stats::<Unversioned>(database, collection, name, |index| index.value_length)
+}
+
+fn tree_stats<R: Root>(
+    database: &Database,
+    collection: Option<&CollectionName>,
+    name: String,
+    value_length: impl Fn(&R::Index) -> u32,
+) -> Result<TreeStats, Error> {
+    let file_bytes = match database
+        .storage()
+        .instance
+        .tree_file_length(database.name(), &name)?
+    {
+        Some(file_bytes) => file_bytes,
+        // The tree has not been written to.
+        None => {
+            return Ok(TreeStats {
+                name,
+                ..TreeStats::default()
+            })
+        }
+    };
+
+    let root = match collection {
+        Some(collection) => database.collection_tree::<R, _>(collection, name.clone())?,
+        None => R::tree(name.clone()),
+    };
+    let (entries, live_bytes) = read_tree_index(database, root, value_length)?;
+    Ok(TreeStats {
+        name,
+        entries,
+        file_bytes,
+        live_bytes,
+    })
+}
+
+/// Reads the index of the tree `root`, returning the number of entries and
+/// the number of bytes the current keys and values occupy. The values
+/// themselves are not read.
+pub(crate) fn read_tree_index<R: Root>(
+    database: &Database,
+    root: TreeRoot<R, AnyFile>,
+    value_length: impl Fn(&R::Index) -> u32,
+) -> Result<(u64, u64), Error> {
+    let tree = database.roots().tree(root)?;
+    let mut entries = 0_u64;
+    let mut bytes = 0_u64;
+    tree.scan::<Infallible, _, _, _, _>(
+        &(..),
+        true,
+        |_, _, _| ScanEvaluation::ReadData,
+        |key, index| {
+            entries += 1;
+            bytes += key.len() as u64 + u64::from(value_length(index));
+            ScanEvaluation::Skip
+        },
+        |_, _, _| Ok(()),
+    )?;
+    Ok((entries, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use bonsaidb_core::connection::StorageConnection as _;
+    use bonsaidb_core::schema::{Collection, SerializedCollection, View};
+    use bonsaidb_core::test_util::{Basic, BasicByParentIdEager, TestDirectory};
+
+    use super::*;
+    use crate::config::{Builder, StorageConfiguration};
+
+    #[test]
+    fn database_and_storage_stats() -> anyhow::Result<()> {
+        let directory = TestDirectory::new("stats.bonsaidb");
+        let storage = Storage::open(StorageConfiguration::new(&directory).with_schema::<Basic>()?)?;
+        let db = storage.create_database::<Basic>("basic", false)?;
+        let parent = Basic::new("a").push_into(&db)?;
+        Basic::new("b")
+            .with_parent_id(parent.header.id)
+            .push_into(&db)?;
+
+        let stats = db.stats()?;
+        assert_eq!(stats.name, "basic");
+        assert!(stats.transaction_log_bytes > 0);
+        let collection = stats
+            .collections
+            .iter()
+            .find(|collection| collection.collection == Basic::collection_name())
+            .expect("collection missing");
+        assert_eq!(collection.documents, 2);
+        assert!(collection.documents_tree.file_bytes > 0);
+        assert!(collection.documents_tree.live_bytes > 0);
+        let view = collection
+            .views
+            .iter()
+            .find(|view| view.view == BasicByParentIdEager.view_name())
+            .expect("view missing");
+        // The two documents have different parent ids.
+        assert_eq!(view.entries, 2);
+        assert!(stats.file_bytes() >= collection.file_bytes());
+
+        let storage_stats = storage.stats()?;
+        let names = storage_stats
+            .databases
+            .iter()
+            .map(|database| database.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["_admin", "basic"]);
+        assert!(storage_stats.file_bytes() > stats.file_bytes());
+
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use bonsaidb_core::connection::Connection;
 use bonsaidb_core::keyvalue::Timestamp;
+use bonsaidb_core::schema::view::ViewIndexState;
 use bonsaidb_core::schema::{view, CollectionName, ViewName};
 use parking_lot::RwLock;
 
@@ -12,7 +14,9 @@ use crate::database::Database;
 use crate::tasks::compactor::Compactor;
 use crate::tasks::handle::Handle;
 use crate::tasks::manager::Manager;
-use crate::views::integrity_scanner::{IntegrityScan, IntegrityScanner, OptionalViewMapHandle};
+use crate::views::integrity_scanner::{
+    invalidate_view, IntegrityScan, IntegrityScanner, OptionalViewMapHandle,
+};
 use crate::views::mapper::{Map, Mapper};
 use crate::Error;
 
@@ -27,6 +31,9 @@ pub use self::traits::{Job, Keyed};
 mod compactor;
 mod task;
 
+#[cfg(feature = "encryption")]
+pub use compactor::reencrypt_database;
+pub use compactor::{collection_tree_targets, tree_targets, Target};
 pub use task::Task;
 
 #[derive(Debug, Clone)]
@@ -42,6 +49,32 @@ pub struct Statuses {
     completed_integrity_checks: HashSet<ViewKey>,
     key_value_expiration_loads: HashSet<Arc<Cow<'static, str>>>,
     view_update_last_status: HashMap<ViewKey, u64>,
+    view_update_progress: HashMap<ViewKey, Arc<ViewUpdateProgress>>,
+}
+
+/// Tracks how many documents a running view update has mapped.
+#[derive(Default, Debug)]
+pub struct ViewUpdateProgress {
+    mapped: AtomicU64,
+    total: AtomicU64,
+}
+
+impl ViewUpdateProgress {
+    pub fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn add_mapped(&self, count: u64) {
+        self.mapped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn state(&self) -> ViewIndexState {
+        let total = self.total.load(Ordering::Relaxed);
+        ViewIndexState::Rebuilding {
+            mapped: self.mapped.load(Ordering::Relaxed).min(total),
+            total,
+        }
+    }
 }
 
 impl TaskManager {
@@ -58,6 +91,11 @@ impl TaskManager {
         database: &Database,
         block_until_updated: bool,
     ) -> Result<(), crate::Error> {
+        // Views of a read-only storage are never updated.
+        if database.storage.instance.is_read_only() {
+            return Ok(());
+        }
+
         let view_name = view.view_name();
         if let Some(job) = self.spawn_integrity_check(view, database) {
             job.receive()??;
@@ -130,6 +168,12 @@ impl TaskManager {
         view: &dyn view::Serialized,
         database: &Database,
     ) -> Option<Handle<OptionalViewMapHandle, Error>> {
+        // Checking a view's integrity may invalidate its data, which a
+        // read-only storage can't do.
+        if database.storage.instance.is_read_only() {
+            return None;
+        }
+
         let view_name = view.view_name();
         if self.view_integrity_checked(
             database.data.name.clone(),
@@ -151,6 +195,38 @@ impl TaskManager {
         }
     }
 
+    /// Deletes the stored index of `view` and begins rebuilding it in the
+    /// background, even if the view is up to date.
+    pub fn rebuild_view(
+        &self,
+        view: &dyn view::Serialized,
+        database: &Database,
+    ) -> Result<(), crate::Error> {
+        if database.storage.instance.is_read_only() {
+            return Ok(());
+        }
+
+        // The integrity check must complete first, otherwise it could
+        // invalidate the view again after the rebuild has begun.
+        if let Some(job) = self.spawn_integrity_check(view, database) {
+            job.receive()??;
+        }
+
+        invalidate_view(
+            database,
+            &view.collection(),
+            &view.view_name(),
+            view.version(),
+        )?;
+        self.statuses.write().view_update_last_status.remove(&(
+            database.data.name.clone(),
+            view.collection(),
+            view.view_name(),
+        ));
+
+        self.update_view_if_needed(view, database, false)
+    }
+
     pub fn mark_integrity_check_complete(
         &self,
         database: Arc<Cow<'static, str>>,
@@ -163,6 +239,45 @@ impl TaskManager {
             .insert((database, collection, view_name));
     }
 
+    pub fn begin_view_update(
+        &self,
+        database: Arc<Cow<'static, str>>,
+        collection: CollectionName,
+        view_name: ViewName,
+    ) -> Arc<ViewUpdateProgress> {
+        let progress = Arc::new(ViewUpdateProgress::default());
+        let mut statuses = self.statuses.write();
+        statuses
+            .view_update_progress
+            .insert((database, collection, view_name), progress.clone());
+        progress
+    }
+
+    pub fn finish_view_update(
+        &self,
+        database: Arc<Cow<'static, str>>,
+        collection: CollectionName,
+        view_name: ViewName,
+    ) {
+        let mut statuses = self.statuses.write();
+        statuses
+            .view_update_progress
+            .remove(&(database, collection, view_name));
+    }
+
+    pub fn view_update_progress(
+        &self,
+        database: Arc<Cow<'static, str>>,
+        collection: CollectionName,
+        view_name: ViewName,
+    ) -> Option<Arc<ViewUpdateProgress>> {
+        let statuses = self.statuses.read();
+        statuses
+            .view_update_progress
+            .get(&(database, collection, view_name))
+            .cloned()
+    }
+
     pub fn mark_key_value_expiration_loaded(&self, database: Arc<Cow<'static, str>>) {
         let mut statuses = self.statuses.write();
         statuses.key_value_expiration_loads.insert(database);
@@ -195,11 +310,7 @@ impl TaskManager {
         }
     }
 
-    pub fn spawn_compact_target(
-        &self,
-        database: Database,
-        target: compactor::Target,
-    ) -> Handle<(), Error> {
+    pub fn spawn_compact_target(&self, database: Database, target: Target) -> Handle<(), Error> {
         self.jobs
             .lookup_or_enqueue(Compactor::target(database, target))
     }
@@ -5,11 +5,12 @@ use bonsaidb_core::schema::CollectionName;
 use nebari::tree::{Root, Unversioned, Versioned};
 
 use crate::database::keyvalue::KEY_TREE;
+use crate::database::quota::QUOTA_TREE;
 use crate::database::{document_tree_name, DatabaseNonBlocking};
 use crate::tasks::{Job, Keyed, Task};
 use crate::views::{
     view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
-    view_versions_tree_name,
+    view_quarantine_tree_name, view_reductions_tree_name, view_versions_tree_name,
 };
 use crate::{Database, Error};
 
@@ -75,12 +76,32 @@ impl Target {
                     gather_collection_trees(database, collection, &mut trees);
                 }
                 trees.push(Target::KeyValue);
+                trees.push(Target::UnversionedTree(QUOTA_TREE.to_string()));
                 compact_trees(database, trees)
             }
         }
     }
 }
 
+/// Returns a target for each individual tree stored in `database`.
+pub fn tree_targets(database: &Database) -> Vec<Target> {
+    let mut trees = Vec::new();
+    for collection in database.schematic().collections() {
+        gather_collection_trees(database, collection, &mut trees);
+    }
+    trees.push(Target::UnversionedTree(KEY_TREE.to_string()));
+    trees.push(Target::UnversionedTree(QUOTA_TREE.to_string()));
+    trees
+}
+
+/// Returns a target for each individual tree storing `collection` in
+/// `database`.
+pub fn collection_tree_targets(database: &Database, collection: &CollectionName) -> Vec<Target> {
+    let mut trees = Vec::new();
+    gather_collection_trees(database, collection, &mut trees);
+    trees
+}
+
 impl Job for Compactor {
     type Error = Error;
     type Output = ();
@@ -112,6 +133,8 @@ fn gather_collection_trees(
         trees.push(Target::UnversionedTree(view_invalidated_docs_tree_name(
             &name,
         )));
+        trees.push(Target::UnversionedTree(view_reductions_tree_name(&name)));
+        trees.push(Target::UnversionedTree(view_quarantine_tree_name(&name)));
     }
 }
 
@@ -134,6 +157,44 @@ fn compact_trees(database: &Database, targets: Vec<Target>) -> Result<(), Error>
     Ok(())
 }
 
+/// Re-encrypts every tree in `database`. Compacting a tree rewrites each of its
+/// values through the tree's vault, which encrypts them using the current
+/// master key.
+#[cfg(feature = "encryption")]
+pub fn reencrypt_database(database: &Database) -> Result<(), Error> {
+    for collection in database.schematic().collections() {
+        let mut trees = Vec::new();
+        gather_collection_trees(database, collection, &mut trees);
+        for target in trees {
+            match target {
+                Target::VersionedTree(name) => {
+                    compact_collection_tree::<Versioned>(database, collection, name)?;
+                }
+                Target::UnversionedTree(name) => {
+                    compact_collection_tree::<Unversioned>(database, collection, name)?;
+                }
+                Target::Collection(_) | Target::KeyValue | Target::Database => {
+                    unreachable!("only individual trees are gathered")
+                }
+            }
+        }
+    }
+    compact_tree::<Unversioned, _>(database, KEY_TREE)
+}
+
+#[cfg(feature = "encryption")]
+fn compact_collection_tree<R: Root>(
+    database: &Database,
+    collection: &CollectionName,
+    name: String,
+) -> Result<(), Error> {
+    let tree = database
+        .roots()
+        .tree(database.collection_tree::<R, _>(collection, name)?)?;
+    tree.compact()?;
+    Ok(())
+}
+
 fn compact_tree<R: Root, S: Into<Cow<'static, str>>>(
     database: &Database,
     name: S,
@@ -11,4 +11,6 @@ pub enum Task {
     ViewMap(Map),
     Compaction(Compaction),
     ExpirationLoader(Arc<Cow<'static, str>>),
+    #[cfg(feature = "encryption")]
+    Reencryption,
 }
@@ -224,6 +224,307 @@ fn integrity_checks() -> anyhow::Result<()> {
     unreachable!("Integrity checker didn't run in the allocated time")
 }
 
+#[test]
+fn view_statuses() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::view::ViewIndexState;
+    use bonsaidb_core::schema::View;
+
+    let path = TestDirectory::new("view-statuses");
+    let db = Database::open::<Basic>(StorageConfiguration::new(&path))?;
+    db.collection::<Basic>()
+        .push(&Basic::default().with_parent_id(1))?;
+
+    let view = BasicByParentId.view_name();
+    let status = db.view_status(&view)?;
+    assert_eq!(status.state, ViewIndexState::Unverified);
+    assert_eq!(status.invalidated_documents, 1);
+
+    db.reindex_views(Some(&[view.clone()]))?;
+    for _ in 0_u8..100 {
+        if db.view_status(&view)?.is_up_to_date() {
+            assert_eq!(
+                db.view::<BasicByParentId>()
+                    .with_access_policy(AccessPolicy::NoUpdate)
+                    .query()?
+                    .len(),
+                1
+            );
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    unreachable!("view wasn't re-indexed in the allocated time")
+}
+
+#[test]
+fn reindex_rebuilds_up_to_date_views() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::View;
+
+    let path = TestDirectory::new("reindex-rebuilds-views");
+    let db = Database::open::<Basic>(StorageConfiguration::new(&path))?;
+    db.collection::<Basic>()
+        .push(&Basic::default().with_parent_id(1))?;
+    assert_eq!(db.view::<BasicByParentId>().query()?.len(), 1);
+
+    // Remove the view's entries behind its back. The view still believes it
+    // is up to date, so only an explicit rebuild will restore the entries.
+    let view = BasicByParentId.view_name();
+    db.roots()
+        .delete_tree(crate::views::view_entries_tree_name(&view))?;
+    assert!(db.view_status(&view)?.is_up_to_date());
+    assert!(db.view::<BasicByParentId>().query()?.is_empty());
+
+    db.reindex_views(Some(&[view.clone()]))?;
+    for _ in 0_u8..100 {
+        if db.view_status(&view)?.is_up_to_date()
+            && db
+                .view::<BasicByParentId>()
+                .with_access_policy(AccessPolicy::NoUpdate)
+                .query()?
+                .len()
+                == 1
+        {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    unreachable!("view wasn't rebuilt in the allocated time")
+}
+
+#[test]
+fn cached_range_reductions() -> anyhow::Result<()> {
+    let path = TestDirectory::new("cached-range-reductions");
+    let db = Database::open::<Basic>(StorageConfiguration::new(&path))?;
+    let mut documents = Vec::new();
+    for parent_id in (0..2_000).step_by(13) {
+        for _ in 0..parent_id % 3 + 1 {
+            documents.push(
+                db.collection::<Basic>()
+                    .push(&Basic::default().with_parent_id(parent_id))?,
+            );
+        }
+    }
+    // Removing documents removes their keys from the cached reductions.
+    for header in documents.iter().step_by(5) {
+        db.collection::<Basic>().delete(header)?;
+    }
+
+    let grouped = db.view::<BasicByParentId>().reduce_grouped()?;
+    let expected = |start: u64, end: u64| -> usize {
+        grouped
+            .iter()
+            .filter(|mapping| matches!(mapping.key, Some(key) if key >= start && key < end))
+            .map(|mapping| mapping.value)
+            .sum()
+    };
+    for (start, end) in [(0, 2_000), (1, 1_000), (13, 14), (250, 1_999), (500, 500)] {
+        assert_eq!(
+            db.view::<BasicByParentId>()
+                .with_key_range(Some(start)..Some(end))
+                .reduce()?,
+            expected(start, end)
+        );
+    }
+    assert_eq!(
+        db.view::<BasicByParentId>().reduce()?,
+        grouped.iter().map(|mapping| mapping.value).sum::<usize>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn view_dependencies() -> anyhow::Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use bonsaidb_core::document::{BorrowedDocument, CollectionDocument, Emit};
+    use bonsaidb_core::schema::{
+        Collection, CollectionMapReduce, SerializedCollection, View, ViewMapResult, ViewSchema,
+    };
+    use serde::{Deserialize, Serialize};
+
+    static MAPPED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Collection, Clone, Debug, Serialize, Deserialize)]
+    #[collection(name = "products", views = [ProductsByCategory], core = bonsaidb_core)]
+    struct Product {
+        category: String,
+        stock: u32,
+    }
+
+    #[derive(Clone, View, ViewSchema, Debug)]
+    #[view(collection = Product, key = String, core = bonsaidb_core)]
+    #[view_schema(core = bonsaidb_core, filter = has_contents, dependencies = category)]
+    struct ProductsByCategory;
+
+    impl CollectionMapReduce for ProductsByCategory {
+        fn map<'doc>(
+            &self,
+            document: CollectionDocument<<Self::View as View>::Collection>,
+        ) -> ViewMapResult<'doc, Self> {
+            MAPPED.fetch_add(1, Ordering::SeqCst);
+            document.header.emit_key(document.contents.category)
+        }
+    }
+
+    fn has_contents(document: &BorrowedDocument<'_>) -> bool {
+        !document.contents.is_empty()
+    }
+
+    fn category(document: &BorrowedDocument<'_>) -> Result<Option<Vec<u8>>, bonsaidb_core::Error> {
+        let product = Product::document_contents(document)?;
+        Ok(Some(product.category.into_bytes()))
+    }
+
+    let path = TestDirectory::new("view-dependencies");
+    let db = Database::open::<Product>(StorageConfiguration::new(&path))?;
+    let mut product = Product {
+        category: String::from("tools"),
+        stock: 1,
+    }
+    .push_into(&db)?;
+    assert_eq!(db.view::<ProductsByCategory>().query()?.len(), 1);
+    assert_eq!(MAPPED.load(Ordering::SeqCst), 1);
+
+    // Changing a field the view doesn't depend on shouldn't map the document
+    // again, but the mapping's source should reflect the new revision.
+    product.contents.stock = 2;
+    product.update(&db)?;
+    let mappings = db.view::<ProductsByCategory>().query()?;
+    assert_eq!(MAPPED.load(Ordering::SeqCst), 1);
+    assert_eq!(mappings.len(), 1);
+    assert_eq!(mappings[0].source.revision, product.header.revision);
+
+    product.contents.category = String::from("garden");
+    product.update(&db)?;
+    let mappings = db.view::<ProductsByCategory>().with_key("garden").query()?;
+    assert_eq!(MAPPED.load(Ordering::SeqCst), 2);
+    assert_eq!(mappings.len(), 1);
+    assert_eq!(
+        db.view::<ProductsByCategory>()
+            .with_key("tools")
+            .query()?
+            .len(),
+        0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn view_quarantine() -> anyhow::Result<()> {
+    use bonsaidb_core::document::{CollectionDocument, DocumentId, Emit};
+    use bonsaidb_core::schema::{
+        Collection, CollectionMapReduce, SerializedCollection, View, ViewMapResult, ViewSchema,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Clone, Debug, Serialize, Deserialize)]
+    #[collection(name = "readings", views = [ReadingsByValue], core = bonsaidb_core)]
+    struct Reading {
+        value: Option<u32>,
+    }
+
+    #[derive(Clone, View, ViewSchema, Debug)]
+    #[view(collection = Reading, key = u32, core = bonsaidb_core)]
+    #[view_schema(core = bonsaidb_core, error_policy = Quarantine)]
+    struct ReadingsByValue;
+
+    impl CollectionMapReduce for ReadingsByValue {
+        fn map<'doc>(
+            &self,
+            document: CollectionDocument<<Self::View as View>::Collection>,
+        ) -> ViewMapResult<'doc, Self> {
+            let value = document
+                .contents
+                .value
+                .ok_or_else(|| bonsaidb_core::Error::other("test", "missing value"))?;
+            document.header.emit_key(value)
+        }
+    }
+
+    let path = TestDirectory::new("view-quarantine");
+    let db = Database::open::<Reading>(StorageConfiguration::new(&path))?;
+    Reading { value: Some(1) }.push_into(&db)?;
+    let mut broken = Reading { value: None }.push_into(&db)?;
+
+    // The failing document is excluded rather than failing the query.
+    assert_eq!(db.view::<ReadingsByValue>().query()?.len(), 1);
+    let quarantined = db.quarantined_documents(&ReadingsByValue.view_name())?;
+    assert_eq!(quarantined.len(), 1);
+    assert_eq!(quarantined[0].id, DocumentId::new(&broken.header.id)?);
+    assert!(quarantined[0].error.contains("missing value"));
+
+    broken.contents.value = Some(2);
+    broken.update(&db)?;
+    assert_eq!(db.view::<ReadingsByValue>().query()?.len(), 2);
+    assert!(db
+        .quarantined_documents(&ReadingsByValue.view_name())?
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "compression", feature = "zstd"))]
+fn zstd_compression() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::SerializedCollection;
+
+    use crate::config::{Compression, CompressionDictionary};
+
+    let path = TestDirectory::new("zstd-compression");
+    let value = |index: usize| format!("{index}: {}", "a".repeat(200));
+    let dictionary = CompressionDictionary::new(value(0));
+
+    // Store documents using lz4, then using zstd with a dictionary.
+    {
+        let db = Database::open::<Basic>(
+            StorageConfiguration::new(&path).default_compression(Compression::Lz4),
+        )?;
+        Basic::new(value(1)).push_into(&db)?;
+    }
+    {
+        let db = Database::open::<Basic>(
+            StorageConfiguration::new(&path)
+                .default_compression(Compression::Zstd { level: 3 })
+                .collection_compression(
+                    Basic::collection_name(),
+                    Compression::ZstdDictionary {
+                        level: 3,
+                        dictionary: dictionary.clone(),
+                    },
+                ),
+        )?;
+        Basic::new(value(2)).push_into(&db)?;
+        assert_eq!(Basic::all(&db).query()?.len(), 2);
+    }
+
+    // Without the dictionary, the documents it compressed can't be read.
+    {
+        let db = Database::open::<Basic>(
+            StorageConfiguration::new(&path).default_compression(Compression::Lz4),
+        )?;
+        assert!(Basic::all(&db).query().is_err());
+    }
+
+    // Registering the dictionary allows all of the data to be read, regardless
+    // of the algorithm currently configured.
+    let mut config = StorageConfiguration::new(&path).default_compression(Compression::Lz4);
+    config.compression_dictionaries.push(dictionary);
+    let db = Database::open::<Basic>(config)?;
+    let mut values = Basic::all(&db)
+        .query()?
+        .into_iter()
+        .map(|doc| doc.contents.value)
+        .collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(values, vec![value(1), value(2)]);
+
+    Ok(())
+}
+
 #[test]
 #[cfg(feature = "encryption")]
 fn encryption() -> anyhow::Result<()> {
@@ -266,6 +567,171 @@ fn encryption() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg(feature = "encryption")]
+fn named_encryption_keys() -> anyhow::Result<()> {
+    use std::borrow::Cow;
+
+    use bonsaidb_core::document::KeyId;
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Clone, Debug, Serialize, Deserialize)]
+    #[collection(name = "tenant-records", core = bonsaidb_core)]
+    #[collection(encryption_key = Some(KeyId::Id(Cow::Borrowed("tenant-a"))))]
+    struct TenantRecord {
+        value: String,
+    }
+
+    let path = TestDirectory::new("named-encryption-keys");
+    let key_directory = || -> anyhow::Result<Option<std::path::PathBuf>> {
+        for entry in std::fs::read_dir(path.join("vault-keys"))? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                return Ok(Some(entry.path()));
+            }
+        }
+        Ok(None)
+    };
+
+    let header = {
+        let db = Database::open::<TenantRecord>(StorageConfiguration::new(&path))?;
+        let document = TenantRecord {
+            value: String::from("hello"),
+        }
+        .push_into(&db)?;
+        document.header
+    };
+
+    // The named key should have been stored in the vault key storage.
+    let keys = key_directory()?.expect("named key not stored");
+    assert_eq!(std::fs::read_dir(&keys)?.count(), 1);
+
+    // Reopening the database loads the key from the vault key storage.
+    {
+        let db = Database::open::<TenantRecord>(StorageConfiguration::new(&path))?;
+        let document = TenantRecord::get(&header.id, &db)?.expect("document not found");
+        assert_eq!(document.contents.value, "hello");
+    }
+
+    // Without the named key, the document can no longer be decrypted.
+    std::fs::remove_dir_all(keys)?;
+    let db = Database::open::<TenantRecord>(StorageConfiguration::new(&path))?;
+    assert!(TenantRecord::get(&header.id, &db).is_err());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "encryption")]
+fn master_key_rotation() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let path = TestDirectory::new("master-key-rotation");
+    let configuration = || {
+        StorageConfiguration::new(&path)
+            .with_schema::<BasicSchema>()
+            .unwrap()
+    };
+    let header = {
+        let storage = Storage::open(configuration())?;
+        let db = storage.create_database::<BasicSchema>("tenant", true)?;
+        let header = EncryptedBasic::new("hello").push_into(&db)?.header;
+
+        assert_eq!(storage.rotate_master_key()?, 1);
+        let started = std::time::Instant::now();
+        while storage.reencryption_status()?.is_some() {
+            assert!(
+                started.elapsed() < Duration::from_secs(30),
+                "re-encryption never finished"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!path.join("master-key-rotation").exists());
+
+        // Data written before and after the rotation is readable.
+        let document = EncryptedBasic::get(&header.id, &db)?.expect("document not found");
+        assert_eq!(document.contents.value, "hello");
+        EncryptedBasic::new("world").push_into(&db)?;
+        header
+    };
+
+    {
+        let storage = Storage::open(configuration())?;
+        let db = storage.database::<BasicSchema>("tenant")?;
+        let document = EncryptedBasic::get(&header.id, &db)?.expect("document not found");
+        assert_eq!(document.contents.value, "hello");
+        assert_eq!(EncryptedBasic::all(&db).count()?, 2);
+        assert!(storage.reencryption_status()?.is_none());
+
+        storage.retire_master_keys()?;
+    }
+
+    // The original master key no longer exists, so every tree must have been
+    // re-encrypted for the data to be readable.
+    let storage = Storage::open(configuration())?;
+    let db = storage.database::<BasicSchema>("tenant")?;
+    let document = EncryptedBasic::get(&header.id, &db)?.expect("document not found");
+    assert_eq!(document.contents.value, "hello");
+    assert_eq!(EncryptedBasic::all(&db).count()?, 2);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "encryption")]
+fn master_key_rotation_skips_unopenable_databases() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+
+    let path = TestDirectory::new("master-key-rotation-skips");
+    let configuration = || {
+        StorageConfiguration::new(&path)
+            .with_schema::<BasicSchema>()
+            .unwrap()
+    };
+    Storage::open(configuration())?.create_database::<BasicSchema>("tenant", true)?;
+
+    // Without its schema registered, the tenant database can't be opened, but
+    // the remaining databases are still re-encrypted.
+    let storage = Storage::open(StorageConfiguration::new(&path))?;
+    storage.rotate_master_key()?;
+    let started = std::time::Instant::now();
+    loop {
+        let status = storage
+            .reencryption_status()?
+            .expect("re-encryption finished despite skipping a database");
+        if status.databases_reencrypted + status.databases_skipped == status.total_databases {
+            assert_eq!(status.databases_skipped, 1);
+            break;
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "tenant database was never skipped"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(matches!(
+        storage.retire_master_keys(),
+        Err(crate::Error::ReencryptionInProgress)
+    ));
+    drop(storage);
+
+    // The skipped database is re-encrypted once it can be opened.
+    let storage = Storage::open(configuration())?;
+    let started = std::time::Instant::now();
+    while storage.reencryption_status()?.is_some() {
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "re-encryption never finished"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    storage.retire_master_keys()?;
+
+    Ok(())
+}
+
 #[test]
 fn expiration_after_close() -> anyhow::Result<()> {
     use bonsaidb_core::keyvalue::KeyValue;
@@ -318,3 +784,78 @@ fn expiration_after_close() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn read_only() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::keyvalue::KeyValue;
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let path = TestDirectory::new("read-only");
+    let header = {
+        let storage = Storage::open(StorageConfiguration::new(&path).with_schema::<Basic>()?)?;
+        let db = storage.create_database::<Basic>("basic", false)?;
+        let header = Basic::new("a").push_into(&db)?.header;
+        db.set_numeric_key("key", 1_u64).execute()?;
+        db.set_numeric_key("expiring", 1_u64)
+            .expire_in(Duration::from_secs(1))
+            .execute()?;
+        assert_eq!(db.view::<BasicByParentId>().query()?.len(), 1);
+        header
+    };
+    // The expired key is still stored, as the storage was closed before it
+    // expired.
+    std::thread::sleep(Duration::from_secs(1));
+
+    let configuration = || {
+        StorageConfiguration::new(&path)
+            .with_schema::<Basic>()
+            .unwrap()
+            .read_only()
+    };
+    // Multiple read-only instances can be open at the same time.
+    let storage = Storage::open(configuration())?;
+    let other = Storage::open(configuration())?;
+    assert!(storage.is_read_only());
+
+    for storage in [&storage, &other] {
+        let db = storage.database::<Basic>("basic")?;
+        let document = Basic::get(&header.id, &db)?.expect("document missing");
+        assert_eq!(document.contents.value, "a");
+        assert_eq!(db.view::<BasicByParentId>().query()?.len(), 1);
+        assert_eq!(db.get_key("key").into_u64()?, Some(1));
+        assert_eq!(db.get_key("expiring").into_u64()?, None);
+
+        assert!(matches!(
+            Basic::new("b").push_into(&db).unwrap_err().error,
+            bonsaidb_core::Error::ReadOnly
+        ));
+        assert!(matches!(
+            db.set_numeric_key("key", 2_u64).execute(),
+            Err(bonsaidb_core::Error::ReadOnly)
+        ));
+        assert!(matches!(db.compact(), Err(bonsaidb_core::Error::ReadOnly)));
+    }
+
+    assert!(matches!(
+        storage.create_database::<Basic>("other", false),
+        Err(bonsaidb_core::Error::ReadOnly)
+    ));
+    assert!(matches!(
+        storage.delete_database("basic"),
+        Err(bonsaidb_core::Error::ReadOnly)
+    ));
+    assert!(storage.database::<Basic>("basic").is_ok());
+    drop((storage, other));
+
+    // A read-only storage can't be opened while the storage is open for
+    // writing.
+    let writable = Storage::open(StorageConfiguration::new(&path).with_schema::<Basic>()?)?;
+    assert!(matches!(
+        Storage::open(configuration()),
+        Err(crate::Error::StorageLocked)
+    ));
+    drop(writable);
+
+    Ok(())
+}
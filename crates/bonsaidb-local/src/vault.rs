@@ -43,6 +43,18 @@
 //! Long term, we plan to offer public key encryption APIs on top of these same
 //! choices.
 //!
+//! ## Named Keys
+//!
+//! In addition to the master key, collections can be encrypted using named
+//! keys by returning [`KeyId::Id`] from
+//! [`Collection::encryption_key()`](bonsaidb_core::schema::Collection::encryption_key).
+//! Named keys are created the first time they are used to encrypt data. Each
+//! key is encrypted using the current master key and stored in
+//! [`VaultKeyStorage`], keeping the keys separate from the data they protect.
+//!
+//! Access to named keys is controlled through
+//! [`encryption_key_resource_name()`] and [`EncryptionKeyAction`].
+//!
 //! For at-rest data encryption, the [`AEAD`
 //! `XChaCha20Poly1305`](https://github.com/RustCrypto/AEADs) implementation is
 //! used directly. This variant of `ChaCha20Poly1305` extends the nonce from 12
@@ -52,7 +64,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -67,6 +79,7 @@ use hpke::aead::{AeadTag, ChaCha20Poly1305};
 use hpke::kdf::HkdfSha256;
 use hpke::kem::DhP256HkdfSha256;
 use hpke::{self, Deserializable, Kem, OpModeS, Serializable};
+use parking_lot::RwLock;
 use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
 use zeroize::{Zeroize, Zeroizing};
@@ -125,22 +138,50 @@ impl<'a> From<&'a KeyPair> for PublicKey {
 use crate::storage::StorageId;
 
 pub(crate) struct Vault {
-    _vault_public_key: PublicKey,
-    master_keys: HashMap<u32, EncryptionKey>,
-    current_master_key_id: u32,
+    vault_public_key: PublicKey,
+    storage_id: StorageId,
+    master_keys_path: PathBuf,
+    master_keys: RwLock<MasterKeys>,
+    named_keys: RwLock<HashMap<String, EncryptionKey>>,
     master_key_storage: Arc<dyn AnyVaultKeyStorage>,
 }
 
 impl Debug for Vault {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Vault")
+            .field("master_keys_path", &self.master_keys_path)
             .field("master_keys", &self.master_keys)
-            .field("current_master_key_id", &self.current_master_key_id)
+            .field("named_keys", &self.named_keys)
             .field("master_key_storage", &self.master_key_storage)
             .finish_non_exhaustive()
     }
 }
 
+/// The master keys of a [`Vault`]. Previous master keys are kept so that data
+/// encrypted before a rotation can still be decrypted.
+#[derive(Debug)]
+struct MasterKeys {
+    keys: HashMap<u32, EncryptionKey>,
+    current_id: u32,
+}
+
+impl MasterKeys {
+    fn new(keys: HashMap<u32, EncryptionKey>) -> Self {
+        let current_id = keys.keys().copied().max().unwrap_or_default();
+        Self { keys, current_id }
+    }
+
+    fn current(&self) -> &EncryptionKey {
+        &self.keys[&self.current_id]
+    }
+
+    fn get(&self, id: u32) -> Result<&EncryptionKey, Error> {
+        self.keys
+            .get(&id)
+            .ok_or_else(|| Error::Encryption(format!("master key version {id} not found")))
+    }
+}
+
 /// Errors relating to encryption and/or secret storage.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -157,6 +198,9 @@ pub enum Error {
     /// doesn't contain the key.
     #[error("vault key not found")]
     VaultKeyNotFound,
+    /// A named encryption key was not found in the vault key storage.
+    #[error("encryption key not found: {0}")]
+    EncryptionKeyNotFound(String),
 }
 
 impl From<chacha20poly1305::aead::Error> for Error {
@@ -220,39 +264,15 @@ impl Vault {
             .map(|r| PublicKey::from(&r).to_bytes().ok() == Some(expected_public_key_bytes))
             .unwrap_or_default();
         if retrieved_key_matches {
-            let mut serialized_master_keys = bincode::serialize(&master_keys)?;
-
-            let (encapsulated_key, aead_tag) = hpke::single_shot_seal_in_place_detached::<
-                ChaCha20Poly1305,
-                HkdfSha256,
-                DhP256HkdfSha256,
-                _,
-            >(
-                &OpModeS::Base,
-                &public,
-                b"",
-                &mut serialized_master_keys,
-                b"",
-                &mut thread_rng(),
-            )?;
-            let mut tag = [0_u8; 16];
-            tag.copy_from_slice(&aead_tag.to_bytes());
-
-            let encrypted_master_keys_payload = bincode::serialize(&HpkePayload {
-                encryption: PublicKeyEncryption::DhP256HkdfSha256ChaCha20,
-                payload: Bytes::from(serialized_master_keys),
-                encapsulated_key,
-                tag,
-            })?;
-
-            File::create(master_keys_path)
-                .and_then(move |mut file| file.write_all(&encrypted_master_keys_payload))
-                .map_err(|err| Error::Initializing(format!("error saving vault key: {err:?}")))?;
+            let vault_public_key = PublicKey::P256(public);
+            seal_master_keys(&vault_public_key, &master_keys, master_keys_path)?;
 
             Ok(Self {
-                _vault_public_key: PublicKey::P256(public),
-                master_keys,
-                current_master_key_id: 0,
+                vault_public_key,
+                storage_id: server_id,
+                master_keys_path: master_keys_path.to_path_buf(),
+                master_keys: RwLock::new(MasterKeys::new(master_keys)),
+                named_keys: RwLock::default(),
                 master_key_storage,
             })
         } else {
@@ -303,11 +323,12 @@ impl Vault {
                 }
             };
 
-            let current_master_key_id = *master_keys.keys().max().unwrap();
             Ok(Self {
-                _vault_public_key: PublicKey::from(&vault_key),
-                master_keys,
-                current_master_key_id,
+                vault_public_key: PublicKey::from(&vault_key),
+                storage_id: server_id,
+                master_keys_path: master_keys_path.to_path_buf(),
+                master_keys: RwLock::new(MasterKeys::new(master_keys)),
+                named_keys: RwLock::default(),
                 master_key_storage,
             })
         } else {
@@ -315,8 +336,192 @@ impl Vault {
         }
     }
 
-    fn current_master_key(&self) -> &EncryptionKey {
-        self.master_keys.get(&self.current_master_key_id).unwrap()
+    /// Generates a new master key and seals it alongside the existing master
+    /// keys. New data will be encrypted using the new key, while data encrypted
+    /// using previous keys can still be decrypted. Returns the id of the new
+    /// master key.
+    pub fn rotate_master_key(&self) -> Result<u32, Error> {
+        let mut master_keys = self.master_keys.write();
+        let new_id = master_keys.current_id + 1;
+        let mut keys = master_keys
+            .keys
+            .iter()
+            .map(|(id, key)| (*id, EncryptionKey::new(*key.0)))
+            .collect::<HashMap<_, _>>();
+        keys.insert(new_id, EncryptionKey::random());
+        self.save_master_keys(&keys)?;
+
+        *master_keys = MasterKeys::new(keys);
+        Ok(new_id)
+    }
+
+    /// Removes every master key except the current one. Data that is still
+    /// encrypted using a removed key can no longer be decrypted.
+    pub fn retire_master_keys(&self) -> Result<(), Error> {
+        let mut master_keys = self.master_keys.write();
+        let mut keys = HashMap::new();
+        keys.insert(
+            master_keys.current_id,
+            EncryptionKey::new(*master_keys.current().0),
+        );
+        self.save_master_keys(&keys)?;
+
+        *master_keys = MasterKeys::new(keys);
+        Ok(())
+    }
+
+    fn save_master_keys(&self, keys: &HashMap<u32, EncryptionKey>) -> Result<(), Error> {
+        // Write the new keys to a temporary file first, ensuring that the
+        // existing master keys are never left partially written.
+        let temporary_path = self.master_keys_path.with_extension("rotating");
+        seal_master_keys(&self.vault_public_key, keys, &temporary_path)?;
+        fs::rename(&temporary_path, &self.master_keys_path)
+            .map_err(|err| Error::Encryption(format!("error saving master keys: {err:?}")))
+    }
+
+    /// Re-encrypts every stored named key that was sealed using a previous
+    /// master key using the current master key.
+    pub fn reseal_named_keys(&self) -> Result<(), Error> {
+        for name in self.named_key_names()? {
+            let Some(sealed_key) = self
+                .master_key_storage
+                .encryption_key_for(self.storage_id, &name)?
+            else {
+                continue;
+            };
+            let payload = VaultPayload::from_slice(&sealed_key)?;
+            let resealed = {
+                let master_keys = self.master_keys.read();
+                if payload.key_version == master_keys.current_id {
+                    continue;
+                }
+
+                let secret = Zeroizing::new(
+                    master_keys
+                        .get(payload.key_version)?
+                        .decrypt_payload(&payload)?,
+                );
+                master_keys.current().encrypt_payload(
+                    KeyId::Master,
+                    master_keys.current_id,
+                    &secret,
+                )
+            };
+            self.master_key_storage.set_encryption_key_for(
+                self.storage_id,
+                &name,
+                &resealed.to_vec(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn named_key_names_path(&self) -> PathBuf {
+        self.master_keys_path.with_file_name("named-keys")
+    }
+
+    /// Returns the names of the named keys this vault has used. Because vault
+    /// key storage can't list the keys it contains, the names are tracked
+    /// alongside the master keys.
+    fn named_key_names(&self) -> Result<Vec<String>, Error> {
+        match fs::read(self.named_key_names_path()) {
+            Ok(contents) => Ok(bincode::deserialize(&contents)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(Error::Initializing(format!(
+                "error reading named key list: {err:?}"
+            ))),
+        }
+    }
+
+    fn record_named_key(&self, name: &str) -> Result<(), Error> {
+        let mut names = self.named_key_names()?;
+        if !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+            fs::write(self.named_key_names_path(), bincode::serialize(&names)?).map_err(|err| {
+                Error::Encryption(format!("error saving named key list: {err:?}"))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns the id of the master key used to encrypt new data.
+    pub fn current_master_key_id(&self) -> u32 {
+        self.master_keys.read().current_id
+    }
+
+    /// Invokes `callback` with the key named `name`, loading it from the vault
+    /// key storage if needed. If the key doesn't exist and `create_if_missing`
+    /// is true, a new key is generated and stored.
+    fn with_named_key<R, F: FnOnce(&EncryptionKey) -> R>(
+        &self,
+        name: &str,
+        create_if_missing: bool,
+        callback: F,
+    ) -> Result<R, Error> {
+        {
+            let named_keys = self.named_keys.read();
+            if let Some(key) = named_keys.get(name) {
+                return Ok(callback(key));
+            }
+        }
+
+        let mut named_keys = self.named_keys.write();
+        // Another thread may have loaded the key while we were waiting for the
+        // write lock.
+        if !named_keys.contains_key(name) {
+            let key = match self.load_named_key(name)? {
+                Some(key) => key,
+                None if create_if_missing => self.create_named_key(name)?,
+                None => return Err(Error::EncryptionKeyNotFound(name.to_string())),
+            };
+            self.record_named_key(name)?;
+            named_keys.insert(name.to_string(), key);
+        }
+
+        Ok(callback(&named_keys[name]))
+    }
+
+    fn load_named_key(&self, name: &str) -> Result<Option<EncryptionKey>, Error> {
+        let sealed_key = match self
+            .master_key_storage
+            .encryption_key_for(self.storage_id, name)?
+        {
+            Some(sealed_key) => sealed_key,
+            None => return Ok(None),
+        };
+
+        let payload = VaultPayload::from_slice(&sealed_key)?;
+        let secret = Zeroizing::new(
+            self.master_keys
+                .read()
+                .get(payload.key_version)?
+                .decrypt_payload(&payload)?,
+        );
+        if secret.len() != 32 {
+            return Err(Error::Encryption(format!(
+                "stored encryption key {name} is not the correct length"
+            )));
+        }
+
+        let mut key = Zeroizing::new([0_u8; 32]);
+        key.copy_from_slice(&secret);
+        Ok(Some(EncryptionKey::new(*key)))
+    }
+
+    fn create_named_key(&self, name: &str) -> Result<EncryptionKey, Error> {
+        let key = EncryptionKey::random();
+        let sealed_key = {
+            let master_keys = self.master_keys.read();
+            master_keys
+                .current()
+                .encrypt_payload(KeyId::Master, master_keys.current_id, key.key())
+        };
+        self.master_key_storage.set_encryption_key_for(
+            self.storage_id,
+            name,
+            &sealed_key.to_vec(),
+        )?;
+        Ok(key)
     }
 
     pub fn encrypt_payload(
@@ -332,12 +537,20 @@ impl Vault {
             )?;
         }
 
-        let (key, version) = match key_id {
-            KeyId::Master => (self.current_master_key(), self.current_master_key_id),
-            KeyId::Id(_) => todo!(),
+        let payload = match key_id {
+            KeyId::Master => {
+                let master_keys = self.master_keys.read();
+                master_keys.current().encrypt_payload(
+                    key_id.clone(),
+                    master_keys.current_id,
+                    payload,
+                )
+            }
+            KeyId::Id(name) => self.with_named_key(name, true, |key| {
+                key.encrypt_payload(key_id.clone(), 0, payload)
+            })?,
             KeyId::None => unreachable!(),
         };
-        let payload = key.encrypt_payload(key_id.clone(), version, payload);
         Ok(payload.to_vec())
     }
 
@@ -369,16 +582,57 @@ impl Vault {
             )?;
         }
 
-        // TODO handle key version
-        let key = match &payload.key_id {
-            KeyId::Master => self.current_master_key(),
-            KeyId::Id(_) => todo!(),
+        let decrypted = match &payload.key_id {
+            KeyId::Master => self
+                .master_keys
+                .read()
+                .get(payload.key_version)?
+                .decrypt_payload(payload)?,
+            KeyId::Id(name) => {
+                self.with_named_key(name, false, |key| key.decrypt_payload(payload))??
+            }
             KeyId::None => unreachable!(),
         };
-        Ok(key.decrypt_payload(payload)?)
+        Ok(decrypted)
     }
 }
 
+fn seal_master_keys(
+    vault_public_key: &PublicKey,
+    master_keys: &HashMap<u32, EncryptionKey>,
+    master_keys_path: &Path,
+) -> Result<(), Error> {
+    let PublicKey::P256(public) = vault_public_key;
+    let mut serialized_master_keys = bincode::serialize(master_keys)?;
+
+    let (encapsulated_key, aead_tag) = hpke::single_shot_seal_in_place_detached::<
+        ChaCha20Poly1305,
+        HkdfSha256,
+        DhP256HkdfSha256,
+        _,
+    >(
+        &OpModeS::Base,
+        public,
+        b"",
+        &mut serialized_master_keys,
+        b"",
+        &mut thread_rng(),
+    )?;
+    let mut tag = [0_u8; 16];
+    tag.copy_from_slice(&aead_tag.to_bytes());
+
+    let encrypted_master_keys_payload = bincode::serialize(&HpkePayload {
+        encryption: PublicKeyEncryption::DhP256HkdfSha256ChaCha20,
+        payload: Bytes::from(serialized_master_keys),
+        encapsulated_key,
+        tag,
+    })?;
+
+    File::create(master_keys_path)
+        .and_then(move |mut file| file.write_all(&encrypted_master_keys_payload))
+        .map_err(|err| Error::Initializing(format!("error saving vault key: {err:?}")))
+}
+
 /// Stores encrypted keys for a vault.
 pub trait VaultKeyStorage: Send + Sync + Debug + 'static {
     /// The error type that the functions return.
@@ -388,6 +642,54 @@ pub trait VaultKeyStorage: Send + Sync + Debug + 'static {
 
     /// Retrieve all previously stored vault key for a given storage id.
     fn vault_key_for(&self, storage_id: StorageId) -> Result<Option<KeyPair>, Self::Error>;
+
+    /// Store the encryption key named `name`. The key has already been
+    /// encrypted by the vault's master key.
+    ///
+    /// The default implementation returns
+    /// [`OptionalOperationError::Unsupported`], which prevents named
+    /// encryption keys from being used.
+    fn set_encryption_key_for(
+        &self,
+        storage_id: StorageId,
+        name: &str,
+        key: &[u8],
+    ) -> Result<(), OptionalOperationError<Self::Error>> {
+        let _ = (storage_id, name, key);
+        Err(OptionalOperationError::Unsupported)
+    }
+
+    /// Retrieve the encryption key named `name` that was previously stored
+    /// using [`set_encryption_key_for()`](Self::set_encryption_key_for).
+    ///
+    /// The default implementation returns
+    /// [`OptionalOperationError::Unsupported`].
+    fn encryption_key_for(
+        &self,
+        storage_id: StorageId,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, OptionalOperationError<Self::Error>> {
+        let _ = (storage_id, name);
+        Err(OptionalOperationError::Unsupported)
+    }
+}
+
+/// An error from a [`VaultKeyStorage`] function that not every implementation
+/// supports.
+#[derive(thiserror::Error, Debug)]
+pub enum OptionalOperationError<E> {
+    /// The vault key storage doesn't support this operation.
+    #[error("operation not supported by this vault key storage")]
+    Unsupported,
+    /// An error occurred within the vault key storage.
+    #[error("{0}")]
+    Storage(E),
+}
+
+impl<E> From<E> for OptionalOperationError<E> {
+    fn from(err: E) -> Self {
+        Self::Storage(err)
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -493,6 +795,21 @@ pub trait AnyVaultKeyStorage: Send + Sync + Debug + 'static {
     /// uniquely encrypted per storage id and can only be decrypted by keys
     /// contained in the storage itself.
     fn set_vault_key_for(&self, storage_id: StorageId, key: KeyPair) -> Result<(), Error>;
+
+    /// Store an encrypted named key.
+    fn set_encryption_key_for(
+        &self,
+        storage_id: StorageId,
+        name: &str,
+        key: &[u8],
+    ) -> Result<(), Error>;
+
+    /// Retrieve a previously stored encrypted named key.
+    fn encryption_key_for(
+        &self,
+        storage_id: StorageId,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, Error>;
 }
 
 impl<T> AnyVaultKeyStorage for T
@@ -508,6 +825,25 @@ where
         VaultKeyStorage::set_vault_key_for(self, server_id, key)
             .map_err(|err| Error::VaultKeyStorage(err.to_string()))
     }
+
+    fn set_encryption_key_for(
+        &self,
+        storage_id: StorageId,
+        name: &str,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        VaultKeyStorage::set_encryption_key_for(self, storage_id, name, key)
+            .map_err(|err| Error::VaultKeyStorage(err.to_string()))
+    }
+
+    fn encryption_key_for(
+        &self,
+        storage_id: StorageId,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        VaultKeyStorage::encryption_key_for(self, storage_id, name)
+            .map_err(|err| Error::VaultKeyStorage(err.to_string()))
+    }
 }
 
 /// Stores vault key locally on disk. This is in general considered insecure,
@@ -539,6 +875,18 @@ impl LocalVaultKeyStorage {
         }
         Ok(Self { directory })
     }
+
+    fn encryption_key_path(&self, server_id: StorageId, name: &str) -> PathBuf {
+        // Key names can contain any character, so they are hex-encoded to
+        // ensure they are valid file names.
+        let file_name = name
+            .bytes()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        self.directory
+            .join(format!("{server_id}-keys"))
+            .join(file_name)
+    }
 }
 
 /// Errors from local vault key storage.
@@ -582,6 +930,36 @@ impl VaultKeyStorage for LocalVaultKeyStorage {
         File::create(server_file).and_then(|mut file| file.write_all(&bytes))?;
         Ok(())
     }
+
+    fn set_encryption_key_for(
+        &self,
+        server_id: StorageId,
+        name: &str,
+        key: &[u8],
+    ) -> Result<(), OptionalOperationError<Self::Error>> {
+        let key_file = self.encryption_key_path(server_id, name);
+        if let Some(directory) = key_file.parent() {
+            fs::create_dir_all(directory).map_err(LocalVaultKeyStorageError::from)?;
+        }
+        File::create(key_file)
+            .and_then(|mut file| file.write_all(key))
+            .map_err(LocalVaultKeyStorageError::from)?;
+        Ok(())
+    }
+
+    fn encryption_key_for(
+        &self,
+        server_id: StorageId,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, OptionalOperationError<Self::Error>> {
+        let key_file = self.encryption_key_path(server_id, name);
+        if !key_file.exists() {
+            return Ok(None);
+        }
+        Ok(Some(
+            fs::read(key_file).map_err(LocalVaultKeyStorageError::from)?,
+        ))
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -646,20 +1024,65 @@ mod tests {
         }
     }
 
-    fn random_null_vault() -> Vault {
+    #[derive(Debug, Default)]
+    struct MemoryKeyStorage {
+        keys: parking_lot::Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl VaultKeyStorage for MemoryKeyStorage {
+        type Error = anyhow::Error;
+
+        fn set_vault_key_for(
+            &self,
+            _storage_id: StorageId,
+            _key: KeyPair,
+        ) -> Result<(), Self::Error> {
+            unreachable!()
+        }
+
+        fn vault_key_for(&self, _storage_id: StorageId) -> Result<Option<KeyPair>, Self::Error> {
+            unreachable!()
+        }
+
+        fn set_encryption_key_for(
+            &self,
+            _storage_id: StorageId,
+            name: &str,
+            key: &[u8],
+        ) -> Result<(), OptionalOperationError<Self::Error>> {
+            self.keys.lock().insert(name.to_string(), key.to_vec());
+            Ok(())
+        }
+
+        fn encryption_key_for(
+            &self,
+            _storage_id: StorageId,
+            name: &str,
+        ) -> Result<Option<Vec<u8>>, OptionalOperationError<Self::Error>> {
+            Ok(self.keys.lock().get(name).cloned())
+        }
+    }
+
+    fn random_vault(master_key_storage: Arc<dyn AnyVaultKeyStorage>, directory: &Path) -> Vault {
         let mut master_keys = HashMap::new();
         master_keys.insert(0, EncryptionKey::random());
 
         let (_, public_key) = <DhP256HkdfSha256 as Kem>::gen_keypair(&mut thread_rng());
 
         Vault {
-            _vault_public_key: PublicKey::P256(public_key),
-            master_keys,
-            current_master_key_id: 0,
-            master_key_storage: Arc::new(NullKeyStorage),
+            vault_public_key: PublicKey::P256(public_key),
+            storage_id: StorageId(0),
+            master_keys_path: directory.join("master-keys"),
+            master_keys: RwLock::new(MasterKeys::new(master_keys)),
+            named_keys: RwLock::default(),
+            master_key_storage,
         }
     }
 
+    fn random_null_vault() -> Vault {
+        random_vault(Arc::new(NullKeyStorage), Path::new("."))
+    }
+
     #[test]
     fn vault_encryption_test() {
         let vault = random_null_vault();
@@ -671,6 +1094,106 @@ mod tests {
         assert_eq!(decrypted, b"hello");
     }
 
+    #[test]
+    fn vault_named_key_test() {
+        let directory = bonsaidb_core::test_util::TestDirectory::new("vault-named-key");
+        std::fs::create_dir_all(&directory).unwrap();
+        let key_storage = Arc::new(MemoryKeyStorage::default());
+        let vault = random_vault(key_storage.clone(), &directory);
+        let tenant = KeyId::Id(Cow::Borrowed("tenant"));
+        let encrypted = vault.encrypt_payload(&tenant, b"hello", None).unwrap();
+        assert!(key_storage.keys.lock().contains_key("tenant"));
+        assert_eq!(vault.decrypt_payload(&encrypted, None).unwrap(), b"hello");
+
+        // Named keys are not interchangeable with the master key.
+        let master_encrypted = vault
+            .encrypt_payload(&KeyId::Master, b"hello", None)
+            .unwrap();
+        assert_ne!(encrypted, master_encrypted);
+
+        // Clearing the cached keys forces the key to be loaded from storage.
+        vault.named_keys.write().clear();
+        assert_eq!(vault.decrypt_payload(&encrypted, None).unwrap(), b"hello");
+
+        // Decrypting with a key that doesn't exist shouldn't create it.
+        key_storage.keys.lock().clear();
+        vault.named_keys.write().clear();
+        assert!(matches!(
+            vault.decrypt_payload(&encrypted, None),
+            Err(crate::Error::Vault(Error::EncryptionKeyNotFound(_)))
+        ));
+        assert!(key_storage.keys.lock().is_empty());
+
+        assert!(matches!(
+            vault.encrypt_payload(&tenant, b"hello", Some(&Permissions::default())),
+            Err(crate::Error::Core(bonsaidb_core::Error::PermissionDenied(
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    fn vault_named_keys_unsupported_test() {
+        let vault = random_null_vault();
+        let tenant = KeyId::Id(Cow::Borrowed("tenant"));
+        assert!(matches!(
+            vault.encrypt_payload(&tenant, b"hello", None),
+            Err(crate::Error::Vault(Error::VaultKeyStorage(_)))
+        ));
+    }
+
+    #[test]
+    fn vault_rotation_test() {
+        let directory = bonsaidb_core::test_util::TestDirectory::new("vault-rotation");
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut vault = random_null_vault();
+        vault.master_keys_path = directory.join("master-keys");
+
+        let original = vault
+            .encrypt_payload(&KeyId::Master, b"hello", None)
+            .unwrap();
+        assert_eq!(vault.rotate_master_key().unwrap(), 1);
+        assert!(vault.master_keys_path.exists());
+
+        let rotated = vault
+            .encrypt_payload(&KeyId::Master, b"hello", None)
+            .unwrap();
+        assert_eq!(VaultPayload::from_slice(&rotated).unwrap().key_version, 1);
+
+        // Data encrypted with the previous key can still be decrypted.
+        assert_eq!(vault.decrypt_payload(&original, None).unwrap(), b"hello");
+        assert_eq!(vault.decrypt_payload(&rotated, None).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn vault_retirement_test() {
+        let directory = bonsaidb_core::test_util::TestDirectory::new("vault-retirement");
+        std::fs::create_dir_all(&directory).unwrap();
+        let key_storage = Arc::new(MemoryKeyStorage::default());
+        let vault = random_vault(key_storage.clone(), &directory);
+        let tenant = KeyId::Id(Cow::Borrowed("tenant"));
+
+        let original = vault
+            .encrypt_payload(&KeyId::Master, b"hello", None)
+            .unwrap();
+        let named = vault.encrypt_payload(&tenant, b"hello", None).unwrap();
+        assert_eq!(vault.rotate_master_key().unwrap(), 1);
+        vault.reseal_named_keys().unwrap();
+        let sealed_key = key_storage.keys.lock()["tenant"].clone();
+        assert_eq!(
+            VaultPayload::from_slice(&sealed_key).unwrap().key_version,
+            1
+        );
+
+        vault.retire_master_keys().unwrap();
+        assert!(vault.decrypt_payload(&original, None).is_err());
+
+        // The named key was re-sealed, so it can still be loaded after the
+        // previous master key was removed.
+        vault.named_keys.write().clear();
+        assert_eq!(vault.decrypt_payload(&named, None).unwrap(), b"hello");
+    }
+
     #[test]
     fn vault_permissions_test() {
         let vault = random_null_vault();
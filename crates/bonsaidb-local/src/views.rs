@@ -1,6 +1,8 @@
+use std::collections::HashSet;
 use std::fmt::Display;
 
 use bonsaidb_core::arc_bytes::serde::Bytes;
+use bonsaidb_core::arc_bytes::OwnedBytes;
 use bonsaidb_core::document::Header;
 use bonsaidb_core::schema::CollectionName;
 use serde::{Deserialize, Serialize};
@@ -20,8 +22,18 @@ pub struct EntryMapping {
     pub value: Bytes,
 }
 
+/// The keys a document was mapped to, stored in the document map tree.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentMap {
+    pub keys: HashSet<OwnedBytes>,
+    /// The result of `ViewSchema::dependencies()` when the document was
+    /// mapped.
+    pub dependencies: Option<Bytes>,
+}
+
 pub mod integrity_scanner;
 pub mod mapper;
+pub mod reductions;
 
 pub fn view_entries_tree_name(view_name: &impl Display) -> String {
     format!("view.{view_name:#}")
@@ -36,6 +48,18 @@ pub fn view_invalidated_docs_tree_name(view_name: &impl Display) -> String {
     format!("view.{view_name:#}.invalidated")
 }
 
+/// Used to store cached reductions of ranges of keys, allowing range reductions
+/// to avoid re-reducing every entry within the range.
+pub fn view_reductions_tree_name(view_name: &impl Display) -> String {
+    format!("view.{view_name:#}.reductions")
+}
+
+/// Used to store Document ID -> mapping error, for documents that were
+/// quarantined due to errors while mapping them.
+pub fn view_quarantine_tree_name(view_name: &impl Display) -> String {
+    format!("view.{view_name:#}.quarantine")
+}
+
 pub fn view_versions_tree_name(collection: &CollectionName) -> String {
     format!("view-versions.{collection:#}")
 }
@@ -17,7 +17,10 @@ use super::{view_invalidated_docs_tree_name, view_versions_tree_name};
 use crate::database::{document_tree_name, Database};
 use crate::tasks::handle::Handle;
 use crate::tasks::{Job, Keyed, Task};
-use crate::views::{view_document_map_tree_name, view_entries_tree_name};
+use crate::views::{
+    view_document_map_tree_name, view_entries_tree_name, view_quarantine_tree_name,
+    view_reductions_tree_name,
+};
 use crate::Error;
 
 #[derive(Debug)]
@@ -43,20 +46,14 @@ impl Job for IntegrityScanner {
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     #[allow(clippy::too_many_lines)]
     fn execute(&mut self) -> Result<Self::Output, Self::Error> {
-        let documents =
+        let view_versions =
             self.database
                 .roots()
-                .tree(self.database.collection_tree::<Versioned, _>(
+                .tree(self.database.collection_tree::<Unversioned, _>(
                     &self.scan.collection,
-                    document_tree_name(&self.scan.collection),
+                    view_versions_tree_name(&self.scan.collection),
                 )?)?;
 
-        let view_versions_tree = self.database.collection_tree::<Unversioned, _>(
-            &self.scan.collection,
-            view_versions_tree_name(&self.scan.collection),
-        )?;
-        let view_versions = self.database.roots().tree(view_versions_tree.clone())?;
-
         let view_name = self.scan.view_name.clone();
         let view_version = self.scan.view_version;
         let roots = self.database.roots().clone();
@@ -72,37 +69,13 @@ impl Job for IntegrityScanner {
             None
         } else {
             // The view isn't the current version, queue up all documents.
-            let missing_entries = tree_keys::<Versioned>(&documents)?;
-            // When a version is updated, we can make no guarantees about
-            // existing keys. The best we can do is delete the existing files so
-            // that the view starts fresh.
-            roots.delete_tree(view_invalidated_docs_tree_name(&self.scan.view_name))?;
-            roots.delete_tree(view_entries_tree_name(&self.scan.view_name))?;
-            roots.delete_tree(view_document_map_tree_name(&self.scan.view_name))?;
-            // Add all missing entries to the invalidated list. The view
-            // mapping job will update them on the next pass.
-            let invalidated_entries_tree = self.database.collection_tree::<Unversioned, _>(
+            invalidate_view(
+                &self.database,
                 &self.scan.collection,
-                view_invalidated_docs_tree_name(&self.scan.view_name),
+                &self.scan.view_name,
+                view_version,
             )?;
 
-            let transaction = roots.transaction(&[invalidated_entries_tree, view_versions_tree])?;
-            {
-                let mut view_versions = transaction.tree::<Unversioned>(1).unwrap();
-                view_versions.set(
-                    view_name.to_string().as_bytes().to_vec(),
-                    ViewVersion::current_for(view_version).to_vec()?,
-                )?;
-                let mut invalidated_entries = transaction.tree::<Unversioned>(0).unwrap();
-                let mut missing_entries = missing_entries
-                    .into_iter()
-                    .map(|id| ArcBytes::from(id.to_vec()))
-                    .collect::<Vec<_>>();
-                missing_entries.sort();
-                invalidated_entries.modify(missing_entries, Operation::Set(ArcBytes::default()))?;
-            }
-            transaction.commit()?;
-
             Some(Arc::new(Mutex::new(Some(
                 self.database
                     .storage
@@ -134,6 +107,57 @@ impl Job for IntegrityScanner {
     }
 }
 
+/// Deletes all data stored for `view_name` and invalidates every document in
+/// `collection`, causing the view to be rebuilt the next time it is mapped.
+pub fn invalidate_view(
+    database: &Database,
+    collection: &CollectionName,
+    view_name: &ViewName,
+    view_version: u64,
+) -> Result<(), Error> {
+    let roots = database.roots();
+    let documents = roots.tree(
+        database.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?,
+    )?;
+    let view_versions_tree = database
+        .collection_tree::<Unversioned, _>(collection, view_versions_tree_name(collection))?;
+
+    let missing_entries = tree_keys::<Versioned>(&documents)?;
+    // When a version is updated, we can make no guarantees about
+    // existing keys. The best we can do is delete the existing files so
+    // that the view starts fresh.
+    roots.delete_tree(view_invalidated_docs_tree_name(view_name))?;
+    roots.delete_tree(view_entries_tree_name(view_name))?;
+    roots.delete_tree(view_document_map_tree_name(view_name))?;
+    roots.delete_tree(view_reductions_tree_name(view_name))?;
+    roots.delete_tree(view_quarantine_tree_name(view_name))?;
+    // Add all missing entries to the invalidated list. The view
+    // mapping job will update them on the next pass.
+    let invalidated_entries_tree = database.collection_tree::<Unversioned, _>(
+        collection,
+        view_invalidated_docs_tree_name(view_name),
+    )?;
+
+    let transaction = roots.transaction(&[invalidated_entries_tree, view_versions_tree])?;
+    {
+        let mut view_versions = transaction.tree::<Unversioned>(1).unwrap();
+        view_versions.set(
+            view_name.to_string().as_bytes().to_vec(),
+            ViewVersion::current_for(view_version).to_vec()?,
+        )?;
+        let mut invalidated_entries = transaction.tree::<Unversioned>(0).unwrap();
+        let mut missing_entries = missing_entries
+            .into_iter()
+            .map(|id| ArcBytes::from(id.to_vec()))
+            .collect::<Vec<_>>();
+        missing_entries.sort();
+        invalidated_entries.modify(missing_entries, Operation::Set(ArcBytes::default()))?;
+    }
+    transaction.commit()?;
+
+    Ok(())
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct ViewVersion {
     internal_version: u8,
@@ -141,7 +165,7 @@ pub struct ViewVersion {
 }
 
 impl ViewVersion {
-    const CURRENT_VERSION: u8 = 3;
+    const CURRENT_VERSION: u8 = 5;
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, crate::Error> {
         match pot::from_slice(bytes) {
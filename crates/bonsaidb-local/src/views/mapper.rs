@@ -6,7 +6,10 @@ use std::sync::Arc;
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::arc_bytes::{ArcBytes, OwnedBytes};
 use bonsaidb_core::connection::Connection;
-use bonsaidb_core::schema::view::{self, map, Serialized, ViewUpdatePolicy};
+use bonsaidb_core::document::Header;
+use bonsaidb_core::schema::view::{
+    self, map, Serialized, ViewMappingErrorPolicy, ViewUpdatePolicy,
+};
 use bonsaidb_core::schema::{CollectionName, ViewName};
 use easy_parallel::Parallel;
 use nebari::io::any::AnyFile;
@@ -14,10 +17,11 @@ use nebari::tree::{AnyTreeRoot, CompareSwap, KeyOperation, Operation, Unversione
 use nebari::{LockedTransactionTree, Tree, UnlockedTransactionTree};
 
 use crate::database::{deserialize_document, document_tree_name, Database};
-use crate::tasks::{Job, Keyed, Task};
+use crate::tasks::{Job, Keyed, Task, ViewUpdateProgress};
 use crate::views::{
-    view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
-    EntryMapping, ViewEntry,
+    reductions, view_document_map_tree_name, view_entries_tree_name,
+    view_invalidated_docs_tree_name, view_quarantine_tree_name, view_reductions_tree_name,
+    DocumentMap, EntryMapping, ViewEntry,
 };
 use crate::Error;
 
@@ -73,6 +77,22 @@ impl Job for Mapper {
                     view_invalidated_docs_tree_name(&self.map.view_name),
                 )?)?;
 
+        let reductions =
+            self.database
+                .roots()
+                .tree(self.database.collection_tree::<Unversioned, _>(
+                    &self.map.collection,
+                    view_reductions_tree_name(&self.map.view_name),
+                )?)?;
+
+        let quarantine =
+            self.database
+                .roots()
+                .tree(self.database.collection_tree::<Unversioned, _>(
+                    &self.map.collection,
+                    view_quarantine_tree_name(&self.map.view_name),
+                )?)?;
+
         let transaction_id = self
             .database
             .last_transaction_id()?
@@ -81,16 +101,31 @@ impl Job for Mapper {
         let storage = self.database.clone();
         let map_request = self.map.clone();
 
-        map_view(
+        let tasks = self.database.storage.instance.tasks();
+        let progress = tasks.begin_view_update(
+            self.map.database.clone(),
+            self.map.collection.clone(),
+            self.map.view_name.clone(),
+        );
+        let result = map_view(
             &invalidated_entries,
             &document_map,
             &documents,
             &view_entries,
+            &reductions,
+            &quarantine,
             &storage,
             &map_request,
-        )?;
+            &progress,
+        );
+        tasks.finish_view_update(
+            self.map.database.clone(),
+            self.map.collection.clone(),
+            self.map.view_name.clone(),
+        );
+        result?;
 
-        self.database.storage.instance.tasks().mark_view_updated(
+        tasks.mark_view_updated(
             self.map.database.clone(),
             self.map.collection.clone(),
             self.map.view_name.clone(),
@@ -106,8 +141,11 @@ fn map_view(
     document_map: &Tree<Unversioned, AnyFile>,
     documents: &Tree<Versioned, AnyFile>,
     view_entries: &Tree<Unversioned, AnyFile>,
+    reductions: &Tree<Unversioned, AnyFile>,
+    quarantine: &Tree<Unversioned, AnyFile>,
     database: &Database,
     map_request: &Map,
+    progress: &ViewUpdateProgress,
 ) -> Result<(), Error> {
     const CHUNK_SIZE: usize = 100_000;
     // Only do any work if there are invalidated documents to process
@@ -116,6 +154,7 @@ fn map_view(
         .into_iter()
         .map(|(key, _)| key)
         .collect::<Vec<_>>();
+    progress.set_total(invalidated_ids.len() as u64);
     while !invalidated_ids.is_empty() {
         let transaction = database
             .roots()
@@ -124,6 +163,8 @@ fn map_view(
                 Box::new(document_map.clone()),
                 Box::new(documents.clone()),
                 Box::new(view_entries.clone()),
+                Box::new(reductions.clone()),
+                Box::new(quarantine.clone()),
             ])?;
         {
             let view = database
@@ -138,6 +179,8 @@ fn map_view(
             let document_map = transaction.unlocked_tree(1).unwrap();
             let documents = transaction.unlocked_tree(2).unwrap();
             let view_entries = transaction.unlocked_tree(3).unwrap();
+            let reductions = transaction.unlocked_tree(4).unwrap();
+            let quarantine = transaction.unlocked_tree(5).unwrap();
             DocumentRequest {
                 document_ids: document_ids.clone(),
                 map_request,
@@ -145,7 +188,10 @@ fn map_view(
                 document_map,
                 documents,
                 view_entries,
+                reductions,
+                quarantine,
                 view,
+                progress: Some(progress),
             }
             .map()?;
 
@@ -166,10 +212,17 @@ pub struct DocumentRequest<'a> {
     pub document_map: &'a UnlockedTransactionTree<AnyFile>,
     pub documents: &'a UnlockedTransactionTree<AnyFile>,
     pub view_entries: &'a UnlockedTransactionTree<AnyFile>,
+    pub reductions: &'a UnlockedTransactionTree<AnyFile>,
+    pub quarantine: &'a UnlockedTransactionTree<AnyFile>,
     pub view: &'a dyn Serialized,
+    pub progress: Option<&'a ViewUpdateProgress>,
 }
 
-type DocumentIdPayload = (ArcBytes<'static>, Option<ArcBytes<'static>>);
+type DocumentIdPayload = (
+    ArcBytes<'static>,
+    Option<ArcBytes<'static>>,
+    Option<DocumentMap>,
+);
 type BatchPayload = (Vec<ArcBytes<'static>>, flume::Receiver<DocumentIdPayload>);
 
 impl<'a> DocumentRequest<'a> {
@@ -177,6 +230,7 @@ impl<'a> DocumentRequest<'a> {
         batch_sender: flume::Sender<BatchPayload>,
         document_ids: &[ArcBytes<'static>],
         documents: &UnlockedTransactionTree<AnyFile>,
+        document_map: &UnlockedTransactionTree<AnyFile>,
     ) -> Result<(), Error> {
         // Generate batches
         let mut documents = documents.lock::<Versioned>();
@@ -187,15 +241,28 @@ impl<'a> DocumentRequest<'a> {
                 .unwrap();
             let mut documents = documents.get_multiple(chunk.iter().map(ArcBytes::as_slice))?;
             documents.sort_by(|a, b| a.0.cmp(&b.0));
+            // The existing document maps are used to determine whether a
+            // document's dependencies have changed. The document map tree is
+            // only locked briefly, as it is also updated while saving each
+            // batch.
+            let mut existing_maps = document_map
+                .lock::<Unversioned>()
+                .get_multiple(chunk.iter().map(ArcBytes::as_slice))?;
+            existing_maps.sort_by(|a, b| a.0.cmp(&b.0));
 
             for document_id in chunk.iter().rev() {
                 let document = documents
                     .last()
                     .map_or(false, |(key, _)| (key == document_id))
                     .then(|| documents.pop().unwrap().1);
+                let existing_map = existing_maps
+                    .last()
+                    .map_or(false, |(key, _)| (key == document_id))
+                    .then(|| bincode::deserialize::<DocumentMap>(&existing_maps.pop().unwrap().1))
+                    .transpose()?;
 
                 document_id_sender
-                    .send((document_id.clone(), document))
+                    .send((document_id.clone(), document, existing_map))
                     .unwrap();
             }
 
@@ -220,41 +287,57 @@ impl<'a> DocumentRequest<'a> {
             for result in Parallel::new()
                 .each(1..=parallelization, |_| -> Result<_, Error> {
                     let mut results = Vec::new();
-                    while let Ok((document_id, document)) = document_id_receiver.recv() {
-                        let map_result = if let Some(document) = document {
-                            let document = deserialize_document(&document)?;
-
-                            // Call the schema map function
-                            view.map(&document).map_err(bonsaidb_core::Error::from)?
-                        } else {
-                            // Get multiple didn't return this document ID.
-                            Vec::new()
-                        };
-                        let keys: HashSet<OwnedBytes> = map_result
-                            .iter()
-                            .map(|map| OwnedBytes::from(map.key.as_slice()))
-                            .collect();
-                        let new_keys = ArcBytes::from(bincode::serialize(&keys)?);
-
-                        results.push((document_id, new_keys, keys, map_result));
+                    while let Ok((document_id, document, existing_map)) =
+                        document_id_receiver.recv()
+                    {
+                        results.push(Self::map_document(
+                            view,
+                            document_id,
+                            document,
+                            existing_map,
+                        )?);
                     }
 
                     Ok(results)
                 })
                 .run()
             {
-                for (document_id, new_keys, keys, map_result) in result? {
-                    for key in &keys {
-                        batch.all_keys.insert(key.0.clone());
-                    }
-                    batch.document_maps.insert(document_id.clone(), new_keys);
-                    batch.document_keys.insert(document_id.clone(), keys);
-                    for mapping in map_result {
-                        let key_mappings = batch
-                            .new_mappings
-                            .entry(ArcBytes::from(mapping.key.to_vec()))
-                            .or_insert_with(Vec::default);
-                        key_mappings.push(mapping);
+                for mapped in result? {
+                    match mapped {
+                        MappedDocument::Mapped {
+                            document_id,
+                            document_map,
+                            keys,
+                            mappings,
+                            error,
+                        } => {
+                            if view.mapping_error_policy() == ViewMappingErrorPolicy::Quarantine {
+                                batch.quarantine.insert(document_id.clone(), error);
+                            }
+                            for key in &keys {
+                                batch.all_keys.insert(key.0.clone());
+                            }
+                            batch
+                                .document_maps
+                                .insert(document_id.clone(), document_map);
+                            batch.document_keys.insert(document_id, keys);
+                            for mapping in mappings {
+                                let key_mappings = batch
+                                    .new_mappings
+                                    .entry(ArcBytes::from(mapping.key.to_vec()))
+                                    .or_insert_with(Vec::default);
+                                key_mappings.push(mapping);
+                            }
+                        }
+                        MappedDocument::Unchanged { source, keys } => {
+                            for key in keys {
+                                batch
+                                    .updated_sources
+                                    .entry(key.0)
+                                    .or_insert_with(Vec::default)
+                                    .push(source.clone());
+                            }
+                        }
                     }
                 }
             }
@@ -264,6 +347,97 @@ impl<'a> DocumentRequest<'a> {
         Ok(())
     }
 
+    fn map_document(
+        view: &dyn Serialized,
+        document_id: ArcBytes<'static>,
+        document: Option<ArcBytes<'static>>,
+        existing_map: Option<DocumentMap>,
+    ) -> Result<MappedDocument, Error> {
+        match Self::try_map_document(view, document_id.clone(), document, existing_map) {
+            Ok(mapped) => Ok(mapped),
+            Err(err) => {
+                let policy = view.mapping_error_policy();
+                if policy == ViewMappingErrorPolicy::Fail {
+                    return Err(err);
+                }
+
+                log::warn!(
+                    "Error mapping document {:?} in view {}: {err}",
+                    document_id,
+                    view.view_name()
+                );
+                // The document is excluded from the view by clearing its
+                // mappings.
+                Ok(MappedDocument::Mapped {
+                    document_id,
+                    document_map: ArcBytes::from(bincode::serialize(&DocumentMap {
+                        keys: HashSet::new(),
+                        dependencies: None,
+                    })?),
+                    keys: HashSet::new(),
+                    mappings: Vec::new(),
+                    error: (policy == ViewMappingErrorPolicy::Quarantine).then(|| err.to_string()),
+                })
+            }
+        }
+    }
+
+    fn try_map_document(
+        view: &dyn Serialized,
+        document_id: ArcBytes<'static>,
+        document: Option<ArcBytes<'static>>,
+        existing_map: Option<DocumentMap>,
+    ) -> Result<MappedDocument, Error> {
+        let (mappings, dependencies) = match document {
+            Some(document) => {
+                let document = deserialize_document(&document)?;
+                if view.filter(&document) {
+                    let dependencies = view
+                        .dependencies(&document)
+                        .map_err(bonsaidb_core::Error::from)?;
+                    if let (Some(dependencies), Some(existing_map)) = (&dependencies, existing_map)
+                    {
+                        let existing = existing_map.dependencies.as_ref().map(|bytes| &bytes[..]);
+                        if existing == Some(dependencies.as_slice()) {
+                            // The data the view depends on hasn't changed, so
+                            // the existing mappings only need their source
+                            // updated.
+                            return Ok(MappedDocument::Unchanged {
+                                source: document.header,
+                                keys: existing_map.keys,
+                            });
+                        }
+                    }
+
+                    // Call the schema map function
+                    let mappings = view.map(&document).map_err(bonsaidb_core::Error::from)?;
+                    (mappings, dependencies)
+                } else {
+                    (Vec::new(), None)
+                }
+            }
+            // Get multiple didn't return this document ID.
+            None => (Vec::new(), None),
+        };
+
+        let keys: HashSet<OwnedBytes> = mappings
+            .iter()
+            .map(|map| OwnedBytes::from(map.key.as_slice()))
+            .collect();
+        let document_map = ArcBytes::from(bincode::serialize(&DocumentMap {
+            keys: keys.clone(),
+            dependencies: dependencies.map(Bytes::from),
+        })?);
+
+        Ok(MappedDocument::Mapped {
+            document_id,
+            document_map,
+            keys,
+            mappings,
+            error: None,
+        })
+    }
+
     fn update_document_map(
         document_ids: Vec<ArcBytes<'static>>,
         document_map: &mut LockedTransactionTree<'_, Unversioned, AnyFile>,
@@ -285,9 +459,9 @@ impl<'a> DocumentRequest<'a> {
         )?;
         let mut view_entries_to_clean = BTreeMap::new();
         for (document_id, existing_map) in maps_to_clear {
-            let existing_keys = bincode::deserialize::<HashSet<OwnedBytes>>(&existing_map)?;
+            let existing_map = bincode::deserialize::<DocumentMap>(&existing_map)?;
             let new_keys = document_keys.remove(&document_id).unwrap();
-            for key in existing_keys.difference(&new_keys) {
+            for key in existing_map.keys.difference(&new_keys) {
                 all_keys.insert(key.clone().0);
                 let key_documents = view_entries_to_clean
                     .entry(key.clone().0)
@@ -305,12 +479,14 @@ impl<'a> DocumentRequest<'a> {
         all_keys: BTreeSet<ArcBytes<'static>>,
         view_entries_to_clean: BTreeMap<ArcBytes<'static>, HashSet<ArcBytes<'static>>>,
         new_mappings: BTreeMap<ArcBytes<'static>, Vec<map::Serialized>>,
+        updated_sources: BTreeMap<ArcBytes<'static>, Vec<Header>>,
     ) -> Result<(), Error> {
         let mut updater = ViewEntryUpdater {
             view,
             map_request,
             view_entries_to_clean,
             new_mappings,
+            updated_sources,
             result: Ok(()),
             has_reduce: true,
         };
@@ -325,28 +501,47 @@ impl<'a> DocumentRequest<'a> {
             .and(updater.result)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn save_mappings(
         mapped_receiver: &flume::Receiver<Batch>,
         view: &dyn Serialized,
         map_request: &Map,
-        document_map: &mut LockedTransactionTree<'_, Unversioned, AnyFile>,
+        document_map: &UnlockedTransactionTree<AnyFile>,
         view_entries: &mut LockedTransactionTree<'_, Unversioned, AnyFile>,
+        reductions: &mut LockedTransactionTree<'_, Unversioned, AnyFile>,
+        quarantine: &mut LockedTransactionTree<'_, Unversioned, AnyFile>,
+        progress: Option<&ViewUpdateProgress>,
     ) -> Result<(), Error> {
+        let mut changed_keys = BTreeSet::new();
         while let Ok(Batch {
             document_ids,
             document_maps,
             document_keys,
             new_mappings,
+            updated_sources,
+            quarantine: quarantined,
             mut all_keys,
         }) = mapped_receiver.recv()
         {
+            let batch_size = document_ids.len() as u64;
+            Self::update_quarantine(quarantine, quarantined)?;
+            // Documents whose dependencies were unchanged keep their existing
+            // document map.
+            let document_ids = document_ids
+                .into_iter()
+                .filter(|id| document_maps.contains_key(id))
+                .collect();
             let view_entries_to_clean = Self::update_document_map(
                 document_ids,
-                document_map,
+                &mut document_map.lock(),
                 &document_maps,
                 document_keys,
                 &mut all_keys,
             )?;
+            changed_keys.extend(all_keys.iter().cloned());
+            // Updating the source of a mapping doesn't alter the reduced
+            // values, so these keys aren't included in `changed_keys`.
+            all_keys.extend(updated_sources.keys().cloned());
 
             Self::update_view_entries(
                 view,
@@ -355,7 +550,32 @@ impl<'a> DocumentRequest<'a> {
                 all_keys,
                 view_entries_to_clean,
                 new_mappings,
+                updated_sources,
             )?;
+
+            if let Some(progress) = progress {
+                progress.add_mapped(batch_size);
+            }
+        }
+
+        reductions::update_reductions(view, &changed_keys, view_entries, reductions)
+    }
+
+    fn update_quarantine(
+        quarantine: &mut LockedTransactionTree<'_, Unversioned, AnyFile>,
+        quarantined: BTreeMap<ArcBytes<'static>, Option<String>>,
+    ) -> Result<(), Error> {
+        let mut mapped = Vec::new();
+        for (document_id, error) in quarantined {
+            if let Some(error) = error {
+                quarantine.set(document_id, ArcBytes::from(error.into_bytes()))?;
+            } else {
+                mapped.push(document_id);
+            }
+        }
+        // Documents that were mapped successfully are no longer quarantined.
+        if !mapped.is_empty() {
+            quarantine.modify(mapped, Operation::Remove)?;
         }
         Ok(())
     }
@@ -365,7 +585,14 @@ impl<'a> DocumentRequest<'a> {
         let (mapped_sender, mapped_receiver) = flume::bounded(1);
 
         for result in Parallel::new()
-            .add(|| Self::generate_batches(batch_sender, &self.document_ids, self.documents))
+            .add(|| {
+                Self::generate_batches(
+                    batch_sender,
+                    &self.document_ids,
+                    self.documents,
+                    self.document_map,
+                )
+            })
             .add(|| {
                 Self::map_batches(
                     &batch_receiver,
@@ -375,14 +602,18 @@ impl<'a> DocumentRequest<'a> {
                 )
             })
             .add(|| {
-                let mut document_map = self.document_map.lock();
                 let mut view_entries = self.view_entries.lock();
+                let mut reductions = self.reductions.lock();
+                let mut quarantine = self.quarantine.lock();
                 Self::save_mappings(
                     &mapped_receiver,
                     self.view,
                     self.map_request,
-                    &mut document_map,
+                    self.document_map,
                     &mut view_entries,
+                    &mut reductions,
+                    &mut quarantine,
+                    self.progress,
                 )
             })
             .run()
@@ -400,9 +631,25 @@ struct Batch {
     document_maps: BTreeMap<ArcBytes<'static>, ArcBytes<'static>>,
     document_keys: BTreeMap<ArcBytes<'static>, HashSet<OwnedBytes>>,
     new_mappings: BTreeMap<ArcBytes<'static>, Vec<map::Serialized>>,
+    updated_sources: BTreeMap<ArcBytes<'static>, Vec<Header>>,
+    quarantine: BTreeMap<ArcBytes<'static>, Option<String>>,
     all_keys: BTreeSet<ArcBytes<'static>>,
 }
 
+enum MappedDocument {
+    Mapped {
+        document_id: ArcBytes<'static>,
+        document_map: ArcBytes<'static>,
+        keys: HashSet<OwnedBytes>,
+        mappings: Vec<map::Serialized>,
+        error: Option<String>,
+    },
+    Unchanged {
+        source: Header,
+        keys: HashSet<OwnedBytes>,
+    },
+}
+
 impl Keyed<Task> for Mapper {
     fn key(&self) -> Task {
         Task::ViewMap(self.map.clone())
@@ -414,6 +661,7 @@ struct ViewEntryUpdater<'a> {
     map_request: &'a Map,
     view_entries_to_clean: BTreeMap<ArcBytes<'static>, HashSet<ArcBytes<'static>>>,
     new_mappings: BTreeMap<ArcBytes<'static>, Vec<map::Serialized>>,
+    updated_sources: BTreeMap<ArcBytes<'static>, Vec<Header>>,
     result: Result<(), Error>,
     has_reduce: bool,
 }
@@ -424,6 +672,10 @@ impl<'a> ViewEntryUpdater<'a> {
         key: &ArcBytes<'_>,
         view_entries: Option<ArcBytes<'static>>,
     ) -> KeyOperation<ArcBytes<'static>> {
+        if view_entries.is_none() && !self.new_mappings.contains_key(&key[..]) {
+            // There is nothing to clean up or update for this key.
+            return KeyOperation::Skip;
+        }
         let mut view_entry = view_entries
             .and_then(|view_entries| bincode::deserialize::<ViewEntry>(&view_entries).ok())
             .unwrap_or_else(|| ViewEntry {
@@ -433,6 +685,17 @@ impl<'a> ViewEntryUpdater<'a> {
                 reduced_value: Bytes::default(),
             });
         let key = key.to_owned();
+        if let Some(sources) = self.updated_sources.remove(&key) {
+            for source in sources {
+                if let Some(mapping) = view_entry
+                    .mappings
+                    .iter_mut()
+                    .find(|mapping| mapping.source.id == source.id)
+                {
+                    mapping.source = source;
+                }
+            }
+        }
         if let Some(document_ids) = self.view_entries_to_clean.remove(&key) {
             view_entry
                 .mappings
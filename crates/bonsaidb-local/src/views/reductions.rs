@@ -0,0 +1,315 @@
+use std::collections::BTreeSet;
+use std::ops::RangeBounds;
+
+use bonsaidb_core::arc_bytes::serde::Bytes;
+use bonsaidb_core::arc_bytes::ArcBytes;
+use bonsaidb_core::connection::{Bound, Range};
+use bonsaidb_core::schema::view::{self, Serialized};
+use nebari::io::any::AnyFile;
+use nebari::tree::{Operation, Unversioned};
+use nebari::{LockedTransactionTree, Tree};
+use serde::{Deserialize, Serialize};
+
+use crate::views::ViewEntry;
+use crate::Error;
+
+// Reductions are cached for every key prefix up to this many bytes long. Each
+// cached value is the re-reduction of the entry whose key equals the prefix and
+// the cached values of the prefixes one byte longer. At the maximum depth, the
+// cached value is the re-reduction of every entry beginning with the prefix.
+//
+// This forms a trie over the view's keys, which allows reducing a range of keys
+// by combining the cached values that are entirely contained within the range
+// and only visiting individual entries along the two edges of the range.
+//
+// Buckets are never split beyond this depth. All entries whose keys share the
+// same first `MAX_PREFIX_DEPTH` bytes are re-reduced together whenever one of
+// them changes, and a range that partially covers such a bucket visits every
+// entry in it. Views whose keys commonly share long prefixes, such as strings
+// with a common leading path, will see reductions degrade towards O(n) within
+// those buckets.
+const MAX_PREFIX_DEPTH: usize = 8;
+
+// Bucket keys begin with their depth, which never reaches this value. When
+// present, this key records that the view doesn't implement reduce, and no
+// cache is maintained for it. Because the reductions tree is deleted when the
+// view's version changes, the marker is cleared whenever the view is rebuilt.
+const REDUCE_UNIMPLEMENTED_KEY: &[u8] = &[u8::MAX];
+
+/// The cached reduction of all view entries whose key begins with a prefix.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedReduction {
+    /// The first key contained in this reduction. Because reduce functions
+    /// receive deserialized keys, this key is passed alongside the reduced
+    /// value when re-reducing.
+    first_key: Bytes,
+    value: Bytes,
+}
+
+/// Recomputes the cached reductions for every prefix of `changed_keys`.
+///
+/// This must be called within the same transaction that updated the view
+/// entries for `changed_keys`.
+pub fn update_reductions(
+    view: &dyn Serialized,
+    changed_keys: &BTreeSet<ArcBytes<'static>>,
+    view_entries: &mut LockedTransactionTree<'_, Unversioned, AnyFile>,
+    reductions: &mut LockedTransactionTree<'_, Unversioned, AnyFile>,
+) -> Result<(), Error> {
+    if changed_keys.is_empty() || reductions.get(REDUCE_UNIMPLEMENTED_KEY)?.is_some() {
+        return Ok(());
+    }
+
+    let mut dirty_prefixes = vec![BTreeSet::new(); MAX_PREFIX_DEPTH + 1];
+    for key in changed_keys {
+        for depth in 0..=key.len().min(MAX_PREFIX_DEPTH) {
+            dirty_prefixes[depth].insert(key[..depth].to_vec());
+        }
+    }
+
+    // Each prefix depends on the prefixes one byte longer, so the deepest
+    // prefixes must be updated first.
+    for (depth, prefixes) in dirty_prefixes.into_iter().enumerate().rev() {
+        for prefix in prefixes {
+            let mut values = Vec::new();
+            if depth == MAX_PREFIX_DEPTH {
+                let end = prefix_end(&prefix);
+                for (_, entry) in view_entries.get_range(&prefix_range(&prefix, end.as_deref()))? {
+                    let entry = bincode::deserialize::<ViewEntry>(&entry)?;
+                    values.push((entry.key, entry.reduced_value));
+                }
+            } else {
+                if let Some(entry) = view_entries.get(&prefix)? {
+                    let entry = bincode::deserialize::<ViewEntry>(&entry)?;
+                    values.push((entry.key, entry.reduced_value));
+                }
+                let (first_child, last_child) = child_bucket_keys(&prefix);
+                for (_, child) in reductions.get_range(&(&first_child[..]..=&last_child[..]))? {
+                    let child = bincode::deserialize::<CachedReduction>(&child)?;
+                    values.push((child.first_key, child.value));
+                }
+            }
+
+            let bucket = ArcBytes::from(bucket_key(&prefix));
+            if values.is_empty() {
+                reductions.modify(vec![bucket], Operation::Remove)?;
+                continue;
+            }
+
+            let first_key = values[0].0.clone();
+            let value = if values.len() == 1 {
+                values.pop().unwrap().1
+            } else {
+                match rereduce(view, &values) {
+                    Ok(reduced) => Bytes::from(reduced),
+                    Err(view::Error::Core(bonsaidb_core::Error::ReduceUnimplemented)) => {
+                        // Without a reduce implementation, the cache can't be
+                        // maintained. Removing the root prefix disables the
+                        // cache for queries, and the marker prevents future
+                        // updates from attempting to rebuild it.
+                        let everything = reductions
+                            .get_range(&(..))?
+                            .into_iter()
+                            .map(|(key, _)| key)
+                            .collect::<Vec<_>>();
+                        reductions.modify(everything, Operation::Remove)?;
+                        reductions.set(
+                            ArcBytes::from(REDUCE_UNIMPLEMENTED_KEY),
+                            ArcBytes::default(),
+                        )?;
+                        return Ok(());
+                    }
+                    Err(other) => return Err(Error::from(other)),
+                }
+            };
+
+            reductions.set(
+                bucket,
+                ArcBytes::from(bincode::serialize(&CachedReduction { first_key, value })?),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reduces all entries within `range` using the cached reductions. Returns
+/// `None` if the cache isn't available for this view.
+pub fn reduce_range(
+    view: &dyn Serialized,
+    range: &Range<&[u8]>,
+    view_entries: &Tree<Unversioned, AnyFile>,
+    reductions: &Tree<Unversioned, AnyFile>,
+) -> Result<Option<Vec<u8>>, Error> {
+    let Some(root) = reductions.get(&bucket_key(&[]))? else {
+        return Ok(None);
+    };
+    let root = bincode::deserialize::<CachedReduction>(&root)?;
+
+    let mut values = Vec::new();
+    collect_range(&[], root, range, view_entries, reductions, &mut values)?;
+
+    if values.len() == 1 {
+        Ok(Some(values.pop().unwrap().1.into_vec()))
+    } else {
+        rereduce(view, &values).map(Some).map_err(Error::from)
+    }
+}
+
+fn collect_range(
+    prefix: &[u8],
+    cached: CachedReduction,
+    range: &Range<&[u8]>,
+    view_entries: &Tree<Unversioned, AnyFile>,
+    reductions: &Tree<Unversioned, AnyFile>,
+    values: &mut Vec<(Bytes, Bytes)>,
+) -> Result<(), Error> {
+    let end = prefix_end(prefix);
+    match overlap(prefix, end.as_deref(), range) {
+        Overlap::None => {}
+        Overlap::Full => values.push((cached.first_key, cached.value)),
+        Overlap::Partial if prefix.len() == MAX_PREFIX_DEPTH => {
+            for (key, entry) in view_entries.get_range(&prefix_range(prefix, end.as_deref()))? {
+                if range.contains(&key.as_slice()) {
+                    let entry = bincode::deserialize::<ViewEntry>(&entry)?;
+                    values.push((entry.key, entry.reduced_value));
+                }
+            }
+        }
+        Overlap::Partial => {
+            if range.contains(&prefix) {
+                if let Some(entry) = view_entries.get(prefix)? {
+                    let entry = bincode::deserialize::<ViewEntry>(&entry)?;
+                    values.push((entry.key, entry.reduced_value));
+                }
+            }
+            let (first_child, last_child) = child_bucket_keys(prefix);
+            for (child_key, child) in reductions.get_range(&(&first_child[..]..=&last_child[..]))? {
+                let child = bincode::deserialize::<CachedReduction>(&child)?;
+                collect_range(
+                    &child_key[1..],
+                    child,
+                    range,
+                    view_entries,
+                    reductions,
+                    values,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn rereduce(view: &dyn Serialized, values: &[(Bytes, Bytes)]) -> Result<Vec<u8>, view::Error> {
+    let mappings = values
+        .iter()
+        .map(|(key, value)| (key.as_slice(), value.as_slice()))
+        .collect::<Vec<_>>();
+    view.reduce(&mappings, true)
+}
+
+enum Overlap {
+    None,
+    Partial,
+    Full,
+}
+
+/// Determines how the keys beginning with `prefix` overlap `range`. `end` must
+/// be the result of [`prefix_end()`].
+fn overlap(prefix: &[u8], end: Option<&[u8]>, range: &Range<&[u8]>) -> Overlap {
+    let before_start = match (&range.start, end) {
+        (Bound::Included(start) | Bound::Excluded(start), Some(end)) => end <= *start,
+        _ => false,
+    };
+    let after_end = match &range.end {
+        Bound::Unbounded => false,
+        Bound::Included(range_end) => prefix > *range_end,
+        Bound::Excluded(range_end) => prefix >= *range_end,
+    };
+    if before_start || after_end {
+        return Overlap::None;
+    }
+
+    let starts_within = match &range.start {
+        Bound::Unbounded => true,
+        Bound::Included(start) => *start <= prefix,
+        Bound::Excluded(start) => *start < prefix,
+    };
+    let ends_within = match (&range.end, end) {
+        (Bound::Unbounded, _) => true,
+        (Bound::Included(range_end) | Bound::Excluded(range_end), Some(end)) => end <= *range_end,
+        (_, None) => false,
+    };
+    if starts_within && ends_within {
+        Overlap::Full
+    } else {
+        Overlap::Partial
+    }
+}
+
+/// Returns the smallest key that is greater than every key beginning with
+/// `prefix`, or `None` if no such key exists.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+fn prefix_range<'a>(prefix: &'a [u8], end: Option<&'a [u8]>) -> Range<&'a [u8]> {
+    Range {
+        start: Bound::Included(prefix),
+        end: end.map_or(Bound::Unbounded, Bound::Excluded),
+    }
+}
+
+fn bucket_key(prefix: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(prefix.len() + 1);
+    key.push(u8::try_from(prefix.len()).expect("prefix depth is limited"));
+    key.extend_from_slice(prefix);
+    key
+}
+
+fn child_bucket_keys(prefix: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut first = Vec::with_capacity(prefix.len() + 2);
+    first.push(u8::try_from(prefix.len() + 1).expect("prefix depth is limited"));
+    first.extend_from_slice(prefix);
+    let mut last = first.clone();
+    first.push(0);
+    last.push(u8::MAX);
+    (first, last)
+}
+
+#[test]
+fn overlap_tests() {
+    fn overlap_of(prefix: &[u8], range: &Range<&[u8]>) -> Overlap {
+        overlap(prefix, prefix_end(prefix).as_deref(), range)
+    }
+
+    let everything = Range::<&[u8]>::default();
+    assert!(matches!(overlap_of(&[], &everything), Overlap::Full));
+    assert!(matches!(overlap_of(&[0xff], &everything), Overlap::Full));
+
+    let range = Range::<&[u8]>::default().start_at(&[1, 5]).before(&[3]);
+    assert!(matches!(overlap_of(&[], &range), Overlap::Partial));
+    assert!(matches!(overlap_of(&[0], &range), Overlap::None));
+    assert!(matches!(overlap_of(&[1], &range), Overlap::Partial));
+    assert!(matches!(overlap_of(&[1, 4], &range), Overlap::None));
+    assert!(matches!(overlap_of(&[1, 5], &range), Overlap::Full));
+    assert!(matches!(overlap_of(&[2], &range), Overlap::Full));
+    assert!(matches!(overlap_of(&[3], &range), Overlap::None));
+
+    let range = Range::<&[u8]>::default().after(&[1]).end_at(&[2, 0]);
+    assert!(matches!(overlap_of(&[1], &range), Overlap::Partial));
+    assert!(matches!(overlap_of(&[1, 0], &range), Overlap::Full));
+    assert!(matches!(overlap_of(&[2], &range), Overlap::Partial));
+    assert!(matches!(overlap_of(&[2, 0], &range), Overlap::Partial));
+    assert!(matches!(overlap_of(&[2, 1], &range), Overlap::None));
+
+    assert_eq!(prefix_end(&[1, 0xff]), Some(vec![2]));
+    assert_eq!(prefix_end(&[0xff, 0xff]), None);
+}
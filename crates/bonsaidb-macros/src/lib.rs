@@ -251,9 +251,12 @@ pub fn view_derive(input: proc_macro::TokenStream) -> Result {
 }
 /// Derives the `bonsaidb::core::schema::ViewSchema` trait.
 #[manyhow]
-/// `#[view_schema(version = 1, unique = true, lazy = false, view=ViewType, mapped_key=KeyType<'doc>)]`
+/// `#[view_schema(version = 1, unique = true, lazy = false, view=ViewType, mapped_key=KeyType<'doc>, error_policy=Quarantine, reduce=Sum, filter=filter_fn, dependencies=dependencies_fn)]`
 ///
-/// All attributes are optional.
+/// All attributes are optional. `reduce` accepts the name of a reducer in
+/// `bonsaidb::core::schema::view::reducers` or the path to a type implementing
+/// `Reducer`. `filter` and `dependencies` accept paths to functions that are
+/// called by `ViewSchema::filter()` and `ViewSchema::dependencies()`.
 #[proc_macro_derive(ViewSchema, attributes(view_schema))]
 pub fn view_schema_derive(input: proc_macro::TokenStream) -> Result {
     view::derive_schema(parse(input)?)
@@ -98,10 +98,30 @@ struct ViewSchemaAttribute {
     version: Option<u64>,
     #[attribute(example = "Lazy")]
     policy: Option<Ident>,
+    #[attribute(example = "Quarantine")]
+    error_policy: Option<Ident>,
+    #[attribute(example = "Sum")]
+    reduce: Option<Path>,
+    #[attribute(example = "filter_fn")]
+    filter: Option<Path>,
+    #[attribute(example = "dependencies_fn")]
+    dependencies: Option<Path>,
     #[attribute(example = "bosaidb::core")]
     core: Option<Path>,
 }
 
+/// The reducers in `bonsaidb::core::schema::view::reducers` that can be
+/// referred to by name.
+const BUILTIN_REDUCERS: &[&str] = &[
+    "Sum",
+    "Count",
+    "Min",
+    "Max",
+    "Average",
+    "Aggregate",
+    "CountDistinct",
+];
+
 pub fn derive_schema(
     DeriveInput {
         attrs,
@@ -115,6 +135,10 @@ pub fn derive_schema(
         mapped_key,
         version,
         policy,
+        error_policy,
+        reduce,
+        filter,
+        dependencies,
         core,
     } = ViewSchemaAttribute::from_attributes(&attrs)?;
 
@@ -139,6 +163,47 @@ pub fn derive_schema(
         })
     });
 
+    let error_policy = error_policy.map(|policy| {
+        quote!(fn mapping_error_policy(&self) -> #core::schema::view::ViewMappingErrorPolicy {
+            #core::schema::view::ViewMappingErrorPolicy::#policy
+        })
+    });
+
+    let reduce = reduce.map(|reducer| {
+        // Built-in reducers can be referred to by name. Any other path refers
+        // to a custom reducer.
+        let reducer = match reducer.get_ident() {
+            Some(name) if BUILTIN_REDUCERS.iter().any(|builtin| name == builtin) => {
+                quote!(#core::schema::view::reducers::#name)
+            }
+            _ => quote!(#reducer),
+        };
+        quote!(fn builtin_reduce(
+            &self,
+            mappings: &[#core::schema::ViewMappedValue<'_, Self>],
+            rereduce: bool,
+        ) -> #core::schema::ReduceResult<<Self as #core::schema::ViewSchema>::View> {
+            <#reducer as #core::schema::view::reducers::Reducer<
+                <<Self as #core::schema::ViewSchema>::View as #core::schema::View>::Value,
+            >>::reduce(mappings.iter().map(|mapping| &mapping.value), rereduce)
+        })
+    });
+
+    let filter = filter.map(|filter| {
+        quote!(fn filter(&self, document: &#core::document::BorrowedDocument<'_>) -> bool {
+            #filter(document)
+        })
+    });
+
+    let dependencies = dependencies.map(|dependencies| {
+        quote!(fn dependencies(
+            &self,
+            document: &#core::document::BorrowedDocument<'_>,
+        ) -> Result<Option<Vec<u8>>, #core::Error> {
+            #dependencies(document)
+        })
+    });
+
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     Ok(quote! {
@@ -148,6 +213,10 @@ pub fn derive_schema(
 
             #version
             #policy
+            #error_policy
+            #reduce
+            #filter
+            #dependencies
         }
     })
 }
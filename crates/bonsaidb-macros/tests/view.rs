@@ -1,6 +1,7 @@
 use core::fmt::Debug;
 
-use bonsaidb::core::schema::{Collection, View};
+use bonsaidb::core::schema::view::reducers::Reducer;
+use bonsaidb::core::schema::{Collection, MappedValue, View, ViewSchema};
 
 #[derive(Collection, Debug)]
 #[collection(name = "name", authority = "authority")]
@@ -31,3 +32,37 @@ fn serialization_none() {
     #[view(serialization = None)]
     struct TestView;
 }
+
+#[test]
+fn reduce() {
+    #[derive(View, ViewSchema, Debug)]
+    #[view(collection = TestCollection, key = u32, value = u32)]
+    #[view_schema(reduce = Sum)]
+    struct SumView;
+
+    #[derive(View, ViewSchema, Debug)]
+    #[view(collection = TestCollection, key = u32, value = u32)]
+    #[view_schema(reduce = bonsaidb::core::schema::view::reducers::Max)]
+    struct MaxView;
+
+    struct First;
+
+    impl Reducer<u32> for First {
+        fn reduce<'a, I: Iterator<Item = &'a u32>>(
+            mut values: I,
+            _rereduce: bool,
+        ) -> Result<u32, bonsaidb::core::Error> {
+            Ok(values.next().copied().unwrap_or_default())
+        }
+    }
+
+    #[derive(View, ViewSchema, Debug)]
+    #[view(collection = TestCollection, key = u32, value = u32)]
+    #[view_schema(reduce = First)]
+    struct FirstView;
+
+    let mappings = [MappedValue::new(1, 2), MappedValue::new(2, 3)];
+    assert_eq!(SumView.builtin_reduce(&mappings, false).unwrap(), 5);
+    assert_eq!(MaxView.builtin_reduce(&mappings, false).unwrap(), 3);
+    assert_eq!(FirstView.builtin_reduce(&mappings, false).unwrap(), 2);
+}
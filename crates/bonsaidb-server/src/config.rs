@@ -3,18 +3,26 @@ use std::marker::PhantomData;
 use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use bonsaidb_core::api;
 use bonsaidb_core::api::ApiName;
 #[cfg(feature = "encryption")]
 use bonsaidb_core::document::KeyId;
 use bonsaidb_core::permissions::{Permissions, Statement};
-use bonsaidb_core::schema::Schema;
+#[cfg(feature = "compression")]
+use bonsaidb_core::schema::CollectionName;
+use bonsaidb_core::schema::{Schema, SchemaName, SerializedCollection};
+use bonsaidb_core::transaction::Durability;
 #[cfg(feature = "compression")]
 use bonsaidb_local::config::Compression;
-use bonsaidb_local::config::{Builder, KeyValuePersistence, StorageConfiguration};
+use bonsaidb_local::config::{
+    Builder, Cache, CompactionPolicy, KeyValuePersistence, Quota, Snapshots, StorageConfiguration,
+};
 #[cfg(feature = "encryption")]
 use bonsaidb_local::vault::AnyVaultKeyStorage;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 use crate::api::{AnyHandler, AnyWrapper, Handler};
 use crate::{Backend, Error, NoBackend};
@@ -210,11 +218,31 @@ impl<B: Backend> Builder for ServerConfiguration<B> {
         Ok(self)
     }
 
+    fn with_json_collection<C>(mut self) -> Self
+    where
+        C: SerializedCollection + 'static,
+        C::Contents: Serialize + DeserializeOwned,
+        C::PrimaryKey: Serialize + DeserializeOwned,
+    {
+        self.storage.register_json_collection::<C>();
+        self
+    }
+
     fn memory_only(mut self) -> Self {
         self.storage.memory_only = true;
         self
     }
 
+    fn snapshots(mut self, snapshots: Snapshots) -> Self {
+        self.storage.snapshots = Some(snapshots);
+        self
+    }
+
+    fn read_only(mut self) -> Self {
+        self.storage.read_only = true;
+        self
+    }
+
     fn path<P: AsRef<Path>>(mut self, path: P) -> Self {
         self.storage.path = Some(path.as_ref().to_owned());
         self
@@ -261,11 +289,60 @@ impl<B: Backend> Builder for ServerConfiguration<B> {
         self
     }
 
+    #[cfg(feature = "compression")]
+    fn collection_compression(
+        mut self,
+        collection: CollectionName,
+        compression: Compression,
+    ) -> Self {
+        self.storage
+            .collection_compression
+            .insert(collection, compression);
+        self
+    }
+
     fn key_value_persistence(mut self, persistence: KeyValuePersistence) -> Self {
         self.storage.key_value_persistence = persistence;
         self
     }
 
+    fn cache(mut self, cache: Cache) -> Self {
+        self.storage.cache = cache;
+        self
+    }
+
+    fn compaction_policy(mut self, policy: CompactionPolicy) -> Self {
+        self.storage.compaction_policy = Some(policy);
+        self
+    }
+
+    fn database_quota(mut self, database: impl Into<String>, quota: Quota) -> Self {
+        self.storage.database_quotas.insert(database.into(), quota);
+        self
+    }
+
+    fn schema_quota(mut self, schema: SchemaName, quota: Quota) -> Self {
+        self.storage.schema_quotas.insert(schema, quota);
+        self
+    }
+
+    fn durability(mut self, durability: Durability) -> Self {
+        self.storage.durability = durability;
+        self
+    }
+
+    fn database_durability(mut self, database: impl Into<String>, durability: Durability) -> Self {
+        self.storage
+            .database_durability
+            .insert(database.into(), durability);
+        self
+    }
+
+    fn max_group_commit_window(mut self, window: Duration) -> Self {
+        self.storage.max_group_commit_window = window;
+        self
+    }
+
     fn authenticated_permissions<P: Into<Permissions>>(
         mut self,
         authenticated_permissions: P,
@@ -7,11 +7,13 @@ use bonsaidb_core::connection::{
 use bonsaidb_core::keyvalue::AsyncKeyValue;
 use bonsaidb_core::networking::{
     AlterUserPermissionGroupMembership, AlterUserRoleMembership, ApplyTransaction, AssumeIdentity,
-    Compact, CompactCollection, CompactKeyValueStore, Count, CreateDatabase, CreateSubscriber,
-    CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Get, GetMultiple,
-    LastTransactionId, List, ListAvailableSchemas, ListDatabases, ListExecutedTransactions,
-    ListHeaders, LogOutSession, Publish, PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped,
-    SubscribeTo, UnregisterSubscriber, UnsubscribeFrom,
+    Compact, CompactCollection, CompactKeyValueStore, CopyDatabase, Count, CreateDatabase,
+    CreateSubscriber, CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Get,
+    GetDatabaseStats, GetMultiple, GetStorageStats, LastTransactionId, List, ListAvailableSchemas,
+    ListDatabases, ListExecutedTransactions, ListHeaders, ListQuarantinedDocuments,
+    ListViewStatuses, LogOutSession, Publish, PublishToAll, Query, QueryWithDocs, Reduce,
+    ReduceGrouped, ReindexViews, RenameDatabase, SubscribeTo, UnregisterSubscriber,
+    UnsubscribeFrom,
 };
 #[cfg(feature = "password-hashing")]
 use bonsaidb_core::networking::{Authenticate, SetUserPassword};
@@ -32,6 +34,7 @@ pub fn register_api_handlers<B: Backend>(
         .with_api::<ServerDispatcher, Compact>()?
         .with_api::<ServerDispatcher, CompactCollection>()?
         .with_api::<ServerDispatcher, CompactKeyValueStore>()?
+        .with_api::<ServerDispatcher, CopyDatabase>()?
         .with_api::<ServerDispatcher, Count>()?
         .with_api::<ServerDispatcher, CreateDatabase>()?
         .with_api::<ServerDispatcher, CreateSubscriber>()?
@@ -41,13 +44,17 @@ pub fn register_api_handlers<B: Backend>(
         .with_api::<ServerDispatcher, DeleteUser>()?
         .with_api::<ServerDispatcher, ExecuteKeyOperation>()?
         .with_api::<ServerDispatcher, Get>()?
+        .with_api::<ServerDispatcher, GetDatabaseStats>()?
         .with_api::<ServerDispatcher, GetMultiple>()?
+        .with_api::<ServerDispatcher, GetStorageStats>()?
         .with_api::<ServerDispatcher, LastTransactionId>()?
         .with_api::<ServerDispatcher, List>()?
         .with_api::<ServerDispatcher, ListHeaders>()?
         .with_api::<ServerDispatcher, ListAvailableSchemas>()?
         .with_api::<ServerDispatcher, ListDatabases>()?
         .with_api::<ServerDispatcher, ListExecutedTransactions>()?
+        .with_api::<ServerDispatcher, ListQuarantinedDocuments>()?
+        .with_api::<ServerDispatcher, ListViewStatuses>()?
         .with_api::<ServerDispatcher, LogOutSession>()?
         .with_api::<ServerDispatcher, Publish>()?
         .with_api::<ServerDispatcher, PublishToAll>()?
@@ -55,6 +62,8 @@ pub fn register_api_handlers<B: Backend>(
         .with_api::<ServerDispatcher, QueryWithDocs>()?
         .with_api::<ServerDispatcher, Reduce>()?
         .with_api::<ServerDispatcher, ReduceGrouped>()?
+        .with_api::<ServerDispatcher, ReindexViews>()?
+        .with_api::<ServerDispatcher, RenameDatabase>()?
         .with_api::<ServerDispatcher, SubscribeTo>()?
         .with_api::<ServerDispatcher, UnregisterSubscriber>()?
         .with_api::<ServerDispatcher, UnsubscribeFrom>()?;
@@ -114,6 +123,34 @@ impl<B: Backend> Handler<B, DeleteDatabase> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<B, RenameDatabase> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: RenameDatabase,
+    ) -> HandlerResult<RenameDatabase> {
+        session
+            .as_client
+            .rename_database(&command.name, &command.new_name)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<B, CopyDatabase> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: CopyDatabase,
+    ) -> HandlerResult<CopyDatabase> {
+        session
+            .as_client
+            .copy_database(&command.name, &command.destination)
+            .await?;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<B, ListDatabases> for ServerDispatcher {
     async fn handle(
@@ -142,6 +179,16 @@ impl<B: Backend> Handler<B, ListAvailableSchemas> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<B, GetStorageStats> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        _command: GetStorageStats,
+    ) -> HandlerResult<GetStorageStats> {
+        session.as_client.stats().await.map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<B, CreateUser> for ServerDispatcher {
     async fn handle(
@@ -670,6 +717,20 @@ impl<B: Backend> Handler<B, CompactKeyValueStore> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<B, GetDatabaseStats> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: GetDatabaseStats,
+    ) -> HandlerResult<GetDatabaseStats> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database.stats().await.map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<B, Compact> for ServerDispatcher {
     async fn handle(client: HandlerSession<'_, B>, command: Compact) -> HandlerResult<Compact> {
@@ -680,3 +741,51 @@ impl<B: Backend> Handler<B, Compact> for ServerDispatcher {
         database.compact().await.map_err(HandlerError::from)
     }
 }
+
+#[async_trait]
+impl<B: Backend> Handler<B, ListViewStatuses> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: ListViewStatuses,
+    ) -> HandlerResult<ListViewStatuses> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database.view_statuses().await.map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<B, ListQuarantinedDocuments> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: ListQuarantinedDocuments,
+    ) -> HandlerResult<ListQuarantinedDocuments> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database
+            .quarantined_documents(&command.view)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<B, ReindexViews> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: ReindexViews,
+    ) -> HandlerResult<ReindexViews> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database
+            .reindex_views(command.views)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
@@ -14,7 +14,7 @@ use bonsaidb_core::api::ApiName;
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::connection::{
     self, AsyncConnection, AsyncStorageConnection, HasSession, IdentityReference, Session,
-    SessionId,
+    SessionId, StorageStats,
 };
 use bonsaidb_core::networking::{self, Payload, CURRENT_PROTOCOL_VERSION};
 use bonsaidb_core::permissions::bonsai::{bonsaidb_resource_name, BonsaiAction, ServerAction};
@@ -913,6 +913,22 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
         self.storage.delete_database(name).await
     }
 
+    async fn rename_database(
+        &self,
+        name: &str,
+        new_name: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.storage.rename_database(name, new_name).await
+    }
+
+    async fn copy_database(
+        &self,
+        name: &str,
+        destination: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.storage.copy_database(name, destination).await
+    }
+
     async fn list_databases(&self) -> Result<Vec<connection::Database>, bonsaidb_core::Error> {
         self.storage.list_databases().await
     }
@@ -921,6 +937,10 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
         self.storage.list_available_schemas().await
     }
 
+    async fn stats(&self) -> Result<StorageStats, bonsaidb_core::Error> {
+        self.storage.stats().await
+    }
+
     async fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         self.storage.create_user(username).await
     }
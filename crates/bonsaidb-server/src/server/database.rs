@@ -2,7 +2,8 @@ use std::ops::Deref;
 
 use async_trait::async_trait;
 use bonsaidb_core::connection::{
-    AccessPolicy, AsyncLowLevelConnection, HasSchema, HasSession, Range, SerializedQueryKey, Sort,
+    AccessPolicy, AsyncLowLevelConnection, DatabaseStats, HasSchema, HasSession, Range,
+    SerializedQueryKey, Sort,
 };
 use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::keyvalue::AsyncKeyValue;
@@ -124,6 +125,10 @@ impl<B: Backend> bonsaidb_core::connection::AsyncConnection for ServerDatabase<B
     async fn compact_key_value_store(&self) -> Result<(), bonsaidb_core::Error> {
         self.db.compact_key_value_store().await
     }
+
+    async fn stats(&self) -> Result<DatabaseStats, bonsaidb_core::Error> {
+        self.db.stats().await
+    }
 }
 
 /// Pass-through implementation
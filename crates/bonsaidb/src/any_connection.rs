@@ -2,10 +2,12 @@ use bonsaidb_client::{AsyncClient, AsyncRemoteDatabase};
 use bonsaidb_core::async_trait::async_trait;
 use bonsaidb_core::connection::{
     self, AccessPolicy, AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection,
-    HasSchema, HasSession, IdentityReference, Range, SerializedQueryKey, Session, Sort,
+    DatabaseStats, HasSchema, HasSession, IdentityReference, Range, SerializedQueryKey, Session,
+    Sort, StorageStats,
 };
 use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
+use bonsaidb_core::schema::view::{QuarantinedDocument, ViewStatus};
 use bonsaidb_core::schema::{
     self, Collection, CollectionName, Nameable, Schema, SchemaName, SchemaSummary, Schematic,
     ViewName,
@@ -84,6 +86,28 @@ impl<B: Backend> AsyncStorageConnection for AnyServerConnection<B> {
         }
     }
 
+    async fn rename_database(
+        &self,
+        name: &str,
+        new_name: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.rename_database(name, new_name).await,
+            Self::Networked(client) => client.rename_database(name, new_name).await,
+        }
+    }
+
+    async fn copy_database(
+        &self,
+        name: &str,
+        destination: &str,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.copy_database(name, destination).await,
+            Self::Networked(client) => client.copy_database(name, destination).await,
+        }
+    }
+
     async fn list_databases(&self) -> Result<Vec<connection::Database>, bonsaidb_core::Error> {
         match self {
             Self::Local(server) => server.list_databases().await,
@@ -98,6 +122,13 @@ impl<B: Backend> AsyncStorageConnection for AnyServerConnection<B> {
         }
     }
 
+    async fn stats(&self) -> Result<StorageStats, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.stats().await,
+            Self::Networked(client) => client.stats().await,
+        }
+    }
+
     async fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         match self {
             Self::Local(server) => server.create_user(username).await,
@@ -242,6 +273,39 @@ pub enum AnyDatabase<B: Backend = NoBackend> {
     Networked(AsyncRemoteDatabase),
 }
 
+impl<B: Backend> AnyDatabase<B> {
+    /// Returns the indexing status of each view in this database.
+    pub async fn view_statuses(&self) -> Result<Vec<ViewStatus>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.view_statuses().await,
+            Self::Networked(client) => client.view_statuses().await,
+        }
+    }
+
+    /// Returns the documents that failed to map in `view`.
+    pub async fn quarantined_documents(
+        &self,
+        view: &ViewName,
+    ) -> Result<Vec<QuarantinedDocument>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.quarantined_documents(view).await,
+            Self::Networked(client) => client.quarantined_documents(view).await,
+        }
+    }
+
+    /// Begins updating the indexes of `views` in the background. If `views`
+    /// is `None`, all views in this database will be updated.
+    pub async fn reindex_views(
+        &self,
+        views: Option<Vec<ViewName>>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.reindex_views(views).await,
+            Self::Networked(client) => client.reindex_views(views).await,
+        }
+    }
+}
+
 impl<B: Backend> HasSession for AnyDatabase<B> {
     fn session(&self) -> Option<&Session> {
         match self {
@@ -308,6 +372,13 @@ impl<B: Backend> AsyncConnection for AnyDatabase<B> {
             Self::Networked(client) => client.compact_key_value_store().await,
         }
     }
+
+    async fn stats(&self) -> Result<DatabaseStats, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.stats().await,
+            Self::Networked(client) => client.stats().await,
+        }
+    }
 }
 
 #[async_trait]
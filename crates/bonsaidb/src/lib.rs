@@ -32,6 +32,8 @@ pub use bonsaidb_server as server;
 mod any_connection;
 #[cfg(all(feature = "cli", feature = "client", feature = "server"))]
 pub mod cli;
+#[cfg(feature = "replication")]
+pub mod replication;
 
 /// `VaultKeyStorage` implementors.
 #[cfg(feature = "keystorage-s3")]
@@ -0,0 +1,644 @@
+//! Asynchronous leader-follower replication between BonsaiDb servers.
+//!
+//! A [`Follower`] connects to a leader server using an [`AsyncClient`] and
+//! copies the changes recorded in each of the leader's databases' transaction
+//! logs into its own [`AsyncStorage`]. The follower subscribes to
+//! [`EXECUTED_TRANSACTIONS_TOPIC`] on each replicated database, and applies new
+//! transactions as soon as the leader publishes that they have been executed.
+//! Replication is asynchronous: reads from the follower may not reflect the
+//! most recent writes to the leader. [`ReadPolicy`] controls how stale the
+//! data returned by [`Follower::database()`] is allowed to be.
+//!
+//! Documents are replicated atomically: the follower applies the documents
+//! changed by a range of the leader's transactions in a single transaction,
+//! using their contents as of the last transaction in the range. Readers of a
+//! follower never observe part of a leader transaction, unless the follower
+//! is more than [`ReplicationConfiguration::max_lookahead`] transactions
+//! behind the leader. Key-value entries are replicated along with their
+//! expiration after the documents, using their most recent value.
+//!
+//! Changes should only be made to the leader. Changes made directly to a
+//! follower's databases may be overwritten or lost.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bonsaidb_client::{AsyncClient, AsyncRemoteDatabase, AsyncRemoteSubscriber};
+use bonsaidb_core::admin::ADMIN_DATABASE_NAME;
+use bonsaidb_core::arc_bytes::serde::Bytes;
+use bonsaidb_core::connection::{AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection};
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::keyvalue::{
+    AsyncKeyValue, Command, KeyOperation, Numeric, Output, SetCommand, Value,
+};
+use bonsaidb_core::pubsub::{AsyncPubSub, AsyncSubscriber};
+use bonsaidb_core::schema::CollectionName;
+use bonsaidb_core::transaction::{
+    Changes, Executed, Operation, Transaction, EXECUTED_TRANSACTIONS_TOPIC,
+};
+use bonsaidb_core::Error;
+use bonsaidb_local::{AsyncDatabase, AsyncStorage};
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+/// The key-value namespace that a follower stores its replication progress
+/// in. Keys in this namespace are never replicated, which allows followers to
+/// act as leaders for other followers.
+pub const REPLICATION_NAMESPACE: &str = "_bonsaidb_replication";
+
+/// The key within [`REPLICATION_NAMESPACE`] that contains the id of the last
+/// leader transaction applied to a database.
+const APPLIED_TRANSACTION_KEY: &str = "applied-transaction-id";
+
+/// Configuration options for a [`Follower`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ReplicationConfiguration {
+    /// The names of the databases to replicate. If `None`, all databases on
+    /// the leader other than the admin database are replicated. Defaults to
+    /// `None`.
+    pub databases: Option<Vec<String>>,
+    /// The longest [`Follower::run()`] waits for the leader to publish that a
+    /// transaction was executed before checking for new transactions and
+    /// databases anyway. Notifications can be missed while reconnecting to
+    /// the leader. Defaults to 1 second.
+    pub poll_interval: Duration,
+    /// The maximum number of transactions to request from the leader at once.
+    /// Defaults to 100.
+    pub batch_size: u32,
+    /// The maximum number of transactions executed after a batch that are
+    /// examined for changes to the documents read for the batch. Documents
+    /// changed by transactions beyond this limit may be applied with contents
+    /// from those transactions before the transactions themselves are
+    /// replicated. Defaults to 1,000.
+    pub max_lookahead: u32,
+}
+
+impl Default for ReplicationConfiguration {
+    fn default() -> Self {
+        Self {
+            databases: None,
+            poll_interval: Duration::from_secs(1),
+            batch_size: 100,
+            max_lookahead: 1_000,
+        }
+    }
+}
+
+impl ReplicationConfiguration {
+    /// Only replicates the database named `name`. Can be called multiple times
+    /// to replicate multiple databases.
+    #[must_use]
+    pub fn database(mut self, name: impl Into<String>) -> Self {
+        self.databases
+            .get_or_insert_with(Vec::new)
+            .push(name.into());
+        self
+    }
+
+    /// Sets [`Self::poll_interval`](Self#structfield.poll_interval) to
+    /// `interval` and returns self.
+    #[must_use]
+    pub const fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Sets [`Self::batch_size`](Self#structfield.batch_size) to `batch_size`
+    /// and returns self.
+    #[must_use]
+    pub const fn batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Sets [`Self::max_lookahead`](Self#structfield.max_lookahead) to
+    /// `max_lookahead` and returns self.
+    #[must_use]
+    pub const fn max_lookahead(mut self, max_lookahead: u32) -> Self {
+        self.max_lookahead = max_lookahead;
+        self
+    }
+}
+
+/// Controls how stale the data read from a [`Follower`] may be.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReadPolicy {
+    /// Read whatever has been replicated so far, regardless of how far behind
+    /// the leader the follower is.
+    AllowStale,
+    /// Wait until the follower has been caught up with the leader within the
+    /// specified duration.
+    MaxLag(Duration),
+    /// Wait until every transaction that the leader had executed when the
+    /// read began has been replicated.
+    UpToDate,
+}
+
+/// The replication progress of a single database.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ReplicationStatus {
+    /// The id of the last transaction from the leader that has been applied.
+    pub applied_transaction_id: Option<u64>,
+    /// The id of the most recent transaction executed on the leader, as of the
+    /// last time the leader was checked.
+    pub leader_transaction_id: Option<u64>,
+    /// The last time the follower had applied every transaction executed on
+    /// the leader.
+    pub caught_up_at: Option<Instant>,
+}
+
+impl ReplicationStatus {
+    /// Returns the number of transaction ids between the last transaction
+    /// applied and the leader's most recent transaction.
+    #[must_use]
+    pub fn transactions_behind(&self) -> u64 {
+        self.leader_transaction_id
+            .unwrap_or_default()
+            .saturating_sub(self.applied_transaction_id.unwrap_or_default())
+    }
+
+    /// Returns how long it has been since the follower was last caught up
+    /// with the leader. Changes made to the leader within this duration may
+    /// not be visible on the follower. Returns `None` if the follower has never
+    /// caught up.
+    #[must_use]
+    pub fn lag(&self) -> Option<Duration> {
+        self.caught_up_at.map(|caught_up_at| caught_up_at.elapsed())
+    }
+}
+
+/// Replicates the databases of a leader server into a local storage.
+///
+/// [`Follower::run()`] must be running for the follower to receive changes
+/// from the leader.
+#[derive(Debug, Clone)]
+pub struct Follower {
+    data: Arc<Data>,
+}
+
+#[derive(Debug)]
+struct Data {
+    leader: AsyncClient,
+    storage: AsyncStorage,
+    configuration: ReplicationConfiguration,
+    statuses: Mutex<HashMap<String, ReplicationStatus>>,
+    last_error: Mutex<Option<Error>>,
+    updated: Notify,
+    /// Notified when the leader publishes that a transaction was executed.
+    executed: Arc<Notify>,
+}
+
+impl Follower {
+    /// Returns a follower that replicates the databases of `leader` into
+    /// `storage`. The schemas of the replicated databases must be registered
+    /// with `storage`.
+    #[must_use]
+    pub fn new(
+        leader: AsyncClient,
+        storage: AsyncStorage,
+        configuration: ReplicationConfiguration,
+    ) -> Self {
+        Self {
+            data: Arc::new(Data {
+                leader,
+                storage,
+                configuration,
+                statuses: Mutex::default(),
+                last_error: Mutex::default(),
+                updated: Notify::new(),
+                executed: Arc::default(),
+            }),
+        }
+    }
+
+    /// Replicates changes from the leader until the task is dropped.
+    ///
+    /// The follower subscribes to [`EXECUTED_TRANSACTIONS_TOPIC`] on each
+    /// replicated database and synchronizes whenever the leader executes a
+    /// transaction, or after the poll interval elapses without any
+    /// notifications. Errors are retried, and the most recent error can be
+    /// retrieved using [`last_error()`](Self::last_error).
+    pub async fn run(&self) {
+        let mut subscribers = HashMap::new();
+        loop {
+            let result = self.synchronize_databases(Some(&mut subscribers)).await;
+            if result.is_err() {
+                // The connection to the leader may have been lost, which also
+                // loses its subscriptions.
+                subscribers.clear();
+            }
+            *self.data.last_error.lock() = result.err();
+            let _: Result<_, _> = tokio::time::timeout(
+                self.data.configuration.poll_interval,
+                self.data.executed.notified(),
+            )
+            .await;
+        }
+    }
+
+    /// Applies all transactions that have been executed on the leader and not
+    /// yet replicated, creating any databases that don't exist on the
+    /// follower.
+    pub async fn synchronize(&self) -> Result<(), Error> {
+        self.synchronize_databases(None).await
+    }
+
+    async fn synchronize_databases(
+        &self,
+        mut subscribers: Option<&mut HashMap<String, AsyncRemoteSubscriber>>,
+    ) -> Result<(), Error> {
+        for database in self.data.leader.list_databases().await? {
+            if database.name == ADMIN_DATABASE_NAME
+                || self
+                    .data
+                    .configuration
+                    .databases
+                    .as_ref()
+                    .map_or(false, |databases| !databases.contains(&database.name))
+            {
+                continue;
+            }
+
+            self.data
+                .storage
+                .create_database_with_schema(&database.name, database.schema, true)
+                .await?;
+            // Subscribing before synchronizing ensures that transactions
+            // executed while synchronizing aren't missed.
+            if let Some(subscribers) = subscribers.as_deref_mut() {
+                if !subscribers.contains_key(&database.name) {
+                    let subscriber = self.subscribe(&database.name).await?;
+                    subscribers.insert(database.name.clone(), subscriber);
+                }
+            }
+            self.synchronize_database(&database.name).await?;
+        }
+        Ok(())
+    }
+
+    /// Subscribes to the transactions executed on the leader's database named
+    /// `name`. Messages are forwarded until the returned subscriber is
+    /// dropped.
+    async fn subscribe(&self, name: &str) -> Result<AsyncRemoteSubscriber, Error> {
+        let leader = self.data.leader.database::<()>(name).await?;
+        let subscriber = leader.create_subscriber().await?;
+        subscriber
+            .subscribe_to(&EXECUTED_TRANSACTIONS_TOPIC)
+            .await?;
+        let receiver = subscriber.receiver().clone();
+        let executed = self.data.executed.clone();
+        tokio::spawn(async move {
+            while receiver.receive_async().await.is_ok() {
+                executed.notify_one();
+            }
+        });
+        Ok(subscriber)
+    }
+
+    async fn synchronize_database(&self, name: &str) -> Result<(), Error> {
+        let leader = self.data.leader.database::<()>(name).await?;
+        let follower = self.data.storage.database_without_schema(name).await?;
+        let mut applied = applied_transaction_id(&follower).await?;
+
+        loop {
+            let leader_transaction_id = leader.last_transaction_id().await?;
+            let caught_up = leader_transaction_id <= applied;
+            self.update_status(name, |status| {
+                status.applied_transaction_id = applied;
+                status.leader_transaction_id = leader_transaction_id;
+                if caught_up {
+                    status.caught_up_at = Some(Instant::now());
+                }
+            });
+            if caught_up {
+                return Ok(());
+            }
+
+            let transactions = leader
+                .list_executed_transactions(
+                    Some(applied.map_or(0, |id| id + 1)),
+                    Some(self.data.configuration.batch_size),
+                )
+                .await?;
+            if transactions.is_empty() {
+                return Ok(());
+            }
+            let mut batch = ReplicationBatch::default();
+            for executed in transactions {
+                batch.push(executed);
+            }
+            batch
+                .read_documents(
+                    &leader,
+                    self.data.configuration.batch_size,
+                    self.data.configuration.max_lookahead,
+                )
+                .await?;
+            batch.apply(&leader, &follower).await?;
+
+            applied = Some(batch.last_transaction_id);
+            set_applied_transaction_id(&follower, batch.last_transaction_id).await?;
+        }
+    }
+
+    fn update_status(&self, name: &str, update: impl FnOnce(&mut ReplicationStatus)) {
+        let mut statuses = self.data.statuses.lock();
+        update(statuses.entry(name.to_string()).or_default());
+        drop(statuses);
+        self.data.updated.notify_waiters();
+    }
+
+    /// Returns the replication status of the database named `name`, or `None`
+    /// if the database hasn't been replicated yet.
+    #[must_use]
+    pub fn status(&self, name: &str) -> Option<ReplicationStatus> {
+        self.data.statuses.lock().get(name).cloned()
+    }
+
+    /// Returns the replication status of every replicated database.
+    #[must_use]
+    pub fn statuses(&self) -> HashMap<String, ReplicationStatus> {
+        self.data.statuses.lock().clone()
+    }
+
+    /// Returns the error that occurred during the most recent replication
+    /// attempt made by [`run()`](Self::run), if any.
+    #[must_use]
+    pub fn last_error(&self) -> Option<Error> {
+        self.data.last_error.lock().clone()
+    }
+
+    /// Returns the follower's copy of the database named `name`, after waiting
+    /// for the replicated data to satisfy `policy`.
+    ///
+    /// Waiting requires [`run()`](Self::run) to be running. Use
+    /// [`tokio::time::timeout`] to limit how long to wait.
+    pub async fn database(&self, name: &str, policy: ReadPolicy) -> Result<AsyncDatabase, Error> {
+        match policy {
+            ReadPolicy::AllowStale => {}
+            ReadPolicy::MaxLag(max_lag) => {
+                self.wait_for(name, |status| {
+                    status.lag().map_or(false, |lag| lag <= max_lag)
+                })
+                .await;
+            }
+            ReadPolicy::UpToDate => {
+                let leader = self.data.leader.database::<()>(name).await?;
+                if let Some(target) = leader.last_transaction_id().await? {
+                    self.wait_for(name, |status| status.applied_transaction_id >= Some(target))
+                        .await;
+                }
+            }
+        }
+
+        Ok(self.data.storage.database_without_schema(name).await?)
+    }
+
+    async fn wait_for(&self, name: &str, mut satisfied: impl FnMut(&ReplicationStatus) -> bool) {
+        loop {
+            // The notification must be created before checking the status to
+            // ensure an update between the check and waiting isn't missed.
+            let updated = self.data.updated.notified();
+            if self.status(name).map_or(false, |status| satisfied(&status)) {
+                return;
+            }
+            updated.await;
+        }
+    }
+}
+
+async fn applied_transaction_id(follower: &AsyncDatabase) -> Result<Option<u64>, Error> {
+    let output = follower
+        .execute_key_operation(KeyOperation {
+            namespace: Some(REPLICATION_NAMESPACE.to_string()),
+            key: APPLIED_TRANSACTION_KEY.to_string(),
+            command: Command::Get { delete: false },
+        })
+        .await?;
+    match output {
+        Output::Value(Some(Value::Numeric(Numeric::UnsignedInteger(id)))) => Ok(Some(id)),
+        Output::Value(None) => Ok(None),
+        other => Err(Error::other(
+            "replication",
+            format!("invalid applied transaction id: {other:?}"),
+        )),
+    }
+}
+
+async fn set_applied_transaction_id(follower: &AsyncDatabase, id: u64) -> Result<(), Error> {
+    follower
+        .execute_key_operation(KeyOperation {
+            namespace: Some(REPLICATION_NAMESPACE.to_string()),
+            key: APPLIED_TRANSACTION_KEY.to_string(),
+            command: Command::Set(SetCommand {
+                value: Value::Numeric(Numeric::UnsignedInteger(id)),
+                expiration: None,
+                keep_existing_expiration: false,
+                check: None,
+                return_previous_value: false,
+            }),
+        })
+        .await?;
+    Ok(())
+}
+
+/// The changes made by a range of the leader's executed transactions.
+#[derive(Default)]
+struct ReplicationBatch {
+    /// The id of the last transaction in the batch.
+    last_transaction_id: u64,
+    /// The documents changed by the batch that need to be read from the
+    /// leader.
+    unread: HashMap<CollectionName, BTreeSet<DocumentId>>,
+    /// The contents of each document changed by the batch, or `None` if the
+    /// document has been deleted.
+    documents: HashMap<CollectionName, HashMap<DocumentId, Option<Bytes>>>,
+    /// The key-value entries changed by the batch.
+    keys: BTreeSet<(Option<String>, String)>,
+}
+
+impl ReplicationBatch {
+    fn push(&mut self, executed: Executed) {
+        self.last_transaction_id = executed.id;
+        match executed.changes {
+            Changes::Documents(changes) => {
+                for (collection, document) in changes {
+                    self.unread
+                        .entry(collection)
+                        .or_default()
+                        .insert(document.id);
+                }
+            }
+            Changes::Keys(changes) => {
+                self.keys.extend(
+                    changes
+                        .into_iter()
+                        .filter(|change| change.namespace.as_deref() != Some(REPLICATION_NAMESPACE))
+                        .map(|change| (change.namespace, change.key)),
+                );
+            }
+        }
+    }
+
+    /// Returns true if `executed` changed any document that has been read.
+    fn changes_read_documents(&self, executed: &Executed) -> bool {
+        match &executed.changes {
+            Changes::Documents(changes) => changes.iter().any(|(collection, document)| {
+                self.documents
+                    .get(collection)
+                    .map_or(false, |documents| documents.contains_key(&document.id))
+            }),
+            Changes::Keys(_) => false,
+        }
+    }
+
+    /// Reads the current contents of the documents changed by this batch.
+    ///
+    /// The leader can execute more transactions while the documents are being
+    /// read, so the contents read may include changes from after the batch.
+    /// Once the documents have first been read, the leader's last transaction
+    /// id is captured, and the transactions up to that id are examined, up to
+    /// `max_lookahead` transactions after the batch. Transactions that changed
+    /// a document that was read are added to the batch, and only the documents
+    /// they changed are read again, until no examined transaction has changed
+    /// a document that was read. The contents read are then the contents as of
+    /// the batch's last transaction, unless they were changed by transactions
+    /// that weren't examined.
+    async fn read_documents(
+        &mut self,
+        leader: &AsyncRemoteDatabase,
+        batch_size: u32,
+        max_lookahead: u32,
+    ) -> Result<(), Error> {
+        self.read_unread_documents(leader).await?;
+        let lookahead_end = leader
+            .last_transaction_id()
+            .await?
+            .unwrap_or_default()
+            .min(self.last_transaction_id + u64::from(max_lookahead));
+
+        let mut lookahead = VecDeque::new();
+        let mut next_transaction_id = self.last_transaction_id + 1;
+        while next_transaction_id <= lookahead_end {
+            let remaining = u32::try_from(lookahead_end - next_transaction_id + 1)
+                .unwrap_or(u32::MAX)
+                .min(batch_size);
+            let page = leader
+                .list_executed_transactions(Some(next_transaction_id), Some(remaining))
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            next_transaction_id = last.id + 1;
+            lookahead.extend(
+                page.into_iter()
+                    .filter(|executed| executed.id <= lookahead_end),
+            );
+        }
+
+        while let Some(last_conflict) = lookahead
+            .iter()
+            .rposition(|executed| self.changes_read_documents(executed))
+        {
+            for executed in lookahead.drain(..=last_conflict) {
+                self.push(executed);
+            }
+            self.read_unread_documents(leader).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the documents that were changed by this batch and haven't been
+    /// read since.
+    async fn read_unread_documents(&mut self, leader: &AsyncRemoteDatabase) -> Result<(), Error> {
+        for (collection, ids) in std::mem::take(&mut self.unread) {
+            let documents = self.documents.entry(collection.clone()).or_default();
+            let mut removed = ids;
+            let ids = removed.iter().cloned().collect::<Vec<_>>();
+            for document in leader
+                .get_multiple_from_collection(&ids, &collection)
+                .await?
+            {
+                removed.remove(&document.header.id);
+                documents.insert(document.header.id, Some(document.contents));
+            }
+            for id in removed {
+                documents.insert(id, None);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the documents read in a single transaction, followed by the
+    /// key-value entries changed by the batch.
+    async fn apply(
+        self,
+        leader: &AsyncRemoteDatabase,
+        follower: &AsyncDatabase,
+    ) -> Result<(), Error> {
+        let mut transaction = Transaction::new();
+        for (collection, documents) in self.documents {
+            let mut removed = Vec::new();
+            for (id, contents) in documents {
+                if let Some(contents) = contents {
+                    transaction.push(Operation::overwrite(collection.clone(), id, contents));
+                } else {
+                    removed.push(id);
+                }
+            }
+
+            // Documents that no longer exist on the leader are deleted.
+            if !removed.is_empty() {
+                for document in follower
+                    .get_multiple_from_collection(&removed, &collection)
+                    .await?
+                {
+                    transaction.push(Operation::delete(collection.clone(), document.header));
+                }
+            }
+        }
+        if !transaction.operations.is_empty() {
+            follower.apply_transaction(transaction).await?;
+        }
+
+        for (namespace, key) in self.keys {
+            let output = leader
+                .execute_key_operation(KeyOperation {
+                    namespace: namespace.clone(),
+                    key: key.clone(),
+                    command: Command::GetEntry,
+                })
+                .await?;
+            let command = match output {
+                Output::Entry {
+                    value: Some(value),
+                    expiration,
+                } => Command::Set(SetCommand {
+                    value,
+                    expiration,
+                    keep_existing_expiration: false,
+                    check: None,
+                    return_previous_value: false,
+                }),
+                Output::Entry { value: None, .. } => Command::Delete,
+                other => {
+                    return Err(Error::other(
+                        "replication",
+                        format!("unexpected output from GetEntry: {other:?}"),
+                    ))
+                }
+            };
+            follower
+                .execute_key_operation(KeyOperation {
+                    namespace,
+                    key,
+                    command,
+                })
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! Tests replicating a server's databases to a follower.
+
+use std::time::Duration;
+
+use bonsaidb::client::url::Url;
+use bonsaidb::client::AsyncClient;
+use bonsaidb::core::test_util::{Basic, TestDirectory};
+use bonsaidb::local::config::Builder;
+use bonsaidb::replication::{Follower, ReadPolicy, ReplicationConfiguration};
+use bonsaidb::server::{DefaultPermissions, Server, ServerConfiguration};
+use bonsaidb_core::connection::AsyncStorageConnection;
+use bonsaidb_core::keyvalue::{AsyncKeyValue, Command, KeyOperation, Output};
+use bonsaidb_core::schema::SerializedCollection;
+use bonsaidb_core::transaction::{Operation, Transaction};
+
+#[tokio::test]
+async fn leader_follower() -> anyhow::Result<()> {
+    let leader_dir = TestDirectory::new("replication-leader.bonsaidb");
+    let leader = Server::open(
+        ServerConfiguration::new(&leader_dir)
+            .default_permissions(DefaultPermissions::AllowAll)
+            .with_schema::<Basic>()?,
+    )
+    .await?;
+    leader.install_self_signed_certificate(false).await?;
+    let certificate = leader
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    let listening_leader = leader.clone();
+    tokio::spawn(async move { listening_leader.listen_on(12347).await });
+
+    let follower_dir = TestDirectory::new("replication-follower.bonsaidb");
+    let follower_server = Server::open(
+        ServerConfiguration::new(&follower_dir)
+            .default_permissions(DefaultPermissions::AllowAll)
+            .with_schema::<Basic>()?,
+    )
+    .await?;
+
+    let leader_db = leader.create_database::<Basic>("basic", false).await?;
+    let deleted = Basic::new("deleted").push_into_async(&leader_db).await?;
+    let updated = Basic::new("original").push_into_async(&leader_db).await?;
+    leader_db.set_numeric_key("key", 1_u64).await?;
+    leader_db
+        .set_numeric_key("expiring", 1_u64)
+        .expire_in(Duration::from_secs(3600))
+        .await?;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:12347")?)
+        .with_certificate(certificate)
+        .build()?;
+    let follower = Follower::new(
+        client,
+        (*follower_server).clone(),
+        // The long poll interval ensures that changes are replicated because
+        // the leader published that they were executed.
+        ReplicationConfiguration::default().poll_interval(Duration::from_secs(60)),
+    );
+    let running_follower = follower.clone();
+    tokio::spawn(async move { running_follower.run().await });
+
+    let replica = tokio::time::timeout(
+        Duration::from_secs(10),
+        follower.database("basic", ReadPolicy::UpToDate),
+    )
+    .await??;
+    assert_eq!(Basic::all_async(&replica).await?.len(), 2);
+    assert_eq!(replica.get_key("key").into_u64().await?, Some(1));
+    let expiring = replica
+        .execute_key_operation(KeyOperation {
+            namespace: None,
+            key: String::from("expiring"),
+            command: Command::GetEntry,
+        })
+        .await?;
+    assert!(matches!(
+        expiring,
+        Output::Entry {
+            value: Some(_),
+            expiration: Some(_)
+        }
+    ));
+
+    // Changes made after the initial synchronization are replicated.
+    deleted.delete_async(&leader_db).await?;
+    let mut updated = Basic::get_async(&updated.header.id, &leader_db)
+        .await?
+        .expect("document missing");
+    updated.contents.value = String::from("updated");
+    updated.update_async(&leader_db).await?;
+    leader_db.delete_key("key").await?;
+    // Documents changed together are replicated together.
+    Transaction::new()
+        .with(Operation::push_serialized::<Basic>(&Basic::new("first"))?)
+        .with(Operation::push_serialized::<Basic>(&Basic::new("second"))?)
+        .apply_async(&leader_db)
+        .await?;
+
+    let replica = tokio::time::timeout(
+        Duration::from_secs(10),
+        follower.database("basic", ReadPolicy::UpToDate),
+    )
+    .await??;
+    let mut values = Basic::all_async(&replica)
+        .await?
+        .into_iter()
+        .map(|document| document.contents.value)
+        .collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(values, ["first", "second", "updated"]);
+    assert_eq!(replica.get_key("key").into_u64().await?, None);
+
+    let status = follower.status("basic").expect("status missing");
+    assert_eq!(status.transactions_behind(), 0);
+    assert!(status.lag().is_some());
+    assert!(follower.last_error().is_none());
+
+    Ok(())
+}